@@ -1,6 +1,6 @@
 use criterion::{criterion_group, Bencher, Criterion};
 use gw_common::{h256_ext::H256Ext, state::State, H256};
-use gw_config::GenesisConfig;
+use gw_config::{FeeQueueConfig, GenesisConfig};
 use gw_generator::genesis::init_genesis;
 use gw_mem_pool::fee::{
     queue::FeeQueue,
@@ -19,8 +19,17 @@ use gw_types::{
 
 const MAX_QUEUE_SIZE: usize = 100_000;
 
+// All entries are from the same sender, most of them are far ahead of its nonce.
+fn unlimited_sender_config() -> FeeQueueConfig {
+    FeeQueueConfig {
+        max_items_per_sender: 0,
+        max_nonce_gap: u32::MAX,
+        ..Default::default()
+    }
+}
+
 fn bench_add_full(b: &mut Bencher) {
-    let mut queue = FeeQueue::new();
+    let mut queue = FeeQueue::with_config(unlimited_sender_config());
 
     let store = Store::open_tmp().expect("open store");
     setup_genesis(&store);
@@ -50,20 +59,19 @@ fn bench_add_full(b: &mut Bencher) {
             sender: FeeItemSender::AccountId(2),
             order: queue.len(),
         };
-        queue.add(entry1, ());
+        queue.add(entry1, (), 0);
     }
 
     assert_eq!(queue.len(), MAX_QUEUE_SIZE);
 
+    // Use a new nonce each time, or the entry is rejected as a replacement.
+    let mut nonce = MAX_QUEUE_SIZE as u32;
     b.iter(|| {
+        nonce += 1;
         let entry1 = FeeEntry {
             item: FeeItem::Tx(
                 L2Transaction::new_builder()
-                    .raw(
-                        RawL2Transaction::new_builder()
-                            .nonce(10001u32.pack())
-                            .build(),
-                    )
+                    .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
                     .build(),
             ),
             fee: (100 * 1000u64).into(),
@@ -71,12 +79,13 @@ fn bench_add_full(b: &mut Bencher) {
             sender: FeeItemSender::AccountId(2),
             order: queue.len(),
         };
-        queue.add(entry1, ());
+        queue.add(entry1, (), 0);
+        queue.take_evicted();
     });
 }
 
 fn bench_add_fetch_20(b: &mut Bencher) {
-    let mut queue = FeeQueue::new();
+    let mut queue = FeeQueue::with_config(unlimited_sender_config());
 
     let store = Store::open_tmp().expect("open store");
     setup_genesis(&store);
@@ -107,7 +116,7 @@ fn bench_add_fetch_20(b: &mut Bencher) {
             sender: FeeItemSender::AccountId(2),
             order: queue.len(),
         };
-        queue.add(entry1, ());
+        queue.add(entry1, (), 0);
     }
 
     let tree = MemStateDB::from_store(snap).unwrap();
@@ -129,8 +138,9 @@ fn bench_add_fetch_20(b: &mut Bencher) {
                 sender: FeeItemSender::AccountId(2),
                 order: queue.len(),
             };
-            queue.add(entry1, ());
+            queue.add(entry1, (), 0);
         }
+        queue.take_evicted();
         queue.fetch(&tree, 20)
     });
 }
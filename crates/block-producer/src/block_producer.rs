@@ -188,6 +188,7 @@ impl BlockProducer {
             since,
             withdrawal_extras,
             local_cells_manager,
+            fee_rate,
         } = args;
 
         let rollup_cell = query_rollup_cell(local_cells_manager, &self.rpc_client)
@@ -451,7 +452,7 @@ impl BlockProducer {
             &self.rpc_client.indexer,
             self.wallet.lock_script().to_owned(),
             local_cells_manager,
-            self.config.fee_rate,
+            fee_rate,
         )
         .await?;
         debug_assert_eq!(
@@ -460,7 +461,7 @@ impl BlockProducer {
             "check duplicated inputs"
         );
         // sign
-        let tx = self.wallet.sign_tx_skeleton(tx_skeleton)?;
+        let tx = self.wallet.sign_tx_skeleton(tx_skeleton).await?;
         ensure!(
             (tx.as_slice().len() as u64) < MAX_BLOCK_BYTES,
             TransactionSizeError::TransactionTooLarge
@@ -477,6 +478,8 @@ pub struct ComposeSubmitTxArgs<'a> {
     pub since: Since,
     pub withdrawal_extras: Vec<WithdrawalRequestExtra>,
     pub local_cells_manager: &'a LocalCellsManager,
+    /// Shannons per KB.
+    pub fee_rate: u64,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -1,29 +1,36 @@
 //! L1 and P2P block sync.
 
-use std::{collections::VecDeque, sync::Arc, time::Duration};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::{ensure, Context, Result};
+use anyhow::{anyhow, ensure, Context, Result};
 use bytes::Bytes;
 use ckb_types::prelude::{Builder, Entity, Reader};
 use futures::TryStreamExt;
 use gw_chain::chain::Chain;
+use gw_common::{blake2b::new_blake2b, H256};
 use gw_generator::generator::CyclesPool;
-use gw_mem_pool::pool::MemPool;
-use gw_p2p_network::{FnSpawn, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME};
+use gw_mem_pool::{block_sync_server::BlockSyncServerState, pool::MemPool};
+use gw_p2p_network::{FnSpawn, PeerBook, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME};
 use gw_rpc_client::rpc_client::RPCClient;
 use gw_store::{traits::chain_store::ChainStore, Store};
 use gw_types::{
     packed::{
         BlockSync, BlockSyncReader, BlockSyncUnion, NumberHash, P2PSyncRequest,
-        P2PSyncResponseReader, P2PSyncResponseUnionReader, Script,
+        P2PSyncResponseReader, P2PSyncResponseUnionReader, Revert, Script,
     },
-    prelude::Unpack,
+    prelude::{Pack, Unpack},
 };
 use gw_utils::{compression::StreamDecoder, liveness::Liveness};
 use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
 use tentacle::{
     builder::MetaBuilder,
+    secio::PeerId,
     service::{ProtocolMeta, ServiceAsyncControl},
+    utils::extract_peer_id,
     SessionId, SubstreamReadPart,
 };
 use tokio::{sync::Mutex, task::block_in_place};
@@ -32,9 +39,23 @@ use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 use crate::{
     chain_updater::ChainUpdater,
+    psc::publish_local_block,
     sync_l1::{revert, sync_l1, SyncL1Context},
 };
 
+/// Fail over to another peer if the active peer sends nothing for this long.
+///
+/// The chain may just be quiet, so a stall is only penalized once another peer
+/// has made progress, see `StalledPeers`.
+const STALL_TIMEOUT: Duration = Duration::from_secs(60);
+const STALL_PENALTY: i32 = 20;
+const MISBEHAVIOR_PENALTY: i32 = 50;
+const LOCAL_BLOCK_REWARD: i32 = 1;
+const RECENT_MESSAGES_CAPACITY: usize = 4096;
+
+/// Streams of connected block sync server peers.
+pub type P2PStreamInbox = Arc<std::sync::Mutex<Vec<P2PStream>>>;
+
 pub struct BlockSyncClient {
     pub store: Store,
     pub rpc_client: RPCClient,
@@ -42,7 +63,10 @@ pub struct BlockSyncClient {
     pub mem_pool: Option<Arc<Mutex<MemPool>>>,
     pub chain_updater: ChainUpdater,
     pub rollup_type_script: Script,
-    pub p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
+    pub p2p_stream_inbox: P2PStreamInbox,
+    pub peer_book: PeerBook,
+    /// Re-publish received messages, e.g. for WebSocket subscriptions.
+    pub block_sync_server_state: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
     pub completed_initial_syncing: bool,
     pub liveness: Arc<Liveness>,
 }
@@ -70,28 +94,58 @@ impl SyncL1Context for BlockSyncClient {
 
 impl BlockSyncClient {
     pub async fn run(mut self) {
-        let mut p2p_stream = None;
+        // Connected peers. The one with the highest score is synced from, the
+        // others are standbys to fail over to.
+        let mut peers: Vec<P2PStream> = Vec::new();
+        let mut recent_messages = RecentMessages::new(RECENT_MESSAGES_CAPACITY);
+        let mut stalled_peers = StalledPeers::default();
         loop {
-            if let Some(ref mut s) = p2p_stream {
-                if let Err(err) = run_with_p2p_stream(&mut self, s).await {
-                    if err.is::<gw_db::error::Error>() {
-                        // Cannot recover from db error.
-                        log::error!("db error, exiting: {:#}", err);
-                        return;
-                    }
-                    if !err.is::<RecoverableCtx>() {
+            peers.extend(self.p2p_stream_inbox.lock().unwrap().drain(..));
+            for s in peers.iter_mut().filter(|s| s.is_banned(&self.peer_book)) {
+                let _ = s.disconnect().await;
+            }
+            peers.retain(|s| !s.is_banned(&self.peer_book));
+
+            if let Some(mut s) = select_peer(&mut peers, |s| s.score(&self.peer_book)) {
+                log::info!("sync from peer {}", s.peer_name());
+                let result = run_with_p2p_stream(
+                    &mut self,
+                    &mut s,
+                    &mut recent_messages,
+                    &mut stalled_peers,
+                )
+                .await;
+                match result {
+                    Ok(()) => {
+                        log::info!("peer {} stream ended", s.peer_name());
                         let _ = s.disconnect().await;
-                        p2p_stream = None;
                     }
-                    log::warn!("{:#}", err);
+                    Err(err) => {
+                        if err.is::<gw_db::error::Error>() {
+                            // Cannot recover from db error.
+                            log::error!("db error, exiting: {:#}", err);
+                            return;
+                        }
+                        log::warn!("peer {}: {:#}", s.peer_name(), err);
+                        if err.is::<RecoverableCtx>() {
+                            peers.push(s);
+                        } else {
+                            if err.is::<Misbehavior>() {
+                                s.penalize(&self.peer_book, MISBEHAVIOR_PENALTY, &err);
+                            } else if err.is::<Stalled>() {
+                                if let Some(ref peer_id) = s.peer_id {
+                                    stalled_peers.insert(peer_id.clone());
+                                }
+                            }
+                            // The peer will be re-dialed by the p2p network,
+                            // fail over to the next one meanwhile.
+                            let _ = s.disconnect().await;
+                        }
+                    }
                 }
                 // TODO: backoff.
                 tokio::time::sleep(Duration::from_secs(3)).await;
             } else {
-                p2p_stream = self.p2p_stream_inbox.lock().unwrap().take();
-                if p2p_stream.is_some() {
-                    continue;
-                }
                 if let Err(err) = run_once_without_p2p_stream(&mut self).await {
                     if err.is::<gw_db::error::Error>() {
                         // Cannot recover from db error.
@@ -107,6 +161,16 @@ impl BlockSyncClient {
     }
 }
 
+/// Pick the peer with the highest score. The first one wins a tie.
+fn select_peer<T>(peers: &mut Vec<T>, score: impl Fn(&T) -> i32) -> Option<T> {
+    let idx = peers
+        .iter()
+        .enumerate()
+        .max_by_key(|(idx, s)| (score(*s), std::cmp::Reverse(*idx)))
+        .map(|(idx, _)| idx)?;
+    Some(peers.remove(idx))
+}
+
 #[derive(Debug)]
 struct RecoverableCtx;
 
@@ -116,13 +180,176 @@ impl std::fmt::Display for RecoverableCtx {
     }
 }
 
+/// The peer sent an invalid message or a message that doesn't match our chain.
+#[derive(Debug)]
+struct Misbehavior;
+
+impl std::fmt::Display for Misbehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "peer misbehavior")
+    }
+}
+
+/// The peer sent nothing for `STALL_TIMEOUT`.
+#[derive(Debug)]
+struct Stalled;
+
+impl std::fmt::Display for Stalled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "peer stalled")
+    }
+}
+
+/// Peers that stalled since the last progress.
+///
+/// A single upstream may send nothing for a long time simply because no block
+/// is produced, so stalls are not penalized right away. They are penalized
+/// when another peer makes progress, which shows that the stalled peer was
+/// lagging behind.
+#[derive(Default)]
+struct StalledPeers {
+    peers: HashSet<PeerId>,
+}
+
+impl StalledPeers {
+    fn insert(&mut self, peer_id: PeerId) {
+        self.peers.insert(peer_id);
+    }
+
+    /// The peer made progress. Penalize the other stalled peers.
+    fn progressed(&mut self, peer_book: &PeerBook, peer_id: &PeerId) {
+        self.peers.remove(peer_id);
+        for stalled in self.peers.drain() {
+            peer_book.penalize(&stalled, STALL_PENALTY, "peer stalled");
+        }
+    }
+}
+
+/// Keys of recently applied messages.
+///
+/// After failing over, the new peer replays messages since the last confirmed
+/// block, some of which have already been applied. Applying them again would
+/// e.g. move the last submitted block backwards or re-publish them.
+struct RecentMessages {
+    capacity: usize,
+    keys: HashSet<[u8; 32]>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl RecentMessages {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            keys: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn contains(&self, key: &[u8; 32]) -> bool {
+        self.keys.contains(key)
+    }
+
+    fn insert(&mut self, key: [u8; 32]) {
+        if !self.keys.insert(key) {
+            return;
+        }
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.keys.clear();
+        self.order.clear();
+    }
+}
+
+/// Local blocks and transactions are identified by their hashes because trace
+/// ids differ between peers. Other messages are identified by their content.
+fn message_key(msg: &BlockSync) -> [u8; 32] {
+    let mut hasher = new_blake2b();
+    match msg.to_enum() {
+        BlockSyncUnion::LocalBlock(l) => {
+            hasher.update(&[0]);
+            hasher.update(&l.block().hash());
+        }
+        BlockSyncUnion::PushTransaction(p) => {
+            hasher.update(&[1]);
+            hasher.update(&p.transaction().hash());
+        }
+        _ => {
+            hasher.update(&[2]);
+            hasher.update(msg.as_slice());
+        }
+    }
+    let mut key = [0u8; 32];
+    hasher.finalize(&mut key);
+    key
+}
+
 async fn run_once_without_p2p_stream(client: &mut BlockSyncClient) -> Result<()> {
+    let last_tip_hash = client.store.get_last_valid_tip_block_hash()?;
     sync_l1(client).await?;
     notify_new_tip(client, true).await?;
+    publish_synced_blocks(client, &last_tip_hash)?;
     Ok(())
 }
 
-async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStream) -> Result<()> {
+/// Publish blocks synced from L1, e.g. for WebSocket subscriptions.
+///
+/// Blocks reverted by `sync_l1` are published as a revert to the last block
+/// still on the chain, then blocks after it are published again, like
+/// `reset_local_state` does.
+fn publish_synced_blocks(client: &BlockSyncClient, last_tip_hash: &H256) -> Result<()> {
+    let state = match client.block_sync_server_state {
+        Some(ref state) => state,
+        None => return Ok(()),
+    };
+    let snap = client.store.get_snapshot();
+    // Reverted blocks are detached but still in the store, walk back from the
+    // last tip to the last block still on the chain.
+    let last_tip = snap.get_block(last_tip_hash)?.context("get last tip")?;
+    let last_tip_number: u64 = last_tip.raw().number().unpack();
+    let mut common = last_tip.raw();
+    loop {
+        let number = common.number().unpack();
+        let hash: H256 = common.hash().into();
+        if snap.get_block_hash_by_number(number)? == Some(hash) {
+            break;
+        }
+        let parent_hash = common.parent_block_hash().unpack();
+        common = snap
+            .get_block(&parent_hash)?
+            .context("get parent block")?
+            .raw();
+    }
+    let common_number: u64 = common.number().unpack();
+    let common_hash: H256 = common.hash().into();
+
+    let tip = snap.get_last_valid_tip_block()?.raw().number().unpack();
+    let mut sync_server = state.lock().unwrap();
+    if common_number < last_tip_number {
+        let number_hash = NumberHash::new_builder()
+            .number(common_number.pack())
+            .block_hash(common_hash.pack())
+            .build();
+        sync_server.publish_revert(Revert::new_builder().number_hash(number_hash).build());
+    }
+    for b in (common_number + 1)..=tip {
+        publish_local_block(&mut sync_server, &snap, b)?;
+    }
+    Ok(())
+}
+
+async fn run_with_p2p_stream(
+    client: &mut BlockSyncClient,
+    stream: &mut P2PStream,
+    recent_messages: &mut RecentMessages,
+    stalled_peers: &mut StalledPeers,
+) -> Result<()> {
     loop {
         sync_l1(client).await.context(RecoverableCtx)?;
         notify_new_tip(client, false)
@@ -138,8 +365,11 @@ async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStrea
             .block_number(last_confirmed.number())
             .build();
         stream.send(request.as_bytes()).await?;
-        let response = stream.recv().await?.context("unexpected end of stream")?;
-        let response = P2PSyncResponseReader::from_slice(&response)?;
+        let response = tokio::time::timeout(STALL_TIMEOUT, stream.recv())
+            .await
+            .map_err(|_| anyhow!(Stalled))??
+            .context("unexpected end of stream")?;
+        let response = P2PSyncResponseReader::from_slice(&response).context(Misbehavior)?;
         match response.to_enum() {
             P2PSyncResponseUnionReader::Found(_) => break,
             P2PSyncResponseUnionReader::TryAgain(_) => {}
@@ -149,6 +379,7 @@ async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStrea
     }
     log::info!("receiving block sync messages from peer");
     let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let peer_id = stream.peer_id.clone();
     let mut stream = stream.take_receiver();
     // Receive from the stream promptly but only send to tx when the previous
     // one has been applied.
@@ -163,7 +394,7 @@ async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStrea
                 biased;
                 recv_result = stream.recv(), if !stream_ended && buffer.len() < 1024 => {
                     if let Some(msg) = recv_result? {
-                        BlockSyncReader::from_slice(&msg[..])?;
+                        BlockSyncReader::from_slice(&msg[..]).context(Misbehavior)?;
                         buffer.push_back(BlockSync::new_unchecked(msg));
                         if buffer.len() % 128 == 0 {
                             log::info!("receive buffer: {}", buffer.len());
@@ -196,8 +427,36 @@ async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStrea
         }
         anyhow::Ok(())
     });
-    while let Some(msg) = rx.recv().await {
+    loop {
+        let msg = match tokio::time::timeout(STALL_TIMEOUT, rx.recv()).await {
+            Ok(Some(msg)) => msg,
+            Ok(None) => break,
+            Err(_) => {
+                recv_handle.abort();
+                return Err(anyhow!(Stalled));
+            }
+        };
+        let key = message_key(&msg);
+        if recent_messages.contains(&key) {
+            log::debug!("skip applied message");
+            continue;
+        }
+        let msg_union = msg.to_enum();
         apply_msg(client, msg).await?;
+        match msg_union {
+            BlockSyncUnion::Revert(_) => {
+                // Messages before the revert may be valid again.
+                recent_messages.clear();
+            }
+            BlockSyncUnion::LocalBlock(_) => {
+                if let Some(ref peer_id) = peer_id {
+                    client.peer_book.reward(peer_id, LOCAL_BLOCK_REWARD);
+                    stalled_peers.progressed(&client.peer_book, peer_id);
+                }
+            }
+            _ => {}
+        }
+        recent_messages.insert(key);
     }
     recv_handle.await??;
     Ok(())
@@ -311,8 +570,15 @@ async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
                     log::warn!("{:#}", err);
                 }
             }
+            // Transactions are re-published by the mem pool after executed.
+            if client.mem_pool.is_some() {
+                return Ok(());
+            }
         }
     }
+    if let Some(ref state) = client.block_sync_server_state {
+        state.lock().unwrap().republish(msg);
+    }
     Ok(())
 }
 
@@ -362,6 +628,8 @@ async fn handle_local_block(
 
 pub struct P2PStream {
     id: SessionId,
+    peer_id: Option<PeerId>,
+    peer_book: PeerBook,
     control: ServiceAsyncControl,
     read_part: Option<SubstreamReadPart>,
     decoder: StreamDecoder,
@@ -374,6 +642,8 @@ impl P2PStream {
     fn take_receiver(&mut self) -> Self {
         Self {
             id: self.id,
+            peer_id: self.peer_id.clone(),
+            peer_book: self.peer_book.clone(),
             control: self.control.clone(),
             read_part: self.read_part.take(),
             decoder: core::mem::take(&mut self.decoder),
@@ -383,6 +653,7 @@ impl P2PStream {
     async fn recv(&mut self) -> Result<Option<Bytes>> {
         let receiver = self.read_part.as_mut().context("stream is taken")?;
         Ok(if let Some(msg) = receiver.try_next().await? {
+            self.peer_book.record_received(self.id, msg.len());
             // Decompress message.
             Some(self.decoder.decode(&msg)?.into())
         } else {
@@ -391,6 +662,7 @@ impl P2PStream {
     }
 
     async fn send(&mut self, msg: Bytes) -> Result<()> {
+        self.peer_book.record_sent(self.id, msg.len());
         self.control
             .send_message_to(self.id, P2P_SYNC_PROTOCOL, msg)
             .await?;
@@ -401,22 +673,53 @@ impl P2PStream {
         self.control.disconnect(self.id).await?;
         Ok(())
     }
+
+    fn peer_name(&self) -> String {
+        match self.peer_id {
+            Some(ref peer_id) => peer_id.to_base58(),
+            None => format!("session {}", self.id),
+        }
+    }
+
+    fn score(&self, peer_book: &PeerBook) -> i32 {
+        match self.peer_id {
+            Some(ref peer_id) => peer_book.score(peer_id),
+            None => 0,
+        }
+    }
+
+    fn is_banned(&self, peer_book: &PeerBook) -> bool {
+        match self.peer_id {
+            Some(ref peer_id) => peer_book.is_banned(peer_id),
+            None => false,
+        }
+    }
+
+    fn penalize(&self, peer_book: &PeerBook, delta: i32, err: &anyhow::Error) {
+        if let Some(ref peer_id) = self.peer_id {
+            peer_book.penalize(peer_id, delta, &format!("{:#}", err));
+        }
+    }
 }
 
-/// The p2p protocol just sends the p2p stream to the client.
+/// The p2p protocol just sends the p2p streams to the client.
 pub fn block_sync_client_protocol(
-    stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
+    stream_inbox: P2PStreamInbox,
+    peer_book: PeerBook,
 ) -> ProtocolMeta {
     let spawn = FnSpawn(move |context, control, read_part| {
         let control = control.clone();
         let id = context.id;
+        peer_book.record_protocol(&context, P2P_SYNC_PROTOCOL_NAME);
         let stream = P2PStream {
             id,
+            peer_id: extract_peer_id(&context.address),
+            peer_book: peer_book.clone(),
             control,
             read_part: Some(read_part),
             decoder: StreamDecoder::new(),
         };
-        *stream_inbox.lock().unwrap() = Some(stream);
+        stream_inbox.lock().unwrap().push(stream);
     });
     MetaBuilder::new()
         .name(|_| P2P_SYNC_PROTOCOL_NAME.into())
@@ -442,13 +745,137 @@ async fn notify_new_tip(client: &mut BlockSyncClient, update_state: bool) -> Res
     Ok(())
 }
 
+/// Check that the block is on our chain.
+///
+/// A block we don't have yet is not the peer's fault, e.g. we may be behind
+/// after failing over, so only a hash mismatch is a misbehavior.
 fn check_number_hash(client: &BlockSyncClient, number_hash: &NumberHash) -> Result<()> {
-    // Check block hash.
     let number = number_hash.number().unpack();
     let store_block_hash = client
         .store
         .get_block_hash_by_number(number)?
-        .context("get block hash")?;
-    ensure!(store_block_hash.as_slice() == number_hash.block_hash().as_slice());
+        .with_context(|| format!("block {} not found locally", number))?;
+    ensure!(
+        store_block_hash.as_slice() == number_hash.block_hash().as_slice(),
+        Misbehavior
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use gw_config::PeerScoreConfig;
+    use gw_types::packed::{
+        Byte16, Confirmed, L2Block, L2Transaction, LocalBlock, PushTransaction,
+    };
+
+    use super::*;
+
+    fn peer_book() -> PeerBook {
+        PeerBook::new(&PeerScoreConfig {
+            ban_threshold: -100,
+            ban_duration_secs: 3600,
+        })
+    }
+
+    #[test]
+    fn test_recent_messages() {
+        let mut recent = RecentMessages::new(2);
+        let (a, b, c) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        recent.insert(a);
+        recent.insert(a);
+        recent.insert(b);
+        assert!(recent.contains(&a) && recent.contains(&b));
+        // The oldest key is evicted.
+        recent.insert(c);
+        assert!(!recent.contains(&a));
+        assert!(recent.contains(&b) && recent.contains(&c));
+        recent.clear();
+        assert!(!recent.contains(&b) && !recent.contains(&c));
+    }
+
+    #[test]
+    fn test_message_key() {
+        let block = L2Block::default();
+        let local_block = |trace_id: [u8; 16]| {
+            BlockSync::new_builder()
+                .set(
+                    LocalBlock::new_builder()
+                        .block(block.clone())
+                        .trace_id(Byte16::from_slice(&trace_id).unwrap())
+                        .build(),
+                )
+                .build()
+        };
+        // Same block from different peers.
+        assert_eq!(
+            message_key(&local_block([1; 16])),
+            message_key(&local_block([2; 16]))
+        );
+
+        let push_tx = BlockSync::new_builder()
+            .set(
+                PushTransaction::new_builder()
+                    .transaction(L2Transaction::default())
+                    .build(),
+            )
+            .build();
+        let confirmed = BlockSync::new_builder().set(Confirmed::default()).build();
+        let keys: HashSet<_> = [local_block([1; 16]), push_tx, confirmed]
+            .iter()
+            .map(message_key)
+            .collect();
+        assert_eq!(keys.len(), 3);
+    }
+
+    #[test]
+    fn test_select_peer() {
+        let mut peers = vec![("a", 0), ("b", 10), ("c", 10), ("d", -5)];
+        let select = |peers: &mut Vec<(&str, i32)>| select_peer(peers, |p| p.1).map(|p| p.0);
+        assert_eq!(select(&mut peers), Some("b"));
+        assert_eq!(select(&mut peers), Some("c"));
+        assert_eq!(select(&mut peers), Some("a"));
+        assert_eq!(select(&mut peers), Some("d"));
+        assert_eq!(select(&mut peers), None);
+    }
+
+    #[test]
+    fn test_stall_single_peer() {
+        let book = peer_book();
+        let peer = PeerId::random();
+        let mut stalled = StalledPeers::default();
+        // A quiet single upstream is never penalized.
+        for _ in 0..100 {
+            stalled.insert(peer.clone());
+        }
+        assert_eq!(book.score(&peer), 0);
+        // Nor when it makes progress itself.
+        stalled.progressed(&book, &peer);
+        assert_eq!(book.score(&peer), 0);
+        assert!(!book.is_banned(&peer));
+    }
+
+    #[test]
+    fn test_failover() {
+        let book = peer_book();
+        let (a, b) = (PeerId::random(), PeerId::random());
+        book.reward(&a, 10);
+        let mut stalled = StalledPeers::default();
+
+        let mut peers = vec![a.clone(), b.clone()];
+        let active = select_peer(&mut peers, |p| book.score(p)).unwrap();
+        assert_eq!(active, a);
+        // a stalls, fail over to b.
+        stalled.insert(active.clone());
+        peers.push(active);
+        let active = select_peer(&mut peers, |p| book.score(p)).unwrap();
+        assert_eq!(active, b);
+        // b makes progress, so a was lagging.
+        stalled.progressed(&book, &b);
+        assert_eq!(book.score(&a), 10 - STALL_PENALTY);
+        assert_eq!(book.score(&b), 0);
+        // Only penalized once per stall.
+        stalled.progressed(&book, &b);
+        assert_eq!(book.score(&a), 10 - STALL_PENALTY);
+    }
+}
@@ -257,7 +257,7 @@ impl Challenger {
         )
         .await?;
 
-        let tx = self.wallet.sign_tx_skeleton(tx_skeleton)?;
+        let tx = self.wallet.sign_tx_skeleton(tx_skeleton).await?;
 
         if let Err(err) = self.dry_run_transaction(&tx, "challenge block").await {
             utils::dump_transaction(&self.debug_config.debug_tx_dump_path, &self.rpc_client, &tx)
@@ -512,7 +512,7 @@ impl Challenger {
         )
         .await?;
 
-        let tx = self.wallet.sign_tx_skeleton(tx_skeleton)?;
+        let tx = self.wallet.sign_tx_skeleton(tx_skeleton).await?;
 
         if let Err(err) = self.dry_run_transaction(&tx, "revert block").await {
             utils::dump_transaction(&self.debug_config.debug_tx_dump_path, &self.rpc_client, &tx)
@@ -555,7 +555,7 @@ impl Challenger {
         )
         .await?;
 
-        self.wallet.sign_tx_skeleton(tx_skeleton)
+        self.wallet.sign_tx_skeleton(tx_skeleton).await
     }
 
     async fn build_cancel_tx(
@@ -663,7 +663,7 @@ impl Challenger {
             self.config.fee_rate,
         )
         .await?;
-        self.wallet.sign_tx_skeleton(tx_skeleton)
+        self.wallet.sign_tx_skeleton(tx_skeleton).await
     }
 
     async fn query_owner_cell_for_verifier(
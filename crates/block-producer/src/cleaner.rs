@@ -243,7 +243,7 @@ impl Cleaner {
             self.fee_rate,
         )
         .await?;
-        self.wallet.sign_tx_skeleton(tx_skeleton)
+        self.wallet.sign_tx_skeleton(tx_skeleton).await
     }
 }
 
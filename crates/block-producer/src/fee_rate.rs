@@ -0,0 +1,167 @@
+//! Fee rates of block submit transactions.
+
+use std::{collections::HashMap, time::Duration};
+
+use gw_common::H256;
+use gw_config::SubmitFeeRateConfig;
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_types::{packed::Transaction, prelude::*};
+
+pub struct FeeRateEstimator {
+    config: SubmitFeeRateConfig,
+    /// `block_producer.fee_rate`, used if estimation is disabled or fails.
+    fallback: u64,
+}
+
+impl FeeRateEstimator {
+    pub fn new(config: SubmitFeeRateConfig, fallback: u64) -> Self {
+        Self { config, fallback }
+    }
+
+    /// Fee rate of a new submit transaction, shannons per KB.
+    pub async fn estimate(&self, rpc_client: &RPCClient) -> u64 {
+        if !self.config.estimate {
+            return self.fallback();
+        }
+        let stats = rpc_client
+            .ckb
+            .get_fee_rate_statistics(Some(self.config.estimate_blocks))
+            .await;
+        match stats {
+            Ok(Some(stats)) => self.clamp(stats.median.value()),
+            // No transactions in recent blocks.
+            Ok(None) => self.fallback(),
+            Err(err) => {
+                log::warn!("failed to estimate fee rate: {:#}", err);
+                self.fallback()
+            }
+        }
+    }
+
+    pub fn fallback(&self) -> u64 {
+        self.clamp(self.fallback)
+    }
+
+    /// Fee rate to replace a transaction of `fee_rate`. Returns `None` if it
+    /// can't be increased any more.
+    pub fn bump(&self, fee_rate: u64) -> Option<u64> {
+        let bumped = fee_rate.saturating_mul(100 + self.config.bump_percent) / 100;
+        let bumped = self.clamp(bumped);
+        if bumped > fee_rate {
+            Some(bumped)
+        } else {
+            None
+        }
+    }
+
+    /// How long a submit transaction can be pending before it is replaced.
+    pub fn bump_after(&self) -> Option<Duration> {
+        match self.config.bump_after_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    fn clamp(&self, fee_rate: u64) -> u64 {
+        fee_rate
+            .max(self.config.min_fee_rate)
+            .min(self.config.max_fee_rate)
+    }
+}
+
+#[derive(Default)]
+struct BlockSubmitHistory {
+    fee_rate: Option<u64>,
+    /// Submit transactions of this block that have been replaced. One of them
+    /// may still get committed.
+    replaced: Vec<Transaction>,
+}
+
+/// Fee rates and replaced submit transactions of not confirmed blocks.
+#[derive(Default)]
+pub struct SubmitHistory {
+    blocks: HashMap<u64, BlockSubmitHistory>,
+}
+
+impl SubmitHistory {
+    pub fn fee_rate(&self, block_number: u64) -> Option<u64> {
+        self.blocks.get(&block_number).and_then(|b| b.fee_rate)
+    }
+
+    pub fn set_fee_rate(&mut self, block_number: u64, fee_rate: u64) {
+        self.blocks.entry(block_number).or_default().fee_rate = Some(fee_rate);
+    }
+
+    pub fn add_replaced(&mut self, block_number: u64, tx: Transaction) {
+        self.blocks
+            .entry(block_number)
+            .or_default()
+            .replaced
+            .push(tx);
+    }
+
+    pub fn find_replaced(&self, block_number: u64, tx_hash: &H256) -> Option<Transaction> {
+        let block = self.blocks.get(&block_number)?;
+        block
+            .replaced
+            .iter()
+            .find(|tx| H256::from(tx.hash()) == *tx_hash)
+            .cloned()
+    }
+
+    /// Remove blocks up to and including `block_number`.
+    pub fn confirm(&mut self, block_number: u64) {
+        self.blocks.retain(|b, _| *b > block_number);
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback() {
+        let config = SubmitFeeRateConfig {
+            min_fee_rate: 1000,
+            max_fee_rate: 5000,
+            ..Default::default()
+        };
+        let fallback = |f| FeeRateEstimator::new(config.clone(), f).fallback();
+        assert_eq!(fallback(2000), 2000);
+        assert_eq!(fallback(500), 1000);
+        assert_eq!(fallback(8000), 5000);
+    }
+
+    #[test]
+    fn test_bump() {
+        let config = SubmitFeeRateConfig {
+            min_fee_rate: 1000,
+            max_fee_rate: 4000,
+            bump_percent: 50,
+            ..Default::default()
+        };
+        let estimator = FeeRateEstimator::new(config, 1000);
+        assert_eq!(estimator.bump(1000), Some(1500));
+        assert_eq!(estimator.bump(2000), Some(3000));
+        assert_eq!(estimator.bump(3000), Some(4000));
+        assert_eq!(estimator.bump(4000), None);
+        assert!(estimator.bump_after().is_none());
+    }
+
+    #[test]
+    fn test_submit_history() {
+        let tx = Transaction::default();
+        let mut history = SubmitHistory::default();
+        history.set_fee_rate(2, 1500);
+        history.add_replaced(2, tx.clone());
+        assert_eq!(history.fee_rate(2), Some(1500));
+        assert!(history.find_replaced(2, &tx.hash().into()).is_some());
+        assert!(history.find_replaced(3, &tx.hash().into()).is_none());
+        history.confirm(2);
+        assert_eq!(history.fee_rate(2), None);
+    }
+}
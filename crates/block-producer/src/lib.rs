@@ -6,11 +6,13 @@ pub mod cleaner;
 pub mod custodian;
 pub mod debugger;
 pub mod deposit;
+pub mod fee_rate;
 pub mod produce_block;
-pub(crate) mod psc;
+pub mod psc;
 pub mod replay_block;
 pub mod runner;
 pub mod stake;
+pub mod state_pruner;
 pub mod sync_l1;
 pub mod test_mode_control;
 pub mod trace;
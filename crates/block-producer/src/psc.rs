@@ -36,6 +36,7 @@ use tracing_opentelemetry::OpenTelemetrySpanExt;
 use crate::{
     block_producer::{check_block_size, BlockProducer, ComposeSubmitTxArgs, TransactionSizeError},
     chain_updater::ChainUpdater,
+    fee_rate::{FeeRateEstimator, SubmitHistory},
     produce_block::ProduceBlockResult,
     sync_l1::{revert, sync_l1, SyncL1Context},
 };
@@ -59,6 +60,8 @@ pub struct PSCContext {
     pub chain_updater: ChainUpdater,
     pub rollup_type_script: Script,
     pub psc_config: PscConfig,
+    pub fee_rate_estimator: FeeRateEstimator,
+    pub submit_history: std::sync::Mutex<SubmitHistory>,
     pub block_sync_server_state: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
     pub liveness: Arc<Liveness>,
 }
@@ -152,59 +155,14 @@ impl ProduceSubmitConfirm {
                         let store_tx = self.context.store.begin_transaction();
                         revert(&*self.context, &store_tx, revert_to).await?;
                         store_tx.commit()?;
+                        self.context.submit_history.lock().unwrap().clear();
                     }
-                    if e.is::<ShouldResyncError>() || e.is::<ShouldRevertError>() {
+                    if let Some(bump) = e.downcast_ref::<ShouldBumpFeeError>() {
+                        self.bump_fee(bump)?;
+                        self.reset_local_state().await?;
+                    } else if e.is::<ShouldResyncError>() || e.is::<ShouldRevertError>() {
                         sync_l1(&*self.context).await?;
-
-                        // Reset local_count, submitted_count and local_cells_manager.
-                        let snap = self.context.store.get_snapshot();
-                        let last_valid = snap.get_last_valid_tip_block()?.raw().number().unpack();
-                        let last_submitted_nh = snap
-                            .get_last_submitted_block_number_hash()
-                            .expect("get last submitted");
-                        let last_submitted = last_submitted_nh.number().unpack();
-                        let last_confirmed = snap
-                            .get_last_confirmed_block_number_hash()
-                            .expect("get last confirmed")
-                            .number()
-                            .unpack();
-                        ensure!(last_submitted == last_confirmed);
-                        log::info!(
-                            "last valid: {}, last_submitted: {}, last_confirmed: {}",
-                            last_valid,
-                            last_submitted,
-                            last_confirmed
-                        );
-                        if let Some(ref sync_server) = self.context.block_sync_server_state {
-                            let mut sync_server = sync_server.lock().unwrap();
-                            sync_server.publish_revert(
-                                Revert::new_builder().number_hash(last_submitted_nh).build(),
-                            );
-                            for b in last_confirmed + 1..=last_valid {
-                                publish_local_block(&mut sync_server, &snap, b)?;
-                            }
-                        }
-
-                        {
-                            let mut local_cells_manager =
-                                self.context.local_cells_manager.lock().await;
-                            local_cells_manager.reset();
-                            for b in last_confirmed + 1..=last_valid {
-                                let deposits =
-                                    snap.get_block_deposit_info_vec(b).expect("deposit info");
-                                let deposits = deposits.into_iter().map(|d| d.cell());
-                                for c in deposits {
-                                    local_cells_manager.lock_cell(c.out_point());
-                                }
-                            }
-                            let new_tip = snap.get_last_valid_tip_block_hash()?;
-                            let mut mem_pool = self.context.mem_pool.lock().await;
-                            mem_pool
-                                .notify_new_tip(new_tip, &local_cells_manager)
-                                .await?;
-                        }
-                        self.local_count = last_valid - last_submitted;
-                        self.submitted_count = last_submitted - last_confirmed;
+                        self.reset_local_state().await?;
                     } else {
                         bail!(e);
                     }
@@ -212,6 +170,94 @@ impl ProduceSubmitConfirm {
             }
         }
     }
+
+    /// Reset local_count, submitted_count and local_cells_manager after
+    /// last_submitted is reset to last_confirmed.
+    async fn reset_local_state(&mut self) -> Result<()> {
+        let snap = self.context.store.get_snapshot();
+        let last_valid = snap.get_last_valid_tip_block()?.raw().number().unpack();
+        let last_submitted_nh = snap
+            .get_last_submitted_block_number_hash()
+            .expect("get last submitted");
+        let last_submitted = last_submitted_nh.number().unpack();
+        let last_confirmed = snap
+            .get_last_confirmed_block_number_hash()
+            .expect("get last confirmed")
+            .number()
+            .unpack();
+        ensure!(last_submitted == last_confirmed);
+        log::info!(
+            "last valid: {}, last_submitted: {}, last_confirmed: {}",
+            last_valid,
+            last_submitted,
+            last_confirmed
+        );
+        if let Some(ref sync_server) = self.context.block_sync_server_state {
+            let mut sync_server = sync_server.lock().unwrap();
+            sync_server
+                .publish_revert(Revert::new_builder().number_hash(last_submitted_nh).build());
+            for b in last_confirmed + 1..=last_valid {
+                publish_local_block(&mut sync_server, &snap, b)?;
+            }
+        }
+
+        {
+            let mut local_cells_manager = self.context.local_cells_manager.lock().await;
+            local_cells_manager.reset();
+            for b in last_confirmed + 1..=last_valid {
+                let deposits = snap.get_block_deposit_info_vec(b).expect("deposit info");
+                let deposits = deposits.into_iter().map(|d| d.cell());
+                for c in deposits {
+                    local_cells_manager.lock_cell(c.out_point());
+                }
+            }
+            let new_tip = snap.get_last_valid_tip_block_hash()?;
+            let mut mem_pool = self.context.mem_pool.lock().await;
+            mem_pool
+                .notify_new_tip(new_tip, &local_cells_manager)
+                .await?;
+        }
+        self.local_count = last_valid - last_submitted;
+        self.submitted_count = last_submitted - last_confirmed;
+        Ok(())
+    }
+
+    /// Delete submit transactions of not confirmed blocks, so that they are
+    /// composed again, and the first one replaces the pending transaction with
+    /// a higher fee rate.
+    fn bump_fee(&self, bump: &ShouldBumpFeeError) -> Result<()> {
+        let snap = self.context.store.get_snapshot();
+        let last_submitted: u64 = snap
+            .get_last_submitted_block_number_hash()
+            .expect("get last submitted")
+            .number()
+            .unpack();
+        let last_confirmed_nh = snap
+            .get_last_confirmed_block_number_hash()
+            .expect("get last confirmed");
+        let last_confirmed: u64 = last_confirmed_nh.number().unpack();
+        ensure!(bump.block_number == last_confirmed + 1);
+        log::info!(
+            "replace submit transaction of block {} with fee rate {}",
+            bump.block_number,
+            bump.fee_rate
+        );
+
+        let mut history = self.context.submit_history.lock().unwrap();
+        let store_tx = self.context.store.begin_transaction();
+        // The submit transaction of the first block may be composed but not
+        // submitted.
+        for b in bump.block_number..=last_submitted.max(bump.block_number) {
+            if let Some(tx) = snap.get_block_submit_tx(b) {
+                history.add_replaced(b, tx);
+                store_tx.delete_submit_tx(b)?;
+            }
+        }
+        store_tx.set_last_submitted_block_number_hash(&last_confirmed_nh.as_reader())?;
+        store_tx.commit()?;
+        history.set_fee_rate(bump.block_number, bump.fee_rate);
+        Ok(())
+    }
 }
 
 async fn run(mut state: &mut ProduceSubmitConfirm) -> Result<()> {
@@ -227,6 +273,10 @@ async fn run(mut state: &mut ProduceSubmitConfirm) -> Result<()> {
     let mut revert_submitted_signal = signal(SignalKind::user_defined2())?;
 
     loop {
+        let metrics = gw_metrics::block_producer();
+        metrics.local_count.set(state.local_count as i64);
+        metrics.submitted_count.set(state.submitted_count as i64);
+
         if !submitting && state.local_count > 0 && state.submitted_count < config.submitted_limit {
             submitting = true;
             let context = state.context.clone();
@@ -235,11 +285,15 @@ async fn run(mut state: &mut ProduceSubmitConfirm) -> Result<()> {
             // anymore, and we should sync with L1 again.
             let is_first = state.submitted_count == 0;
             submit_handle.replace_with(tokio::spawn(async move {
+                let t = Instant::now();
                 loop {
                     match submit_next_block(&context, is_first).await {
-                        Ok(nh) => return Ok(nh),
+                        Ok(nh) => {
+                            metrics.submit_latency.observe(t.elapsed().as_secs_f64());
+                            return Ok(nh);
+                        }
                         Err(err) => {
-                            if err.is::<ShouldResyncError>() || err.is::<ShouldRevertError>() {
+                            if should_reset(&err) {
                                 bail!(err);
                             }
                             log::warn!("failed to submit next block: {:#}", err);
@@ -254,11 +308,15 @@ async fn run(mut state: &mut ProduceSubmitConfirm) -> Result<()> {
             confirming = true;
             let context = state.context.clone();
             confirm_handle.replace_with(tokio::spawn(async move {
+                let t = Instant::now();
                 loop {
                     match confirm_next_block(&context).await {
-                        Ok(nh) => break Ok(nh),
+                        Ok(nh) => {
+                            metrics.confirm_latency.observe(t.elapsed().as_secs_f64());
+                            break Ok(nh);
+                        }
                         Err(err) => {
-                            if err.is::<ShouldResyncError>() || err.is::<ShouldRevertError>() {
+                            if should_reset(&err) {
                                 bail!(err);
                             }
                             log::warn!("failed to confirm next block: {:#}", err);
@@ -306,6 +364,7 @@ async fn run(mut state: &mut ProduceSubmitConfirm) -> Result<()> {
                         let store_tx = state.context.store.begin_transaction();
                         store_tx.set_last_confirmed_block_number_hash(&nh.as_reader())?;
                         store_tx.commit()?;
+                        state.context.submit_history.lock().unwrap().confirm(nh.number().unpack());
                         if let Some(ref sync_server) = state.context.block_sync_server_state {
                             let mut sync_server = sync_server.lock().unwrap();
                             publish_confirmed(&mut sync_server, &state.context.store.get_snapshot(), nh.number().unpack())?;
@@ -339,9 +398,11 @@ async fn run(mut state: &mut ProduceSubmitConfirm) -> Result<()> {
             // there are not too many local blocks.
             _ = interval.tick(), if state.local_count < config.local_limit => {
                 log::info!("producing next block");
+                let t = Instant::now();
                 if let Err(e) = produce_local_block(&state.context).await {
                     log::warn!("failed to produce local block: {:#}", e);
                 } else {
+                    metrics.produce_latency.observe(t.elapsed().as_secs_f64());
                     state.local_count += 1;
                 }
             }
@@ -514,6 +575,19 @@ async fn submit_block(
             .context("get block global_state")?;
         drop(snap);
 
+        let fee_rate = ctx.submit_history.lock().unwrap().fee_rate(block_number);
+        let fee_rate = match fee_rate {
+            Some(fee_rate) => fee_rate,
+            None => {
+                let fee_rate = ctx.fee_rate_estimator.estimate(&ctx.rpc_client).await;
+                ctx.submit_history
+                    .lock()
+                    .unwrap()
+                    .set_fee_rate(block_number, fee_rate);
+                fee_rate
+            }
+        };
+
         let local_cells_manager = ctx.local_cells_manager.lock().await;
 
         let args = ComposeSubmitTxArgs {
@@ -523,6 +597,7 @@ async fn submit_block(
             since,
             withdrawal_extras,
             local_cells_manager: &*local_cells_manager,
+            fee_rate,
         };
         let tx = ctx
             .block_producer
@@ -540,7 +615,7 @@ async fn submit_block(
         store_tx.set_block_submit_tx(block_number, &tx.as_reader())?;
         store_tx.commit()?;
 
-        log::info!("generated submission transaction");
+        log::info!("generated submission transaction, fee rate {}", fee_rate);
 
         tx
     };
@@ -590,7 +665,21 @@ async fn submit_block(
             }
             bail!(e);
         } else if e.is::<DeadCellError>() {
+            restore_replaced_submit_tx(ctx, block_number, &e)?;
             bail!(e.context(ShouldRevertError(block_number)));
+        } else if is_first
+            && get_jsonrpc_error_code(&e) == Some(CkbRpcError::PoolRejectedRBF as i64)
+        {
+            // The fee rate is not high enough to replace the pending transaction.
+            let fee_rate = ctx.submit_history.lock().unwrap().fee_rate(block_number);
+            let fee_rate = fee_rate.unwrap_or_else(|| ctx.fee_rate_estimator.fallback());
+            if let Some(fee_rate) = ctx.fee_rate_estimator.bump(fee_rate) {
+                bail!(e.context(ShouldBumpFeeError {
+                    block_number,
+                    fee_rate
+                }));
+            }
+            bail!(e);
         } else {
             bail!(e);
         }
@@ -602,8 +691,15 @@ async fn submit_block(
         .build())
 }
 
-async fn poll_tx_confirmed(rpc_client: &RPCClient, tx: &Transaction) -> Result<()> {
+/// Returns `TxPendingTooLongError` if the transaction has been pending for
+/// `bump_after`.
+async fn poll_tx_confirmed(
+    rpc_client: &RPCClient,
+    tx: &Transaction,
+    bump_after: Option<Duration>,
+) -> Result<()> {
     log::info!("waiting for tx 0x{}", hex::encode(tx.hash()));
+    let started = Instant::now();
     let mut last_sent = Instant::now();
     loop {
         let status = rpc_client
@@ -613,6 +709,9 @@ async fn poll_tx_confirmed(rpc_client: &RPCClient, tx: &Transaction) -> Result<(
         let should_resend = match status {
             Some(TxStatus::Committed) => break,
             Some(TxStatus::Rejected) => true,
+            Some(TxStatus::Pending) if bump_after.map_or(false, |d| started.elapsed() > d) => {
+                bail!(TxPendingTooLongError);
+            }
             // Resend the transaction if it has been unknown, pending, or
             // proposed for some time. Or the transaction could be stuck in the
             // current state.
@@ -672,17 +771,29 @@ async fn confirm_block(
         .get_block_submit_tx(block_number)
         .expect("get submit tx");
     drop(snap);
-    poll_tx_confirmed(&context.rpc_client, &tx)
-        .await
-        .map_err(|e| {
-            if e.is::<UnknownCellError>() {
-                e.context(ShouldResyncError)
-            } else if e.is::<DeadCellError>() {
-                e.context(ShouldRevertError(block_number))
-            } else {
-                e
-            }
-        })?;
+    let fee_rate = context
+        .submit_history
+        .lock()
+        .unwrap()
+        .fee_rate(block_number)
+        .unwrap_or_else(|| context.fee_rate_estimator.fallback());
+    let bumped_fee_rate = context.fee_rate_estimator.bump(fee_rate);
+    let bump_after = bumped_fee_rate.and(context.fee_rate_estimator.bump_after());
+    if let Err(e) = poll_tx_confirmed(&context.rpc_client, &tx, bump_after).await {
+        if e.is::<UnknownCellError>() {
+            bail!(e.context(ShouldResyncError));
+        } else if e.is::<DeadCellError>() {
+            restore_replaced_submit_tx(context, block_number, &e)?;
+            bail!(e.context(ShouldRevertError(block_number)));
+        } else if let Some(fee_rate) = bumped_fee_rate.filter(|_| e.is::<TxPendingTooLongError>()) {
+            bail!(e.context(ShouldBumpFeeError {
+                block_number,
+                fee_rate
+            }));
+        } else {
+            bail!(e);
+        }
+    }
     log::info!("block confirmed");
     context.local_cells_manager.lock().await.confirm_tx(&tx);
     Ok(NumberHash::new_builder()
@@ -821,6 +932,66 @@ impl Display for DeadCellError {
 #[error("previous transaction not confirmed")]
 struct UnknownCellError;
 
+#[derive(thiserror::Error, Debug)]
+#[error("transaction pending for too long")]
+struct TxPendingTooLongError;
+
+#[derive(thiserror::Error, Debug)]
+#[error("should replace submit transaction of block {block_number} with fee rate {fee_rate}")]
+struct ShouldBumpFeeError {
+    block_number: u64,
+    fee_rate: u64,
+}
+
+/// Errors that the submitting and confirming tasks can't handle by retrying.
+fn should_reset(err: &anyhow::Error) -> bool {
+    err.is::<ShouldResyncError>() || err.is::<ShouldRevertError>() || err.is::<ShouldBumpFeeError>()
+}
+
+/// If a dead input of the submit transaction of `block_number` is consumed by
+/// a replaced submit transaction of the same block, that transaction is
+/// committed instead. Restore it and resync.
+fn restore_replaced_submit_tx(
+    ctx: &PSCContext,
+    block_number: u64,
+    err: &anyhow::Error,
+) -> Result<()> {
+    let consumed_by_tx = match err.downcast_ref::<DeadCellError>() {
+        Some(DeadCellError {
+            consumed_by_tx: Some(tx_hash),
+        }) => tx_hash,
+        _ => return Ok(()),
+    };
+    let replaced = ctx
+        .submit_history
+        .lock()
+        .unwrap()
+        .find_replaced(block_number, consumed_by_tx);
+    let replaced = match replaced {
+        Some(tx) => tx,
+        None => return Ok(()),
+    };
+    log::info!(
+        "replaced submit transaction 0x{} of block {} is committed",
+        hex::encode(replaced.hash()),
+        block_number
+    );
+    let last_valid: u64 = ctx
+        .store
+        .get_last_valid_tip_block()?
+        .raw()
+        .number()
+        .unpack();
+    let store_tx = ctx.store.begin_transaction();
+    store_tx.set_block_submit_tx(block_number, &replaced.as_reader())?;
+    // Submit transactions of later blocks depend on the replacing one.
+    for b in block_number + 1..=last_valid {
+        store_tx.delete_submit_tx(b)?;
+    }
+    store_tx.commit()?;
+    bail!(ShouldResyncError);
+}
+
 async fn check_tx_input(rpc_client: &RPCClient, tx: &Transaction) -> Result<()> {
     // Check inputs.
     for input in tx.raw().inputs() {
@@ -841,7 +1012,7 @@ async fn check_tx_input(rpc_client: &RPCClient, tx: &Transaction) -> Result<()>
     Ok(())
 }
 
-fn publish_local_block(
+pub(crate) fn publish_local_block(
     sync_server: &mut BlockSyncServerState,
     snap: &StoreSnapshot,
     b: u64,
@@ -1,10 +1,12 @@
 use crate::{
     block_producer::{BlockProducer, BlockProducerCreateArgs},
-    block_sync_client::{block_sync_client_protocol, BlockSyncClient, P2PStream},
+    block_sync_client::{block_sync_client_protocol, BlockSyncClient, P2PStreamInbox},
     chain_updater::ChainUpdater,
     challenger::{Challenger, ChallengerNewArgs},
     cleaner::Cleaner,
+    fee_rate::{FeeRateEstimator, SubmitHistory},
     psc::{PSCContext, ProduceSubmitConfirm},
+    state_pruner::StatePruner,
     test_mode_control::TestModeControl,
     types::ChainEvent,
     withdrawal_unlocker::FinalizedWithdrawalUnlocker,
@@ -16,11 +18,11 @@ use gw_chain::chain::Chain;
 use gw_challenge::offchain::{OffChainMockContext, OffChainMockContextBuildArgs};
 use gw_ckb_hardfork::{GLOBAL_CURRENT_EPOCH_NUMBER, GLOBAL_HARDFORK_SWITCH, GLOBAL_VM_VERSION};
 use gw_common::{blake2b::new_blake2b, registry_address::RegistryAddress, H256};
-use gw_config::{BlockProducerConfig, Config, NodeMode};
+use gw_config::{BlockProducerConfig, Config, NodeMode, PruningConfig, SignerConfig};
 use gw_db::migrate::open_or_create_db;
 use gw_dynamic_config::manager::DynamicConfigManager;
 use gw_generator::{
-    account_lock_manage::{secp256k1::Secp256k1Eth, AccountLockManage},
+    account_lock_manage::{secp256k1::Secp256k1Eth, tron::Secp256k1Tron, AccountLockManage},
     backend_manage::BackendManage,
     genesis::init_genesis,
     ArcSwap, Generator,
@@ -30,7 +32,7 @@ use gw_mem_pool::{
     default_provider::DefaultMemPoolProvider,
     pool::{MemPool, MemPoolCreateArgs},
 };
-use gw_p2p_network::P2PNetwork;
+use gw_p2p_network::{P2PNetwork, PeerBook};
 use gw_polyjuice_sender_recover::recover::PolyjuiceSenderRecover;
 use gw_rpc_client::{
     ckb_client::CKBClient, contract::ContractsCellDepManager, error::RPCRequestError,
@@ -39,6 +41,7 @@ use gw_rpc_client::{
 use gw_rpc_server::{
     registry::{Registry, RegistryArgs},
     server::start_jsonrpc_server,
+    ws_server::WsServerArgs,
 };
 use gw_store::Store;
 use gw_types::{
@@ -347,6 +350,7 @@ impl BaseInitComponents {
         } else {
             Store::new(open_or_create_db(&config.store)?)
         };
+        let store = store.with_account_tx_index(config.store.enable_account_tx_index);
         let elapsed_ms = timer.elapsed().as_millis();
         log::debug!("Open rocksdb costs: {}ms.", elapsed_ms);
 
@@ -373,14 +377,15 @@ impl BaseInitComponents {
         init_genesis(&store, &config.genesis, &genesis_tx_hash, secp_data.clone())
             .with_context(|| "init genesis")?;
 
-        let dynamic_config_manager = Arc::new(ArcSwap::from_pointee(DynamicConfigManager::create(
-            config.clone(),
-        )));
+        let dynamic_config_manager = Arc::new(ArcSwap::from_pointee(
+            DynamicConfigManager::create(config.clone()).with_context(|| "reload config")?,
+        ));
 
         //Reload config
         if let Some(res) = gw_dynamic_config::try_reload(dynamic_config_manager.clone()).await {
             log::info!("Reload dynamic config: {:?}", res);
         }
+        tokio::spawn(gw_dynamic_config::watch(dynamic_config_manager.clone()));
         let rollup_config_hash: H256 = rollup_config.hash().into();
         let generator = {
             let backend_manage = BackendManage::from_config(config.backend_switches.clone())
@@ -395,6 +400,16 @@ impl BaseInitComponents {
                 eth_lock_script_type_hash.hash().unpack(),
                 Box::new(Secp256k1Eth::default()),
             );
+            // Tron EOA is optional
+            if let Some(tron_lock_script_type_hash) = allowed_eoa_type_hashes
+                .iter()
+                .find(|th| th.type_().to_entity() == AllowedEoaType::Tron.into())
+            {
+                account_lock_manage.register_lock_algorithm(
+                    tron_lock_script_type_hash.hash().unpack(),
+                    Box::new(Secp256k1Tron::default()),
+                );
+            }
             Arc::new(Generator::new(
                 backend_manage,
                 account_lock_manage,
@@ -449,6 +464,13 @@ impl BaseInitComponents {
             Some(ref c) => Wallet::from_config(c).with_context(|| "offchain init wallet")?,
             None => return Ok(None),
         };
+        // Mock transactions are signed synchronously and never sent, use a
+        // throwaway key if the wallet key is held by a remote signer.
+        let wallet = match wallet.signer().local_key() {
+            Some(_) => wallet,
+            None => Wallet::random_like(wallet.lock_script())
+                .with_context(|| "offchain init mock wallet")?,
+        };
         let ckb_genesis_info = gw_challenge::offchain::CKBGenesisInfo {
             sighash_dep: self.ckb_genesis_info.sighash_dep(),
         };
@@ -492,9 +514,13 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     }
     let base = BaseInitComponents::init(&config, skip_config_check).await?;
 
-    let has_block_producer_and_p2p =
-        config.block_producer.is_some() && config.p2p_network_config.is_some();
-    let block_sync_server_state = if has_block_producer_and_p2p {
+    // Block sync messages are published for p2p block sync and WebSocket
+    // subscriptions. Read-only nodes re-publish messages they synced.
+    let has_block_producer_and_p2p = config.block_producer.is_some()
+        && config.node_mode != NodeMode::Watchtower
+        && config.p2p_network_config.is_some();
+    let publish_block_sync = has_block_producer_and_p2p || config.rpc_server.ws_listen.is_some();
+    let block_sync_server_state = if publish_block_sync {
         Some(Arc::new(std::sync::Mutex::new(BlockSyncServerState::new(
             &config.sync_server,
         ))))
@@ -512,37 +538,43 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             let opt_offchain_mock_context = base
                 .init_offchain_mock_context(block_producer_config)
                 .await?;
-            let mem_pool_provider = DefaultMemPoolProvider::new(
-                base.rpc_client.clone(),
-                base.store.clone(),
-                config.mem_pool.mem_block.clone(),
-            );
-            let mem_pool = {
-                let block_producer = RegistryAddress::new(
-                    block_producer_config.block_producer.registry_id,
-                    block_producer_config
-                        .block_producer
-                        .address
-                        .as_bytes()
-                        .to_vec(),
+            // Watchtowers don't produce blocks or accept transactions.
+            let opt_mem_pool = if !config.node_mode.has_mem_pool() {
+                None
+            } else {
+                let mem_pool_provider = DefaultMemPoolProvider::new(
+                    base.rpc_client.clone(),
+                    base.store.clone(),
+                    config.mem_pool.mem_block.clone(),
                 );
-                let args = MemPoolCreateArgs {
-                    block_producer,
-                    store: base.store.clone(),
-                    generator: base.generator.clone(),
-                    provider: Box::new(mem_pool_provider),
-                    config: config.mem_pool.clone(),
-                    node_mode: config.node_mode,
-                    dynamic_config_manager: base.dynamic_config_manager.clone(),
-                    sync_server: block_sync_server_state.clone(),
+                let mem_pool = {
+                    let block_producer = RegistryAddress::new(
+                        block_producer_config.block_producer.registry_id,
+                        block_producer_config
+                            .block_producer
+                            .address
+                            .as_bytes()
+                            .to_vec(),
+                    );
+                    let args = MemPoolCreateArgs {
+                        block_producer,
+                        store: base.store.clone(),
+                        generator: base.generator.clone(),
+                        provider: Box::new(mem_pool_provider),
+                        config: config.mem_pool.clone(),
+                        node_mode: config.node_mode,
+                        dynamic_config_manager: base.dynamic_config_manager.clone(),
+                        sync_server: block_sync_server_state.clone(),
+                    };
+                    Arc::new(Mutex::new(
+                        MemPool::create(args)
+                            .await
+                            .with_context(|| "create mem-pool")?,
+                    ))
                 };
-                Arc::new(Mutex::new(
-                    MemPool::create(args)
-                        .await
-                        .with_context(|| "create mem-pool")?,
-                ))
+                Some(mem_pool)
             };
-            (Some(mem_pool), opt_wallet, opt_offchain_mock_context)
+            (opt_mem_pool, opt_wallet, opt_offchain_mock_context)
         }
         None => (None, None, None),
     };
@@ -572,6 +604,9 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         store.check_state()?;
         log::info!("Check state db done: {}ms", t.elapsed().as_millis());
     }
+    if let PruningConfig::KeepRecent(keep_recent) = config.store.pruning {
+        StatePruner::new(store.clone(), rollup_context.clone(), keep_recent).spawn();
+    }
     let chain = Arc::new(Mutex::new(
         Chain::create(
             &rollup_config,
@@ -595,7 +630,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     let (block_producer, challenger, test_mode_control, withdrawal_unlocker, cleaner) = match config
         .node_mode
     {
-        NodeMode::ReadOnly => (None, None, None, None, None),
+        mode if !mode.challenges() => (None, None, None, None, None),
         mode => {
             let block_producer_config = config
                 .block_producer
@@ -610,6 +645,8 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 let msg = "offchain mock require block producer config and wallet in mode: ";
                 ctx.ok_or_else(|| anyhow!("{} {:?}", msg, mode))?
             };
+            // Watchtowers only challenge and clean up verifier cells.
+            let produces_blocks = mode.produces_blocks();
             let tests_control = if let NodeMode::Test = config.node_mode {
                 Some(TestModeControl::new(rpc_client.clone(), store.clone()))
             } else {
@@ -630,14 +667,16 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 }
             };
 
-            let withdrawal_unlocker = FinalizedWithdrawalUnlocker::new(
-                rpc_client.clone(),
-                ckb_genesis_info.clone(),
-                contracts_dep_manager.clone(),
-                unlocker_wallet,
-                config.debug.clone(),
-                block_producer_config.fee_rate,
-            );
+            let withdrawal_unlocker = produces_blocks.then(|| {
+                FinalizedWithdrawalUnlocker::new(
+                    rpc_client.clone(),
+                    ckb_genesis_info.clone(),
+                    contracts_dep_manager.clone(),
+                    unlocker_wallet,
+                    config.debug.clone(),
+                    block_producer_config.fee_rate,
+                )
+            });
 
             let cleaner = Arc::new(Cleaner::new(
                 rpc_client.clone(),
@@ -680,14 +719,18 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 tests_control: tests_control.clone(),
                 contracts_dep_manager,
             };
-            let block_producer =
-                BlockProducer::create(create_args).with_context(|| "init block producer")?;
+            let block_producer = if produces_blocks {
+                Some(BlockProducer::create(create_args).with_context(|| "init block producer")?)
+            } else {
+                log::info!("watchtower: block producing and withdrawal unlocking are disabled");
+                None
+            };
 
             (
-                Some(block_producer),
+                block_producer,
                 Some(challenger),
                 tests_control,
-                Some(withdrawal_unlocker),
+                withdrawal_unlocker,
                 Some(cleaner),
             )
         }
@@ -698,8 +741,13 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     // Broadcast shutdown event.
     let (shutdown_event, shutdown_event_recv) = broadcast::channel(1);
 
-    let block_sync_client_p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>> =
-        Arc::new(std::sync::Mutex::new(None));
+    let block_sync_client_p2p_stream_inbox: P2PStreamInbox = Default::default();
+    let peer_score_config = config
+        .p2p_network_config
+        .as_ref()
+        .map(|c| c.peer_score.clone())
+        .unwrap_or_default();
+    let peer_book = PeerBook::new(&peer_score_config);
 
     // P2P network.
     let p2p_control_and_handle = if let Some(ref p2p_network_config) = config.p2p_network_config {
@@ -709,22 +757,28 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 log::info!("will enable p2p block sync client");
                 protocols.push(block_sync_client_protocol(
                     block_sync_client_p2p_stream_inbox.clone(),
+                    peer_book.clone(),
                 ));
             }
             NodeMode::FullNode | NodeMode::Test => {
                 if let Some(ref state) = block_sync_server_state {
                     log::info!("will enable p2p block sync server");
-                    protocols.push(block_sync_server_protocol(state.clone()));
+                    protocols.push(block_sync_server_protocol(state.clone(), peer_book.clone()));
                 }
             }
+            NodeMode::Watchtower => {
+                log::info!("watchtower syncs blocks from L1 only, p2p block sync is disabled");
+            }
         }
-        let mut network = P2PNetwork::init(p2p_network_config, protocols).await?;
+        let mut network =
+            P2PNetwork::init(p2p_network_config, protocols, peer_book.clone()).await?;
         let control = network.control().clone();
+        let admin = network.admin().clone();
         let handle = tokio::spawn(async move {
             log::info!("running the p2p network");
             network.run().await;
         });
-        Some((control, handle))
+        Some((control, admin, handle))
     } else {
         None
     };
@@ -732,6 +786,11 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     // RPC registry
     let polyjuice_sender_recover = {
         let opt_wallet = match config.block_producer.as_ref().map(|c| &c.wallet_config) {
+            Some(Some(c)) if matches!(c.signer, Some(SignerConfig::Remote { .. })) => {
+                log::info!("[tx from zero] block producer wallet key is not held locally");
+
+                None
+            }
             Some(Some(c)) => {
                 log::info!("[tx from zero] use block producer wallet");
 
@@ -761,6 +820,9 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         server_config: config.rpc_server.clone(),
         dynamic_config_manager,
         polyjuice_sender_recover,
+        p2p_admin: p2p_control_and_handle
+            .as_ref()
+            .map(|(_, admin, _)| admin.clone()),
     };
 
     let rpc_registry = Registry::create(args).await;
@@ -775,6 +837,36 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         }
         addrs.remove(0)
     };
+    let ws_server_args = match (&config.rpc_server.ws_listen, &block_sync_server_state) {
+        (Some(ws_listen), Some(state)) => {
+            let mut addrs: Vec<_> = ws_listen.to_socket_addrs()?.collect();
+            if addrs.len() != 1 {
+                return Err(anyhow!(
+                    "Invalid WebSocket RPC listen address `{}`",
+                    ws_listen
+                ));
+            }
+            Some(WsServerArgs {
+                listen_addr: addrs.remove(0),
+                store: store.clone(),
+                block_sync_server_state: state.clone(),
+            })
+        }
+        _ => None,
+    };
+    let metrics_listen_addr = match config.rpc_server.metrics_listen {
+        Some(ref metrics_listen) => {
+            let mut addrs: Vec<_> = metrics_listen.to_socket_addrs()?.collect();
+            if addrs.len() != 1 {
+                return Err(anyhow!(
+                    "Invalid metrics listen address `{}`",
+                    metrics_listen
+                ));
+            }
+            Some(addrs.remove(0))
+        }
+        None => None,
+    };
 
     {
         let rollup_type_script_hash = {
@@ -791,6 +883,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
 
     let bm = (block_producer, mem_pool.clone()); // To keep the next line short.
     let psc_task = if let (Some(block_producer), Some(mem_pool)) = bm {
+        let block_producer_config = config.block_producer.as_ref().unwrap();
         let psc_state = ProduceSubmitConfirm::init(Arc::new(PSCContext {
             store: store.clone(),
             block_producer,
@@ -800,7 +893,12 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             local_cells_manager: Mutex::new(LocalCellsManager::default()),
             chain_updater: chain_updater.clone(),
             rollup_type_script: rollup_type_script.clone(),
-            psc_config: config.block_producer.as_ref().unwrap().psc_config.clone(),
+            psc_config: block_producer_config.psc_config.clone(),
+            fee_rate_estimator: FeeRateEstimator::new(
+                block_producer_config.submit_fee_rate.clone(),
+                block_producer_config.fee_rate,
+            ),
+            submit_history: std::sync::Mutex::new(SubmitHistory::default()),
             block_sync_server_state: block_sync_server_state.clone(),
             liveness: liveness.clone(),
         }))
@@ -825,7 +923,9 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     let has_psc_task = psc_task.is_some();
     let psc_task = OptionFuture::from(psc_task);
 
-    let block_sync_task = if config.node_mode == NodeMode::ReadOnly {
+    // Watchtowers sync and verify blocks from L1 like read-only nodes without
+    // p2p block sync.
+    let block_sync_task = if matches!(config.node_mode, NodeMode::ReadOnly | NodeMode::Watchtower) {
         let client = BlockSyncClient {
             store: store.clone(),
             rpc_client: rpc_client.clone(),
@@ -834,6 +934,8 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             chain_updater,
             rollup_type_script: rollup_type_script.clone(),
             p2p_stream_inbox: block_sync_client_p2p_stream_inbox,
+            peer_book,
+            block_sync_server_state: block_sync_server_state.clone(),
             completed_initial_syncing: false,
             liveness: liveness.clone(),
         };
@@ -931,6 +1033,8 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     let rpc_task = spawn(async move {
         if let Err(err) = start_jsonrpc_server(
             rpc_address,
+            ws_server_args,
+            metrics_listen_addr,
             rpc_registry,
             liveness,
             rpc_shutdown_send,
@@ -956,7 +1060,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         log::error!("Failed to brodcast error message: {:?}", err);
     }
     // Shutdown p2p network.
-    if let Some((control, handle)) = p2p_control_and_handle {
+    if let Some((control, _, handle)) = p2p_control_and_handle {
         log::info!("closing p2p network");
         let _ = control.close().await;
         let _ = handle.await;
@@ -0,0 +1,81 @@
+//! Background pruner of block state history, see `gw_config::PruningConfig`.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::{offchain::RollupContext, prelude::Unpack};
+use tokio::time::MissedTickBehavior;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+/// Blocks pruned in one DB transaction.
+const PRUNE_BATCH_BLOCKS: u64 = 100;
+
+pub struct StatePruner {
+    store: Store,
+    rollup_context: RollupContext,
+    keep_recent: u64,
+}
+
+impl StatePruner {
+    pub fn new(store: Store, rollup_context: RollupContext, keep_recent: u64) -> Self {
+        Self {
+            store,
+            rollup_context,
+            keep_recent,
+        }
+    }
+
+    /// Prune every `PRUNE_INTERVAL`, each pass runs on the blocking thread
+    /// pool.
+    pub fn spawn(self) {
+        let pruner = Arc::new(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let pruner = Arc::clone(&pruner);
+                match tokio::task::spawn_blocking(move || pruner.prune()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => log::error!("[state pruner] prune error: {:#}", err),
+                    Err(err) => log::error!("[state pruner] prune task error: {}", err),
+                }
+            }
+        });
+    }
+
+    /// Prune history of blocks before the horizon, which is `keep_recent`
+    /// blocks behind the tip and never after the last finalized block.
+    fn prune(&self) -> Result<()> {
+        let tip_number: u64 = self
+            .store
+            .get_last_valid_tip_block()?
+            .raw()
+            .number()
+            .unpack();
+        let horizon = tip_number
+            .saturating_sub(self.keep_recent)
+            .min(self.rollup_context.last_finalized_block_number(tip_number));
+        let mut pruned = self.store.get_state_pruned_block_number()?;
+        if pruned >= horizon {
+            return Ok(());
+        }
+        log::info!(
+            "[state pruner] prune state history #{} -> #{}",
+            pruned,
+            horizon
+        );
+        while pruned < horizon {
+            let end = horizon.min(pruned + PRUNE_BATCH_BLOCKS);
+            let db = self.store.begin_transaction();
+            for block_number in pruned + 1..=end {
+                db.prune_block_state_record(block_number)?;
+            }
+            db.set_state_pruned_block_number(end)?;
+            db.commit()?;
+            pruned = end;
+        }
+        Ok(())
+    }
+}
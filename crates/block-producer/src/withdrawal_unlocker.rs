@@ -280,6 +280,6 @@ impl BuildUnlockWithdrawalToOwner for DefaultUnlocker {
             self.fee_rate,
         )
         .await?;
-        self.wallet.sign_tx_skeleton(tx_skeleton)
+        self.wallet.sign_tx_skeleton(tx_skeleton).await
     }
 }
@@ -1056,7 +1056,8 @@ impl Chain {
     }
 }
 
-fn parse_global_state(tx: &Transaction, rollup_id: &[u8; 32]) -> Result<GlobalState> {
+/// Parse the global state of the rollup cell in the outputs of `tx`.
+pub fn parse_global_state(tx: &Transaction, rollup_id: &[u8; 32]) -> Result<GlobalState> {
     // find rollup state cell from outputs
     let (i, _) = tx
         .raw()
@@ -2,7 +2,7 @@
 
 use crate::types::{RevertContext, RevertWitness, VerifyContext, VerifyWitness};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use gw_common::h256_ext::H256Ext;
 use gw_common::merkle_utils::{calculate_state_checkpoint, ckb_merkle_leaf_hash, CBMT};
 use gw_common::smt::Blake2bHasher;
@@ -42,6 +42,20 @@ pub fn build_challenge_context(
         opt_.ok_or_else(|| anyhow!("bad block {} not found", hex::encode(block_hash.as_slice())))?
     };
 
+    build_challenge_context_for_block(db, target, &block)
+}
+
+/// Build challenge context of a given block, e.g. a main chain block to
+/// simulate a challenge.
+pub fn build_challenge_context_for_block(
+    db: &StoreTransaction,
+    target: ChallengeTarget,
+    block: &L2Block,
+) -> Result<ChallengeContext> {
+    if target.block_hash().as_slice() != block.hash().as_slice() {
+        bail!("challenge target block hash not match");
+    }
+
     let block_smt = db.block_smt()?;
     let block_proof = block_smt
         .merkle_proof(vec![block.smt_key().into()])?
@@ -28,7 +28,7 @@ pub mod mock_block;
 pub mod mock_tx;
 pub mod verify_tx;
 pub use mock_block::RollBackSavePointError;
-pub use mock_tx::mock_cancel_challenge_tx;
+pub use mock_tx::{mock_cancel_challenge_tx, mock_enter_challenge_tx, mock_revert_tx};
 pub use verify_tx::dump_tx;
 
 use self::{
@@ -112,6 +112,7 @@ impl OffChainMockContext {
                 mock_rollup.cell_deps().rollup_cell_type.clone().into(),
                 mock_rollup.config.rollup_config_cell_dep.clone().into(),
                 mock_rollup.cell_deps().challenge_cell_lock.clone().into(),
+                mock_rollup.cell_deps().stake_cell_lock.clone().into(),
                 mock_rollup.ckb_genesis_info.sighash_dep.clone(),
             ];
             deps.extend({
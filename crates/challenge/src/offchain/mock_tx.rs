@@ -4,7 +4,8 @@ use crate::cancel_challenge::{
 };
 use crate::enter_challenge::EnterChallenge;
 use crate::offchain::CKBGenesisInfo;
-use crate::types::VerifyContext;
+use crate::revert::Revert;
+use crate::types::{RevertContext, VerifyContext};
 use gw_rpc_client::contract::ContractsCellDepManager;
 use gw_utils::transaction_skeleton::TransactionSkeleton;
 use gw_utils::wallet::Wallet;
@@ -16,14 +17,15 @@ use gw_common::H256;
 use gw_config::{BlockProducerConfig, ContractsCellDep};
 use gw_generator::types::vm::ChallengeContext;
 use gw_types::bytes::Bytes;
+use gw_types::core::ScriptHashType;
 use gw_types::offchain::{CellInfo, InputCellInfo, RollupContext};
 use gw_types::packed::{
     Byte32, CellDep, CellInput, CellOutput, ChallengeTarget, ChallengeWitness, GlobalState,
-    OutPoint, Script, ScriptOpt, Transaction, WitnessArgs,
+    OutPoint, Script, ScriptOpt, StakeLockArgs, Transaction, WitnessArgs,
 };
 use gw_types::prelude::{Builder, Entity, Pack, Unpack};
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::{sync::Arc, time::Duration};
 
 pub struct MockRollup {
@@ -180,7 +182,7 @@ pub fn mock_cancel_challenge_tx(
 
     let owner_lock = mock_rollup.wallet.lock_script().to_owned();
     mock_rollup.fill_tx_fee(&mut tx_skeleton, owner_lock)?;
-    let tx = mock_rollup.wallet.sign_tx_skeleton(tx_skeleton)?;
+    let tx = mock_rollup.wallet.sign_tx_skeleton_locally(tx_skeleton)?;
 
     Ok(MockOutput {
         cell_deps,
@@ -189,6 +191,186 @@ pub fn mock_cancel_challenge_tx(
     })
 }
 
+pub fn mock_enter_challenge_tx(
+    mock_rollup: &MockRollup,
+    global_state: GlobalState,
+    challenge_context: ChallengeContext,
+) -> Result<MockOutput> {
+    let rewards_lock = {
+        let challenger_config = &mock_rollup.config.challenger_config;
+        challenger_config.rewards_receiver_lock.clone().into()
+    };
+    let enter_challenge = EnterChallenge::new(
+        global_state.clone(),
+        &mock_rollup.rollup_context,
+        challenge_context,
+        rewards_lock,
+    );
+    let challenge_output = enter_challenge.build_output();
+    let contracts_dep = mock_rollup.contracts_dep_manager.load();
+
+    let omni_lock_code_hash = {
+        let scripts = mock_rollup.contracts_dep_manager.load_scripts();
+        scripts.omni_lock.hash()
+    };
+    let mut tx_skeleton = TransactionSkeleton::new(omni_lock_code_hash.0);
+    let mut inputs = Vec::new();
+
+    // Rollup
+    let rollup_input =
+        mock_rollup.mock_rollup_cell(global_state, mock_rollup.wallet.lock_script().to_owned());
+    inputs.push(rollup_input.clone());
+
+    let rollup_deps = vec![
+        contracts_dep.rollup_cell_type.clone().into(),
+        mock_rollup.config.rollup_config_cell_dep.clone().into(),
+        contracts_dep.omni_lock.clone().into(),
+    ];
+    let rollup_output = (
+        rollup_input.cell.output.clone(),
+        challenge_output.post_global_state.as_bytes(),
+    );
+    let rollup_witness = challenge_output.rollup_witness;
+
+    tx_skeleton.cell_deps_mut().extend(rollup_deps);
+    tx_skeleton.inputs_mut().push(rollup_input);
+    tx_skeleton.outputs_mut().push(rollup_output);
+    tx_skeleton.witnesses_mut().push(rollup_witness);
+
+    // Challenge
+    let challenge_cell = challenge_output.challenge_cell;
+    tx_skeleton.outputs_mut().push(challenge_cell);
+
+    // Challenger pays challenge cell capacity and fee
+    let owner_cell = mock_rollup.mock_owner_cell();
+    inputs.push(owner_cell.clone());
+
+    let owner_dep = mock_rollup.ckb_genesis_info.sighash_dep.clone();
+    tx_skeleton.cell_deps_mut().push(owner_dep);
+    tx_skeleton.inputs_mut().push(owner_cell);
+
+    let owner_lock = mock_rollup.wallet.lock_script().to_owned();
+    mock_rollup.fill_tx_fee(&mut tx_skeleton, owner_lock)?;
+    let tx = mock_rollup.wallet.sign_tx_skeleton_locally(tx_skeleton)?;
+
+    Ok(MockOutput {
+        cell_deps: vec![],
+        inputs,
+        tx,
+    })
+}
+
+/// NOTE: Caller should build `context` in a db transaction and rollback it, see
+/// `build_revert_context`.
+pub fn mock_revert_tx(
+    mock_rollup: &MockRollup,
+    global_state: GlobalState,
+    challenge_target: ChallengeTarget,
+    context: RevertContext,
+) -> Result<MockOutput> {
+    let burn_lock = {
+        let challenger_config = &mock_rollup.config.challenger_config;
+        challenger_config.burn_lock.clone().into()
+    };
+
+    // Challenge cell must be mature
+    let mut challenge_input = mock_rollup.mock_challenge_cell(challenge_target);
+    challenge_input.input = {
+        let builder = challenge_input.input.as_builder();
+        let since = mock_rollup.mock_challenge_maturity_since();
+        builder.since(since.pack()).build()
+    };
+
+    // One stake cell per owner, staked by its last reverted block
+    let stake_inputs: Vec<InputCellInfo> = {
+        let mut owners = BTreeMap::new();
+        for block in context.revert_witness.reverted_blocks.clone().into_iter() {
+            let owner_lock_hash: [u8; 32] = block.stake_cell_owner_lock_hash().unpack();
+            owners.insert(owner_lock_hash, block.number().unpack());
+        }
+        let to_stake = |(owner, number)| mock_rollup.mock_stake_cell(owner, number);
+        owners.into_iter().map(to_stake).collect()
+    };
+    let stake_cells: Vec<CellInfo> = stake_inputs.iter().map(|i| i.cell.clone()).collect();
+
+    let revert = Revert::new(
+        &mock_rollup.rollup_context,
+        global_state.clone(),
+        &challenge_input.cell,
+        &stake_cells,
+        burn_lock,
+        context,
+    );
+    let revert_output = revert.build_output()?;
+    let contracts_dep = mock_rollup.contracts_dep_manager.load();
+
+    let omni_lock_code_hash = {
+        let scripts = mock_rollup.contracts_dep_manager.load_scripts();
+        scripts.omni_lock.hash()
+    };
+    let mut tx_skeleton = TransactionSkeleton::new(omni_lock_code_hash.0);
+    let mut inputs = Vec::new();
+
+    // Rollup
+    let rollup_input =
+        mock_rollup.mock_rollup_cell(global_state, mock_rollup.wallet.lock_script().to_owned());
+    inputs.push(rollup_input.clone());
+
+    let rollup_deps = vec![
+        contracts_dep.rollup_cell_type.clone().into(),
+        mock_rollup.config.rollup_config_cell_dep.clone().into(),
+        contracts_dep.omni_lock.clone().into(),
+    ];
+    let rollup_output = (
+        rollup_input.cell.output.clone(),
+        revert_output.post_global_state.as_bytes(),
+    );
+    let rollup_witness = revert_output.rollup_witness;
+
+    tx_skeleton.cell_deps_mut().extend(rollup_deps);
+    tx_skeleton.inputs_mut().push(rollup_input);
+    tx_skeleton.outputs_mut().push(rollup_output);
+    tx_skeleton.witnesses_mut().push(rollup_witness);
+
+    // Challenge
+    inputs.push(challenge_input.clone());
+
+    let challenge_dep = contracts_dep.challenge_cell_lock.clone().into();
+    tx_skeleton.cell_deps_mut().push(challenge_dep);
+    tx_skeleton.inputs_mut().push(challenge_input);
+
+    // Stake
+    inputs.extend(stake_inputs.clone());
+
+    let stake_dep = contracts_dep.stake_cell_lock.clone().into();
+    tx_skeleton.cell_deps_mut().push(stake_dep);
+    tx_skeleton.inputs_mut().extend(stake_inputs);
+
+    // Rewards
+    tx_skeleton.outputs_mut().extend(revert_output.reward_cells);
+
+    // Burn
+    tx_skeleton.outputs_mut().extend(revert_output.burn_cells);
+
+    // Fee
+    let owner_cell = mock_rollup.mock_owner_cell();
+    inputs.push(owner_cell.clone());
+
+    let owner_dep = mock_rollup.ckb_genesis_info.sighash_dep.clone();
+    tx_skeleton.cell_deps_mut().push(owner_dep);
+    tx_skeleton.inputs_mut().push(owner_cell);
+
+    let owner_lock = mock_rollup.wallet.lock_script().to_owned();
+    mock_rollup.fill_tx_fee(&mut tx_skeleton, owner_lock)?;
+    let tx = mock_rollup.wallet.sign_tx_skeleton_locally(tx_skeleton)?;
+
+    Ok(MockOutput {
+        cell_deps: vec![],
+        inputs,
+        tx,
+    })
+}
+
 pub struct NewMockRollupArgs {
     pub rollup_type_script: ScriptOpt,
     pub rollup_context: RollupContext,
@@ -235,6 +417,14 @@ impl MockRollup {
         SINCE_BLOCK_TIMESTAMP_FLAG | self.median_time.as_secs()
     }
 
+    fn mock_challenge_maturity_since(&self) -> u64 {
+        /// Relative block number since flag
+        const SINCE_RELATIVE_BLOCK_NUMBER_FLAG: u64 = 0x8000_0000_0000_0000;
+        let rollup_config = &self.rollup_context.rollup_config;
+        let maturity_blocks: u64 = rollup_config.challenge_maturity_blocks().unpack();
+        SINCE_RELATIVE_BLOCK_NUMBER_FLAG | maturity_blocks
+    }
+
     fn mock_owner_cell(&self) -> InputCellInfo {
         let out_point = OutPoint::new_builder()
             .tx_hash(random_hash())
@@ -330,6 +520,49 @@ impl MockRollup {
         InputCellInfo { input, cell }
     }
 
+    fn mock_stake_cell(&self, owner_lock_hash: [u8; 32], stake_block_number: u64) -> InputCellInfo {
+        let out_point = OutPoint::new_builder()
+            .tx_hash(random_hash())
+            .index(0u32.pack())
+            .build();
+
+        let input = CellInput::new_builder()
+            .previous_output(out_point.clone())
+            .build();
+
+        let rollup_config = &self.rollup_context.rollup_config;
+        let lock_args: Bytes = {
+            let stake_lock_args = StakeLockArgs::new_builder()
+                .owner_lock_hash(owner_lock_hash.pack())
+                .stake_block_number(stake_block_number.pack())
+                .build();
+
+            let rollup_type_hash = self.rollup_context.rollup_script_hash.as_slice().iter();
+            rollup_type_hash
+                .chain(stake_lock_args.as_slice().iter())
+                .cloned()
+                .collect()
+        };
+        let lock = Script::new_builder()
+            .code_hash(rollup_config.stake_script_type_hash())
+            .hash_type(ScriptHashType::Type.into())
+            .args(lock_args.pack())
+            .build();
+
+        let output = CellOutput::new_builder()
+            .capacity(rollup_config.required_staking_capacity())
+            .lock(lock)
+            .build();
+
+        let cell = CellInfo {
+            out_point,
+            output,
+            data: Bytes::new(),
+        };
+
+        InputCellInfo { input, cell }
+    }
+
     fn fill_tx_fee(
         &self,
         tx_skeleton: &mut TransactionSkeleton,
@@ -25,7 +25,6 @@ impl RegistryContext {
     }
 
     /// Extract EOA registry address from deposit request
-    // TODO support extract ETH address from tron EOA
     pub fn extract_registry_address_from_deposit(
         &self,
         registry_id: u32,
@@ -46,8 +45,9 @@ impl RegistryContext {
             .transpose()
             .map_err(|_err| Error::UnknownEoaCodeHash)?
         {
-            Some(AllowedEoaType::Eth) => {
-                // extract ETH EOA
+            Some(AllowedEoaType::Eth) | Some(AllowedEoaType::Tron) => {
+                // extract ETH EOA, Tron EOA shares the same args layout and
+                // its address is derived from the pubkey in the same way as ETH
                 let address =
                     { crate::registry::eth_registry::extract_eth_address_from_eoa(args)? };
                 let addr = RegistryAddress::new(registry_id, address);
@@ -57,3 +57,87 @@ impl RegistryContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use gw_types::{core::AllowedEoaType, packed::AllowedTypeHash, prelude::Pack};
+
+    use super::RegistryContext;
+    use crate::{
+        builtins::ETH_REGISTRY_ACCOUNT_ID, error::Error, registry_address::RegistryAddress,
+    };
+
+    const ETH_LOCK_CODE_HASH: [u8; 32] = [1u8; 32];
+    const TRON_LOCK_CODE_HASH: [u8; 32] = [2u8; 32];
+
+    fn registry_ctx() -> RegistryContext {
+        RegistryContext::new(vec![
+            AllowedTypeHash::new(AllowedEoaType::Eth, ETH_LOCK_CODE_HASH),
+            AllowedTypeHash::new(AllowedEoaType::Tron, TRON_LOCK_CODE_HASH),
+        ])
+    }
+
+    #[test]
+    fn test_extract_tron_registry_address_from_deposit() {
+        let ctx = registry_ctx();
+        let tron_address = [3u8; 20];
+        let mut args = [42u8; 32].to_vec();
+        args.extend_from_slice(&tron_address);
+
+        // Tron EOA is registered in the ETH registry with its 20 bytes address
+        let addr = ctx
+            .extract_registry_address_from_deposit(
+                ETH_REGISTRY_ACCOUNT_ID,
+                &TRON_LOCK_CODE_HASH.pack(),
+                &args,
+            )
+            .expect("extract");
+        assert_eq!(
+            addr,
+            RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, tron_address.to_vec())
+        );
+        // The same address as an ETH EOA with the same args
+        let eth_addr = ctx
+            .extract_registry_address_from_deposit(
+                ETH_REGISTRY_ACCOUNT_ID,
+                &ETH_LOCK_CODE_HASH.pack(),
+                &args,
+            )
+            .expect("extract");
+        assert_eq!(addr, eth_addr);
+
+        // invalid args length
+        let err = ctx
+            .extract_registry_address_from_deposit(
+                ETH_REGISTRY_ACCOUNT_ID,
+                &TRON_LOCK_CODE_HASH.pack(),
+                &args[..51],
+            )
+            .unwrap_err();
+        assert_eq!(err, Error::InvalidArgs);
+
+        // unsupported registry
+        let err = ctx
+            .extract_registry_address_from_deposit(
+                ETH_REGISTRY_ACCOUNT_ID + 1,
+                &TRON_LOCK_CODE_HASH.pack(),
+                &args,
+            )
+            .unwrap_err();
+        assert_eq!(err, Error::InvalidArgs);
+
+        // Tron lock isn't allowed
+        let ctx = RegistryContext::new(vec![AllowedTypeHash::new(
+            AllowedEoaType::Eth,
+            ETH_LOCK_CODE_HASH,
+        )]);
+        let err = ctx
+            .extract_registry_address_from_deposit(
+                ETH_REGISTRY_ACCOUNT_ID,
+                &TRON_LOCK_CODE_HASH.pack(),
+                &args,
+            )
+            .unwrap_err();
+        assert_eq!(err, Error::UnknownEoaCodeHash);
+    }
+}
@@ -45,6 +45,8 @@ pub struct Config {
     pub consensus: ConsensusConfig,
     pub reload_config_github_url: Option<GithubConfigUrl>,
     #[serde(default)]
+    pub reload_config: ReloadConfig,
+    #[serde(default)]
     pub dynamic_config: DynamicConfig,
     #[serde(default)]
     pub p2p_network_config: Option<P2PNetworkConfig>,
@@ -57,11 +59,21 @@ pub struct Config {
 pub enum RPCMethods {
     PProf,
     Test,
+    Debug,
+    P2P,
+    Admin,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RPCServerConfig {
     pub listen: String,
+    /// WebSocket JSON-RPC listen address, disabled if not set.
+    #[serde(default)]
+    pub ws_listen: Option<String>,
+    /// Prometheus metrics listen address, disabled if not set. Metrics are
+    /// not served on the public RPC address.
+    #[serde(default)]
+    pub metrics_listen: Option<String>,
     #[serde(default)]
     pub enable_methods: HashSet<RPCMethods>,
 }
@@ -111,8 +123,88 @@ pub struct GenesisConfig {
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WalletConfig {
+    /// Hex encoded private key file. Not used if `signer` is set.
+    #[serde(default)]
     pub privkey_path: PathBuf,
     pub lock: Script,
+    #[serde(default)]
+    pub signer: Option<SignerConfig>,
+}
+
+/// Where the wallet key is kept if not in a plain `privkey_path` file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// Web3 secret storage (version 3) keystore file, the password is read
+    /// from `password_path`.
+    Keystore {
+        path: PathBuf,
+        password_path: PathBuf,
+    },
+    /// Remote signer, `unix:///path/to/socket` or `http(s)://host:port`.
+    Remote {
+        url: String,
+        /// Sent as `Authorization: Bearer <auth_token>` to an HTTP signer.
+        #[serde(default)]
+        auth_token: Option<String>,
+    },
+}
+
+#[test]
+fn test_wallet_signer_config() {
+    let config: WalletConfig = toml::from_str(
+        r#"
+        privkey_path = "pk"
+        [lock]
+        code_hash = "0x0000000000000000000000000000000000000000000000000000000000000000"
+        hash_type = "type"
+        args = "0x"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.privkey_path, PathBuf::from("pk"));
+    assert_eq!(config.signer, None);
+
+    let config: WalletConfig = toml::from_str(
+        r#"
+        [lock]
+        code_hash = "0x0000000000000000000000000000000000000000000000000000000000000000"
+        hash_type = "type"
+        args = "0x"
+        [signer]
+        type = "remote"
+        url = "unix:///run/godwoken/signer.sock"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.signer,
+        Some(SignerConfig::Remote {
+            url: "unix:///run/godwoken/signer.sock".into(),
+            auth_token: None,
+        })
+    );
+
+    let config: WalletConfig = toml::from_str(
+        r#"
+        [lock]
+        code_hash = "0x0000000000000000000000000000000000000000000000000000000000000000"
+        hash_type = "type"
+        args = "0x"
+        [signer]
+        type = "remote"
+        url = "https://signer.internal:8443"
+        auth_token = "secret"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.signer,
+        Some(SignerConfig::Remote {
+            url: "https://signer.internal:8443".into(),
+            auth_token: Some("secret".into()),
+        })
+    );
 }
 
 // NOTE: Rewards receiver lock must be different than lock in WalletConfig,
@@ -167,9 +259,12 @@ pub struct RegistryAddressConfig {
 #[serde(default)]
 pub struct BlockProducerConfig {
     pub check_mem_block_before_submit: bool,
+    /// Fee rate of L1 transactions, shannons per KB. Submit transactions use
+    /// it if fee rate estimation is disabled or fails.
     pub fee_rate: u64,
     #[serde(flatten)]
     pub psc_config: PscConfig,
+    pub submit_fee_rate: SubmitFeeRateConfig,
     pub block_producer: RegistryAddressConfig,
     pub rollup_config_cell_dep: CellDep,
     pub challenger_config: ChallengerConfig,
@@ -183,6 +278,7 @@ impl Default for BlockProducerConfig {
             check_mem_block_before_submit: false,
             fee_rate: 1000,
             psc_config: PscConfig::default(),
+            submit_fee_rate: SubmitFeeRateConfig::default(),
             block_producer: RegistryAddressConfig::default(),
             rollup_config_cell_dep: CellDep::default(),
             challenger_config: ChallengerConfig::default(),
@@ -199,6 +295,58 @@ fn test_default_block_producer_config() {
     assert!(config.fee_rate > 0);
 }
 
+/// Fee rate of block submit transactions, shannons per KB.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubmitFeeRateConfig {
+    /// Estimate the fee rate with the median fee rate of recent CKB blocks.
+    /// Requires CKB 0.109 or later. Default is false.
+    pub estimate: bool,
+    /// Number of recent CKB blocks to estimate from. Default is 21.
+    pub estimate_blocks: u64,
+    /// Estimated and bumped fee rates are kept in `[min_fee_rate,
+    /// max_fee_rate]`. Defaults are 1000 and 100000.
+    pub min_fee_rate: u64,
+    pub max_fee_rate: u64,
+    /// Replace a submit transaction that has been pending for
+    /// `bump_after_secs` with one of a higher fee rate. Replacing requires CKB
+    /// 0.110 or later with RBF enabled. Disabled if 0, which is the default.
+    pub bump_after_secs: u64,
+    /// Fee rate increase of each replacement. It must be large enough for
+    /// CKB `min_rbf_rate`. Default is 50.
+    pub bump_percent: u64,
+}
+
+impl Default for SubmitFeeRateConfig {
+    fn default() -> Self {
+        Self {
+            estimate: false,
+            estimate_blocks: 21,
+            min_fee_rate: 1000,
+            max_fee_rate: 100_000,
+            bump_after_secs: 0,
+            bump_percent: 50,
+        }
+    }
+}
+
+#[test]
+fn test_submit_fee_rate_config() {
+    let config: BlockProducerConfig = toml::from_str(
+        r#"
+        fee_rate = 2000
+        [submit_fee_rate]
+        estimate = true
+        bump_after_secs = 300
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.fee_rate, 2000);
+    assert!(config.submit_fee_rate.estimate);
+    assert_eq!(config.submit_fee_rate.bump_after_secs, 300);
+    assert_eq!(config.submit_fee_rate.max_fee_rate, 100_000);
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct PscConfig {
@@ -324,6 +472,26 @@ pub struct P2PNetworkConfig {
     pub dial: Vec<String>,
     pub secret_key_path: Option<PathBuf>,
     pub allowed_peer_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub peer_score: PeerScoreConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerScoreConfig {
+    /// Peers are banned when their score drops to this value. Default is -100.
+    pub ban_threshold: i32,
+    /// How long a banned peer is refused. Default is 1 hour.
+    pub ban_duration_secs: u64,
+}
+
+impl Default for PeerScoreConfig {
+    fn default() -> Self {
+        Self {
+            ban_threshold: -100,
+            ban_duration_secs: 3600,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -348,6 +516,45 @@ pub struct MemPoolConfig {
     pub restore_path: PathBuf,
     #[serde(default)]
     pub mem_block: MemBlockConfig,
+    #[serde(default)]
+    pub fee_queue: FeeQueueConfig,
+    /// Number of recent blocks to sample fee rates from for fee estimation.
+    #[serde(default = "default_fee_estimate_blocks")]
+    pub fee_estimate_blocks: u64,
+}
+
+/// Limits of the queue holding txs and withdrawals before they are pushed to
+/// the mem pool.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeQueueConfig {
+    /// A queued tx or withdrawal can be replaced by a new one of the same
+    /// sender and nonce, only if the new one pays at least this percent higher
+    /// fee rate.
+    pub replace_fee_rate_bump_percent: u64,
+    /// Max queued items of a sender, 0 to disable.
+    pub max_items_per_sender: usize,
+    /// Max gap between the nonce of a queued item and its sender's nonce.
+    pub max_nonce_gap: u32,
+    /// Min fee rate (fee / cycles limit) of new items when the queue is full,
+    /// 0 to disable.
+    ///
+    /// The min fee rate rises linearly from 0 once the queue is
+    /// `min_fee_rate_start_percent` full.
+    pub full_queue_min_fee_rate: u64,
+    pub min_fee_rate_start_percent: u64,
+}
+
+impl Default for FeeQueueConfig {
+    fn default() -> Self {
+        Self {
+            replace_fee_rate_bump_percent: 10,
+            max_items_per_sender: 32,
+            max_nonce_gap: 64,
+            full_queue_min_fee_rate: 0,
+            min_fee_rate_start_percent: 50,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -426,12 +633,18 @@ fn default_restore_path() -> PathBuf {
     DEFAULT_RESTORE_PATH.into()
 }
 
+fn default_fee_estimate_blocks() -> u64 {
+    20
+}
+
 impl Default for MemPoolConfig {
     fn default() -> Self {
         Self {
             execute_l2tx_max_cycles: 100_000_000,
             restore_path: default_restore_path(),
             mem_block: MemBlockConfig::default(),
+            fee_queue: FeeQueueConfig::default(),
+            fee_estimate_blocks: default_fee_estimate_blocks(),
         }
     }
 }
@@ -455,6 +668,11 @@ pub enum NodeMode {
     FullNode,
     Test,
     ReadOnly,
+    /// Sync and verify blocks from L1, challenge bad blocks, cancel bad
+    /// challenges and clean up verifier cells. Doesn't produce blocks or
+    /// accept transactions. Requires `block_producer` for the wallet and the
+    /// challenger config.
+    Watchtower,
 }
 
 impl Default for NodeMode {
@@ -463,6 +681,56 @@ impl Default for NodeMode {
     }
 }
 
+impl NodeMode {
+    /// Whether the node runs a mem pool. Watchtowers don't execute
+    /// transactions.
+    pub fn has_mem_pool(&self) -> bool {
+        !matches!(self, NodeMode::Watchtower)
+    }
+
+    /// Whether the node produces blocks, unlocks finalized withdrawals and
+    /// accepts transactions and withdrawals through RPC.
+    pub fn produces_blocks(&self) -> bool {
+        matches!(self, NodeMode::FullNode | NodeMode::Test)
+    }
+
+    /// Whether the node challenges bad blocks and cancels bad challenges.
+    pub fn challenges(&self) -> bool {
+        !matches!(self, NodeMode::ReadOnly)
+    }
+}
+
+#[test]
+fn test_node_mode() {
+    #[derive(Deserialize)]
+    struct Mode {
+        node_mode: NodeMode,
+    }
+    let mode: Mode = toml::from_str(r#"node_mode = "watchtower""#).unwrap();
+    assert_eq!(mode.node_mode, NodeMode::Watchtower);
+    let mode: Mode = toml::from_str(r#"node_mode = "fullnode""#).unwrap();
+    assert_eq!(mode.node_mode, NodeMode::FullNode);
+}
+
+#[test]
+fn test_node_mode_components() {
+    for mode in [NodeMode::FullNode, NodeMode::Test] {
+        assert!(mode.has_mem_pool());
+        assert!(mode.produces_blocks());
+        assert!(mode.challenges());
+    }
+
+    let mode = NodeMode::ReadOnly;
+    assert!(mode.has_mem_pool());
+    assert!(!mode.produces_blocks());
+    assert!(!mode.challenges());
+
+    let mode = NodeMode::Watchtower;
+    assert!(!mode.has_mem_pool());
+    assert!(!mode.produces_blocks());
+    assert!(mode.challenges());
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DBBlockValidatorConfig {
     pub verify_max_cycles: u64,
@@ -492,6 +760,31 @@ pub struct StoreConfig {
     pub options_file: Option<PathBuf>,
     #[serde(default)]
     pub options: HashMap<String, String>,
+    /// Index transactions by sender and receiver account id, required by
+    /// `gw_get_transactions_by_account`. Only blocks inserted after enabling
+    /// are indexed.
+    #[serde(default)]
+    pub enable_account_tx_index: bool,
+    #[serde(default)]
+    pub pruning: PruningConfig,
+}
+
+/// Pruning of block state history, i.e. state of history blocks queried by
+/// RPCs such as `gw_get_balance` with a block number.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PruningConfig {
+    /// Keep state history of all blocks.
+    Archive,
+    /// Only keep state history of the recent N blocks. History of unfinalized
+    /// blocks is always kept.
+    KeepRecent(u64),
+}
+
+impl Default for PruningConfig {
+    fn default() -> Self {
+        PruningConfig::Archive
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -537,7 +830,75 @@ pub struct GithubConfigUrl {
     pub token: String,
 }
 
-// Configs in DynamicConfig can be hot reloaded from remote. But GithubConfigUrl must be setup.
+/// Where to reload `DynamicConfig` from. Takes precedence over
+/// `reload_config_github_url`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReloadConfig {
+    pub source: Option<ReloadConfigSource>,
+    /// Token for pushing dynamic config with `gw_update_dynamic_config`. The
+    /// RPC is disabled if not set or `admin` is not in
+    /// `[rpc_server] enable_methods`.
+    pub admin_token: Option<String>,
+}
+
+/// The source must contain a `[dynamic_config]` table, other tables are
+/// ignored, so a whole config file can be used as the source too.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReloadConfigSource {
+    File {
+        path: PathBuf,
+        /// Check the file for modification every `watch_interval_secs` and
+        /// reload automatically. Disabled if not set.
+        watch_interval_secs: Option<u64>,
+    },
+    Url {
+        url: String,
+        /// Value of the `Authorization` header, e.g. `token xxx`.
+        authorization: Option<String>,
+    },
+}
+
+#[test]
+fn test_reload_config() {
+    let config: ReloadConfig = toml::from_str(
+        r#"
+        admin_token = "secret"
+        [source]
+        type = "file"
+        path = "dynamic_config.toml"
+        watch_interval_secs = 10
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.source,
+        Some(ReloadConfigSource::File {
+            path: "dynamic_config.toml".into(),
+            watch_interval_secs: Some(10),
+        })
+    );
+    assert_eq!(config.admin_token.as_deref(), Some("secret"));
+
+    let config: ReloadConfig = toml::from_str(
+        r#"
+        [source]
+        type = "url"
+        url = "https://example.com/config.toml"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.source,
+        Some(ReloadConfigSource::Url {
+            url: "https://example.com/config.toml".into(),
+            authorization: None,
+        })
+    );
+}
+
+// Configs in DynamicConfig can be hot reloaded, see ReloadConfig.
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DynamicConfig {
     pub fee_config: FeeConfig,
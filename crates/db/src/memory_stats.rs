@@ -36,6 +36,26 @@ pub struct CfMemStat {
     value: PropertyValue<u64>,
 }
 
+impl CfMemStat {
+    /// Column family name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Property name, e.g. `block-cache-usage`.
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// Property value, `None` if the property is not available.
+    pub fn value(&self) -> Option<u64> {
+        match self.value {
+            PropertyValue::Value(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
 /// A trait which used to track the RocksDB memory usage.
 ///
 /// References: [Memory usage in RocksDB](https://github.com/facebook/rocksdb/wiki/Memory-usage-in-RocksDB)
@@ -5,15 +5,20 @@ use crate::{
     error::Error,
     read_only_db::{self, ReadOnlyDB},
     schema::{
-        COLUMN_BAD_BLOCK, COLUMN_BLOCK, COLUMN_META, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
-        META_TIP_BLOCK_HASH_KEY, REMOVED_COLUMN_BLOCK_DEPOSIT_REQUESTS,
-        REMOVED_COLUMN_L2BLOCK_COMMITTED_INFO,
+        COLUMN_BAD_BLOCK, COLUMN_BLOCK, COLUMN_BLOCK_DEPOSIT_INFO_VEC, COLUMN_DEPOSIT_INDEX,
+        COLUMN_DEPOSIT_LOG, COLUMN_INDEX, COLUMN_LOG_INDEX, COLUMN_META,
+        COLUMN_TRANSACTION_RECEIPT, META_LAST_VALID_TIP_BLOCK_HASH_KEY, META_TIP_BLOCK_HASH_KEY,
+        REMOVED_COLUMN_BLOCK_DEPOSIT_REQUESTS, REMOVED_COLUMN_L2BLOCK_COMMITTED_INFO,
     },
     DBIterator, Result,
 };
-use std::{cmp::Ordering, collections::BTreeMap};
+use std::{cmp::Ordering, collections::BTreeMap, convert::TryInto};
 
 use gw_config::StoreConfig;
+use gw_types::{
+    packed::{Byte32, DepositInfoVecReader, L2BlockReader, TransactionKey, TxReceiptReader},
+    prelude::*,
+};
 
 use crate::{
     schema::{COLUMNS, MIGRATION_VERSION_KEY},
@@ -168,6 +173,111 @@ impl Migration for BadBlockColumnMigration {
     }
 }
 
+struct DepositIndexMigration;
+
+impl DepositIndexMigration {
+    // Flush the write batch every this many deposits
+    const BATCH_SIZE: usize = 10_000;
+}
+
+impl Migration for DepositIndexMigration {
+    fn migrate(&self, db: RocksDB) -> Result<RocksDB> {
+        // Deposits of main chain blocks committed before the deposit index was
+        // added, their out points are only kept in the block deposit info vec.
+        let mut batch = db.new_write_batch();
+        let mut count = 0usize;
+        db.traverse(COLUMN_BLOCK_DEPOSIT_INFO_VEC, |block_number, data| {
+            let deposit_info_vec = DepositInfoVecReader::from_slice(data)
+                .map_err(|err| format!("invalid deposit info vec: {}", err))?;
+            for deposit_info in deposit_info_vec.iter() {
+                let out_point = deposit_info.cell().out_point();
+                batch.put(COLUMN_DEPOSIT_INDEX, out_point.as_slice(), block_number)?;
+                batch.delete(COLUMN_DEPOSIT_LOG, out_point.as_slice())?;
+                count += 1;
+            }
+            if batch.len() >= Self::BATCH_SIZE {
+                db.write(&batch)?;
+                batch.clear()?;
+            }
+            Ok(())
+        })?;
+        db.write(&batch)?;
+        log::info!("Indexed {} deposits of committed blocks", count);
+        Ok(db)
+    }
+    fn version(&self) -> &str {
+        "20221201"
+    }
+    fn expensive(&self) -> bool {
+        true
+    }
+}
+
+struct LogIndexMigration;
+
+impl LogIndexMigration {
+    // Flush the write batch every this many logs
+    const BATCH_SIZE: usize = 10_000;
+}
+
+impl Migration for LogIndexMigration {
+    fn migrate(&self, db: RocksDB) -> Result<RocksDB> {
+        // Logs of main chain blocks committed before the log index was added,
+        // they are only kept in transaction receipts.
+        let mut batch = db.new_write_batch();
+        let mut count = 0usize;
+        db.traverse(COLUMN_INDEX, |key, block_hash| {
+            // The chain index also maps block hashes to block numbers.
+            let block_number = match key.try_into() {
+                Ok(block_number) => u64::from_le_bytes(block_number),
+                Err(_) => return Ok(()),
+            };
+            let block = db
+                .get_pinned(COLUMN_BLOCK, block_hash)?
+                .ok_or_else(|| format!("block {} not found", block_number))?;
+            let tx_count = L2BlockReader::from_slice(&block)
+                .map_err(|err| format!("invalid block {}: {}", block_number, err))?
+                .transactions()
+                .len();
+            let block_hash = Byte32::from_slice(block_hash)
+                .map_err(|err| format!("invalid block hash: {}", err))?;
+            for tx_index in 0..tx_count as u32 {
+                let tx_key = TransactionKey::build_transaction_key(block_hash.clone(), tx_index);
+                let receipt = db
+                    .get_pinned(COLUMN_TRANSACTION_RECEIPT, tx_key.as_slice())?
+                    .ok_or_else(|| {
+                        format!("receipt {} of block {} not found", tx_index, block_number)
+                    })?;
+                let receipt = TxReceiptReader::from_slice(&receipt)
+                    .map_err(|err| format!("invalid tx receipt: {}", err))?;
+                for (log_index, log) in receipt.logs().iter().enumerate() {
+                    // (block number, tx index, log index) in big endian
+                    let mut log_key = [0u8; 16];
+                    log_key[..8].copy_from_slice(&block_number.to_be_bytes());
+                    log_key[8..12].copy_from_slice(&tx_index.to_be_bytes());
+                    log_key[12..].copy_from_slice(&(log_index as u32).to_be_bytes());
+                    batch.put(COLUMN_LOG_INDEX, &log_key, log.as_slice())?;
+                    count += 1;
+                }
+            }
+            if batch.len() >= Self::BATCH_SIZE {
+                db.write(&batch)?;
+                batch.clear()?;
+            }
+            Ok(())
+        })?;
+        db.write(&batch)?;
+        log::info!("Indexed {} logs of committed blocks", count);
+        Ok(db)
+    }
+    fn version(&self) -> &str {
+        "20221202"
+    }
+    fn expensive(&self) -> bool {
+        true
+    }
+}
+
 struct MigrationFactory {
     migration_map: BTreeMap<String, Box<dyn Migration>>,
 }
@@ -179,6 +289,8 @@ fn init_migration_factory() -> MigrationFactory {
     factory.insert(Box::new(
         DecoupleBlockProducingSubmissionAndConfirmationMigration,
     ));
+    factory.insert(Box::new(DepositIndexMigration));
+    factory.insert(Box::new(LogIndexMigration));
     factory
 }
 
@@ -223,12 +335,23 @@ impl MigrationFactory {
 #[cfg(test)]
 mod tests {
     use crate::Result;
-    use std::collections::HashMap;
+    use std::{collections::HashMap, convert::TryInto};
 
     use gw_config::StoreConfig;
+    use gw_types::{
+        packed::{
+            CellInfo, DepositInfo, DepositInfoVec, L2Block, L2Transaction, LogItem, LogItemReader,
+            OutPoint, RawL2Block, TransactionKey, TxReceipt,
+        },
+        prelude::*,
+    };
 
     use crate::{
-        schema::{COLUMNS, MIGRATION_VERSION_KEY},
+        schema::{
+            COLUMNS, COLUMN_BLOCK, COLUMN_BLOCK_DEPOSIT_INFO_VEC, COLUMN_DEPOSIT_INDEX,
+            COLUMN_DEPOSIT_LOG, COLUMN_INDEX, COLUMN_LOG_INDEX, COLUMN_TRANSACTION_RECEIPT,
+            MIGRATION_VERSION_KEY,
+        },
         RocksDB,
     };
 
@@ -281,4 +404,117 @@ mod tests {
         assert_eq!(v, Some(Ok(factory.last_db_version().unwrap().to_string())));
         Ok(())
     }
+
+    #[test]
+    fn test_deposit_index_migration() -> Result<()> {
+        let db = RocksDB::open_tmp(COLUMNS);
+        db.put_default(MIGRATION_VERSION_KEY, "20220517")?;
+
+        let out_point = |index: u32| {
+            OutPoint::new_builder()
+                .tx_hash([index as u8; 32].pack())
+                .index(index.pack())
+                .build()
+        };
+        let deposit_info_vec = |out_points: Vec<OutPoint>| {
+            let deposits = out_points.into_iter().map(|out_point| {
+                let cell = CellInfo::new_builder().out_point(out_point).build();
+                DepositInfo::new_builder().cell(cell).build()
+            });
+            DepositInfoVec::new_builder().extend(deposits).build()
+        };
+        let mut batch = db.new_write_batch();
+        for (block_number, out_points) in [
+            (1u64, vec![out_point(1), out_point(2)]),
+            (3, vec![out_point(3)]),
+        ] {
+            batch.put(
+                COLUMN_BLOCK_DEPOSIT_INFO_VEC,
+                &block_number.to_be_bytes(),
+                deposit_info_vec(out_points).as_slice(),
+            )?;
+        }
+        // Stale log of an included deposit and log of a pending deposit
+        batch.put(COLUMN_DEPOSIT_LOG, out_point(1).as_slice(), &[0u8; 9])?;
+        batch.put(COLUMN_DEPOSIT_LOG, out_point(4).as_slice(), &[0u8; 9])?;
+        db.write(&batch)?;
+
+        let factory = init_migration_factory();
+        let db = factory.migrate(db)?;
+
+        let deposit_block_number = |out_point: OutPoint| {
+            db.get_pinned(COLUMN_DEPOSIT_INDEX, out_point.as_slice())
+                .unwrap()
+                .map(|v| u64::from_be_bytes(v.as_ref().try_into().unwrap()))
+        };
+        assert_eq!(deposit_block_number(out_point(1)), Some(1));
+        assert_eq!(deposit_block_number(out_point(2)), Some(1));
+        assert_eq!(deposit_block_number(out_point(3)), Some(3));
+        assert_eq!(deposit_block_number(out_point(4)), None);
+        assert!(db
+            .get_pinned(COLUMN_DEPOSIT_LOG, out_point(1).as_slice())?
+            .is_none());
+        assert!(db
+            .get_pinned(COLUMN_DEPOSIT_LOG, out_point(4).as_slice())?
+            .is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_index_migration() -> Result<()> {
+        let db = RocksDB::open_tmp(COLUMNS);
+        db.put_default(MIGRATION_VERSION_KEY, "20221201")?;
+
+        let log = |account_id: u32| LogItem::new_builder().account_id(account_id.pack()).build();
+        let mut batch = db.new_write_batch();
+        let mut insert_block = |block_number: u64, receipts: Vec<Vec<LogItem>>| -> Result<()> {
+            let raw = RawL2Block::new_builder()
+                .number(block_number.pack())
+                .build();
+            let txs = receipts.iter().map(|_| L2Transaction::default());
+            let block = L2Block::new_builder()
+                .raw(raw)
+                .transactions(txs.collect::<Vec<_>>().pack())
+                .build();
+            let block_hash = block.hash();
+            batch.put(COLUMN_INDEX, block_number.pack().as_slice(), &block_hash)?;
+            batch.put(COLUMN_INDEX, &block_hash, block_number.pack().as_slice())?;
+            batch.put(COLUMN_BLOCK, &block_hash, block.as_slice())?;
+            for (tx_index, logs) in receipts.into_iter().enumerate() {
+                let key = TransactionKey::build_transaction_key(block_hash.pack(), tx_index as u32);
+                let receipt = TxReceipt::new_builder().logs(logs.pack()).build();
+                batch.put(
+                    COLUMN_TRANSACTION_RECEIPT,
+                    key.as_slice(),
+                    receipt.as_slice(),
+                )?;
+            }
+            Ok(())
+        };
+        insert_block(0, vec![])?;
+        insert_block(1, vec![vec![log(1), log(2)], vec![log(3)]])?;
+        insert_block(2, vec![vec![], vec![log(4)]])?;
+        db.write(&batch)?;
+
+        let factory = init_migration_factory();
+        let db = factory.migrate(db)?;
+
+        let mut logs = Vec::new();
+        db.traverse(COLUMN_LOG_INDEX, |key, value| {
+            let block_number = u64::from_be_bytes(key[..8].try_into().unwrap());
+            let tx_index = u32::from_be_bytes(key[8..12].try_into().unwrap());
+            let log_index = u32::from_be_bytes(key[12..].try_into().unwrap());
+            let account_id: u32 = LogItemReader::from_slice(value)
+                .unwrap()
+                .account_id()
+                .unpack();
+            logs.push((block_number, tx_index, log_index, account_id));
+            Ok(())
+        })?;
+        assert_eq!(
+            logs,
+            vec![(1, 0, 0, 1), (1, 0, 1, 2), (1, 1, 0, 3), (2, 1, 0, 4)]
+        );
+        Ok(())
+    }
 }
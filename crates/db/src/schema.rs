@@ -3,7 +3,7 @@
 /// Column families alias type
 pub type Col = u8;
 /// Total column number
-pub const COLUMNS: u32 = 37;
+pub const COLUMNS: u32 = 42;
 /// Column store meta data
 pub const COLUMN_META: Col = 0;
 /// Column store chain index
@@ -79,6 +79,26 @@ pub const COLUMN_BLOCK_SUBMIT_TX_HASH: Col = 7;
 pub const COLUMN_BLOCK_DEPOSIT_INFO_VEC: Col = 16;
 /// block number (in big endian) -> FinalizedCustodianCapacity.
 pub const COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY: Col = 36;
+/// Log index of committed blocks.
+///
+/// (block number, tx index, log index) (in big endian) -> LogItem.
+pub const COLUMN_LOG_INDEX: Col = 37;
+/// Transactions sent from an account, only inserted if enabled. Entries of
+/// detached blocks are always deleted.
+///
+/// (from id, block number, tx index) (in big endian) -> tx hash.
+pub const COLUMN_ACCOUNT_TX_FROM_INDEX: Col = 38;
+/// Transactions sent to an account, only inserted if enabled. Entries of
+/// detached blocks are always deleted.
+///
+/// (to id, block number, tx index) (in big endian) -> tx hash.
+pub const COLUMN_ACCOUNT_TX_TO_INDEX: Col = 39;
+/// Deposit cell out point -> block number (in big endian) of the block which
+/// includes the deposit.
+pub const COLUMN_DEPOSIT_INDEX: Col = 40;
+/// Deposit cell out point -> DepositLog, what the mem-pool did with the
+/// deposit cell before it is included in a block.
+pub const COLUMN_DEPOSIT_LOG: Col = 41;
 
 /// chain id
 pub const META_CHAIN_ID_KEY: &[u8] = b"CHAIN_ID";
@@ -95,6 +115,13 @@ pub const META_LAST_VALID_TIP_BLOCK_HASH_KEY: &[u8] = b"LAST_VALID_TIP_BLOCK_HAS
 pub const META_LAST_CONFIRMED_BLOCK_NUMBER_HASH_KEY: &[u8] = b"LAST_CONFIRMED_BLOCK_NUMBER";
 /// track the last submitted l2 block NumberAndHash
 pub const META_LAST_SUBMITTED_BLOCK_NUMBER_HASH_KEY: &[u8] = b"LAST_SUBMITTED_BLOCK_NUMBER";
+/// block state history before this block number is pruned
+pub const META_STATE_PRUNED_BLOCK_NUMBER_KEY: &[u8] = b"STATE_PRUNED_BLOCK_NUMBER";
+/// the store is imported from a snapshot at this block, blocks before it are not available
+pub const META_SNAPSHOT_BLOCK_NUMBER_KEY: &[u8] = b"SNAPSHOT_BLOCK_NUMBER";
+/// a snapshot import is in progress or failed, the store is incomplete until
+/// the snapshot tip is set
+pub const META_SNAPSHOT_IMPORTING_KEY: &[u8] = b"SNAPSHOT_IMPORTING";
 
 /// CHAIN_SPEC_HASH_KEY tracks the hash of chain spec which created current database
 pub const CHAIN_SPEC_HASH_KEY: &[u8] = b"chain-spec-hash";
@@ -1,5 +1,6 @@
 pub mod fee_config;
 pub mod manager;
+pub mod source;
 pub mod whitelist_config;
 
-pub use crate::manager::{reload, try_reload};
+pub use crate::manager::{reload, try_reload, update, watch};
@@ -1,68 +1,86 @@
-use std::sync::Arc;
+use std::{collections::BTreeSet, path::Path, sync::Arc, time::Duration};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, ensure, Result};
 
 use arc_swap::ArcSwap;
-use gw_config::{Config, DynamicConfig, FeeConfig};
+use gw_config::{Config, DynamicConfig, FeeConfig, ReloadConfigSource};
 use gw_tx_filter::{
     erc20_creator_allowlist::SUDTProxyAccountAllowlist,
     polyjuice_contract_creator_allowlist::PolyjuiceContractCreatorAllowList,
 };
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::{fee_config::FeeConfigManager, whitelist_config::WhilteListConfigManager};
+use crate::{fee_config::FeeConfigManager, source, whitelist_config::WhilteListConfigManager};
 
 // Some configs can be hot reloaded through DynamicConfigManager.
 // So that we don't need to restart to take effect every time.
 #[derive(Default, Clone)]
 pub struct DynamicConfigManager {
-    config_github_url: Option<(String, String)>, // url and token
+    source: Option<ReloadConfigSource>,
+    admin_token: Option<String>,
 
     fee_manager: FeeConfigManager,
     whitelist_manager: WhilteListConfigManager,
 }
 
 impl DynamicConfigManager {
-    pub fn create(config: Config) -> Self {
-        let config_github_url = config.reload_config_github_url.as_ref().map(|r| {
-            (
-                format!(
-                    "https://raw.githubusercontent.com/{}/{}/{}/{}",
-                    r.org, r.repo, r.branch, r.path
-                ),
-                r.token.clone(),
-            )
-        });
+    /// Fails if the reload source is not allowed, see `source::check`.
+    pub fn create(config: Config) -> Result<Self> {
+        let source = match config.reload_config.source {
+            Some(source) => Some(source),
+            None => config
+                .reload_config_github_url
+                .as_ref()
+                .map(source::github_source),
+        };
+        if let Some(source) = source.as_ref() {
+            source::check(source)?;
+        }
         let fee_manager = FeeConfigManager::create(config.dynamic_config.fee_config.clone());
         let whitelist_manager = WhilteListConfigManager::create(config.dynamic_config.rpc_config);
 
-        Self {
-            config_github_url,
+        Ok(Self {
+            source,
+            admin_token: config.reload_config.admin_token,
             fee_manager,
             whitelist_manager,
+        })
+    }
+
+    pub fn source(&self) -> Option<&ReloadConfigSource> {
+        self.source.as_ref()
+    }
+
+    pub fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
+
+    pub fn current_config(&self) -> DynamicConfig {
+        DynamicConfig {
+            fee_config: self.fee_manager.get_fee_config().clone(),
+            rpc_config: self.whitelist_manager.get_rpc_config().clone(),
         }
     }
 
-    pub async fn reload(&mut self) -> Result<DynamicConfigReloadResponse> {
-        // Fetch latest config.
-        let new_config = if let Some((url, token)) = &self.config_github_url {
-            get_github_config(url, token).await?
+    // Validate and diff the new config before applying it.
+    pub fn apply(&mut self, new_config: DynamicConfig) -> Result<DynamicConfigReloadResponse> {
+        if let Err(err) = validate(&new_config) {
+            log::warn!("reject dynamic config: {:#}", err);
+            return Err(err);
+        }
+        let old_config = self.current_config();
+        let changes = diff(&old_config, &new_config)?;
+        if changes.is_empty() {
+            log::info!("dynamic config unchanged");
         } else {
-            return Err(anyhow!("Github config url is absent!"));
-        };
-
-        let new_config = new_config.dynamic_config;
-        let backup_config = new_config.clone();
-        let old_fee_config = self.fee_manager.reload(new_config.fee_config);
-        let old_rpc_config = self.whitelist_manager.reload(new_config.rpc_config);
-        let old_config = DynamicConfig {
-            fee_config: old_fee_config,
-            rpc_config: old_rpc_config,
-        };
+            self.fee_manager.reload(new_config.fee_config.clone());
+            self.whitelist_manager.reload(new_config.rpc_config.clone());
+            log::info!("dynamic config changed: {}", changes.join(", "));
+        }
         let res = DynamicConfigReloadResponse {
             old: old_config,
-            new: backup_config,
+            new: new_config,
+            changes,
         };
         Ok(res)
     }
@@ -83,45 +101,256 @@ impl DynamicConfigManager {
     }
 }
 
-async fn get_github_config(url: &str, token: &str) -> Result<Config> {
-    let token = format!("token {}", token);
-    let res = Client::builder()
-        .build()?
-        .get(url)
-        .header("Authorization", token)
-        .send()
-        .await?
-        .text()
-        .await?;
-    let config = toml::from_str(&res)?;
-    Ok(config)
+fn validate(config: &DynamicConfig) -> Result<()> {
+    let fee_config = &config.fee_config;
+    for (name, cycles_limit) in [
+        ("meta_cycles_limit", fee_config.meta_cycles_limit),
+        ("sudt_cycles_limit", fee_config.sudt_cycles_limit),
+        (
+            "eth_addr_reg_cycles_limit",
+            fee_config.eth_addr_reg_cycles_limit,
+        ),
+        ("withdraw_cycles_limit", fee_config.withdraw_cycles_limit),
+    ] {
+        // Fee rate is fee / cycles limit.
+        ensure!(cycles_limit > 0, "fee_config.{} must not be 0", name);
+    }
+
+    let rpc_config = &config.rpc_config;
+    ensure!(
+        rpc_config.allowed_polyjuice_contract_creator_address.is_none()
+            || rpc_config.polyjuice_script_code_hash.is_some(),
+        "rpc_config.polyjuice_script_code_hash is required by allowed_polyjuice_contract_creator_address"
+    );
+    if let Some(ref rate_limit) = rpc_config.send_tx_rate_limit {
+        ensure!(
+            rate_limit.lru_size > 0,
+            "rpc_config.send_tx_rate_limit.lru_size must not be 0"
+        );
+    }
+    Ok(())
+}
+
+/// Paths of changed fields, e.g. `fee_config.meta_cycles_limit`.
+fn diff(old: &DynamicConfig, new: &DynamicConfig) -> Result<Vec<String>> {
+    let old = toml::Value::try_from(old)?;
+    let new = toml::Value::try_from(new)?;
+    let mut changes = Vec::new();
+    diff_value("", &old, &new, &mut changes);
+    Ok(changes)
+}
+
+fn diff_value(path: &str, old: &toml::Value, new: &toml::Value, changes: &mut Vec<String>) {
+    use toml::Value;
+
+    match (old, new) {
+        (Value::Table(old), Value::Table(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.to_owned()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_value(&path, old, new, changes),
+                    _ => changes.push(path),
+                }
+            }
+        }
+        // Arrays are compared as sets, e.g. allowed creator addresses.
+        (Value::Array(old), Value::Array(new)) => {
+            let old: BTreeSet<String> = old.iter().map(|v| v.to_string()).collect();
+            let new: BTreeSet<String> = new.iter().map(|v| v.to_string()).collect();
+            if old != new {
+                changes.push(path.to_owned());
+            }
+        }
+        (old, new) => {
+            if old != new {
+                changes.push(path.to_owned());
+            }
+        }
+    }
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DynamicConfigReloadResponse {
     old: DynamicConfig,
     new: DynamicConfig,
+    changes: Vec<String>,
 }
 
+impl DynamicConfigReloadResponse {
+    pub fn changes(&self) -> &[String] {
+        &self.changes
+    }
+}
+
+// Fetch before copying the manager, otherwise a config pushed by admin while
+// fetching is overwritten by the stale copy.
 pub async fn reload(
     manager: Arc<ArcSwap<DynamicConfigManager>>,
 ) -> Result<DynamicConfigReloadResponse> {
-    let mut config = (**manager.load()).to_owned();
-    let resp = config.reload().await;
-    manager.store(Arc::new(config));
-    resp
+    let source = manager
+        .load()
+        .source()
+        .cloned()
+        .ok_or_else(|| anyhow!("reload config source is absent!"))?;
+    let new_config = source::fetch(&source).await?;
+    update(manager, new_config)
 }
 
 pub async fn try_reload(
     manager: Arc<ArcSwap<DynamicConfigManager>>,
 ) -> Option<Result<DynamicConfigReloadResponse>> {
-    let mut config = (**manager.load()).to_owned();
-    match config.config_github_url {
-        Some(_) => {
-            let resp = config.reload().await;
-            manager.store(Arc::new(config));
-            Some(resp)
+    if manager.load().source().is_none() {
+        return None;
+    }
+    Some(reload(manager).await)
+}
+
+/// Apply a config pushed by admin, e.g. through RPC.
+pub fn update(
+    manager: Arc<ArcSwap<DynamicConfigManager>>,
+    new_config: DynamicConfig,
+) -> Result<DynamicConfigReloadResponse> {
+    // Apply to the latest manager, retried if another update is stored
+    // meanwhile.
+    let mut result = None;
+    manager.rcu(|current| {
+        let mut config = DynamicConfigManager::clone(current);
+        match config.apply(new_config.clone()) {
+            Ok(resp) => {
+                result = Some(Ok(resp));
+                Arc::new(config)
+            }
+            Err(err) => {
+                result = Some(Err(err));
+                Arc::clone(current)
+            }
+        }
+    });
+    result.expect("rcu result")
+}
+
+/// Reload when the file source is modified. Returns immediately if the source
+/// is not a watched file.
+pub async fn watch(manager: Arc<ArcSwap<DynamicConfigManager>>) {
+    let (path, interval) = match manager.load().source() {
+        Some(ReloadConfigSource::File {
+            path,
+            watch_interval_secs: Some(secs),
+        }) => (path.clone(), Duration::from_secs((*secs).max(1))),
+        _ => return,
+    };
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    log::info!("watching dynamic config file {}", path.to_string_lossy());
+    let mut last_modified = modified(&path);
+    loop {
+        tokio::time::sleep(interval).await;
+        let current = modified(&path);
+        if current.is_none() || current == last_modified {
+            continue;
+        }
+        last_modified = current;
+        log::info!(
+            "dynamic config file {} modified, reloading",
+            path.to_string_lossy()
+        );
+        if let Err(err) = reload(manager.clone()).await {
+            log::warn!("reload dynamic config: {:#}", err);
         }
-        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_config::RPCRateLimit;
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&DynamicConfig::default()).is_ok());
+
+        let mut config = DynamicConfig::default();
+        config.fee_config.sudt_cycles_limit = 0;
+        let err = validate(&config).unwrap_err();
+        assert!(err.to_string().contains("sudt_cycles_limit"), "{}", err);
+
+        let mut config = DynamicConfig::default();
+        config.rpc_config.allowed_polyjuice_contract_creator_address = Some(Default::default());
+        assert!(validate(&config).is_err());
+        config.rpc_config.polyjuice_script_code_hash = Some(Default::default());
+        assert!(validate(&config).is_ok());
+
+        let mut config = DynamicConfig::default();
+        config.rpc_config.send_tx_rate_limit = Some(RPCRateLimit {
+            seconds: 1,
+            lru_size: 0,
+        });
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = DynamicConfig::default();
+        assert!(diff(&old, &old).unwrap().is_empty());
+
+        let mut new = old.clone();
+        new.fee_config.meta_cycles_limit += 1;
+        new.rpc_config.allowed_sudt_proxy_creator_account_id = vec![1, 2];
+        new.rpc_config.polyjuice_script_code_hash = Some(Default::default());
+        assert_eq!(
+            diff(&old, &new).unwrap(),
+            vec![
+                "fee_config.meta_cycles_limit",
+                "rpc_config.allowed_sudt_proxy_creator_account_id",
+                "rpc_config.polyjuice_script_code_hash",
+            ]
+        );
+
+        // Arrays are compared as sets.
+        let mut reordered = new.clone();
+        reordered.rpc_config.allowed_sudt_proxy_creator_account_id = vec![2, 1];
+        assert!(diff(&new, &reordered).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut manager = DynamicConfigManager::default();
+        let mut new_config = manager.current_config();
+        new_config.fee_config.withdraw_cycles_limit += 1;
+
+        let resp = manager.apply(new_config.clone()).unwrap();
+        assert_eq!(resp.changes(), ["fee_config.withdraw_cycles_limit"]);
+        assert_eq!(manager.current_config(), new_config);
+
+        let resp = manager.apply(new_config.clone()).unwrap();
+        assert!(resp.changes().is_empty());
+
+        // An invalid config is rejected and the current one is kept.
+        let mut invalid = new_config.clone();
+        invalid.fee_config.meta_cycles_limit = 0;
+        assert!(manager.apply(invalid).is_err());
+        assert_eq!(manager.current_config(), new_config);
+    }
+
+    #[test]
+    fn test_update() {
+        let manager = Arc::new(ArcSwap::from_pointee(DynamicConfigManager::default()));
+        let mut new_config = manager.load().current_config();
+        new_config.fee_config.sudt_cycles_limit += 1;
+
+        let resp = update(manager.clone(), new_config.clone()).unwrap();
+        assert_eq!(resp.changes(), ["fee_config.sudt_cycles_limit"]);
+        assert_eq!(manager.load().current_config(), new_config);
+
+        let mut invalid = new_config.clone();
+        invalid.fee_config.sudt_cycles_limit = 0;
+        assert!(update(manager.clone(), invalid).is_err());
+        assert_eq!(manager.load().current_config(), new_config);
     }
 }
@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use gw_config::{DynamicConfig, GithubConfigUrl, ReloadConfigSource};
+use reqwest::Client;
+use serde::Deserialize;
+
+// Other tables of the source are ignored.
+#[derive(Deserialize)]
+struct DynamicConfigFile {
+    dynamic_config: DynamicConfig,
+}
+
+/// Legacy `reload_config_github_url` as an URL source.
+pub fn github_source(github: &GithubConfigUrl) -> ReloadConfigSource {
+    ReloadConfigSource::Url {
+        url: format!(
+            "https://raw.githubusercontent.com/{}/{}/{}/{}",
+            github.org, github.repo, github.branch, github.path
+        ),
+        authorization: Some(format!("token {}", github.token)),
+    }
+}
+
+/// Only https URLs are allowed, the `Authorization` header must not be sent
+/// in plain text.
+pub fn check(source: &ReloadConfigSource) -> Result<()> {
+    match source {
+        ReloadConfigSource::File { .. } => Ok(()),
+        ReloadConfigSource::Url { url, .. } => check_url(url),
+    }
+}
+
+fn check_url(url: &str) -> Result<()> {
+    if !url.starts_with("https://") {
+        bail!("dynamic config url must be https, got {}", url);
+    }
+    Ok(())
+}
+
+pub async fn fetch(source: &ReloadConfigSource) -> Result<DynamicConfig> {
+    match source {
+        ReloadConfigSource::File { path, .. } => read_file(path),
+        ReloadConfigSource::Url { url, authorization } => {
+            fetch_url(url, authorization.as_deref()).await
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<DynamicConfig> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("read dynamic config from {}", path.to_string_lossy()))?;
+    parse(&content)
+}
+
+async fn fetch_url(url: &str, authorization: Option<&str>) -> Result<DynamicConfig> {
+    check_url(url)?;
+    let mut req = Client::builder().build()?.get(url);
+    if let Some(authorization) = authorization {
+        req = req.header("Authorization", authorization);
+    }
+    let res = req.send().await?.error_for_status()?.text().await?;
+    parse(&res).with_context(|| format!("parse dynamic config from {}", url))
+}
+
+fn parse(content: &str) -> Result<DynamicConfig> {
+    let file: DynamicConfigFile = toml::from_str(content)?;
+    Ok(file.dynamic_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let url_source = |url: &str| ReloadConfigSource::Url {
+            url: url.into(),
+            authorization: Some("token xxx".into()),
+        };
+        assert!(check(&url_source("https://example.com/config.toml")).is_ok());
+        assert!(check(&url_source("http://example.com/config.toml")).is_err());
+        assert!(check(&url_source("HTTP://example.com/config.toml")).is_err());
+        assert!(check(&ReloadConfigSource::File {
+            path: "config.toml".into(),
+            watch_interval_secs: None,
+        })
+        .is_ok());
+    }
+}
@@ -32,6 +32,10 @@ impl WhilteListConfigManager {
         &self.sudt_proxy_account_whitelist
     }
 
+    pub(crate) fn get_rpc_config(&self) -> &RPCConfig {
+        &self.rpc_config
+    }
+
     // Return old config
     pub fn reload(&mut self, rpc_config: RPCConfig) -> RPCConfig {
         let (polyjuice_contract_creator_allowlist, sudt_proxy_account_whitelist) =
@@ -41,4 +41,13 @@ impl LockAlgorithm for AlwaysSuccess {
     ) -> Result<(), LockAlgorithmError> {
         Ok(())
     }
+
+    fn verify_offchain_message(
+        &self,
+        _sender_script: Script,
+        _message: H256,
+        _signature: &[u8],
+    ) -> Result<(), LockAlgorithmError> {
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use gw_common::{registry_address::RegistryAddress, H256};
+use gw_common::{blake2b::new_blake2b, registry_address::RegistryAddress, H256};
 use gw_types::{
     bytes::Bytes,
     offchain::RollupContext,
@@ -11,6 +11,7 @@ use gw_types::{
 pub mod always_success;
 pub mod eip712;
 pub mod secp256k1;
+pub mod tron;
 
 use crate::error::LockAlgorithmError;
 
@@ -33,6 +34,32 @@ pub trait LockAlgorithm {
         withdrawal: &WithdrawalRequestExtra,
         withdrawal_address: RegistryAddress,
     ) -> Result<(), LockAlgorithmError>;
+
+    /// Verify signature of an off-chain message, e.g. request cancellation.
+    fn verify_offchain_message(
+        &self,
+        sender_script: Script,
+        message: H256,
+        signature: &[u8],
+    ) -> Result<(), LockAlgorithmError>;
+}
+
+/// Message signed by the sender to cancel a queued request before
+/// `expire_at` (unix timestamp in seconds), see
+/// `LockAlgorithm::verify_offchain_message`.
+pub fn cancel_request_message(
+    rollup_script_hash: &H256,
+    request_hash: &H256,
+    expire_at: u64,
+) -> H256 {
+    let mut hasher = new_blake2b();
+    hasher.update(rollup_script_hash.as_slice());
+    hasher.update(b"cancel");
+    hasher.update(request_hash.as_slice());
+    hasher.update(&expire_at.to_le_bytes());
+    let mut message = [0u8; 32];
+    hasher.finalize(&mut message);
+    message.into()
 }
 
 #[derive(Default)]
@@ -24,7 +24,7 @@ lazy_static! {
     pub static ref SECP256K1: secp256k1::Secp256k1<secp256k1::All> = secp256k1::Secp256k1::new();
 }
 
-fn convert_signature_to_byte65(signature: &[u8]) -> Result<[u8; 65], LockAlgorithmError> {
+pub(super) fn convert_signature_to_byte65(signature: &[u8]) -> Result<[u8; 65], LockAlgorithmError> {
     signature.try_into().map_err(|_| {
         LockAlgorithmError::InvalidSignature(format!(
             "Signature length is {}, expect 65",
@@ -233,6 +233,24 @@ impl LockAlgorithm for Secp256k1Eth {
         )?;
         Ok(())
     }
+
+    /// The message is signed with the ethereum personal message prefix.
+    fn verify_offchain_message(
+        &self,
+        sender_script: Script,
+        message: H256,
+        signature: &[u8],
+    ) -> Result<(), LockAlgorithmError> {
+        let mut hasher = Keccak256::new();
+        hasher.update("\x19Ethereum Signed Message:\n32");
+        hasher.update(message.as_slice());
+        let signing_message: [u8; 32] = hasher.finalize().into();
+        self.verify_alone(
+            sender_script.args().unpack(),
+            Bytes::copy_from_slice(signature),
+            signing_message.into(),
+        )
+    }
 }
 
 fn try_assemble_polyjuice_args(
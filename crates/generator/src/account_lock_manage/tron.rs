@@ -0,0 +1,353 @@
+use super::secp256k1::{convert_signature_to_byte65, Secp256k1Eth};
+use super::LockAlgorithm;
+use crate::error::LockAlgorithmError;
+use gw_common::registry_address::RegistryAddress;
+use gw_common::H256;
+use gw_types::offchain::RollupContext;
+use gw_types::packed::WithdrawalRequestExtra;
+use gw_types::prelude::*;
+use gw_types::{
+    bytes::Bytes,
+    packed::{L2Transaction, Script},
+};
+use sha3::{Digest, Keccak256};
+
+/// Tron EOA lock
+///
+/// script args: rollup_script_hash(32 bytes) | tron_address(20 bytes)
+///
+/// A Tron address is derived from the secp256k1 pubkey exactly like an ETH
+/// address, so Tron accounts are registered in the ETH registry. Signatures
+/// are made over `calc_message` with the Tron personal message prefix, the
+/// same format `tron-account-lock` verifies with `SigningType::WithPrefix`.
+#[derive(Debug, Default)]
+pub struct Secp256k1Tron;
+
+impl Secp256k1Tron {
+    pub fn tron_signing_message(message: H256) -> H256 {
+        let mut hasher = Keccak256::new();
+        hasher.update("\x19TRON Signed Message:\n32");
+        hasher.update(message.as_slice());
+        let signing_message: [u8; 32] = hasher.finalize().into();
+        signing_message.into()
+    }
+
+    fn verify_message(
+        &self,
+        lock_args: Bytes,
+        signature: Bytes,
+        message: H256,
+    ) -> Result<(), LockAlgorithmError> {
+        if lock_args.len() != 52 {
+            return Err(LockAlgorithmError::InvalidLockArgs);
+        }
+
+        let signing_message = Self::tron_signing_message(message);
+        let pubkey_hash = self.recover(signing_message, signature.as_ref())?;
+        if pubkey_hash.as_ref() != &lock_args[32..52] {
+            return Err(LockAlgorithmError::InvalidSignature(
+                "Secp256k1Tron: Mismatch pubkey hash".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl LockAlgorithm for Secp256k1Tron {
+    fn recover(&self, message: H256, signature: &[u8]) -> Result<Bytes, LockAlgorithmError> {
+        let mut signature = convert_signature_to_byte65(signature)?;
+        // Same recovery id mapping as tron-account-lock
+        signature[64] = match signature[64] {
+            28 => 1,
+            _ => 0,
+        };
+        // Tron uses the same recoverable signature and pubkey hash as ETH
+        Secp256k1Eth::default().recover(message, &signature)
+    }
+
+    fn verify_tx(
+        &self,
+        ctx: &RollupContext,
+        _sender_address: RegistryAddress,
+        sender_script: Script,
+        receiver_script: Script,
+        tx: L2Transaction,
+    ) -> Result<(), LockAlgorithmError> {
+        let expected_chain_id = ctx.rollup_config.chain_id().unpack();
+        let chain_id = tx.raw().chain_id().unpack();
+        if expected_chain_id != chain_id {
+            return Err(LockAlgorithmError::InvalidTransactionArgs);
+        }
+
+        let message = tx.raw().calc_message(
+            &ctx.rollup_script_hash,
+            &sender_script.hash().into(),
+            &receiver_script.hash().into(),
+        );
+        self.verify_message(
+            sender_script.args().unpack(),
+            tx.signature().unpack(),
+            message,
+        )
+    }
+
+    fn verify_withdrawal(
+        &self,
+        ctx: &RollupContext,
+        sender_script: Script,
+        withdrawal: &WithdrawalRequestExtra,
+        _address: RegistryAddress,
+    ) -> Result<(), LockAlgorithmError> {
+        let expected_chain_id = ctx.rollup_config.chain_id().unpack();
+        let chain_id = withdrawal.raw().chain_id().unpack();
+        if expected_chain_id != chain_id {
+            return Err(LockAlgorithmError::InvalidSignature(format!(
+                "Invalid chain id {} expected {}",
+                chain_id, expected_chain_id
+            )));
+        }
+
+        let message = withdrawal.raw().calc_message(&ctx.rollup_script_hash);
+        self.verify_message(
+            sender_script.args().unpack(),
+            withdrawal.request().signature().unpack(),
+            message,
+        )
+    }
+
+    /// The message is signed with the Tron personal message prefix.
+    fn verify_offchain_message(
+        &self,
+        sender_script: Script,
+        message: H256,
+        signature: &[u8],
+    ) -> Result<(), LockAlgorithmError> {
+        self.verify_message(
+            sender_script.args().unpack(),
+            Bytes::copy_from_slice(signature),
+            message,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account_lock_manage::secp256k1::SECP256K1;
+    use gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID;
+    use gw_types::packed::{
+        RawL2Transaction, RawWithdrawalRequest, RollupConfig, WithdrawalRequest,
+    };
+    use secp256k1::{PublicKey, SecretKey};
+
+    const CHAIN_ID: u64 = 42;
+
+    fn privkey() -> SecretKey {
+        SecretKey::from_slice(&[7u8; 32]).expect("privkey")
+    }
+
+    fn tron_address(privkey: &SecretKey) -> Vec<u8> {
+        let pubkey = PublicKey::from_secret_key(&SECP256K1, privkey);
+        let mut hasher = Keccak256::new();
+        hasher.update(&pubkey.serialize_uncompressed()[1..]);
+        hasher.finalize()[12..].to_vec()
+    }
+
+    fn sign_message(privkey: &SecretKey, message: H256) -> Bytes {
+        let signing_message = Secp256k1Tron::tron_signing_message(message);
+        let msg = secp256k1::Message::from_slice(signing_message.as_slice()).expect("message");
+        let (rec_id, data) = SECP256K1
+            .sign_recoverable(&msg, privkey)
+            .serialize_compact();
+        let mut signature = data.to_vec();
+        // Same as the signer of tron-account-lock tests
+        signature.push(match rec_id.to_i32() {
+            1 => 28,
+            _ => 0,
+        });
+        signature.into()
+    }
+
+    fn ctx() -> RollupContext {
+        RollupContext {
+            rollup_script_hash: [42u8; 32].into(),
+            rollup_config: RollupConfig::new_builder()
+                .chain_id(CHAIN_ID.pack())
+                .build(),
+        }
+    }
+
+    fn build_sender_script(address: &[u8]) -> Script {
+        let mut args = [42u8; 32].to_vec();
+        args.extend_from_slice(address);
+        Script::new_builder().args(Bytes::from(args).pack()).build()
+    }
+
+    #[test]
+    fn test_secp256k1_tron_verify_tx() {
+        let privkey = privkey();
+        let address = tron_address(&privkey);
+        let sender_address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, address.clone());
+        let sender_script = build_sender_script(&address);
+        let receiver_script = Script::new_builder()
+            .args(Bytes::from(vec![3u8; 36]).pack())
+            .build();
+
+        let ctx = ctx();
+        let raw_tx = RawL2Transaction::new_builder()
+            .chain_id(CHAIN_ID.pack())
+            .nonce(3u32.pack())
+            .to_id(4u32.pack())
+            .args(Bytes::from(vec![1u8; 16]).pack())
+            .build();
+        let message = raw_tx.calc_message(
+            &ctx.rollup_script_hash,
+            &sender_script.hash().into(),
+            &receiver_script.hash().into(),
+        );
+        let tx = L2Transaction::new_builder()
+            .raw(raw_tx.clone())
+            .signature(sign_message(&privkey, message).pack())
+            .build();
+
+        let tron = Secp256k1Tron::default();
+        tron.verify_tx(
+            &ctx,
+            sender_address.clone(),
+            sender_script.clone(),
+            receiver_script.clone(),
+            tx,
+        )
+        .expect("verify signature");
+
+        // wrong signature
+        let tx = L2Transaction::new_builder()
+            .raw(raw_tx)
+            .signature(sign_message(&privkey, [1u8; 32].into()).pack())
+            .build();
+        let err = tron
+            .verify_tx(&ctx, sender_address, sender_script, receiver_script, tx)
+            .unwrap_err();
+        assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+    }
+
+    #[test]
+    fn test_secp256k1_tron_reject_raw_signing() {
+        let privkey = privkey();
+        let address = tron_address(&privkey);
+        let sender_address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, address.clone());
+        let sender_script = build_sender_script(&address);
+        let receiver_script = Script::default();
+
+        let ctx = ctx();
+        let raw_tx = RawL2Transaction::new_builder()
+            .chain_id(CHAIN_ID.pack())
+            .build();
+        let message = raw_tx.calc_message(
+            &ctx.rollup_script_hash,
+            &sender_script.hash().into(),
+            &receiver_script.hash().into(),
+        );
+        // sign message without the Tron prefix
+        let signature = {
+            let msg = secp256k1::Message::from_slice(message.as_slice()).expect("message");
+            let (rec_id, data) = SECP256K1
+                .sign_recoverable(&msg, &privkey)
+                .serialize_compact();
+            let mut signature = data.to_vec();
+            signature.push(rec_id.to_i32() as u8);
+            Bytes::from(signature)
+        };
+        let tx = L2Transaction::new_builder()
+            .raw(raw_tx)
+            .signature(signature.pack())
+            .build();
+        Secp256k1Tron::default()
+            .verify_tx(&ctx, sender_address, sender_script, receiver_script, tx)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_secp256k1_tron_fixed_signature() {
+        // privkey: [7u8; 32]
+        let address = hex::decode("4a62316623ad457f02cdc5d997ded67a383ec569").expect("hex decode");
+        assert_eq!(address, tron_address(&privkey()));
+        let sender_script = build_sender_script(&address);
+        let fixed_signature = |sig: &str, v: u8| {
+            let mut signature = hex::decode(sig).expect("hex decode");
+            signature.push(v);
+            signature
+        };
+
+        let tron = Secp256k1Tron::default();
+        // recovery id 1
+        let message: H256 = [1u8; 32].into();
+        let sig = "485c093cea0ce5f8e10e3f0b7dabeb3d9899bf146eefc8a7d5b3c8630afd83dc2e23b4224843b2d565026499b7e8ed8a878bfd556a987ff4c9b09c585b1cafd4";
+        let signing_message = Secp256k1Tron::tron_signing_message(message);
+        assert_eq!(
+            hex::encode(signing_message.as_slice()),
+            "5952e3ac4122e080f502152b5944ee7e30f8aa3db49dc828ffdcaee3b3304d7d"
+        );
+        let pubkey_hash = tron
+            .recover(signing_message, &fixed_signature(sig, 28))
+            .expect("recover");
+        assert_eq!(pubkey_hash.as_ref(), address.as_slice());
+        tron.verify_offchain_message(sender_script.clone(), message, &fixed_signature(sig, 28))
+            .expect("verify signature");
+        tron.verify_offchain_message(sender_script.clone(), message, &fixed_signature(sig, 27))
+            .unwrap_err();
+
+        // recovery id 0, any v other than 28 is recovery id 0
+        let message: H256 = [2u8; 32].into();
+        let sig = "e965e89eb548be02466ceda7fb5ba8702ffade3aac445216f7c92830fa8abfcf612052f11a48050b89bf8fb368254ce9b79bc3b3f11907e944241384ea1b713c";
+        for v in [0u8, 1, 27] {
+            tron.verify_offchain_message(sender_script.clone(), message, &fixed_signature(sig, v))
+                .expect("verify signature");
+        }
+        tron.verify_offchain_message(sender_script.clone(), message, &fixed_signature(sig, 28))
+            .unwrap_err();
+
+        // invalid signature length
+        let err = tron
+            .verify_offchain_message(sender_script, message, &fixed_signature(sig, 0)[..64])
+            .unwrap_err();
+        assert!(matches!(err, LockAlgorithmError::InvalidSignature(_)));
+    }
+
+    #[test]
+    fn test_secp256k1_tron_verify_withdrawal() {
+        let privkey = privkey();
+        let address = tron_address(&privkey);
+        let withdrawal_address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, address.clone());
+        let sender_script = build_sender_script(&address);
+
+        let ctx = ctx();
+        let raw = RawWithdrawalRequest::new_builder()
+            .chain_id(CHAIN_ID.pack())
+            .nonce(1u32.pack())
+            .capacity(1000u64.pack())
+            .build();
+        let message = raw.calc_message(&ctx.rollup_script_hash);
+        let request = WithdrawalRequest::new_builder()
+            .raw(raw)
+            .signature(sign_message(&privkey, message).pack())
+            .build();
+        let withdrawal = WithdrawalRequestExtra::new_builder()
+            .request(request)
+            .build();
+
+        let tron = Secp256k1Tron::default();
+        tron.verify_withdrawal(
+            &ctx,
+            sender_script.clone(),
+            &withdrawal,
+            withdrawal_address.clone(),
+        )
+        .expect("verify signature");
+
+        // mismatch lock args
+        let other_script = build_sender_script(&[9u8; 20]);
+        tron.verify_withdrawal(&ctx, other_script, &withdrawal, withdrawal_address)
+            .unwrap_err();
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 };
 
 use crate::{
-    account_lock_manage::AccountLockManage,
+    account_lock_manage::{cancel_request_message, AccountLockManage},
     backend_manage::BackendManage,
     constants::{L2TX_MAX_CYCLES, MAX_READ_DATA_BYTES_LIMIT, MAX_WRITE_DATA_BYTES_LIMIT},
     error::{BlockError, TransactionValidateError, WithdrawalError},
@@ -40,7 +40,10 @@ use gw_traits::{ChainView, CodeStore};
 use gw_types::{
     bytes::Bytes,
     core::{ChallengeTargetType, ScriptHashType},
-    offchain::{CycleMeter, RollupContext, RunResult},
+    offchain::{
+        CycleMeter, RollupContext, RunResult, StateReadTrace, StateWriteTrace, SyscallTrace,
+        TxTrace,
+    },
     packed::{
         AccountMerkleState, BlockInfo, ChallengeTarget, DepositInfoVec, L2Block, L2Transaction,
         LogItem, RawL2Block, RawL2Transaction, TxReceipt, WithdrawalReceipt,
@@ -145,6 +148,7 @@ pub struct MachineRunArgs<'a, C, S> {
     max_cycles: u64,
     backend: Backend,
     cycles_pool: Option<&'a mut CyclesPool>,
+    syscall_trace: Option<&'a mut Vec<SyscallTrace>>,
 }
 
 pub struct Generator {
@@ -194,6 +198,7 @@ impl Generator {
             max_cycles,
             backend,
             mut cycles_pool,
+            syscall_trace,
         } = args;
 
         let mut context = RunContext::default();
@@ -221,6 +226,7 @@ impl Generator {
                     cycles_pool: &mut cycles_pool,
                     log_buf: &mut sys_log_buf,
                     context: &mut context,
+                    syscall_trace,
                 }))
                 .instruction_cycle_func(Box::new(instruction_cycles));
             let default_machine = machine_builder.build();
@@ -334,6 +340,35 @@ impl Generator {
         Ok(())
     }
 
+    /// Check signature of cancelling a queued request of the sender
+    #[instrument(skip_all)]
+    pub fn check_cancel_request_signature<S: State + CodeStore>(
+        &self,
+        state: &S,
+        sender_script_hash: &H256,
+        request_hash: &H256,
+        expire_at: u64,
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let sender_script = state
+            .get_script(sender_script_hash)
+            .ok_or(StateError::MissingKey)?;
+        let lock_code_hash: [u8; 32] = sender_script.code_hash().unpack();
+        let lock_algo = self
+            .account_lock_manage
+            .get_lock_algorithm(&lock_code_hash.into())
+            .ok_or(LockAlgorithmError::UnknownAccountLock)?;
+
+        let message = cancel_request_message(
+            &self.rollup_context.rollup_script_hash,
+            request_hash,
+            expire_at,
+        );
+        lock_algo.verify_offchain_message(sender_script, message, signature)?;
+
+        Ok(())
+    }
+
     // Check transaction signature
     #[instrument(skip_all)]
     pub fn check_transaction_signature<S: State + CodeStore>(
@@ -728,6 +763,52 @@ impl Generator {
         raw_tx: &RawL2Transaction,
         max_cycles: u64,
         cycles_pool: Option<&mut CyclesPool>,
+    ) -> Result<RunResult, TransactionError> {
+        self.execute_transaction_inner(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            max_cycles,
+            cycles_pool,
+            None,
+        )
+    }
+
+    /// execute a layer2 tx and trace syscalls and state keys, doesn't check exit code
+    #[instrument(skip_all, fields(block = block_info.number().unpack(), tx_hash = %raw_tx.hash().pack()))]
+    pub fn trace_transaction<S: State + CodeStore + JournalDB, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &mut S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        max_cycles: u64,
+        cycles_pool: Option<&mut CyclesPool>,
+    ) -> Result<(RunResult, TxTrace), TransactionError> {
+        let mut trace = TxTrace::default();
+        let run_result = self.execute_transaction_inner(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            max_cycles,
+            cycles_pool,
+            Some(&mut trace),
+        )?;
+        Ok((run_result, trace))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_transaction_inner<S: State + CodeStore + JournalDB, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &mut S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        max_cycles: u64,
+        cycles_pool: Option<&mut CyclesPool>,
+        mut trace: Option<&mut TxTrace>,
     ) -> Result<RunResult, TransactionError> {
         let account_id = raw_tx.to_id().unpack();
         let script_hash = state.get_script_hash(account_id)?;
@@ -748,6 +829,7 @@ impl Generator {
             max_cycles,
             backend,
             cycles_pool,
+            syscall_trace: trace.as_mut().map(|trace| &mut trace.syscalls),
         };
 
         let run_context = self.machine_run(args).map_err(|err| {
@@ -773,6 +855,21 @@ impl Generator {
 
         let state_tracker = state.take_state_tracker().unwrap();
 
+        if let Some(trace) = trace {
+            let written_keys = state_tracker.written_keys().lock().unwrap();
+            for key in state_tracker.touched_keys().lock().unwrap().iter() {
+                let value = state.get_raw(key)?;
+                match written_keys.get(key) {
+                    Some(old_value) => trace.writes.push(StateWriteTrace {
+                        key: *key,
+                        old_value: *old_value,
+                        new_value: value,
+                    }),
+                    None => trace.reads.push(StateReadTrace { key: *key, value }),
+                }
+            }
+        }
+
         // check write data bytes
         if let Some(data) = state_tracker
             .write_data()
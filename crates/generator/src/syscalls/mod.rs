@@ -23,7 +23,7 @@ use gw_traits::{ChainView, CodeStore};
 use gw_types::{
     bytes::Bytes,
     core::ScriptHashType,
-    offchain::{CycleMeter, RollupContext},
+    offchain::{CycleMeter, RollupContext, SyscallTrace},
     packed::{BlockInfo, LogItem, RawL2Transaction, Script},
     prelude::*,
 };
@@ -93,6 +93,7 @@ pub(crate) struct L2Syscalls<'a, 'b, S, C> {
     pub(crate) context: &'b mut RunContext,
     pub(crate) cycles_pool: &'b mut Option<&'a mut CyclesPool>,
     pub(crate) log_buf: &'b mut Vec<u8>,
+    pub(crate) syscall_trace: Option<&'b mut Vec<SyscallTrace>>,
 }
 
 #[allow(dead_code)]
@@ -134,6 +135,32 @@ fn load_bytes<Mac: SupportMachine>(
     Ok(data)
 }
 
+fn syscall_name(code: u64) -> &'static str {
+    match code {
+        SYS_CREATE => "SYS_CREATE",
+        SYS_STORE => "SYS_STORE",
+        SYS_LOAD => "SYS_LOAD",
+        SYS_LOAD_ACCOUNT_SCRIPT => "SYS_LOAD_ACCOUNT_SCRIPT",
+        SYS_SET_RETURN_DATA => "SYS_SET_RETURN_DATA",
+        SYS_STORE_DATA => "SYS_STORE_DATA",
+        SYS_LOAD_DATA => "SYS_LOAD_DATA",
+        SYS_LOAD_ROLLUP_CONFIG => "SYS_LOAD_ROLLUP_CONFIG",
+        SYS_LOAD_TRANSACTION => "SYS_LOAD_TRANSACTION",
+        SYS_LOAD_BLOCKINFO => "SYS_LOAD_BLOCKINFO",
+        SYS_GET_BLOCK_HASH => "SYS_GET_BLOCK_HASH",
+        SYS_PAY_FEE => "SYS_PAY_FEE",
+        SYS_LOG => "SYS_LOG",
+        SYS_RECOVER_ACCOUNT => "SYS_RECOVER_ACCOUNT",
+        SYS_BN_ADD => "SYS_BN_ADD",
+        SYS_BN_MUL => "SYS_BN_MUL",
+        SYS_BN_PAIRING => "SYS_BN_PAIRING",
+        SYS_SNAPSHOT => "SYS_SNAPSHOT",
+        SYS_REVERT => "SYS_REVERT",
+        DEBUG_PRINT_SYSCALL_NUMBER => "DEBUG_PRINT",
+        _ => "UNKNOWN",
+    }
+}
+
 pub fn store_data<Mac: SupportMachine>(machine: &mut Mac, data: &[u8]) -> Result<u64, VMError> {
     let addr = machine.registers()[A0].to_u64();
     let size_addr = machine.registers()[A1].clone();
@@ -162,6 +189,19 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
     fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
         let code = machine.registers()[A7].to_u64();
 
+        if let Some(syscall_trace) = self.syscall_trace.as_mut() {
+            let virtual_cycles = self
+                .cycles_pool
+                .as_ref()
+                .map(|pool| Self::get_syscall_cycles(code, pool.syscall_config()))
+                .unwrap_or(0);
+            syscall_trace.push(SyscallTrace {
+                code,
+                name: syscall_name(code),
+                virtual_cycles,
+            });
+        }
+
         if let Some(cycles_pool) = self.cycles_pool {
             let syscall_cycles = Self::get_syscall_cycles(code, cycles_pool.syscall_config());
             if 0 != syscall_cycles {
@@ -4,13 +4,19 @@ static GLOBAL_ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 use anyhow::{Context, Result};
 use clap::{Arg, Command, CommandFactory, Parser};
-use godwoken_bin::subcommand::db_block_validator;
+use godwoken_bin::subcommand::db_block_validator::{self, VerifyArgs};
 use godwoken_bin::subcommand::export_block::{ExportArgs, ExportBlock};
+use godwoken_bin::subcommand::export_snapshot::{ExportSnapshotCommand, COMMAND_EXPORT_SNAPSHOT};
 use godwoken_bin::subcommand::import_block::{ImportArgs, ImportBlock};
+use godwoken_bin::subcommand::import_snapshot::{ImportSnapshotCommand, COMMAND_IMPORT_SNAPSHOT};
 use godwoken_bin::subcommand::peer_id::{PeerIdCommand, COMMAND_PEER_ID};
 use godwoken_bin::subcommand::rewind_to_last_valid_block::{
     RewindToLastValidBlockCommand, COMMAND_REWIND_TO_LAST_VALID_BLOCK,
 };
+use godwoken_bin::subcommand::signer::{SignerCommand, COMMAND_SIGNER};
+use godwoken_bin::subcommand::simulate_challenge::{
+    SimulateChallengeCommand, COMMAND_SIMULATE_CHALLENGE,
+};
 use gw_block_producer::{runner, trace};
 use gw_config::{BackendSwitchConfig, Config};
 use gw_version::Version;
@@ -30,6 +36,10 @@ const ARG_SHOW_PROGRESS: &str = "show-progress";
 const ARG_SOURCE_PATH: &str = "source-path";
 const ARG_READ_BATCH: &str = "read-batch";
 const ARG_REWIND_TO_LAST_VALID_TIP: &str = "rewind-to-last-valid-tip";
+const ARG_WORKERS: &str = "workers";
+const ARG_CHECKPOINT_PATH: &str = "checkpoint-path";
+const ARG_REPORT_PATH: &str = "report-path";
+const ARG_FAIL_FAST: &str = "fail-fast";
 
 fn read_config<P: AsRef<Path>>(path: P) -> Result<Config> {
     let content = fs::read(&path)
@@ -110,6 +120,30 @@ async fn run_cli() -> Result<()> {
                         .takes_value(true)
                         .help("To block number"),
                 )
+                .arg(
+                    Arg::new(ARG_WORKERS)
+                        .short('w')
+                        .long("workers")
+                        .takes_value(true)
+                        .help("Number of block ranges verified concurrently, requires parallel_verify_blocks"),
+                )
+                .arg(
+                    Arg::new(ARG_CHECKPOINT_PATH)
+                        .long("checkpoint-path")
+                        .takes_value(true)
+                        .help("The progress checkpoint file, saved every 100 blocks and on exit, resume from it if exists"),
+                )
+                .arg(
+                    Arg::new(ARG_REPORT_PATH)
+                        .long("report-path")
+                        .takes_value(true)
+                        .help("The output file for JSON report of failed blocks"),
+                )
+                .arg(
+                    Arg::new(ARG_FAIL_FAST)
+                        .long("fail-fast")
+                        .help("Stop on the first failed block, all failed blocks are reported by default"),
+                )
                 .display_order(2),
         )
         .subcommand(
@@ -206,7 +240,11 @@ async fn run_cli() -> Result<()> {
                 .display_order(4),
         )
         .subcommand(PeerIdCommand::command())
-        .subcommand(RewindToLastValidBlockCommand::command());
+        .subcommand(RewindToLastValidBlockCommand::command())
+        .subcommand(ExportSnapshotCommand::command())
+        .subcommand(ImportSnapshotCommand::command())
+        .subcommand(SignerCommand::command())
+        .subcommand(SimulateChallengeCommand::command());
 
     // handle subcommands
     let matches = app.clone().get_matches();
@@ -228,7 +266,17 @@ async fn run_cli() -> Result<()> {
             let _guard = trace::init(None)?;
             let from_block: Option<u64> = m.value_of(ARG_FROM_BLOCK).map(str::parse).transpose()?;
             let to_block: Option<u64> = m.value_of(ARG_TO_BLOCK).map(str::parse).transpose()?;
-            db_block_validator::verify(config, from_block, to_block).await?;
+            let workers: Option<usize> = m.value_of(ARG_WORKERS).map(str::parse).transpose()?;
+
+            let args = VerifyArgs {
+                from_block,
+                to_block,
+                workers,
+                checkpoint_path: m.value_of(ARG_CHECKPOINT_PATH).map(Into::into),
+                report_path: m.value_of(ARG_REPORT_PATH).map(Into::into),
+                fail_fast: m.is_present(ARG_FAIL_FAST),
+            };
+            db_block_validator::verify(config, args).await?;
         }
         Some((COMMAND_EXPORT_BLOCK, m)) => {
             let config_path = m.value_of(ARG_CONFIG).unwrap();
@@ -275,6 +323,22 @@ async fn run_cli() -> Result<()> {
         Some((COMMAND_REWIND_TO_LAST_VALID_BLOCK, m)) => {
             RewindToLastValidBlockCommand::from_clap(m).run().await?;
         }
+        Some((COMMAND_EXPORT_SNAPSHOT, m)) => {
+            let _guard = trace::init(None)?;
+            ExportSnapshotCommand::from_clap(m).run()?;
+        }
+        Some((COMMAND_IMPORT_SNAPSHOT, m)) => {
+            let _guard = trace::init(None)?;
+            ImportSnapshotCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_SIGNER, m)) => {
+            let _guard = trace::init(None)?;
+            SignerCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_SIMULATE_CHALLENGE, m)) => {
+            let _guard = trace::init(None)?;
+            SimulateChallengeCommand::from_clap(m).run().await?;
+        }
         _ => {
             // default command: start a Godwoken node
             let config_path = "./config.toml";
@@ -12,7 +12,9 @@ use gw_challenge::{
 use gw_common::H256;
 use gw_config::{Config, DBBlockValidatorConfig, DebugConfig};
 use gw_generator::Generator;
-use gw_jsonrpc_types::godwoken::ChallengeTargetType as JsonChallengeTargetType;
+use gw_jsonrpc_types::godwoken::{
+    ChallengeTarget as JsonChallengeTarget, ChallengeTargetType as JsonChallengeTargetType,
+};
 use gw_store::{traits::chain_store::ChainStore, Store};
 use gw_types::{
     core::{ChallengeTargetType, Status},
@@ -20,14 +22,34 @@ use gw_types::{
     prelude::{Builder, Entity, Pack, Unpack},
 };
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use std::{
-    fs::{create_dir_all, write},
-    path::PathBuf,
-    sync::Arc,
+    fs::{self, create_dir_all, write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
-pub async fn verify(config: Config, from_block: Option<u64>, to_block: Option<u64>) -> Result<()> {
+/// Save checkpoint every this many verified blocks.
+const CHECKPOINT_INTERVAL: u64 = 100;
+
+pub struct VerifyArgs {
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+    /// Number of block ranges verified concurrently, default to rayon threads.
+    pub workers: Option<usize>,
+    /// Progress checkpoint file, an interrupted run resumes from it.
+    pub checkpoint_path: Option<PathBuf>,
+    /// JSON report of failed targets.
+    pub report_path: Option<PathBuf>,
+    /// Stop on the first failed block instead of verifying the whole range.
+    pub fail_fast: bool,
+}
+
+pub async fn verify(config: Config, args: VerifyArgs) -> Result<()> {
     if config.store.path.as_os_str().is_empty() {
         bail!("empty store path, no db block to verify");
     }
@@ -36,7 +58,16 @@ pub async fn verify(config: Config, from_block: Option<u64>, to_block: Option<u6
     }
 
     let validator = build_validator(config).await?;
-    validator.verify_db(from_block, to_block)?;
+    // Stop on sigint or sigterm, progress is saved to checkpoint before exit.
+    let stop = Arc::new(AtomicBool::new(false));
+    tokio::spawn({
+        let stop = Arc::clone(&stop);
+        async move {
+            sigint_or_sigterm().await;
+            stop.store(true, Ordering::SeqCst);
+        }
+    });
+    tokio::task::spawn_blocking(move || validator.verify_db(args, &stop)).await??;
 
     Ok(())
 }
@@ -93,31 +124,154 @@ impl DBBlockCancelChallengeValidator {
         }
     }
 
-    fn verify_db(&self, from_block: Option<u64>, to_block: Option<u64>) -> Result<()> {
+    fn verify_db(&self, args: VerifyArgs, stop: &AtomicBool) -> Result<()> {
         let db = &self.store.begin_transaction();
-        let from_block = from_block.unwrap_or(0);
-        let to_block = match to_block {
+        let saved = match args.checkpoint_path {
+            Some(ref path) if path.exists() => Some(Checkpoint::load(path)?),
+            _ => None,
+        };
+        // Resume with the range of saved checkpoint unless another range is given.
+        let from_block = args
+            .from_block
+            .or_else(|| saved.as_ref().map(|c| c.from_block))
+            .unwrap_or(0);
+        let to_block = match args.to_block.or_else(|| saved.as_ref().map(|c| c.to_block)) {
             Some(to) => to,
             None => db.get_last_valid_tip_block()?.raw().number().unpack(),
         };
+        if from_block > to_block {
+            bail!("from {} is bigger than to {}", from_block, to_block);
+        }
+        // Targets are verified against history state of parent block.
+        let pruned = db.get_state_pruned_block_number()?;
+        if from_block > 0 && from_block - 1 < pruned {
+            bail!(
+                "state history before block #{} is pruned, can't verify from block #{}",
+                pruned,
+                from_block
+            );
+        }
+
+        let parallel = self.config.parallel_verify_blocks;
+        let checkpoint = match saved {
+            Some(c) if c.from_block == from_block && c.to_block == to_block => {
+                log::info!("resume verification from checkpoint");
+                c
+            }
+            Some(c) => bail!(
+                "checkpoint range #{}..=#{} mismatch, remove it to verify #{}..=#{}",
+                c.from_block,
+                c.to_block,
+                from_block,
+                to_block
+            ),
+            None => {
+                let workers = match args.workers {
+                    Some(_) if !parallel => {
+                        bail!("--workers requires db_block_validator.parallel_verify_blocks")
+                    }
+                    Some(workers) => workers,
+                    None if parallel => rayon::current_num_threads(),
+                    None => 1,
+                };
+                Checkpoint::new(from_block, to_block, workers)
+            }
+        };
 
-        if self.config.parallel_verify_blocks {
-            (from_block..=to_block)
+        let shards = checkpoint.shards.clone();
+        let progress = Progress::new(checkpoint, args.checkpoint_path, CHECKPOINT_INTERVAL);
+        let fail_fast = args.fail_fast;
+        // Failed block with fail fast, it isn't marked as verified.
+        let unverified_failures = Mutex::new(Vec::new());
+        // Each shard verifies its own range in order, so that progress can be
+        // tracked by next block number. Failed blocks are recorded in checkpoint
+        // and verification goes on, unless fail fast is set, then all shards stop
+        // on the first failure.
+        let verify_shard = |(idx, shard): (usize, Shard)| -> Result<()> {
+            for block_number in shard.next_block..=shard.to_block {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let failures = self.verify_block(block_number);
+                if !failures.is_empty() && fail_fast {
+                    stop.store(true, Ordering::SeqCst);
+                    unverified_failures.lock().unwrap().extend(failures);
+                    break;
+                }
+                if let Err(err) = progress.update(idx, block_number, failures) {
+                    stop.store(true, Ordering::SeqCst);
+                    return Err(err);
+                }
+            }
+            Ok(())
+        };
+        let result = if parallel {
+            shards
                 .into_par_iter()
-                .try_for_each(|block_number| self.verify_block(block_number))?;
+                .enumerate()
+                .try_for_each(verify_shard)
         } else {
-            (from_block..=to_block)
-                .into_iter()
-                .try_for_each(|block_number| self.verify_block(block_number))?;
+            shards.into_iter().enumerate().try_for_each(verify_shard)
+        };
+        // Failed block with fail fast is not marked as verified, it will be
+        // verified again on resume.
+        progress.save()?;
+        result?;
+
+        let mut failures = progress.into_checkpoint().failures;
+        failures.extend(unverified_failures.into_inner().unwrap());
+        failures.sort_by_key(|f| f.block_number);
+        if let Some(path) = args.report_path {
+            let report = serde_json::to_string_pretty(&Report {
+                from_block,
+                to_block,
+                failures: &failures,
+            })?;
+            write(&path, report)?;
+            log::info!("write verification report to {:?}", path);
         }
+        if let Some(failure) = failures.first() {
+            let mut blocks: Vec<_> = failures.iter().map(|f| f.block_number).collect();
+            blocks.dedup();
+            bail!(
+                "{} block(s) failed verification, first block #{}: {}",
+                blocks.len(),
+                failure.block_number,
+                failure.error
+            );
+        }
+        if stop.load(Ordering::SeqCst) {
+            bail!("verification interrupted, run again with the checkpoint to resume");
+        }
+        log::info!("verified block #{}..=#{}", from_block, to_block);
 
         Ok(())
     }
 
-    fn verify_block(&self, block_number: u64) -> Result<()> {
-        let db = &self.store.begin_transaction();
+    /// Verify all targets of block, returns failed ones.
+    fn verify_block(&self, block_number: u64) -> Vec<VerifyFailure> {
         log::info!("verify block #{}", block_number);
 
+        let (global_state, block) = match self.get_block(block_number) {
+            Ok(ok) => ok,
+            Err(err) => {
+                log::error!("verify block #{} error {}", block_number, err);
+                return vec![VerifyFailure {
+                    block_number,
+                    target: None,
+                    error: err.to_string(),
+                }];
+            }
+        };
+
+        let mut failures = self.verify_withdrawals(global_state.clone(), &block);
+        failures.extend(self.verify_txs(global_state, &block));
+
+        failures
+    }
+
+    fn get_block(&self, block_number: u64) -> Result<(GlobalState, L2Block)> {
+        let db = &self.store.begin_transaction();
         let block_hash: H256 = {
             let maybe = db.get_block_hash_by_number(block_number)?;
             maybe.ok_or_else(|| anyhow!("block #{} not found", block_number))?
@@ -134,17 +288,14 @@ impl DBBlockCancelChallengeValidator {
             maybe.ok_or_else(|| anyhow!("block #{} not found", block_number))?
         };
 
-        self.verify_withdrawals(global_state.clone(), &block)?;
-        self.verify_txs(global_state, &block)?;
-
-        Ok(())
+        Ok((global_state, block))
     }
 
-    fn verify_withdrawals(&self, global_state: GlobalState, block: &L2Block) -> Result<()> {
+    fn verify_withdrawals(&self, global_state: GlobalState, block: &L2Block) -> Vec<VerifyFailure> {
         let block_hash: H256 = block.hash().into();
         let block_number: u64 = block.raw().number().unpack();
 
-        let verify_withdrawal = |idx| -> Result<()> {
+        let verify_withdrawal = |idx| -> Result<(), VerifyFailure> {
             if self.config.parallel_verify_blocks {
                 log::info!("verify block #{} withdrawal #{}", block_number, idx);
             } else {
@@ -173,53 +324,52 @@ impl DBBlockCancelChallengeValidator {
             };
 
             let target = build_challenge_target(block_hash, idx, ChallengeTargetType::Withdrawal);
-            self.verify(dump_context, global_state.clone(), target)?;
-
-            Ok(())
+            self.verify(dump_context, global_state.clone(), target.clone())
+                .map_err(|err| VerifyFailure::new(block_number, target, err))
         };
 
         (0..(block.withdrawals().len() as u32))
             .into_par_iter()
-            .try_for_each(verify_withdrawal)?;
-
-        Ok(())
+            .filter_map(|idx| verify_withdrawal(idx).err())
+            .collect()
     }
 
-    fn verify_txs(&self, global_state: GlobalState, block: &L2Block) -> Result<()> {
+    fn verify_txs(&self, global_state: GlobalState, block: &L2Block) -> Vec<VerifyFailure> {
         let block_hash: H256 = block.hash().into();
         let block_number: u64 = block.raw().number().unpack();
 
-        let verify_tx =
-            |idx: u32, target_hash: H256, target_type: ChallengeTargetType| -> Result<()> {
-                if let Some(ref skip_targets) = self.config.skip_targets {
-                    let key = (block_number, target_type.into(), idx);
-                    if skip_targets.contains(&key) {
-                        log::info!(
-                            "skip block #{} tx #{} type: {:?}",
-                            block_number,
-                            idx,
-                            target_type
-                        );
-                        return Ok(());
-                    }
+        let verify_tx = |idx: u32,
+                         target_hash: H256,
+                         target_type: ChallengeTargetType|
+         -> Result<(), VerifyFailure> {
+            if let Some(ref skip_targets) = self.config.skip_targets {
+                let key = (block_number, target_type.into(), idx);
+                if skip_targets.contains(&key) {
+                    log::info!(
+                        "skip block #{} tx #{} type: {:?}",
+                        block_number,
+                        idx,
+                        target_type
+                    );
+                    return Ok(());
                 }
+            }
 
-                let dump_context = DumpContext {
-                    block_number,
-                    target_type,
-                    target_index: idx,
-                    target_hash,
-                };
-
-                let target = build_challenge_target(block_hash, idx, target_type);
-                self.verify(dump_context, global_state.clone(), target)?;
-
-                Ok(())
+            let dump_context = DumpContext {
+                block_number,
+                target_type,
+                target_index: idx,
+                target_hash,
             };
 
+            let target = build_challenge_target(block_hash, idx, target_type);
+            self.verify(dump_context, global_state.clone(), target.clone())
+                .map_err(|err| VerifyFailure::new(block_number, target, err))
+        };
+
         (0..(block.transactions().len() as u32))
             .into_par_iter()
-            .try_for_each(|idx| {
+            .flat_map_iter(|idx| {
                 if self.config.parallel_verify_blocks {
                     log::info!("verify block #{} tx #{}", block_number, idx);
                 } else {
@@ -229,13 +379,13 @@ impl DBBlockCancelChallengeValidator {
                 let tx = block.transactions().get(idx as usize).unwrap();
                 let tx_hash = tx.hash().into();
 
-                verify_tx(idx, tx_hash, ChallengeTargetType::TxSignature)?;
-                verify_tx(idx, tx_hash, ChallengeTargetType::TxExecution)?;
-
-                Ok::<_, anyhow::Error>(())
-            })?;
-
-        Ok(())
+                // Execution is only verified if signature is valid.
+                match verify_tx(idx, tx_hash, ChallengeTargetType::TxSignature) {
+                    Ok(()) => verify_tx(idx, tx_hash, ChallengeTargetType::TxExecution).err(),
+                    Err(failure) => Some(failure),
+                }
+            })
+            .collect()
     }
 
     fn verify(
@@ -335,6 +485,170 @@ impl DBBlockCancelChallengeValidator {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct VerifyFailure {
+    block_number: u64,
+    /// None if the block itself can't be loaded.
+    target: Option<JsonChallengeTarget>,
+    error: String,
+}
+
+impl VerifyFailure {
+    fn new(block_number: u64, target: ChallengeTarget, err: anyhow::Error) -> Self {
+        log::error!(
+            "verify block #{} target {} error {}",
+            block_number,
+            target,
+            err
+        );
+        VerifyFailure {
+            block_number,
+            target: Some(target.into()),
+            error: err.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    from_block: u64,
+    to_block: u64,
+    failures: &'a [VerifyFailure],
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Shard {
+    from_block: u64,
+    to_block: u64,
+    /// Next block to verify, the shard is done if it is bigger than `to_block`.
+    next_block: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    from_block: u64,
+    to_block: u64,
+    shards: Vec<Shard>,
+    /// Failures of verified blocks.
+    #[serde(default)]
+    failures: Vec<VerifyFailure>,
+}
+
+impl Checkpoint {
+    /// Split blocks into contiguous ranges of roughly equal size.
+    fn new(from_block: u64, to_block: u64, workers: usize) -> Self {
+        let total = to_block - from_block + 1;
+        let count = (workers as u64).clamp(1, total);
+        let (size, rem) = (total / count, total % count);
+        let mut next = from_block;
+        let shards = (0..count)
+            .map(|i| {
+                let len = size + u64::from(i < rem);
+                let shard = Shard {
+                    from_block: next,
+                    to_block: next + len - 1,
+                    next_block: next,
+                };
+                next += len;
+                shard
+            })
+            .collect();
+
+        Checkpoint {
+            from_block,
+            to_block,
+            shards,
+            failures: Vec::new(),
+        }
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let content = fs::read(path)?;
+        let checkpoint = serde_json::from_slice(&content)
+            .map_err(|err| anyhow!("invalid checkpoint {:?}: {}", path, err))?;
+        Ok(checkpoint)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        // Write to a temporary file first, so an interrupted write doesn't
+        // corrupt the checkpoint.
+        let tmp_path = path.with_extension("tmp");
+        write(&tmp_path, serde_json::to_vec(self)?)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+}
+
+struct Progress {
+    inner: Mutex<ProgressInner>,
+    path: Option<PathBuf>,
+    interval: u64,
+}
+
+struct ProgressInner {
+    checkpoint: Checkpoint,
+    unsaved_blocks: u64,
+}
+
+impl Progress {
+    fn new(checkpoint: Checkpoint, path: Option<PathBuf>, interval: u64) -> Self {
+        let inner = ProgressInner {
+            checkpoint,
+            unsaved_blocks: 0,
+        };
+        Progress {
+            inner: Mutex::new(inner),
+            path,
+            interval,
+        }
+    }
+
+    /// Mark block as verified with its failures, checkpoint is saved every
+    /// `interval` blocks.
+    fn update(&self, shard: usize, block_number: u64, failures: Vec<VerifyFailure>) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.checkpoint.shards[shard].next_block = block_number + 1;
+        inner.checkpoint.failures.extend(failures);
+        inner.unsaved_blocks += 1;
+        if inner.unsaved_blocks >= self.interval {
+            self.save_inner(&mut inner)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        self.save_inner(&mut self.inner.lock().unwrap())
+    }
+
+    fn into_checkpoint(self) -> Checkpoint {
+        self.inner.into_inner().unwrap().checkpoint
+    }
+
+    fn save_inner(&self, inner: &mut ProgressInner) -> Result<()> {
+        if let Some(ref path) = self.path {
+            inner.checkpoint.save(path)?;
+        }
+        inner.unsaved_blocks = 0;
+        Ok(())
+    }
+}
+
+async fn sigint_or_sigterm() {
+    let int = tokio::signal::ctrl_c();
+    #[cfg(unix)]
+    let mut term = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("creating SIGTERM stream");
+    #[cfg(unix)]
+    tokio::select! {
+        _ = int => {}
+        _ = term.recv() => {}
+    }
+    #[cfg(not(unix))]
+    let _ = int.await;
+
+    log::info!("received sigint or sigterm, stop verification");
+}
+
 #[derive(Clone)]
 struct DumpContext {
     block_number: u64,
@@ -375,3 +689,74 @@ fn build_challenge_target(
         .target_type(target_type.into())
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Checkpoint, Progress, VerifyFailure};
+
+    #[test]
+    fn test_checkpoint_shards() {
+        let checkpoint = Checkpoint::new(10, 20, 3);
+        let ranges: Vec<_> = (checkpoint.shards.iter())
+            .map(|s| (s.from_block, s.to_block, s.next_block))
+            .collect();
+        assert_eq!(ranges, vec![(10, 13, 10), (14, 17, 14), (18, 20, 18)]);
+
+        // No more shards than blocks
+        let checkpoint = Checkpoint::new(5, 6, 8);
+        assert_eq!(checkpoint.shards.len(), 2);
+        let checkpoint = Checkpoint::new(5, 5, 0);
+        assert_eq!(checkpoint.shards.len(), 1);
+    }
+
+    #[test]
+    fn test_progress_save_every_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        let progress = Progress::new(Checkpoint::new(0, 9, 2), Some(path.clone()), 3);
+
+        progress.update(0, 0, vec![]).unwrap();
+        progress.update(1, 5, vec![]).unwrap();
+        assert!(!path.exists());
+
+        progress.update(0, 1, vec![]).unwrap();
+        let saved = Checkpoint::load(&path).unwrap();
+        assert_eq!(saved.shards[0].next_block, 2);
+        assert_eq!(saved.shards[1].next_block, 6);
+
+        // Unsaved progress is kept until next save
+        progress.update(1, 6, vec![]).unwrap();
+        let saved = Checkpoint::load(&path).unwrap();
+        assert_eq!(saved.shards[1].next_block, 6);
+
+        // Saved on shutdown or failure
+        progress.save().unwrap();
+        let saved = Checkpoint::load(&path).unwrap();
+        assert_eq!((saved.from_block, saved.to_block), (0, 9));
+        assert_eq!(saved.shards[0].next_block, 2);
+        assert_eq!(saved.shards[1].next_block, 7);
+    }
+
+    #[test]
+    fn test_progress_keep_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        let progress = Progress::new(Checkpoint::new(0, 9, 1), Some(path.clone()), 1);
+
+        let failure = |block_number| VerifyFailure {
+            block_number,
+            target: None,
+            error: "invalid".to_string(),
+        };
+        progress.update(0, 0, vec![failure(0)]).unwrap();
+        progress.update(0, 1, vec![]).unwrap();
+        progress.update(0, 2, vec![failure(2), failure(2)]).unwrap();
+
+        // Failed blocks are verified, failures are resumed from checkpoint
+        let saved = Checkpoint::load(&path).unwrap();
+        assert_eq!(saved.shards[0].next_block, 3);
+        let blocks: Vec<_> = saved.failures.iter().map(|f| f.block_number).collect();
+        assert_eq!(blocks, vec![0, 2, 2]);
+        assert_eq!(progress.into_checkpoint().failures.len(), 3);
+    }
+}
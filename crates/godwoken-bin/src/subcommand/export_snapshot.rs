@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ckb_types::H256;
+use clap::Parser;
+use gw_config::Config;
+use gw_db::read_only_db::ReadOnlyDB;
+use gw_db::schema::{
+    Col, COLUMNS, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_ASSET_SCRIPT, COLUMN_BLOCK,
+    COLUMN_BLOCK_DEPOSIT_INFO_VEC, COLUMN_BLOCK_GLOBAL_STATE,
+    COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY, COLUMN_BLOCK_SMT_LEAF,
+    COLUMN_BLOCK_SUBMIT_TX_HASH, COLUMN_DATA, COLUMN_INDEX, COLUMN_REVERTED_BLOCK_SMT_LEAF,
+    COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_SCRIPT,
+};
+use gw_db::{DBIterator, IteratorMode};
+use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
+use gw_store::readonly::StoreReadonly;
+use gw_store::traits::{chain_store::ChainStore, kv_store::KVStoreRead};
+use gw_types::packed::RawL2Block;
+use gw_types::prelude::*;
+
+use super::snapshot::{
+    SnapshotManifest, SnapshotWriter, MANIFEST_FILE_NAME, SNAPSHOT_VERSION, STATE_FILE_NAME,
+};
+
+pub const COMMAND_EXPORT_SNAPSHOT: &str = "export-snapshot";
+
+/// Columns copied as a whole into the snapshot, they are content addressed.
+const SNAPSHOT_COLUMNS: [Col; 3] = [COLUMN_SCRIPT, COLUMN_DATA, COLUMN_ASSET_SCRIPT];
+
+/// Export state snapshot at the last finalized block
+#[derive(Parser)]
+#[clap(name = COMMAND_EXPORT_SNAPSHOT)]
+pub struct ExportSnapshotCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config_path: PathBuf,
+    /// The output directory for the snapshot
+    #[clap(short, long)]
+    output_path: PathBuf,
+}
+
+impl ExportSnapshotCommand {
+    pub fn run(self) -> Result<()> {
+        let content = fs::read(&self.config_path).with_context(|| {
+            format!(
+                "read config file from {}",
+                self.config_path.to_string_lossy()
+            )
+        })?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+
+        // Open database in readonly mode, so the node doesn't need to exit.
+        let db = {
+            let cf_names = (0..COLUMNS).map(|c| c.to_string());
+            ReadOnlyDB::open_cf(&config.store.path, cf_names)?
+                .ok_or_else(|| anyhow!("no database"))?
+        };
+        let finality_blocks = config.genesis.rollup_config.finality_blocks.value();
+        let manifest = export_snapshot(
+            db,
+            config.genesis.rollup_type_hash,
+            finality_blocks,
+            &self.output_path,
+        )?;
+
+        println!(
+            "exported {} records to {}",
+            manifest.records,
+            self.output_path.to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+/// Export state of the last finalized block of the last valid tip.
+///
+/// Later blocks may still be reverted, so only the finalized state and the
+/// block index at or below the finalized block are exported. The account
+/// state is rebuilt from state records of the blocks after it.
+pub fn export_snapshot(
+    db: ReadOnlyDB,
+    rollup_type_hash: H256,
+    finality_blocks: u64,
+    output_path: &Path,
+) -> Result<SnapshotManifest> {
+    let snap = StoreReadonly::new(db.clone());
+
+    let tip_block_hash = snap.get_last_valid_tip_block_hash()?;
+    let block_number: u64 = snap
+        .get_block_post_global_state(&tip_block_hash)?
+        .context("tip block post global state not found")?
+        .last_finalized_block_number()
+        .unpack();
+    let pruned_before = snap.get_state_pruned_block_number()?;
+    if block_number < pruned_before {
+        bail!(
+            "state of the last finalized block {} is pruned, history state is available since block {}",
+            block_number,
+            pruned_before
+        );
+    }
+    let block_hash = snap
+        .get_block_hash_by_number(block_number)?
+        .context("snapshot block not found")?;
+    let global_state = snap
+        .get_block_post_global_state(&block_hash)?
+        .context("snapshot block post global state not found")?;
+    let submit_tx_hash = snap
+        .get_block_submit_tx_hash(block_number)
+        .context("snapshot block submit tx hash not found")?;
+    let genesis_hash = snap
+        .get_block_hash_by_number(0)?
+        .context("genesis block not found")?;
+
+    fs::create_dir_all(output_path)?;
+    let state_file = fs::OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(output_path.join(STATE_FILE_NAME))?;
+    let mut writer = SnapshotWriter::new(io::BufWriter::new(state_file));
+
+    println!("export snapshot at block {}", block_number);
+    for col in SNAPSHOT_COLUMNS {
+        let iter = db.iter(col, IteratorMode::Start)?;
+        for (key, value) in iter {
+            writer.write_record(col, &key, &value)?;
+        }
+    }
+
+    // Account state leaves. Keys changed after the snapshot block are read
+    // from history, the others are the same as the tip state.
+    let changed_keys: HashSet<_> = snap.iter_state_keys_changed_after(block_number).collect();
+    for (key, value) in db.iter(COLUMN_ACCOUNT_SMT_LEAF, IteratorMode::Start)? {
+        let key: [u8; 32] = key.as_ref().try_into()?;
+        if !changed_keys.contains(&gw_common::H256::from(key)) {
+            writer.write_record(COLUMN_ACCOUNT_SMT_LEAF, &key, &value)?;
+        }
+    }
+    for key in changed_keys {
+        match snap.get_history_state(block_number, &key) {
+            Some(value) if !value.is_zero() => {
+                writer.write_record(COLUMN_ACCOUNT_SMT_LEAF, key.as_slice(), value.as_slice())?
+            }
+            _ => (),
+        }
+    }
+
+    // Block index and block SMT leaves at or below the snapshot block.
+    for number in 0..=block_number {
+        let hash = snap
+            .get_block_hash_by_number(number)?
+            .with_context(|| format!("block {} hash not found", number))?;
+        copy_record(&snap, &mut writer, COLUMN_INDEX, number.pack().as_slice())?;
+        copy_record(&snap, &mut writer, COLUMN_INDEX, hash.as_slice())?;
+        let smt_key = RawL2Block::compute_smt_key(number);
+        copy_record(&snap, &mut writer, COLUMN_BLOCK_SMT_LEAF, &smt_key)?;
+    }
+    // Reverted blocks of the snapshot block reverted block root.
+    let reverted_block_root = global_state.reverted_block_root().unpack();
+    for (root, block_hashes) in snap.iter_reverted_block_smt_root(reverted_block_root) {
+        copy_record(
+            &snap,
+            &mut writer,
+            COLUMN_REVERTED_BLOCK_SMT_ROOT,
+            root.as_slice(),
+        )?;
+        for block_hash in block_hashes {
+            copy_record(
+                &snap,
+                &mut writer,
+                COLUMN_REVERTED_BLOCK_SMT_LEAF,
+                block_hash.as_slice(),
+            )?;
+        }
+    }
+
+    // Genesis and snapshot block.
+    for hash in [genesis_hash.as_slice(), block_hash.as_slice()] {
+        copy_record(&snap, &mut writer, COLUMN_BLOCK, hash)?;
+        copy_record(&snap, &mut writer, COLUMN_BLOCK_GLOBAL_STATE, hash)?;
+    }
+    for number in [0, block_number] {
+        let key = number.to_be_bytes();
+        copy_record(&snap, &mut writer, COLUMN_BLOCK_SUBMIT_TX_HASH, &key)?;
+        copy_record(
+            &snap,
+            &mut writer,
+            COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY,
+            &key,
+        )?;
+    }
+    // Deposits of blocks not finalized yet are needed to track finalized
+    // custodian capacity of following blocks.
+    for number in block_number.saturating_sub(finality_blocks)..=block_number {
+        let key = number.to_be_bytes();
+        copy_record(&snap, &mut writer, COLUMN_BLOCK_DEPOSIT_INFO_VEC, &key)?;
+    }
+    let records = writer.finish()?;
+
+    let manifest = SnapshotManifest {
+        version: SNAPSHOT_VERSION,
+        rollup_type_hash,
+        block_number,
+        block_hash: H256(block_hash.into()),
+        submit_tx_hash: H256(submit_tx_hash.into()),
+        global_state: JsonBytes::from_bytes(global_state.as_bytes()),
+        records,
+    };
+    let content = serde_json::to_vec_pretty(&manifest)?;
+    fs::write(output_path.join(MANIFEST_FILE_NAME), content)?;
+
+    Ok(manifest)
+}
+
+fn copy_record<W: io::Write>(
+    snap: &StoreReadonly,
+    writer: &mut SnapshotWriter<W>,
+    col: Col,
+    key: &[u8],
+) -> Result<()> {
+    match snap.get(col, key) {
+        Some(value) => writer.write_record(col, key, &value),
+        None if col == COLUMN_BLOCK_DEPOSIT_INFO_VEC => Ok(()),
+        None => bail!("column {} key {:?} not found", col, key),
+    }
+}
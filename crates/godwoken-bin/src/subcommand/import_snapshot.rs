@@ -0,0 +1,350 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use gw_chain::chain::parse_global_state;
+use gw_common::{blake2b::new_blake2b, smt::SMT, H256};
+use gw_config::Config;
+use gw_db::migrate::open_or_create_db;
+use gw_db::schema::{
+    COLUMN_ACCOUNT_SMT_LEAF, COLUMN_ASSET_SCRIPT, COLUMN_BLOCK, COLUMN_BLOCK_DEPOSIT_INFO_VEC,
+    COLUMN_BLOCK_GLOBAL_STATE, COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY,
+    COLUMN_BLOCK_SMT_LEAF, COLUMN_BLOCK_SUBMIT_TX_HASH, COLUMN_DATA, COLUMN_DEPOSIT_INDEX,
+    COLUMN_INDEX, COLUMN_REVERTED_BLOCK_SMT_LEAF, COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_SCRIPT,
+};
+use gw_rpc_client::{
+    ckb_client::CKBClient, indexer_client::CKBIndexerClient, rpc_client::RPCClient,
+};
+use gw_store::{
+    smt::smt_store::{SMTBlockStore, SMTRevertedBlockStore, SMTStateStore},
+    state::history::history_state::HistoryStateStore,
+    traits::{chain_store::ChainStore, kv_store::KVStoreWrite},
+    transaction::StoreTransaction,
+    Store,
+};
+use gw_types::{
+    offchain::{global_state_from_slice, RollupContext, TxStatus},
+    packed::{DepositInfoVec, GlobalState, Script},
+    prelude::*,
+};
+
+use super::snapshot::{
+    SnapshotManifest, SnapshotReader, MANIFEST_FILE_NAME, SNAPSHOT_VERSION, STATE_FILE_NAME,
+};
+
+pub const COMMAND_IMPORT_SNAPSHOT: &str = "import-snapshot";
+
+/// Records imported in one db transaction.
+const IMPORT_BATCH: u64 = 100_000;
+
+/// Import state snapshot into an empty database
+#[derive(Parser)]
+#[clap(name = COMMAND_IMPORT_SNAPSHOT)]
+pub struct ImportSnapshotCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config_path: PathBuf,
+    /// The snapshot directory
+    #[clap(short, long)]
+    source_path: PathBuf,
+}
+
+#[derive(Default)]
+struct SMTRoots {
+    account: H256,
+    block: H256,
+    reverted_block: H256,
+}
+
+impl ImportSnapshotCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = fs::read(&self.config_path).with_context(|| {
+            format!(
+                "read config file from {}",
+                self.config_path.to_string_lossy()
+            )
+        })?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+
+        let manifest = read_manifest(&self.source_path)?;
+        if manifest.rollup_type_hash != config.genesis.rollup_type_hash {
+            bail!(
+                "snapshot is exported from rollup {:x}",
+                manifest.rollup_type_hash
+            );
+        }
+        let global_state = global_state_from_slice(manifest.global_state.as_bytes())
+            .map_err(|_| anyhow!("invalid snapshot global state"))?;
+        verify_global_state_on_l1(&config, &manifest, &global_state).await?;
+
+        let store = Store::new(open_or_create_db(&config.store)?);
+        import_snapshot(&store, &self.source_path, &manifest)
+    }
+}
+
+pub fn read_manifest(source_path: &Path) -> Result<SnapshotManifest> {
+    let content = fs::read(source_path.join(MANIFEST_FILE_NAME))?;
+    let manifest: SnapshotManifest =
+        serde_json::from_slice(&content).context("parse snapshot manifest")?;
+    if manifest.version != SNAPSHOT_VERSION {
+        bail!("unsupported snapshot version {}", manifest.version);
+    }
+    Ok(manifest)
+}
+
+/// Import the snapshot into an empty store, the snapshot block becomes the
+/// tip. The manifest should be verified against L1 first.
+pub fn import_snapshot(
+    store: &Store,
+    source_path: &Path,
+    manifest: &SnapshotManifest,
+) -> Result<()> {
+    let global_state = global_state_from_slice(manifest.global_state.as_bytes())
+        .map_err(|_| anyhow!("invalid snapshot global state"))?;
+    let block_hash: H256 = manifest.block_hash.0.into();
+    let block_count: u64 = global_state.block().count().unpack();
+    let tip_block_hash: H256 = global_state.tip_block_hash().unpack();
+    if tip_block_hash != block_hash || block_count != manifest.block_number + 1 {
+        bail!("snapshot global state doesn't match snapshot block");
+    }
+
+    if store.is_snapshot_importing() {
+        bail!("the database contains a failed snapshot import, remove it and retry");
+    }
+    if store.has_genesis()? {
+        bail!("import snapshot requires an empty database");
+    }
+    // Records are committed in batches before they are verified, mark the
+    // store so that an incomplete import is never mistaken for an empty one.
+    {
+        let db = store.begin_transaction();
+        db.set_snapshot_importing()?;
+        db.commit()?;
+    }
+
+    println!("import snapshot at block {}", manifest.block_number);
+    let state_file = fs::File::open(source_path.join(STATE_FILE_NAME))?;
+    let mut reader = SnapshotReader::new(io::BufReader::new(state_file));
+    let mut roots = SMTRoots::default();
+    let mut records = 0;
+    loop {
+        let db = &store.begin_transaction();
+        let imported = import_records(db, &mut reader, manifest.block_number, &mut roots)?;
+        db.commit()?;
+        records += imported;
+        if imported < IMPORT_BATCH {
+            break;
+        }
+        println!("imported {} records", records);
+    }
+    if records != manifest.records {
+        bail!(
+            "snapshot has {} records, expect {}",
+            records,
+            manifest.records
+        );
+    }
+
+    let db = &store.begin_transaction();
+    let block = db
+        .get_block(&block_hash)?
+        .filter(|b| H256::from(b.hash()) == block_hash)
+        .context("snapshot block not found")?;
+    let block_post_global_state = db
+        .get_block_post_global_state(&block_hash)?
+        .context("snapshot block post global state not found")?;
+    if block_post_global_state.as_slice() != global_state.as_slice()
+        || block.raw().post_account().as_slice() != global_state.account().as_slice()
+    {
+        bail!("snapshot block doesn't match snapshot global state");
+    }
+    if db.get_block_hash_by_number(manifest.block_number)? != Some(block_hash) {
+        bail!("snapshot block index mismatch");
+    }
+    check_root(
+        "account",
+        roots.account,
+        global_state.account().merkle_root().unpack(),
+    )?;
+    check_root(
+        "block",
+        roots.block,
+        global_state.block().merkle_root().unpack(),
+    )?;
+    check_root(
+        "reverted block",
+        roots.reverted_block,
+        global_state.reverted_block_root().unpack(),
+    )?;
+
+    db.setup_chain_id(manifest.rollup_type_hash.0.into())?;
+    db.set_block_smt_root(roots.block)?;
+    db.set_reverted_block_smt_root(roots.reverted_block)?;
+    db.set_snapshot_tip(&block)?;
+    db.commit()?;
+
+    println!(
+        "imported {} records, tip block {}",
+        records, manifest.block_number
+    );
+    Ok(())
+}
+
+/// Import up to `IMPORT_BATCH` records, returns the number of imported records.
+///
+/// Values of SMT leaves are applied to the trees to rebuild branches, account
+/// state is also recorded as the snapshot block state so that history state
+/// reads of later blocks can find it.
+fn import_records<R: io::Read>(
+    db: &StoreTransaction,
+    reader: &mut SnapshotReader<R>,
+    block_number: u64,
+    roots: &mut SMTRoots,
+) -> Result<u64> {
+    let mut account_smt = SMT::new(roots.account, SMTStateStore::new(db));
+    let mut block_smt = SMT::new(roots.block, SMTBlockStore::new(db));
+    let mut reverted_block_smt = SMT::new(roots.reverted_block, SMTRevertedBlockStore::new(db));
+
+    let mut imported = 0;
+    while imported < IMPORT_BATCH {
+        let (col, key, value) = match reader.read_record()? {
+            Some(record) => record,
+            None => break,
+        };
+        match col {
+            COLUMN_ACCOUNT_SMT_LEAF => {
+                let (key, value) = (to_h256(&key)?, to_h256(&value)?);
+                account_smt.update(key, value)?;
+                db.record_block_state(block_number, key, value)?;
+            }
+            COLUMN_BLOCK_SMT_LEAF => {
+                block_smt.update(to_h256(&key)?, to_h256(&value)?)?;
+            }
+            COLUMN_REVERTED_BLOCK_SMT_LEAF => {
+                reverted_block_smt.update(to_h256(&key)?, to_h256(&value)?)?;
+            }
+            COLUMN_SCRIPT | COLUMN_ASSET_SCRIPT => {
+                let script = Script::from_slice(&value).map_err(|_| anyhow!("invalid script"))?;
+                if script.hash().as_slice() != key.as_slice() {
+                    bail!("script hash mismatch");
+                }
+                db.insert_raw(col, &key, &value)?;
+            }
+            COLUMN_DATA => {
+                let mut data_hash = [0u8; 32];
+                let mut hasher = new_blake2b();
+                hasher.update(&value);
+                hasher.finalize(&mut data_hash);
+                if data_hash.as_slice() != key.as_slice() {
+                    bail!("data hash mismatch");
+                }
+                db.insert_raw(col, &key, &value)?;
+            }
+            COLUMN_INDEX
+            | COLUMN_REVERTED_BLOCK_SMT_ROOT
+            | COLUMN_BLOCK
+            | COLUMN_BLOCK_GLOBAL_STATE
+            | COLUMN_BLOCK_SUBMIT_TX_HASH
+            | COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY => {
+                db.insert_raw(col, &key, &value)?;
+            }
+            COLUMN_BLOCK_DEPOSIT_INFO_VEC => {
+                // index deposits of the snapshot block, key is the block number
+                let deposit_info_vec = DepositInfoVec::from_slice(&value)?;
+                for deposit_info in deposit_info_vec.into_iter() {
+                    let out_point = deposit_info.cell().out_point();
+                    db.insert_raw(COLUMN_DEPOSIT_INDEX, out_point.as_slice(), &key)?;
+                }
+                db.insert_raw(col, &key, &value)?;
+            }
+            _ => bail!("unexpected column {} in snapshot", col),
+        }
+        imported += 1;
+    }
+
+    roots.account = *account_smt.root();
+    roots.block = *block_smt.root();
+    roots.reverted_block = *reverted_block_smt.root();
+    Ok(imported)
+}
+
+/// Check the snapshot block is committed and finalized on L1, and its global
+/// state matches the one in the submission transaction.
+async fn verify_global_state_on_l1(
+    config: &Config,
+    manifest: &SnapshotManifest,
+    global_state: &GlobalState,
+) -> Result<()> {
+    let rollup_type_hash: [u8; 32] = manifest.rollup_type_hash.0;
+    let rpc_client = {
+        let rollup_context = RollupContext {
+            rollup_config: config.genesis.rollup_config.clone().into(),
+            rollup_script_hash: rollup_type_hash.into(),
+        };
+        let rollup_type_script: Script = config.chain.rollup_type_script.clone().into();
+        let rollup_type_script =
+            ckb_types::packed::Script::new_unchecked(rollup_type_script.as_bytes());
+        RPCClient::new(
+            rollup_type_script,
+            rollup_context,
+            CKBClient::with_url(&config.rpc_client.ckb_url)?,
+            CKBIndexerClient::with_url(&config.rpc_client.indexer_url)?,
+        )
+    };
+
+    let tx_hash: H256 = manifest.submit_tx_hash.0.into();
+    match rpc_client.ckb.get_transaction_status(tx_hash).await? {
+        Some(TxStatus::Committed) => (),
+        status => bail!(
+            "snapshot block submit tx is not committed, status: {:?}",
+            status
+        ),
+    }
+    let tx = rpc_client
+        .ckb
+        .get_transaction(tx_hash)
+        .await?
+        .context("get snapshot block submit tx")?;
+    if parse_global_state(&tx, &rollup_type_hash)?.as_slice() != global_state.as_slice() {
+        bail!("snapshot global state doesn't match the one on L1");
+    }
+
+    let rollup_cell = rpc_client
+        .query_rollup_cell()
+        .await?
+        .context("rollup cell not found")?;
+    let last_finalized_block_number: u64 = global_state_from_slice(&rollup_cell.data)
+        .map_err(|_| anyhow!("global state unpacking error"))?
+        .last_finalized_block_number()
+        .unpack();
+    if manifest.block_number > last_finalized_block_number {
+        bail!(
+            "snapshot block {} is not finalized, last finalized block {}",
+            manifest.block_number,
+            last_finalized_block_number
+        );
+    }
+
+    Ok(())
+}
+
+fn check_root(name: &str, root: H256, expected: H256) -> Result<()> {
+    if root != expected {
+        bail!(
+            "{} SMT root mismatch, snapshot: {:?}, global state: {:?}",
+            name,
+            root,
+            expected
+        );
+    }
+    Ok(())
+}
+
+fn to_h256(slice: &[u8]) -> Result<H256> {
+    let buf: [u8; 32] = slice
+        .try_into()
+        .map_err(|_| anyhow!("invalid SMT leaf length {}", slice.len()))?;
+    Ok(buf.into())
+}
@@ -1,5 +1,10 @@
 pub mod db_block_validator;
 pub mod export_block;
+pub mod export_snapshot;
 pub mod import_block;
+pub mod import_snapshot;
 pub mod peer_id;
 pub mod rewind_to_last_valid_block;
+pub mod signer;
+pub mod simulate_challenge;
+pub mod snapshot;
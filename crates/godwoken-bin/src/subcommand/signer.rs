@@ -0,0 +1,95 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use gw_utils::signer::{
+    daemon::Daemon,
+    keystore::{Keystore, DEFAULT_SCRYPT_LOG_N},
+    read_password_file, read_privkey_file, LocalSigner,
+};
+
+pub const COMMAND_SIGNER: &str = "signer";
+
+/// Wallet keystore and reference remote signer commands.
+#[derive(Parser)]
+#[clap(name = COMMAND_SIGNER)]
+pub enum SignerCommand {
+    /// Encrypt a hex private key file into a keystore file.
+    Keystore {
+        /// Hex private key file path.
+        #[clap(long)]
+        privkey_path: PathBuf,
+        /// Keystore password file path.
+        #[clap(long)]
+        password_path: PathBuf,
+        /// Output keystore file path.
+        #[clap(long)]
+        output_path: PathBuf,
+    },
+    /// Run the reference remote signer daemon.
+    Daemon {
+        /// `unix:///path/to/socket` or `http://host:port`.
+        #[clap(long)]
+        listen: String,
+        /// Hex private key file path.
+        #[clap(long, conflicts_with = "keystore-path")]
+        privkey_path: Option<PathBuf>,
+        /// Keystore file path.
+        #[clap(long, requires = "password-path")]
+        keystore_path: Option<PathBuf>,
+        /// Keystore password file path.
+        #[clap(long)]
+        password_path: Option<PathBuf>,
+        /// Require HTTP requests to carry the token in this file as a bearer
+        /// token.
+        #[clap(long)]
+        auth_token_path: Option<PathBuf>,
+        /// Allow listening on a non-loopback HTTP address.
+        #[clap(long)]
+        allow_non_loopback: bool,
+    },
+}
+
+impl SignerCommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            SignerCommand::Keystore {
+                privkey_path,
+                password_path,
+                output_path,
+            } => {
+                let secret = read_privkey_file(&privkey_path)?;
+                let password = read_password_file(&password_path)?;
+                let keystore =
+                    Keystore::encrypt(&secret, password.as_bytes(), DEFAULT_SCRYPT_LOG_N)?;
+                let content = serde_json::to_string_pretty(&keystore)?;
+                std::fs::write(&output_path, content).with_context(|| {
+                    format!("write keystore to {}", output_path.to_string_lossy())
+                })?;
+            }
+            SignerCommand::Daemon {
+                listen,
+                privkey_path,
+                keystore_path,
+                password_path,
+                auth_token_path,
+                allow_non_loopback,
+            } => {
+                let signer = match (privkey_path, keystore_path, password_path) {
+                    (Some(privkey_path), _, _) => LocalSigner::from_file(&privkey_path)?,
+                    (None, Some(keystore_path), Some(password_path)) => {
+                        LocalSigner::from_keystore(&keystore_path, &password_path)?
+                    }
+                    _ => bail!("--privkey-path or --keystore-path is required"),
+                };
+                let auth_token = match auth_token_path {
+                    Some(path) => Some(read_password_file(&path)?),
+                    None => None,
+                };
+                let daemon = Daemon::bind(&listen, allow_non_loopback)?;
+                daemon.serve(Arc::new(signer), auth_token).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    fs::{create_dir_all, write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ckb_chain_spec::consensus::MAX_BLOCK_BYTES;
+use clap::{ArgEnum, Parser};
+use gw_block_producer::runner::BaseInitComponents;
+use gw_challenge::{
+    cancel_challenge::LoadDataStrategy,
+    context::{build_challenge_context_for_block, build_revert_context, build_verify_context},
+    offchain::{
+        dump_tx, mock_cancel_challenge_tx, mock_enter_challenge_tx, mock_revert_tx,
+        mock_tx::MockOutput,
+        verify_tx::{verify_tx, TxWithContext},
+        OffChainMockContext,
+    },
+};
+use gw_common::H256;
+use gw_config::Config;
+use gw_generator::Generator;
+use gw_jsonrpc_types::godwoken::ChallengeTarget as JsonChallengeTarget;
+use gw_store::{traits::chain_store::ChainStore, transaction::StoreTransaction, Store};
+use gw_types::{
+    core::{ChallengeTargetType, Status},
+    packed::{ChallengeTarget, L2Block},
+    prelude::{Builder, Entity, Pack, Unpack},
+};
+use serde::Serialize;
+
+pub const COMMAND_SIMULATE_CHALLENGE: &str = "simulate-challenge";
+
+pub const STEP_ENTER_CHALLENGE: &str = "enter-challenge";
+pub const STEP_CANCEL_CHALLENGE: &str = "cancel-challenge";
+pub const STEP_REVERT: &str = "revert";
+
+/// Simulate challenge of a local block: build enter challenge, cancel
+/// challenge and revert transactions and run them with the deployed scripts.
+#[derive(Parser)]
+#[clap(name = COMMAND_SIMULATE_CHALLENGE)]
+pub struct SimulateChallengeCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config_path: PathBuf,
+    /// Number of the challenged block
+    #[clap(long)]
+    block: u64,
+    /// Type of the challenged target
+    #[clap(long, arg_enum)]
+    target_type: TargetType,
+    /// Index of the challenged transaction or withdrawal in the block
+    #[clap(long)]
+    index: u32,
+    /// Directory to write the JSON report and mock transactions
+    #[clap(long)]
+    output_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum TargetType {
+    TxExecution,
+    TxSignature,
+    Withdrawal,
+}
+
+impl From<TargetType> for ChallengeTargetType {
+    fn from(target_type: TargetType) -> Self {
+        match target_type {
+            TargetType::TxExecution => ChallengeTargetType::TxExecution,
+            TargetType::TxSignature => ChallengeTargetType::TxSignature,
+            TargetType::Withdrawal => ChallengeTargetType::Withdrawal,
+        }
+    }
+}
+
+impl SimulateChallengeCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config_path).with_context(|| {
+            format!(
+                "read config file from {}",
+                self.config_path.to_string_lossy()
+            )
+        })?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        if config.store.path.as_os_str().is_empty() {
+            bail!("empty store path, no db block to simulate");
+        }
+        let block_producer_config = match config.block_producer {
+            Some(ref config) => config,
+            None => bail!("simulate challenge require block producer config"),
+        };
+
+        let base = BaseInitComponents::init(&config, true).await?;
+        let mut mock_ctx = match base
+            .init_offchain_mock_context(block_producer_config)
+            .await?
+        {
+            Some(ctx) => ctx,
+            None => bail!("no wallet config for block producer"),
+        };
+        let validator_config = config.db_block_validator.clone().unwrap_or_default();
+        if let Some(ref scripts) = validator_config.replace_scripts {
+            mock_ctx = mock_ctx.replace_scripts(scripts)?;
+        }
+
+        let simulator = ChallengeSimulator {
+            generator: base.generator,
+            store: base.store,
+            mock_ctx,
+            max_cycles: validator_config.verify_max_cycles,
+        };
+        let simulation = simulator.simulate(self.block, self.target_type.into(), self.index)?;
+        if let Some(ref path) = self.output_path {
+            simulation.write(&simulator.mock_ctx, path)?;
+        }
+
+        let failed: Vec<_> = simulation.report.failed_steps().collect();
+        if !failed.is_empty() {
+            bail!("challenge steps would fail: {}", failed.join(", "));
+        }
+        log::info!("all challenge steps would pass");
+
+        Ok(())
+    }
+}
+
+pub struct ChallengeSimulator {
+    pub generator: Arc<Generator>,
+    pub store: Store,
+    pub mock_ctx: OffChainMockContext,
+    pub max_cycles: u64,
+}
+
+impl ChallengeSimulator {
+    pub fn simulate(
+        &self,
+        block_number: u64,
+        target_type: ChallengeTargetType,
+        target_index: u32,
+    ) -> Result<Simulation> {
+        let db = &self.store.begin_transaction();
+        let block = get_block_by_number(db, block_number)?;
+        let target_count = match target_type {
+            ChallengeTargetType::Withdrawal => block.withdrawals().len(),
+            _ => block.transactions().len(),
+        };
+        if target_index as usize >= target_count {
+            bail!(
+                "block #{} has {} {:?} targets, index {} out of bound",
+                block_number,
+                target_count,
+                target_type,
+                target_index
+            );
+        }
+
+        // Blocks after the challenged one are reverted too
+        let tip_block = db.get_last_valid_tip_block()?;
+        let tip_block_number: u64 = tip_block.raw().number().unpack();
+        if block_number > tip_block_number {
+            bail!(
+                "block #{} is after last valid tip #{}",
+                block_number,
+                tip_block_number
+            );
+        }
+        let global_state = {
+            let tip_block_hash = tip_block.hash().into();
+            let maybe = db.get_block_post_global_state(&tip_block_hash)?;
+            let state = maybe.ok_or_else(|| anyhow!("tip block global state not found"))?;
+            let to_builder = state.as_builder().status((Status::Running as u8).into());
+            to_builder.build()
+        };
+        let halting_global_state = {
+            let to_builder = global_state.clone().as_builder();
+            to_builder.status((Status::Halting as u8).into()).build()
+        };
+
+        let target_type_byte: u8 = target_type.into();
+        let target = ChallengeTarget::new_builder()
+            .block_hash(block.hash().pack())
+            .target_index(target_index.pack())
+            .target_type(target_type_byte.into())
+            .build();
+
+        let mut steps = Vec::with_capacity(4);
+        steps.push(self.run_step(STEP_ENTER_CHALLENGE, None, || {
+            let challenge_context = build_challenge_context_for_block(db, target.clone(), &block)?;
+            mock_enter_challenge_tx(&self.mock_ctx.mock_rollup, global_state, challenge_context)
+        }));
+
+        let verify_context = build_verify_context(Arc::clone(&self.generator), db, &target);
+        for strategy in [LoadDataStrategy::Witness, LoadDataStrategy::CellDep] {
+            steps.push(self.run_step(STEP_CANCEL_CHALLENGE, Some(strategy), || {
+                let context = match verify_context {
+                    Ok(ref context) => context.clone(),
+                    Err(ref err) => bail!("build verify context: {:#}", err),
+                };
+                mock_cancel_challenge_tx(
+                    &self.mock_ctx.mock_rollup,
+                    halting_global_state.clone(),
+                    target.clone(),
+                    context,
+                    Some(strategy),
+                )
+            }));
+        }
+
+        steps.push(self.run_step(STEP_REVERT, None, || {
+            // NOTE: Never commit, `build_revert_context` updates reverted block smt
+            let db = &self.store.begin_transaction();
+            let reverted_blocks = (block_number..=tip_block_number)
+                .map(|number| get_block_by_number(db, number))
+                .collect::<Result<Vec<_>>>()?;
+            let context = build_revert_context(db, &reverted_blocks)?;
+            mock_revert_tx(
+                &self.mock_ctx.mock_rollup,
+                halting_global_state.clone(),
+                target.clone(),
+                context,
+            )
+        }));
+
+        let (steps, txs) = steps.into_iter().unzip();
+        let report = Report {
+            block_number,
+            tip_block_number,
+            target: target.into(),
+            steps,
+        };
+
+        Ok(Simulation { report, txs })
+    }
+
+    fn run_step(
+        &self,
+        step: &'static str,
+        load_data_strategy: Option<LoadDataStrategy>,
+        build_tx: impl FnOnce() -> Result<MockOutput>,
+    ) -> (StepReport, Option<MockOutput>) {
+        let mut report = StepReport {
+            step,
+            load_data_strategy: load_data_strategy.map(strategy_name),
+            passed: false,
+            cycles: None,
+            tx_size: None,
+            error: None,
+        };
+
+        let mock_output = match build_tx() {
+            Ok(output) => output,
+            Err(err) => {
+                log::error!("{} build tx error {:#}", report.name(), err);
+                report.error = Some(format!("build tx: {:#}", err));
+                return (report, None);
+            }
+        };
+
+        // tx size + 4 in block serialization cost
+        let tx_size = mock_output.tx.as_slice().len() + 4;
+        report.tx_size = Some(tx_size);
+
+        let tx_with_context = TxWithContext::from(mock_output.clone());
+        match verify_tx(&self.mock_ctx.rollup_cell_deps, tx_with_context, u64::MAX) {
+            Ok(cycles) => {
+                report.cycles = Some(cycles);
+                if cycles > self.max_cycles {
+                    report.error = Some(format!(
+                        "exceeded max cycles, used {} expect <= {}",
+                        cycles, self.max_cycles
+                    ));
+                } else if tx_size as u64 > MAX_BLOCK_BYTES {
+                    report.error = Some(format!(
+                        "exceeded max tx size, size {} expect <= {}",
+                        tx_size, MAX_BLOCK_BYTES
+                    ));
+                } else {
+                    report.passed = true;
+                }
+            }
+            Err(err) => report.error = Some(err.to_string()),
+        }
+
+        match report.error {
+            Some(ref err) => log::error!("{} would fail: {}", report.name(), err),
+            None => log::info!(
+                "{} would pass, cycles {}, tx size {}",
+                report.name(),
+                report.cycles.unwrap_or_default(),
+                tx_size
+            ),
+        }
+
+        (report, Some(mock_output))
+    }
+}
+
+pub struct Simulation {
+    pub report: Report,
+    /// Mock transactions of steps, in the same order.
+    pub txs: Vec<Option<MockOutput>>,
+}
+
+impl Simulation {
+    fn write(&self, mock_ctx: &OffChainMockContext, dir: &Path) -> Result<()> {
+        create_dir_all(dir)?;
+
+        for (step, tx) in self.report.steps.iter().zip(self.txs.iter()) {
+            let tx = match tx {
+                Some(tx) => TxWithContext::from(tx.clone()),
+                None => continue,
+            };
+            let dump = dump_tx(&mock_ctx.rollup_cell_deps, tx)?;
+            let path = dir.join(format!("{}-tx.json", step.name()));
+            write(&path, serde_json::to_string_pretty(&dump)?)?;
+            log::info!("dump {} tx to {:?}", step.name(), path);
+        }
+
+        let path = dir.join("report.json");
+        write(&path, serde_json::to_string_pretty(&self.report)?)?;
+        log::info!("write simulation report to {:?}", path);
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub block_number: u64,
+    /// Last valid tip block, blocks from the challenged one to it are reverted.
+    pub tip_block_number: u64,
+    pub target: JsonChallengeTarget,
+    pub steps: Vec<StepReport>,
+}
+
+impl Report {
+    /// Cancel challenge passes if it passes with any load data strategy.
+    pub fn failed_steps(&self) -> impl Iterator<Item = &'static str> + '_ {
+        let passed = move |step: &str| {
+            let mut reports = self.steps.iter().filter(move |r| r.step == step);
+            reports.any(|r| r.passed)
+        };
+        [STEP_ENTER_CHALLENGE, STEP_CANCEL_CHALLENGE, STEP_REVERT]
+            .into_iter()
+            .filter(move |step| !passed(step))
+    }
+}
+
+#[derive(Serialize)]
+pub struct StepReport {
+    pub step: &'static str,
+    pub load_data_strategy: Option<&'static str>,
+    pub passed: bool,
+    pub cycles: Option<u64>,
+    /// Serialized size in block.
+    pub tx_size: Option<usize>,
+    pub error: Option<String>,
+}
+
+impl StepReport {
+    pub fn name(&self) -> String {
+        match self.load_data_strategy {
+            Some(strategy) => format!("{}-{}", self.step, strategy),
+            None => self.step.to_string(),
+        }
+    }
+}
+
+fn strategy_name(load_data_strategy: LoadDataStrategy) -> &'static str {
+    match load_data_strategy {
+        LoadDataStrategy::Witness => "with-witness-load-data",
+        LoadDataStrategy::CellDep => "with-celldep-load-data",
+    }
+}
+
+fn get_block_by_number(db: &StoreTransaction, block_number: u64) -> Result<L2Block> {
+    let block_hash: H256 = {
+        let maybe = db.get_block_hash_by_number(block_number)?;
+        maybe.ok_or_else(|| anyhow!("block #{} not found", block_number))?
+    };
+    let maybe = db.get_block(&block_hash)?;
+    maybe.ok_or_else(|| anyhow!("block #{} not found", block_number))
+}
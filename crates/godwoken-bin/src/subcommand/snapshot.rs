@@ -0,0 +1,97 @@
+//! State snapshot format shared by `export-snapshot` and `import-snapshot`.
+//!
+//! A snapshot is a directory containing:
+//!
+//! - `manifest.json`: the snapshot block and its post global state.
+//! - `state.bin`: a sequence of `(column, key, value)` records. Each record is
+//!   encoded as the column byte followed by the length-prefixed (u32 little
+//!   endian) key and value.
+
+use std::io::{self, Read, Write};
+
+use anyhow::{bail, Result};
+use ckb_types::H256;
+use gw_db::schema::Col;
+use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
+use serde::{Deserialize, Serialize};
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+pub const STATE_FILE_NAME: &str = "state.bin";
+
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub version: u32,
+    pub rollup_type_hash: H256,
+    pub block_number: u64,
+    pub block_hash: H256,
+    /// L1 transaction which submits the snapshot block.
+    pub submit_tx_hash: H256,
+    /// Molecule encoded post global state of the snapshot block.
+    pub global_state: JsonBytes,
+    /// Number of records in the state file.
+    pub records: u64,
+}
+
+pub struct SnapshotWriter<W> {
+    inner: W,
+    records: u64,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    pub fn new(inner: W) -> Self {
+        SnapshotWriter { inner, records: 0 }
+    }
+
+    pub fn write_record(&mut self, col: Col, key: &[u8], value: &[u8]) -> Result<()> {
+        self.inner.write_all(&[col])?;
+        for field in [key, value] {
+            self.inner.write_all(&(field.len() as u32).to_le_bytes())?;
+            self.inner.write_all(field)?;
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Flush and return the number of written records.
+    pub fn finish(mut self) -> Result<u64> {
+        self.inner.flush()?;
+        Ok(self.records)
+    }
+}
+
+pub struct SnapshotReader<R> {
+    inner: R,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    pub fn new(inner: R) -> Self {
+        SnapshotReader { inner }
+    }
+
+    /// Read next record, returns `None` at the end of file.
+    pub fn read_record(&mut self) -> Result<Option<(Col, Vec<u8>, Vec<u8>)>> {
+        let mut col = [0u8; 1];
+        match self.inner.read_exact(&mut col) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let key = self.read_field()?;
+        let value = self.read_field()?;
+        Ok(Some((col[0], key, value)))
+    }
+
+    fn read_field(&mut self) -> Result<Vec<u8>> {
+        let mut len = [0u8; 4];
+        self.inner.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        // Keys and values in the store are far smaller than this.
+        if len > 64 * 1024 * 1024 {
+            bail!("corrupted snapshot record, field length {}", len);
+        }
+        let mut field = vec![0u8; len];
+        self.inner.read_exact(&mut field)?;
+        Ok(field)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::blockchain::Script;
+use crate::blockchain::{OutPoint, Script};
 use anyhow::{anyhow, Error as JsonError};
 use ckb_fixed_hash::H256;
 use ckb_jsonrpc_types::{JsonBytes, Uint128, Uint32, Uint64};
@@ -194,6 +194,93 @@ impl From<packed::TxReceipt> for TxReceipt {
     }
 }
 
+/// Filter of `gw_get_logs`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LogFilter {
+    /// Defaults to the tip block.
+    pub from_block: Option<Uint64>,
+    /// Defaults to the tip block.
+    pub to_block: Option<Uint64>,
+    pub account_id: Option<Uint32>,
+    pub service_flag: Option<Uint32>,
+    /// Topics of polyjuice user logs by position. `null` matches any topic,
+    /// otherwise the topic must be one of the listed values.
+    #[serde(default)]
+    pub topics: Vec<Option<Vec<H256>>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct IndexedLogItem {
+    #[serde(flatten)]
+    pub log: LogItem,
+    pub block_number: Uint64,
+    pub block_hash: H256,
+    pub tx_hash: H256,
+    pub tx_index: Uint32,
+    pub log_index: Uint32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountTxRole {
+    /// Transactions sent from the account.
+    From,
+    /// Transactions sent to the account.
+    To,
+    /// Transactions sent from or to the account.
+    Any,
+}
+
+impl Default for AccountTxRole {
+    fn default() -> Self {
+        AccountTxRole::Any
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+impl Default for Order {
+    fn default() -> Self {
+        Order::Desc
+    }
+}
+
+/// Options of `gw_get_transactions_by_account`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountTxQuery {
+    #[serde(default)]
+    pub role: AccountTxRole,
+    #[serde(default)]
+    pub order: Order,
+    /// `cursor` of the previous page.
+    pub cursor: Option<JsonBytes>,
+    pub limit: Option<Uint32>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountTx {
+    pub tx_hash: H256,
+    pub block_number: Uint64,
+    pub tx_index: Uint32,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountTxPage {
+    pub transactions: Vec<AccountTx>,
+    /// Pass it to get the next page, `null` if there are no more transactions.
+    pub cursor: Option<JsonBytes>,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum ChallengeTargetType {
@@ -503,11 +590,41 @@ pub struct L2BlockWithStatus {
     pub status: L2BlockStatus,
 }
 
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BlockInfo {
+    pub block_producer: JsonBytes,
+    pub number: Uint64,
+    pub timestamp: Uint64,
+}
+
+impl From<packed::BlockInfo> for BlockInfo {
+    fn from(block_info: packed::BlockInfo) -> BlockInfo {
+        let number: u64 = block_info.number().unpack();
+        let timestamp: u64 = block_info.timestamp().unpack();
+        Self {
+            block_producer: JsonBytes::from_vec(block_info.block_producer().unpack()),
+            number: number.into(),
+            timestamp: timestamp.into(),
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct MemBlockView {
+    pub block_info: BlockInfo,
+    pub withdrawals: Vec<H256>,
+    pub deposits: Vec<DepositRequest>,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum L2TransactionStatus {
     Pending,
     Committed,
+    /// Evicted from the mem pool queue, see `evicted_reason`.
+    Evicted,
 }
 
 impl Default for L2TransactionStatus {
@@ -516,11 +633,35 @@ impl Default for L2TransactionStatus {
     }
 }
 
+/// Why a pending request is evicted from the mem pool queue.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictReason {
+    /// The queue is full and the fee rate is among the lowest.
+    QueueFull,
+    /// Fee rate is lower than the min fee rate of the queue.
+    FeeRateTooLow,
+    /// Sender has too many pending requests.
+    SenderQuotaExceeded,
+    /// Nonce is lower than the sender's nonce.
+    NonceTooLow,
+    /// Nonce is too far ahead of the sender's nonce.
+    NonceGapTooLarge,
+    /// Replaced by a request of the same sender and nonce.
+    Replaced,
+    /// Fee rate isn't high enough to replace the pending request of the same
+    /// sender and nonce.
+    ReplacementUnderpriced,
+    /// Cancelled by the sender, see `gw_cancel_request`.
+    Cancelled,
+}
+
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct L2TransactionWithStatus {
     pub transaction: Option<L2TransactionView>,
     pub status: L2TransactionStatus,
+    pub evicted_reason: Option<EvictReason>,
 }
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
@@ -553,6 +694,48 @@ pub struct L2WithdrawalCommittedInfo {
     pub withdrawal_index: Uint32,
 }
 
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalLifecycleStatus {
+    /// In the mem-pool, waiting to be packaged
+    Pending,
+    /// Included in a L2 block which isn't committed on L1 yet
+    Included,
+    /// The L2 block is committed on L1
+    Committed,
+    /// The L2 block is finalized, the withdrawal cell can be unlocked by owner
+    Finalized,
+    /// The withdrawal cell has been consumed on L1
+    Unlocked,
+}
+
+impl Default for WithdrawalLifecycleStatus {
+    fn default() -> Self {
+        Self::Pending
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct WithdrawalFinality {
+    /// The withdrawal is finalized once the L1 confirmed L2 block reaches this number
+    pub finalized_block_number: Uint64,
+    pub remaining_blocks: Uint64,
+    /// Estimated in milliseconds by the average interval of recent L2 blocks
+    pub estimated_finalized_timestamp: Uint64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct WithdrawalLifecycle {
+    pub status: WithdrawalLifecycleStatus,
+    pub l2_committed_info: Option<L2WithdrawalCommittedInfo>,
+    pub l1_committed_info: Option<L2BlockCommittedInfo>,
+    pub finality: Option<WithdrawalFinality>,
+    pub withdrawal_cell: Option<OutPoint>,
+    pub unlock_tx_hash: Option<H256>,
+}
+
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct SubmitTransactions {
@@ -781,6 +964,48 @@ impl From<packed::DepositRequest> for DepositRequest {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositStatus {
+    /// Live on L1, not collected by the mem-pool yet
+    Pending,
+    /// Collected into the mem block
+    Collected,
+    /// Included in a L2 block
+    Included,
+    /// Rejected by the mem-pool
+    Rejected,
+    /// Consumed on L1 by a tx without the rollup cell, i.e. cancelled by the
+    /// owner after the deposit timeout
+    Refunded,
+}
+
+impl Default for DepositStatus {
+    fn default() -> Self {
+        Self::Pending
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct L2DepositCommittedInfo {
+    pub block_number: Uint64,
+    pub block_hash: H256,
+    pub deposit_index: Uint32,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DepositWithStatus {
+    pub out_point: OutPoint,
+    pub status: DepositStatus,
+    pub request: Option<DepositRequest>,
+    pub l2_committed_info: Option<L2DepositCommittedInfo>,
+    /// The tip block number when the mem-pool collected or rejected the deposit
+    pub tip_block_number: Option<Uint64>,
+    pub reason: Option<String>,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct WithdrawalRequestExtra {
@@ -1180,6 +1405,7 @@ pub enum NodeMode {
     FullNode,
     Test,
     ReadOnly,
+    Watchtower,
 }
 
 impl Default for NodeMode {
@@ -1303,6 +1529,204 @@ impl From<offchain::ErrorTxReceipt> for ErrorTxReceipt {
     }
 }
 
+/// Overrides patched onto the state before executing a raw tx, nothing is
+/// committed. Applied in the order of scripts, data, kv, nonces and balances.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case", default)]
+pub struct StateOverride {
+    pub scripts: Vec<ScriptOverride>,
+    // data are stored by their blake2b hashes
+    pub data: Vec<JsonBytes>,
+    // raw SMT key values
+    pub kv: Vec<KVPair>,
+    pub nonces: Vec<NonceOverride>,
+    pub balances: Vec<BalanceOverride>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ScriptOverride {
+    // replace the script of an existing account, or create a new account if null
+    pub account_id: Option<Uint32>,
+    pub script: Script,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct NonceOverride {
+    pub account_id: Uint32,
+    pub nonce: Uint32,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct BalanceOverride {
+    pub sudt_id: Uint32,
+    // serialized registry address
+    pub address: JsonBytes,
+    pub balance: gw_types::U256,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct CycleMeter {
+    pub execution: Uint64,
+    // syscall cycles
+    pub r#virtual: Uint64,
+    pub total: Uint64,
+}
+
+impl From<offchain::CycleMeter> for CycleMeter {
+    fn from(cycles: offchain::CycleMeter) -> Self {
+        CycleMeter {
+            execution: cycles.execution.into(),
+            r#virtual: cycles.r#virtual.into(),
+            total: cycles.total().into(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct BundleStepResult {
+    pub tx_hash: H256,
+    pub return_data: JsonBytes,
+    pub logs: Vec<LogItem>,
+    // i8 -> u32, actual u8
+    pub exit_code: Uint32,
+    pub cycles: CycleMeter,
+    // the tx can't be executed, its state changes are reverted
+    pub error: Option<String>,
+}
+
+impl BundleStepResult {
+    pub fn from_run_result(tx_hash: H256, run_result: offchain::RunResult) -> Self {
+        let exit_code = run_result.exit_code as u8;
+        BundleStepResult {
+            tx_hash,
+            return_data: JsonBytes::from_bytes(run_result.return_data),
+            logs: run_result.logs.into_iter().map(Into::into).collect(),
+            exit_code: (exit_code as u32).into(),
+            cycles: run_result.cycles.into(),
+            error: None,
+        }
+    }
+
+    pub fn from_error(tx_hash: H256, error: String) -> Self {
+        BundleStepResult {
+            tx_hash,
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none() && self.exit_code.value() == 0
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SyscallTrace {
+    pub code: Uint64,
+    pub name: String,
+    pub virtual_cycles: Uint64,
+}
+
+impl From<offchain::SyscallTrace> for SyscallTrace {
+    fn from(trace: offchain::SyscallTrace) -> Self {
+        SyscallTrace {
+            code: trace.code.into(),
+            name: trace.name.to_string(),
+            virtual_cycles: trace.virtual_cycles.into(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct StateReadTrace {
+    pub key: H256,
+    pub value: H256,
+}
+
+impl From<offchain::StateReadTrace> for StateReadTrace {
+    fn from(trace: offchain::StateReadTrace) -> Self {
+        StateReadTrace {
+            key: H256::from(Into::<[u8; 32]>::into(trace.key)),
+            value: H256::from(Into::<[u8; 32]>::into(trace.value)),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct StateWriteTrace {
+    pub key: H256,
+    pub old_value: H256,
+    pub new_value: H256,
+}
+
+impl From<offchain::StateWriteTrace> for StateWriteTrace {
+    fn from(trace: offchain::StateWriteTrace) -> Self {
+        StateWriteTrace {
+            key: H256::from(Into::<[u8; 32]>::into(trace.key)),
+            old_value: H256::from(Into::<[u8; 32]>::into(trace.old_value)),
+            new_value: H256::from(Into::<[u8; 32]>::into(trace.new_value)),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct TransactionTrace {
+    pub tx_hash: H256,
+    // null if the tx is in mem pool
+    pub block_number: Option<Uint64>,
+    pub return_data: JsonBytes,
+    pub logs: Vec<LogItem>,
+    // i8 -> u32, actual u8
+    pub exit_code: Uint32,
+    pub cycles: CycleMeter,
+    pub syscalls: Vec<SyscallTrace>,
+    pub state_reads: Vec<StateReadTrace>,
+    pub state_writes: Vec<StateWriteTrace>,
+    pub read_data_hashes: Vec<H256>,
+    pub write_data_hashes: Vec<H256>,
+}
+
+impl TransactionTrace {
+    pub fn new(
+        tx_hash: H256,
+        block_number: Option<u64>,
+        run_result: offchain::RunResult,
+        trace: offchain::TxTrace,
+    ) -> Self {
+        let to_json_hashes = |hashes: std::collections::HashSet<gw_common::H256>| {
+            let mut hashes: Vec<H256> = hashes
+                .into_iter()
+                .map(|hash| H256::from(Into::<[u8; 32]>::into(hash)))
+                .collect();
+            hashes.sort_unstable();
+            hashes
+        };
+        let exit_code = run_result.exit_code as u8;
+        TransactionTrace {
+            tx_hash,
+            block_number: block_number.map(Into::into),
+            return_data: JsonBytes::from_bytes(run_result.return_data),
+            logs: run_result.logs.into_iter().map(Into::into).collect(),
+            exit_code: (exit_code as u32).into(),
+            cycles: run_result.cycles.into(),
+            syscalls: trace.syscalls.into_iter().map(Into::into).collect(),
+            state_reads: trace.reads.into_iter().map(Into::into).collect(),
+            state_writes: trace.writes.into_iter().map(Into::into).collect(),
+            read_data_hashes: to_json_hashes(run_result.read_data_hashes),
+            write_data_hashes: to_json_hashes(run_result.write_data_hashes),
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct SUDTFeeConfig {
@@ -1318,6 +1742,41 @@ pub struct FeeConfig {
     pub withdraw_cycles_limit: Uint64,
 }
 
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EstimateFeeKind {
+    Tx,
+    Withdrawal,
+}
+
+impl Default for EstimateFeeKind {
+    fn default() -> Self {
+        EstimateFeeKind::Tx
+    }
+}
+
+/// Fee rates are fee / cycles limit.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct FeeEstimate {
+    /// Cycles used by the dry run, `null` for withdrawals.
+    pub cycles: Option<Uint64>,
+    /// Cycles limit of the item in the mem pool queue, for polyjuice txs it's
+    /// the gas limit.
+    pub cycles_limit: Uint64,
+    /// 25th, 50th and 75th percentile fee rates of items in recent blocks.
+    pub low_fee_rate: Uint128,
+    pub medium_fee_rate: Uint128,
+    pub high_fee_rate: Uint128,
+    /// Min fee rate of new items in the mem pool queue.
+    pub queue_min_fee_rate: Uint128,
+    /// Number of items in the mem pool queue.
+    pub queue_len: Uint32,
+    /// Recommended fee rate, and the fee of the item at this rate.
+    pub fee_rate: Uint128,
+    pub fee: Uint128,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct WithdrawalLockArgs {
@@ -1389,3 +1848,77 @@ impl From<gw_common::registry_address::RegistryAddress> for RegistryAddress {
         }
     }
 }
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct StorageProof {
+    pub key: H256,
+    // key of the leaf in the account SMT
+    pub smt_key: H256,
+    pub value: H256,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountProof {
+    pub block_number: Uint64,
+    pub block_hash: H256,
+    // post_account.merkle_root of the block
+    pub state_root: H256,
+    pub account_id: Uint32,
+    pub script_hash: H256,
+    pub nonce: Uint32,
+    pub registry_address: Option<RegistryAddress>,
+    // CKB balance of the registry address
+    pub balance: gw_types::U256,
+    pub storage: Vec<StorageProof>,
+    // all SMT leaves covered by the proof
+    pub leaves: Vec<KVPair>,
+    // compiled SMT proof of `leaves` against `state_root`
+    pub proof: JsonBytes,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct BlockProof {
+    pub block_number: Uint64,
+    pub block_hash: H256,
+    // block SMT root in the global state of the tip block
+    pub block_root: H256,
+    pub tip_block_number: Uint64,
+    pub tip_block_hash: H256,
+    // compiled SMT proof of (block.smt_key, block_hash) against `block_root`
+    pub proof: JsonBytes,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum P2PSessionDirection {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct P2PSession {
+    pub session_id: Uint64,
+    // multiaddr of the remote peer
+    pub address: String,
+    pub peer_id: Option<String>,
+    pub direction: P2PSessionDirection,
+    // unix timestamp in milliseconds
+    pub opened_at: Uint64,
+    pub protocols: Vec<String>,
+    pub sent_bytes: Uint64,
+    pub received_bytes: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct P2PPeer {
+    pub peer_id: String,
+    pub score: i32,
+    // seconds until the ban expires, none if the peer is not banned
+    pub ban_remaining_secs: Option<Uint64>,
+    pub ban_reason: Option<String>,
+}
@@ -9,7 +9,7 @@ use bytes::Bytes;
 use futures::{StreamExt, TryStreamExt};
 use gw_common::H256;
 use gw_config::SyncServerConfig;
-use gw_p2p_network::{FnSpawn, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME};
+use gw_p2p_network::{FnSpawn, PeerBook, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME};
 use gw_types::{
     packed::{
         self, BlockSync, BlockSyncUnion, Confirmed, Found, L2Transaction, LocalBlock, NextMemBlock,
@@ -136,6 +136,23 @@ impl BlockSyncServerState {
         let _ = self.tx.send(msg);
     }
 
+    /// Publish a message received from another block sync server.
+    pub fn republish(&mut self, msg: BlockSync) {
+        match msg.to_enum() {
+            BlockSyncUnion::Revert(r) => self.publish_revert(r),
+            BlockSyncUnion::LocalBlock(l) => self.publish_local_block(l),
+            BlockSyncUnion::Submitted(s) => self.publish_submitted(s),
+            BlockSyncUnion::Confirmed(c) => self.publish_confirmed(c),
+            BlockSyncUnion::NextMemBlock(m) => self.publish_next_mem_block(m),
+            BlockSyncUnion::PushTransaction(p) => self.publish_transaction(p.transaction()),
+        }
+    }
+
+    /// Subscribe to messages published from now on.
+    pub fn subscribe(&self) -> Receiver<BlockSync> {
+        self.tx.subscribe()
+    }
+
     fn get_and_subscribe(
         &self,
         after: P2PSyncRequest,
@@ -155,11 +172,16 @@ impl BlockSyncServerState {
     }
 }
 
-pub fn block_sync_server_protocol(publisher: Arc<Mutex<BlockSyncServerState>>) -> ProtocolMeta {
+pub fn block_sync_server_protocol(
+    publisher: Arc<Mutex<BlockSyncServerState>>,
+    peer_book: PeerBook,
+) -> ProtocolMeta {
     let spawn = FnSpawn(move |context, control, mut read_part| {
         let publisher = publisher.clone();
+        let peer_book = peer_book.clone();
         let control = control.clone();
         let session_id = context.id;
+        peer_book.record_protocol(&context, P2P_SYNC_PROTOCOL_NAME);
         tokio::spawn(async move {
             // Compress messages.
             //
@@ -169,11 +191,13 @@ pub fn block_sync_server_protocol(publisher: Arc<Mutex<BlockSyncServerState>>) -
             // will be compressed to just a few bytes.
             let mut encoder = StreamEncoder::new(3).expect("create StreamEncoder");
             'outer: while let Some(msg) = read_part.try_next().await? {
+                peer_book.record_received(session_id, msg.len());
                 P2PSyncRequestReader::from_slice(msg.as_ref())?;
                 let request = P2PSyncRequest::new_unchecked(msg);
                 let mut send = |x: Bytes| {
                     let compressed: Bytes = encoder.encode(&x).expect("compress").into();
                     log::debug!("compression: {} -> {}", x.len(), compressed.len());
+                    peer_book.record_sent(session_id, compressed.len());
                     control.send_message_to(session_id, P2P_SYNC_PROTOCOL, compressed)
                 };
                 let result = publisher.lock().unwrap().get_and_subscribe(request);
@@ -14,25 +14,29 @@ use gw_utils::since::{LockValue, Since};
 use crate::custodian::to_custodian_cell;
 
 /// check and reject invalid deposit cells
+///
+/// Returns the sanitized deposit cells and the rejected ones with reasons.
 pub fn sanitize_deposit_cells(
     ctx: &RollupContext,
     config: &DepositTimeoutConfig,
     unsanitize_deposits: Vec<DepositInfo>,
     state: &MemStateDB,
-) -> Vec<DepositInfo> {
+) -> (Vec<DepositInfo>, Vec<(DepositInfo, String)>) {
     log::debug!(target: "collect-deposit-cells", "sanitize {} deposits", unsanitize_deposits.len());
     let mut deposit_cells = Vec::with_capacity(unsanitize_deposits.len());
+    let mut rejected_cells = Vec::new();
     for cell in unsanitize_deposits {
         // check deposit lock
         // the lock should be correct unless the upstream ckb-indexer has bugs
         if let Err(err) = check_deposit_cell(ctx, config, &cell, state) {
             log::debug!(target: "collect-deposit-cells", "invalid deposit cell: {}", err);
+            rejected_cells.push((cell, err.to_string()));
             continue;
         }
         deposit_cells.push(cell);
     }
     log::debug!(target: "collect-deposit-cells", "return {} sanitized deposits", deposit_cells.len());
-    deposit_cells
+    (deposit_cells, rejected_cells)
 }
 
 /// we only package deposit cells with valid cancel timeout, to prevent conflict with user's unlock
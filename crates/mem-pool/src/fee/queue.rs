@@ -1,6 +1,7 @@
 use anyhow::Result;
-use gw_common::state::State;
-use std::collections::{BTreeMap, HashMap};
+use gw_common::{state::State, H256};
+use gw_config::FeeQueueConfig;
+use std::collections::{hash_map, BTreeMap, BinaryHeap, HashMap};
 use tracing::instrument;
 
 /// Max queue size
@@ -8,34 +9,78 @@ const MAX_QUEUE_SIZE: usize = 100_000;
 /// Drop size when queue is full
 const DROP_SIZE: usize = 100;
 
-use super::types::{FeeEntry, FeeItemSender};
+use super::types::{EvictReason, FeeEntry, FeeItemSender};
 
 /// Txs & withdrawals queue sorted by fee rate
+///
+/// Entries of an account sender are queued by nonce, only the lowest nonce
+/// one is in the priority queue. The others are parked until the lower nonces
+/// are fetched. A sender whose lowest nonce is ahead of its account nonce is
+/// stalled, it stays parked until an entry of a lower nonce is added.
+///
+/// Pending create senders are identified by the tx signature, their entries
+/// are always in the priority queue.
 pub struct FeeQueue<T> {
     // priority queue to store tx and withdrawal
     queue: BTreeMap<FeeEntry, T>,
+    // account sender id => its entry in the priority queue
+    ready: HashMap<u32, FeeEntry>,
+    // account sender id => nonce => entries waiting for lower nonces
+    parked: HashMap<u32, BTreeMap<u32, (FeeEntry, T)>>,
+    parked_len: usize,
+    // hash => (account sender id, nonce), for cancellation
+    hashes: HashMap<H256, (u32, u32)>,
+    // evicted entries, until they are taken by `take_evicted`
+    evicted: Vec<(FeeEntry, T, EvictReason)>,
+    config: FeeQueueConfig,
 }
 
 impl<T> FeeQueue<T> {
     #[inline]
     pub fn new() -> Self {
+        Self::with_config(FeeQueueConfig::default())
+    }
+
+    #[inline]
+    pub fn with_config(config: FeeQueueConfig) -> Self {
         Self {
             queue: BTreeMap::new(),
+            ready: HashMap::new(),
+            parked: HashMap::new(),
+            parked_len: 0,
+            hashes: HashMap::new(),
+            evicted: Vec::new(),
+            config,
         }
     }
 
     #[inline]
     pub fn len(&self) -> usize {
-        self.queue.len()
+        self.queue.len() + self.parked_len
     }
 
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.queue.is_empty()
+        self.len() == 0
     }
 
+    /// Add entry to queue, returns false if the entry is rejected.
+    ///
+    /// `sender_nonce` is the nonce of the sender account, an entry too far
+    /// ahead of it is rejected. It's ignored for pending create senders.
+    ///
+    /// An entry with the same sender and nonce of a queued entry replaces it
+    /// if it pays at least `replace_fee_rate_bump_percent` percent higher fee
+    /// rate, see `FeeEntry::can_replace`. Rejected and replaced entries are
+    /// evicted, see `take_evicted`.
     #[instrument(skip_all, fields(count = self.len()))]
-    pub fn add(&mut self, entry: FeeEntry, handle: T) {
+    pub fn add(&mut self, entry: FeeEntry, handle: T, sender_nonce: u32) -> bool {
+        let min_fee_rate = self.min_fee_rate();
+        if entry.fee < u128::from(min_fee_rate).saturating_mul(entry.cycles_limit.into()) {
+            self.evict(entry, handle, EvictReason::FeeRateTooLow);
+            return false;
+        }
+
         // push to queue
         log::debug!(
             "QueueLen: {} | add entry: {:?} {}",
@@ -43,31 +88,330 @@ impl<T> FeeQueue<T> {
             entry.item.kind(),
             hex::encode(entry.item.hash().as_slice())
         );
-        self.queue.insert(entry, handle);
+        match entry.sender {
+            FeeItemSender::AccountId(id) => {
+                let nonce = entry.item.nonce();
+                if nonce > sender_nonce.saturating_add(self.config.max_nonce_gap) {
+                    self.evict(entry, handle, EvictReason::NonceGapTooLarge);
+                    return false;
+                }
+                let bump_percent = self.config.replace_fee_rate_bump_percent;
+                match self
+                    .get(id, nonce)
+                    .map(|old| entry.can_replace(old, bump_percent))
+                {
+                    Some(true) => {
+                        if let Some((old, old_handle)) = self.remove(id, nonce) {
+                            self.evict(old, old_handle, EvictReason::Replaced);
+                        }
+                    }
+                    Some(false) => {
+                        self.evict(entry, handle, EvictReason::ReplacementUnderpriced);
+                        return false;
+                    }
+                    None => {
+                        let max_items = self.config.max_items_per_sender;
+                        if max_items > 0 && self.sender_items(id) >= max_items {
+                            self.evict(entry, handle, EvictReason::SenderQuotaExceeded);
+                            return false;
+                        }
+                    }
+                }
+                self.hashes.insert(entry.item.hash(), (id, nonce));
+                self.insert(id, entry, handle);
+            }
+            FeeItemSender::PendingCreate(_) => self.make_ready(entry, handle),
+        }
 
         // drop items if full
         if self.is_full() {
-            if let Some(first_to_keep) = self.queue.keys().nth(DROP_SIZE + 1).cloned() {
-                self.queue = self.queue.split_off(&first_to_keep);
-            }
+            let len = self.len();
+            self.drop_lowest();
+            gw_metrics::mem_pool()
+                .fee_queue_dropped
+                .inc_by((len - self.len()) as u64);
             log::debug!(
                 "QueueLen: {} | Fee queue is full, drop {} items",
                 self.len(),
-                DROP_SIZE,
+                len - self.len(),
             );
         }
+
+        true
+    }
+
+    /// Min fee rate (fee / cycles limit) of new entries.
+    ///
+    /// It rises linearly from 0 to `full_queue_min_fee_rate` as the queue
+    /// fills from `min_fee_rate_start_percent`.
+    pub fn min_fee_rate(&self) -> u64 {
+        let start_percent = self.config.min_fee_rate_start_percent.min(100) as usize;
+        let start = MAX_QUEUE_SIZE * start_percent / 100;
+        if self.len() <= start || start >= MAX_QUEUE_SIZE {
+            return 0;
+        }
+        let filled = (self.len() - start).min(MAX_QUEUE_SIZE - start) as u128;
+        let rate = u128::from(self.config.full_queue_min_fee_rate) * filled
+            / (MAX_QUEUE_SIZE - start) as u128;
+        rate as u64
+    }
+
+    /// Queued entry of an account sender by the request hash.
+    pub fn get_by_hash(&self, hash: &H256) -> Option<(&FeeEntry, &T)> {
+        let &(id, nonce) = self.hashes.get(hash)?;
+        let queued = match self.ready.get(&id) {
+            Some(head) if head.item.nonce() == nonce => self.queue.get_key_value(head),
+            _ => (self.parked.get(&id)?.get(&nonce)).map(|(e, t)| (e, t)),
+        };
+        queued.filter(|(e, _)| &e.item.hash() == hash)
+    }
+
+    /// Evict the entry of the request hash, returns false if it isn't queued.
+    ///
+    /// Only entries of account senders can be cancelled.
+    pub fn cancel(&mut self, hash: &H256) -> bool {
+        let (id, nonce) = match self.get_by_hash(hash) {
+            Some((entry, _)) => match entry.sender {
+                FeeItemSender::AccountId(id) => (id, entry.item.nonce()),
+                FeeItemSender::PendingCreate(_) => return false,
+            },
+            None => return false,
+        };
+        let removed = self.remove(id, nonce);
+        // the next nonce is ready to fetch if the cancelled one was
+        self.promote(id);
+        match removed {
+            Some((entry, handle)) => {
+                self.evict(entry, handle, EvictReason::Cancelled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take evicted entries with the reasons.
+    pub fn take_evicted(&mut self) -> Vec<(FeeEntry, T, EvictReason)> {
+        std::mem::take(&mut self.evicted)
     }
 
     #[inline]
     pub fn is_full(&self) -> bool {
-        self.queue.len() > MAX_QUEUE_SIZE
+        self.len() > MAX_QUEUE_SIZE
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &FeeEntry> {
+        let parked = self
+            .parked
+            .values()
+            .flat_map(|p| p.values().map(|(e, _)| e));
+        self.queue.keys().chain(parked)
+    }
+
+    fn get(&self, id: u32, nonce: u32) -> Option<&FeeEntry> {
+        match self.ready.get(&id) {
+            Some(entry) if entry.item.nonce() == nonce => Some(entry),
+            _ => self
+                .parked
+                .get(&id)
+                .and_then(|p| p.get(&nonce))
+                .map(|(e, _)| e),
+        }
+    }
+
+    fn sender_items(&self, id: u32) -> usize {
+        let parked = self.parked.get(&id).map(|p| p.len()).unwrap_or(0);
+        usize::from(self.ready.contains_key(&id)) + parked
+    }
+
+    // The lowest nonce entry of a sender is ready to fetch, unless the sender
+    // is stalled on a nonce gap and the entry doesn't fill it.
+    fn insert(&mut self, id: u32, entry: FeeEntry, handle: T) {
+        let nonce = entry.item.nonce();
+        match self.ready.get(&id) {
+            Some(head) if head.item.nonce() < nonce => self.park(id, entry, handle),
+            Some(head) => {
+                let head = head.clone();
+                self.ready.remove(&id);
+                if let Some((head, head_handle)) = self.queue.remove_entry(&head) {
+                    self.park(id, head, head_handle);
+                }
+                self.make_ready(entry, handle);
+            }
+            None => {
+                let lowest_parked = self.parked.get(&id).and_then(|p| p.keys().next());
+                if lowest_parked.map_or(true, |&lowest| nonce < lowest) {
+                    self.make_ready(entry, handle);
+                } else {
+                    self.park(id, entry, handle);
+                }
+            }
+        }
+    }
+
+    fn make_ready(&mut self, entry: FeeEntry, handle: T) {
+        // An entry which compares equal is overwritten
+        let overwritten = self.queue.remove_entry(&entry);
+        if let Some((old, _)) = &overwritten {
+            if let FeeItemSender::AccountId(id) = old.sender {
+                if self.ready.get(&id) == Some(old) {
+                    self.ready.remove(&id);
+                }
+            }
+        }
+        if let FeeItemSender::AccountId(id) = entry.sender {
+            self.ready.insert(id, entry.clone());
+        }
+        self.queue.insert(entry, handle);
+
+        if let Some((old, old_handle)) = overwritten {
+            if let FeeItemSender::AccountId(id) = old.sender {
+                self.promote(id);
+            }
+            self.evict(old, old_handle, EvictReason::Replaced);
+        }
+    }
+
+    fn park(&mut self, id: u32, entry: FeeEntry, handle: T) {
+        let parked = self.parked.entry(id).or_default();
+        match parked.insert(entry.item.nonce(), (entry, handle)) {
+            Some((old, old_handle)) => self.evict(old, old_handle, EvictReason::Replaced),
+            None => self.parked_len += 1,
+        }
+    }
+
+    // Move the lowest nonce parked entry of a sender to the priority queue, if
+    // the sender has no entry there.
+    fn promote(&mut self, id: u32) {
+        if self.ready.contains_key(&id) {
+            return;
+        }
+        let lowest = match self.parked.get(&id).and_then(|p| p.keys().next()) {
+            Some(&nonce) => nonce,
+            None => return,
+        };
+        if let Some((entry, handle)) = self.take_parked(id, lowest) {
+            self.make_ready(entry, handle);
+        }
+    }
+
+    fn take_parked(&mut self, id: u32, nonce: u32) -> Option<(FeeEntry, T)> {
+        let mut parked = match self.parked.entry(id) {
+            hash_map::Entry::Occupied(parked) => parked,
+            hash_map::Entry::Vacant(_) => return None,
+        };
+        let removed = parked.get_mut().remove(&nonce)?;
+        if parked.get().is_empty() {
+            parked.remove();
+        }
+        self.parked_len -= 1;
+        Some(removed)
+    }
+
+    // Take parked entries of a sender from the nonce.
+    fn take_parked_from(&mut self, id: u32, nonce: u32) -> BTreeMap<u32, (FeeEntry, T)> {
+        let mut parked = match self.parked.entry(id) {
+            hash_map::Entry::Occupied(parked) => parked,
+            hash_map::Entry::Vacant(_) => return BTreeMap::new(),
+        };
+        let removed = parked.get_mut().split_off(&nonce);
+        if parked.get().is_empty() {
+            parked.remove();
+        }
+        self.parked_len -= removed.len();
+        removed
+    }
+
+    fn remove(&mut self, id: u32, nonce: u32) -> Option<(FeeEntry, T)> {
+        match self.ready.get(&id) {
+            Some(head) if head.item.nonce() == nonce => {
+                let head = self.ready.remove(&id)?;
+                self.queue.remove_entry(&head)
+            }
+            _ => self.take_parked(id, nonce),
+        }
+    }
+
+    // Drop the lowest fee rate entries, and the later nonces of their senders
+    // which can't be fetched without them.
+    fn drop_lowest(&mut self) {
+        let mut lowest = BinaryHeap::with_capacity(DROP_SIZE + 1);
+        for entry in self.entries() {
+            lowest.push(entry);
+            if lowest.len() > DROP_SIZE {
+                lowest.pop();
+            }
+        }
+        let lowest: Vec<FeeEntry> = lowest.into_iter().cloned().collect();
+
+        for entry in lowest {
+            let mut dropped = Vec::new();
+            match entry.sender {
+                FeeItemSender::AccountId(id) => {
+                    let nonce = entry.item.nonce();
+                    if let Some(head) = self.ready.get(&id) {
+                        if head.item.nonce() >= nonce {
+                            let head = head.clone();
+                            dropped.extend(self.remove(id, head.item.nonce()));
+                        }
+                    }
+                    dropped.extend(self.take_parked_from(id, nonce).into_values());
+                }
+                FeeItemSender::PendingCreate(_) => {
+                    dropped.extend(self.queue.remove_entry(&entry));
+                }
+            }
+            for (entry, handle) in dropped {
+                self.evict(entry, handle, EvictReason::QueueFull);
+            }
+        }
+    }
+
+    // Remove the hash of an entry which has left the queue, unless an entry of
+    // the same hash is still queued.
+    fn unindex(&mut self, entry: &FeeEntry) {
+        if let FeeItemSender::AccountId(id) = entry.sender {
+            let hash = entry.item.hash();
+            let queued = self.get(id, entry.item.nonce()).map(|e| e.item.hash());
+            if queued != Some(hash) {
+                self.hashes.remove(&hash);
+            }
+        }
+    }
+
+    fn evict(&mut self, entry: FeeEntry, handle: T, reason: EvictReason) {
+        self.unindex(&entry);
+        log::debug!(
+            "QueueLen: {} | evict entry: {:?} {} entry_nonce {} reason {:?}",
+            self.len(),
+            entry.item.kind(),
+            hex::encode(entry.item.hash().as_slice()),
+            entry.item.nonce(),
+            reason,
+        );
+        self.evicted.push((entry, handle, reason));
     }
 
     fn pop_last(&mut self) -> Option<(FeeEntry, T)> {
-        if let Some(entry) = self.queue.keys().next_back().cloned() {
-            self.queue.remove_entry(&entry)
+        let entry = self.queue.keys().next_back().cloned()?;
+        if let FeeItemSender::AccountId(id) = entry.sender {
+            self.ready.remove(&id);
+        }
+        self.queue.remove_entry(&entry)
+    }
+
+    // Park the lowest nonce entry of a stalled sender, and evict the entries
+    // too far ahead of the sender's nonce.
+    fn stall(&mut self, id: u32, entry: FeeEntry, handle: T, sender_nonce: u32) {
+        let max_nonce = sender_nonce.saturating_add(self.config.max_nonce_gap);
+        if entry.item.nonce() > max_nonce {
+            self.evict(entry, handle, EvictReason::NonceGapTooLarge);
         } else {
-            None
+            self.park(id, entry, handle);
+        }
+        if let Some(from) = max_nonce.checked_add(1) {
+            for (entry, handle) in self.take_parked_from(id, from).into_values() {
+                self.evict(entry, handle, EvictReason::NonceGapTooLarge);
+            }
         }
     }
 
@@ -77,14 +421,17 @@ impl<T> FeeQueue<T> {
         // sorted fee items
         let mut fetched_items = Vec::with_capacity(count as usize);
         let mut fetched_senders: HashMap<FeeItemSender, u32> = Default::default();
-        // future items, we will push back this queue
-        let mut future_queue = Vec::default();
 
         // Fetch item from PQ
-        while let Some((entry, t)) = self.pop_last() {
-            let nonce = match fetched_senders.get(&entry.sender) {
+        while fetched_items.len() < count {
+            let (entry, t) = match self.pop_last() {
+                Some(item) => item,
+                None => break,
+            };
+            let sender = entry.sender;
+            let nonce = match fetched_senders.get(&sender) {
                 Some(&nonce) => nonce,
-                None => match entry.sender {
+                None => match sender {
                     FeeItemSender::PendingCreate(_) => 0,
                     FeeItemSender::AccountId(account_id) => state.get_nonce(account_id)?,
                 },
@@ -92,44 +439,29 @@ impl<T> FeeQueue<T> {
             match entry.item.nonce().cmp(&nonce) {
                 std::cmp::Ordering::Equal => {
                     // update nonce
-                    fetched_senders.insert(entry.sender, nonce.saturating_add(1));
+                    fetched_senders.insert(sender, nonce.saturating_add(1));
                     // fetch this item
+                    self.unindex(&entry);
                     fetched_items.push((entry, t));
                 }
                 std::cmp::Ordering::Greater => {
-                    // push item back if it still has change to get fetched
-                    future_queue.push((entry, t));
+                    match sender {
+                        FeeItemSender::AccountId(id) => self.stall(id, entry, t, nonce),
+                        // A pending create sender has no account to fill the gap
+                        FeeItemSender::PendingCreate(_) => {
+                            self.evict(entry, t, EvictReason::NonceGapTooLarge)
+                        }
+                    }
+                    continue;
                 }
                 _ => {
-                    log::debug!(
-                        "QueueLen: {} | delete entry: {:?} {} entry_nonce {} nonce {}",
-                        self.len(),
-                        entry.item.kind(),
-                        hex::encode(entry.item.hash().as_slice()),
-                        entry.item.nonce(),
-                        nonce
-                    );
+                    self.evict(entry, t, EvictReason::NonceTooLow);
                 }
             }
 
-            if fetched_items.len() >= count {
-                break;
-            }
-        }
-
-        // Add back future items
-        for (entry, t) in future_queue {
-            // Only add back if we fetched another item from the same sender
-            if fetched_senders.contains_key(&entry.sender) {
-                self.add(entry, t);
-            } else {
-                log::debug!(
-                    "QueueLen: {} | drop future entry: {:?} {} entry_nonce {}",
-                    self.len(),
-                    entry.item.kind(),
-                    hex::encode(entry.item.hash().as_slice()),
-                    entry.item.nonce(),
-                );
+            // the next nonce of the sender is ready to fetch
+            if let FeeItemSender::AccountId(id) = sender {
+                self.promote(id);
             }
         }
 
@@ -154,7 +486,7 @@ impl<T> Default for FeeQueue<T> {
 #[cfg(test)]
 mod tests {
     use gw_common::{h256_ext::H256Ext, state::State, H256};
-    use gw_config::GenesisConfig;
+    use gw_config::{FeeQueueConfig, GenesisConfig};
     use gw_generator::genesis::init_genesis;
     use gw_store::{
         state::{history::history_state::RWConfig, BlockStateDB, MemStateDB},
@@ -169,7 +501,7 @@ mod tests {
 
     use crate::fee::{
         queue::MAX_QUEUE_SIZE,
-        types::{FeeEntry, FeeItem, FeeItemSender},
+        types::{EvictReason, FeeEntry, FeeItem, FeeItemSender},
     };
 
     use super::FeeQueue;
@@ -227,10 +559,10 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
-        queue.add(entry3, ());
-        queue.add(entry4, ());
+        queue.add(entry1, (), 0);
+        queue.add(entry2, (), 0);
+        queue.add(entry3, (), 0);
+        queue.add(entry4, (), 0);
 
         let tree = MemStateDB::from_store(snap).unwrap();
 
@@ -284,7 +616,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
+        queue.add(entry1, (), 0);
 
         let entry2 = FeeEntry {
             item: FeeItem::Tx(Default::default()),
@@ -294,7 +626,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry2, ());
+        queue.add(entry2, (), 0);
 
         let entry3 = FeeEntry {
             item: FeeItem::Tx(Default::default()),
@@ -304,7 +636,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry3, ());
+        queue.add(entry3, (), 0);
 
         let entry4 = FeeEntry {
             item: FeeItem::Withdrawal(Default::default()),
@@ -314,7 +646,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry4, ());
+        queue.add(entry4, (), 0);
 
         let tree = MemStateDB::from_store(snap).unwrap();
 
@@ -373,8 +705,8 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
+        queue.add(entry1, (), 0);
+        queue.add(entry2, (), 0);
 
         let snap = store.get_snapshot();
         let tree = MemStateDB::from_store(snap).unwrap();
@@ -390,7 +722,10 @@ mod tests {
 
     #[test]
     fn test_replace_by_fee() {
-        let mut queue = FeeQueue::new();
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            replace_fee_rate_bump_percent: 1,
+            ..Default::default()
+        });
 
         let store = Store::open_tmp().expect("open store");
         setup_genesis(&store);
@@ -432,8 +767,9 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
+        assert!(queue.add(entry1, (), 0));
+        assert!(queue.add(entry2, (), 0));
+        assert_eq!(queue.len(), 1);
 
         let snap = store.get_snapshot();
         let tree = MemStateDB::from_store(snap).unwrap();
@@ -449,9 +785,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reject_underpriced_replacement() {
+        let config = FeeQueueConfig::default();
+        let bump_percent = config.replace_fee_rate_bump_percent;
+        let mut queue = FeeQueue::with_config(config);
+
+        let new_entry = |fee: u64, cycles_limit: u64| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(
+                        RawL2Transaction::new_builder()
+                            .nonce(0u32.pack())
+                            .args(Bytes::from(fee.to_le_bytes().to_vec()).pack())
+                            .build(),
+                    )
+                    .build(),
+            ),
+            fee: fee.into(),
+            cycles_limit,
+            sender: FeeItemSender::AccountId(2),
+            order: 0,
+        };
+        let queued = new_entry(100 * 1000, 1000);
+        assert!(queue.add(queued.clone(), (), 0));
+
+        // A higher fee doesn't help if the fee rate isn't bumped enough
+        let underpriced = [
+            new_entry((100 + bump_percent - 1) * 1000, 1000),
+            new_entry(200 * 1000, 2000),
+        ];
+        for entry in underpriced.iter() {
+            assert!(!queue.add(entry.clone(), (), 0));
+            let evicted = queue.take_evicted();
+            assert_eq!(evicted.len(), 1);
+            assert!(evicted[0].0 == *entry);
+            assert_eq!(evicted[0].2, EvictReason::ReplacementUnderpriced);
+        }
+        assert_eq!(queue.len(), 1);
+        assert!(queue.get_by_hash(&queued.item.hash()).is_some());
+
+        let replacement = new_entry((100 + bump_percent) * 1000, 1000);
+        assert!(queue.add(replacement.clone(), (), 0));
+        assert_eq!(queue.len(), 1);
+        assert!(queue.get_by_hash(&replacement.item.hash()).is_some());
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert!(evicted[0].0 == queued);
+        assert_eq!(evicted[0].2, EvictReason::Replaced);
+    }
+
     #[test]
     fn test_drop_items() {
-        let mut queue = FeeQueue::new();
+        // All entries are from the same sender, most of them are far ahead of its nonce.
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_items_per_sender: 0,
+            max_nonce_gap: u32::MAX,
+            ..Default::default()
+        });
 
         let store = Store::open_tmp().expect("open store");
         setup_genesis(&store);
@@ -481,7 +872,7 @@ mod tests {
                 sender: FeeItemSender::AccountId(2),
                 order: queue.len(),
             };
-            queue.add(entry1, ());
+            queue.add(entry1, (), 0);
         }
 
         assert_eq!(queue.len(), MAX_QUEUE_SIZE);
@@ -493,7 +884,7 @@ mod tests {
                     L2Transaction::new_builder()
                         .raw(
                             RawL2Transaction::new_builder()
-                                .nonce(10001u32.pack())
+                                .nonce((MAX_QUEUE_SIZE as u32).pack())
                                 .build(),
                         )
                         .build(),
@@ -503,11 +894,16 @@ mod tests {
                 sender: FeeItemSender::AccountId(2),
                 order: queue.len(),
             };
-            queue.add(entry1, ());
+            queue.add(entry1, (), 0);
         }
 
         // we should trigger the drop
         assert!(queue.len() < MAX_QUEUE_SIZE);
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.len(), MAX_QUEUE_SIZE + 1 - queue.len());
+        assert!(evicted
+            .iter()
+            .all(|(_, _, reason)| *reason == EvictReason::QueueFull));
     }
 
     #[test]
@@ -563,10 +959,10 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
-        queue.add(entry3, ());
-        queue.add(entry4, ());
+        queue.add(entry1, (), 0);
+        queue.add(entry2, (), 0);
+        queue.add(entry3, (), 0);
+        queue.add(entry4, (), 0);
 
         let tree = MemStateDB::from_store(snap).unwrap();
 
@@ -626,7 +1022,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
+        queue.add(entry1, (), 0);
 
         let entry2 = FeeEntry {
             item: FeeItem::Tx(Default::default()),
@@ -636,7 +1032,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry2, ());
+        queue.add(entry2, (), 0);
 
         let entry3 = FeeEntry {
             item: FeeItem::Tx(Default::default()),
@@ -646,7 +1042,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry3, ());
+        queue.add(entry3, (), 0);
 
         let entry4 = FeeEntry {
             item: FeeItem::Withdrawal(Default::default()),
@@ -656,7 +1052,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry4, ());
+        queue.add(entry4, (), 0);
 
         let tree = MemStateDB::from_store(snap).unwrap();
 
@@ -708,8 +1104,8 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
+        queue.add(entry1, (), 0);
+        queue.add(entry2, (), 0);
 
         let snap = store.get_snapshot();
         let tree = MemStateDB::from_store(snap).unwrap();
@@ -754,8 +1150,8 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
+        queue.add(entry1, (), 0);
+        queue.add(entry2, (), 0);
 
         let snap = store.get_snapshot();
         let tree = MemStateDB::from_store(snap).unwrap();
@@ -790,7 +1186,7 @@ mod tests {
                 sender: FeeItemSender::PendingCreate(H256::from_u32(2)),
                 order: queue.len(),
             };
-            queue.add(entry1, ());
+            queue.add(entry1, (), 0);
         }
 
         assert_eq!(queue.len(), MAX_QUEUE_SIZE);
@@ -812,13 +1208,323 @@ mod tests {
                 sender: FeeItemSender::PendingCreate(H256::from_u32(2)),
                 order: queue.len(),
             };
-            queue.add(entry1, ());
+            queue.add(entry1, (), 0);
         }
 
         // we should trigger the drop
         assert!(queue.len() < MAX_QUEUE_SIZE);
     }
 
+    #[test]
+    fn test_sender_quota() {
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_items_per_sender: 2,
+            ..Default::default()
+        });
+
+        for nonce in 0..3u32 {
+            let entry = FeeEntry {
+                item: FeeItem::Tx(
+                    L2Transaction::new_builder()
+                        .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                        .build(),
+                ),
+                fee: (100 * 1000u64).into(),
+                cycles_limit: 1000,
+                sender: FeeItemSender::AccountId(2),
+                order: queue.len(),
+            };
+            assert_eq!(queue.add(entry, (), 0), nonce < 2);
+        }
+        assert_eq!(queue.len(), 2);
+
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0.item.nonce(), 2);
+        assert_eq!(evicted[0].2, EvictReason::SenderQuotaExceeded);
+
+        // replacement doesn't count
+        let entry = FeeEntry {
+            item: FeeItem::Withdrawal(Default::default()),
+            fee: (200 * 1000u64).into(),
+            cycles_limit: 1000,
+            sender: FeeItemSender::AccountId(2),
+            order: queue.len(),
+        };
+        assert!(queue.add(entry, (), 0));
+        assert_eq!(queue.len(), 2);
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].2, EvictReason::Replaced);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut queue = FeeQueue::new();
+
+        let entries: Vec<_> = (0..2u32)
+            .map(|nonce| FeeEntry {
+                item: FeeItem::Tx(
+                    L2Transaction::new_builder()
+                        .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                        .build(),
+                ),
+                fee: (100 * 1000u64).into(),
+                cycles_limit: 1000,
+                sender: FeeItemSender::AccountId(2),
+                order: nonce as usize,
+            })
+            .collect();
+        for entry in entries.iter() {
+            assert!(queue.add(entry.clone(), (), 0));
+        }
+
+        // Both ready and parked entries are found by hash
+        for entry in entries.iter() {
+            let (queued, _) = queue.get_by_hash(&entry.item.hash()).unwrap();
+            assert!(queued == entry);
+        }
+
+        assert!(queue.cancel(&entries[0].item.hash()));
+        assert!(!queue.cancel(&entries[0].item.hash()));
+        assert!(!queue.cancel(&H256::from_u32(1)));
+        assert_eq!(queue.len(), 1);
+        assert!(queue.get_by_hash(&entries[0].item.hash()).is_none());
+
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert!(evicted[0].0 == entries[0]);
+        assert_eq!(evicted[0].2, EvictReason::Cancelled);
+
+        // The nonce is free for a new entry without a higher fee rate
+        let entry = FeeEntry {
+            item: FeeItem::Withdrawal(Default::default()),
+            order: queue.len(),
+            ..entries[0].clone()
+        };
+        assert!(queue.add(entry, (), 0));
+        assert_eq!(queue.len(), 2);
+        assert!(queue.take_evicted().is_empty());
+    }
+
+    #[test]
+    fn test_evict_nonce_gap_too_large() {
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_nonce_gap: 2,
+            ..Default::default()
+        });
+
+        let store = Store::open_tmp().expect("open store");
+        setup_genesis(&store);
+        {
+            let db = &store.begin_transaction();
+            let mut state = BlockStateDB::from_store(db, RWConfig::attach_block(1)).unwrap();
+
+            // create accounts
+            for i in 0..4 {
+                state.create_account(H256::from_u32(i)).unwrap();
+            }
+
+            db.commit().expect("commit");
+        }
+
+        for nonce in [2u32, 3] {
+            let entry = FeeEntry {
+                item: FeeItem::Tx(
+                    L2Transaction::new_builder()
+                        .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                        .build(),
+                ),
+                fee: (100 * 1000u64).into(),
+                cycles_limit: 1000,
+                sender: FeeItemSender::AccountId(2),
+                order: queue.len(),
+            };
+            // The sender nonce is 1 when added, e.g. before the mem pool is reset
+            assert!(queue.add(entry, (), 1));
+        }
+
+        let snap = store.get_snapshot();
+        let tree = MemStateDB::from_store(snap).unwrap();
+
+        // nonce 2 is kept for the missing nonce 0 and 1
+        let items = queue.fetch(&tree, 3).expect("fetch");
+        assert!(items.is_empty());
+        assert_eq!(queue.len(), 1);
+
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0.item.nonce(), 3);
+        assert_eq!(evicted[0].2, EvictReason::NonceGapTooLarge);
+    }
+
+    #[test]
+    fn test_reject_future_nonce_flood() {
+        let new_entry = |sender: u32, nonce: u32| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                    .build(),
+            ),
+            fee: (100 * 1000u64).into(),
+            cycles_limit: 1000,
+            sender: FeeItemSender::AccountId(sender),
+            order: nonce as usize,
+        };
+        let config = FeeQueueConfig::default();
+        assert!(config.max_items_per_sender > 0);
+
+        // One sender floods future nonces, only the quota is queued
+        let mut queue = FeeQueue::with_config(config.clone());
+        for nonce in 1..=1000u32 {
+            let accepted = queue.add(new_entry(2, nonce), (), 0);
+            assert_eq!(accepted, nonce as usize <= config.max_items_per_sender);
+        }
+        assert_eq!(queue.len(), config.max_items_per_sender);
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.len(), 1000 - config.max_items_per_sender);
+        assert!(evicted.iter().all(|(entry, _, reason)| {
+            if entry.item.nonce() > config.max_nonce_gap {
+                *reason == EvictReason::NonceGapTooLarge
+            } else {
+                *reason == EvictReason::SenderQuotaExceeded
+            }
+        }));
+
+        // Other senders are not affected
+        assert!(queue.add(new_entry(3, 0), (), 0));
+
+        // Nonces too far ahead are rejected without a quota
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_items_per_sender: 0,
+            ..config.clone()
+        });
+        let max_nonce = 10 + config.max_nonce_gap;
+        for nonce in 11..=(max_nonce + 100) {
+            assert_eq!(queue.add(new_entry(2, nonce), (), 10), nonce <= max_nonce);
+        }
+        assert_eq!(queue.len(), config.max_nonce_gap as usize);
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.len(), 100);
+        assert!(evicted
+            .iter()
+            .all(|(_, _, reason)| *reason == EvictReason::NonceGapTooLarge));
+    }
+
+    #[test]
+    fn test_park_gapped_nonces() {
+        let mut queue = FeeQueue::new();
+
+        let store = Store::open_tmp().expect("open store");
+        setup_genesis(&store);
+        {
+            let db = &store.begin_transaction();
+            let mut state = BlockStateDB::from_store(db, RWConfig::attach_block(1)).unwrap();
+
+            // create accounts
+            for i in 0..4 {
+                state.create_account(H256::from_u32(i)).unwrap();
+            }
+
+            db.commit().expect("commit");
+        }
+
+        let new_entry = |sender: u32, nonce: u32, fee: u64| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                    .build(),
+            ),
+            fee: (fee * 1000).into(),
+            cycles_limit: 1000,
+            sender: FeeItemSender::AccountId(sender),
+            order: nonce as usize,
+        };
+        assert!(queue.add(new_entry(2, 1, 200), (), 0));
+        assert!(queue.add(new_entry(2, 2, 200), (), 0));
+        assert!(queue.add(new_entry(3, 0, 100), (), 0));
+
+        let snap = store.get_snapshot();
+        let tree = MemStateDB::from_store(snap).unwrap();
+
+        // nonce 1 and 2 are parked for the missing nonce 0
+        let items = queue.fetch(&tree, 3).expect("fetch");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].0.sender, FeeItemSender::AccountId(3));
+        assert_eq!(queue.len(), 2);
+
+        // parked entries aren't fetched again
+        let items = queue.fetch(&tree, 3).expect("fetch");
+        assert!(items.is_empty());
+        assert_eq!(queue.len(), 2);
+        assert!(queue.take_evicted().is_empty());
+
+        // the missing nonce unparks the later ones
+        assert!(queue.add(new_entry(2, 0, 100), (), 0));
+        let items = queue.fetch(&tree, 3).expect("fetch");
+        let nonces: Vec<_> = items.iter().map(|(e, _)| e.item.nonce()).collect();
+        assert_eq!(nonces, vec![0, 1, 2]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_min_fee_rate() {
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            full_queue_min_fee_rate: 1000,
+            min_fee_rate_start_percent: 50,
+            ..Default::default()
+        });
+
+        let start = MAX_QUEUE_SIZE / 2;
+        for i in 0..(start as u32) {
+            let entry = FeeEntry {
+                item: FeeItem::Tx(Default::default()),
+                fee: 1000u64.into(),
+                cycles_limit: 1000,
+                sender: FeeItemSender::AccountId(i),
+                order: queue.len(),
+            };
+            assert!(queue.add(entry, (), 0));
+        }
+        assert_eq!(queue.min_fee_rate(), 0);
+
+        let entry = FeeEntry {
+            item: FeeItem::Tx(Default::default()),
+            fee: 1000u64.into(),
+            cycles_limit: 1000,
+            sender: FeeItemSender::AccountId(start as u32),
+            order: queue.len(),
+        };
+        assert!(queue.add(entry, (), 0));
+        assert_eq!(queue.min_fee_rate(), 0);
+
+        // half of the way to full
+        for i in 0..(start as u32 / 2) {
+            let entry = FeeEntry {
+                item: FeeItem::Tx(Default::default()),
+                fee: (1000 * 1000u64).into(),
+                cycles_limit: 1000,
+                sender: FeeItemSender::AccountId(MAX_QUEUE_SIZE as u32 + i),
+                order: queue.len(),
+            };
+            assert!(queue.add(entry, (), 0));
+        }
+        assert_eq!(queue.min_fee_rate(), 500);
+
+        let len = queue.len();
+        let underpaid = FeeEntry {
+            item: FeeItem::Tx(Default::default()),
+            fee: (400 * 1000u64).into(),
+            cycles_limit: 1000,
+            sender: FeeItemSender::AccountId(u32::MAX),
+            order: queue.len(),
+        };
+        assert!(!queue.add(underpaid, (), 0));
+        assert_eq!(queue.len(), len);
+        let evicted = queue.take_evicted();
+        assert_eq!(evicted.last().unwrap().2, EvictReason::FeeRateTooLow);
+    }
+
     const ALWAYS_SUCCESS_CODE_HASH: [u8; 32] = [42u8; 32];
 
     fn setup_genesis(store: &Store) {
@@ -81,6 +81,28 @@ pub enum FeeItemSender {
     PendingCreate(H256), // hash
 }
 
+/// Why an entry is evicted from the fee queue.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum EvictReason {
+    /// One of the lowest fee rate entries dropped when the queue is full.
+    QueueFull,
+    /// Fee rate is lower than the min fee rate of the queue.
+    FeeRateTooLow,
+    /// Sender has too many entries in the queue.
+    SenderQuotaExceeded,
+    /// Nonce is lower than the sender's nonce.
+    NonceTooLow,
+    /// Nonce is too far ahead of the sender's nonce.
+    NonceGapTooLarge,
+    /// Replaced by an entry of the same sender and nonce.
+    Replaced,
+    /// Fee rate isn't high enough to replace the queued entry of the same
+    /// sender and nonce.
+    ReplacementUnderpriced,
+    /// Cancelled by the sender.
+    Cancelled,
+}
+
 #[derive(PartialEq, Eq, Clone)]
 pub struct FeeEntry {
     /// item: tx or withdrawal
@@ -173,6 +195,32 @@ impl FeeEntry {
         };
         Ok(entry)
     }
+
+    /// (sender id, nonce) of a replaceable entry.
+    ///
+    /// Txs from pending create senders are not replaceable, their sender ids
+    /// are unknown before account creation.
+    pub fn replace_key(&self) -> Option<(u32, u32)> {
+        match self.sender {
+            FeeItemSender::AccountId(id) => Some((id, self.item.nonce())),
+            FeeItemSender::PendingCreate(_) => None,
+        }
+    }
+
+    /// Whether this entry can replace `old`, it must pay at least
+    /// `bump_percent` percent higher fee rate.
+    pub fn can_replace(&self, old: &FeeEntry, bump_percent: u64) -> bool {
+        // self.fee / self.cycles_limit >= old.fee / old.cycles_limit * (100 + bump) / 100
+        let new_rate = self
+            .fee
+            .saturating_mul(old.cycles_limit.into())
+            .saturating_mul(100);
+        let required_rate = old
+            .fee
+            .saturating_mul(self.cycles_limit.into())
+            .saturating_mul(100u128.saturating_add(bump_percent.into()));
+        new_rate >= required_rate
+    }
 }
 
 struct L2Fee {
@@ -25,6 +25,7 @@ use gw_generator::{
 };
 use gw_store::{
     chain_view::ChainView,
+    deposit_log::DepositLog,
     mem_pool_state::{self, MemPoolState, Shared},
     state::{traits::JournalDB, MemStateDB},
     traits::chain_store::ChainStore,
@@ -35,8 +36,8 @@ use gw_traits::CodeStore;
 use gw_types::{
     offchain::{DepositInfo, FinalizedCustodianCapacity},
     packed::{
-        AccountMerkleState, BlockInfo, L2Block, L2Transaction, NextMemBlock, Script, TxReceipt,
-        WithdrawalKey, WithdrawalRequest, WithdrawalRequestExtra,
+        AccountMerkleState, BlockInfo, L2Block, L2Transaction, NextMemBlock, OutPoint, Script,
+        TxReceipt, WithdrawalKey, WithdrawalRequest, WithdrawalRequestExtra,
     },
     prelude::{Builder, Entity, Pack, PackVec, Unpack},
 };
@@ -93,6 +94,8 @@ pub struct MemPool {
     pending_restored_tx_hashes: VecDeque<H256>,
     dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     sync_server: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
+    /// Executed txs to publish after their receipts are committed
+    unpublished_txs: Vec<L2Transaction>,
     mem_block_config: MemBlockConfig,
     /// Cycles Pool
     cycles_pool: CyclesPool,
@@ -179,6 +182,7 @@ impl MemPool {
             mem_pool_state,
             dynamic_config_manager,
             sync_server,
+            unpublished_txs: Vec::new(),
             mem_block_config: config.mem_block,
             cycles_pool,
         };
@@ -279,9 +283,13 @@ impl MemPool {
             let db = self.store.begin_transaction();
 
             let mut state = self.mem_pool_state.load_state_db();
-            self.push_transaction_with_db(&db, &mut state, tx)?;
+            if let Err(err) = self.push_transaction_with_db(&db, &mut state, tx) {
+                self.unpublished_txs.clear();
+                return Err(err);
+            }
             db.commit()?;
             self.mem_pool_state.store_state_db(state);
+            self.publish_txs();
 
             Ok(())
         })
@@ -541,6 +549,12 @@ impl MemPool {
         new_tip: Option<H256>,
         local_cells_manager: &LocalCellsManager,
     ) -> Result<()> {
+        gw_metrics::mem_pool()
+            .mem_block_cycles
+            .observe(self.cycles_pool.cycles_used() as f64);
+        // Drop txs of a failed reset, they weren't committed
+        self.unpublished_txs.clear();
+
         let mut reinject_txs = Default::default();
         let mut reinject_withdrawals = Default::default();
         // read block from db
@@ -732,6 +746,11 @@ impl MemPool {
                 self.mem_block_config.syscall_cycles.clone(),
             );
             self.cycles_pool.consume_cycles(used_cycles);
+            let metrics = gw_metrics::mem_pool();
+            metrics.cycles_used.set(used_cycles as i64);
+            metrics
+                .cycles_limit
+                .set(self.mem_block_config.max_cycles_limit as i64);
 
             // store mem state
             let shared = Shared {
@@ -740,6 +759,7 @@ impl MemPool {
             };
             self.mem_pool_state.store_shared(Arc::new(shared));
             db.commit()?;
+            self.publish_txs();
 
             Ok(())
         })
@@ -864,6 +884,12 @@ impl MemPool {
         self.finalize_withdrawals(state, db, withdrawals.clone())?;
         // deposits
         self.finalize_deposits(state, deposit_cells.clone())?;
+        for deposit in deposit_cells.iter() {
+            let deposit_log = DepositLog::Collected {
+                tip_block_number: self.current_tip.1,
+            };
+            db.set_deposit_log(&deposit.cell.out_point, &deposit_log)?;
+        }
 
         if let Some(ref sync_server) = self.sync_server {
             let mut sync_server = sync_server.lock().unwrap();
@@ -902,12 +928,10 @@ impl MemPool {
         // refresh
         let state = self.mem_pool_state.load_state_db();
         let mem_account_count = state.get_account_count()?;
-        let tip_account_count: u32 = {
-            let new_tip_block = db
-                .get_block(&new_block_hash)?
-                .ok_or_else(|| anyhow!("can't find new tip block"))?;
-            new_tip_block.raw().post_account().count().unpack()
-        };
+        let new_tip_block = db
+            .get_block(&new_block_hash)?
+            .ok_or_else(|| anyhow!("can't find new tip block"))?;
+        let tip_account_count: u32 = new_tip_block.raw().post_account().count().unpack();
 
         log::debug!(
             "[mem-pool] refresh pending deposits, mem_account_count: {}, tip_account_count: {}",
@@ -918,12 +942,51 @@ impl MemPool {
             .provider
             .collect_deposit_cells(local_cells_manager)
             .await?;
-        self.pending_deposits = crate::deposit::sanitize_deposit_cells(
+        let (deposits, rejected) = crate::deposit::sanitize_deposit_cells(
             self.generator.rollup_context(),
             &self.mem_block_config.deposit_timeout_config,
             cells,
             &state,
         );
+
+        // deposits collected by the old mem block but not collected again, e.g.
+        // refunded or not returned by the provider, are no longer collected
+        {
+            let refreshed: HashSet<&OutPoint> = deposits
+                .iter()
+                .chain(rejected.iter().map(|(deposit, _reason)| deposit))
+                .map(|deposit| &deposit.cell.out_point)
+                .collect();
+            for deposit in self.mem_block.deposits() {
+                let out_point = &deposit.cell.out_point;
+                if !refreshed.contains(out_point) {
+                    db.delete_deposit_log(out_point)?;
+                }
+            }
+        }
+        self.pending_deposits = deposits;
+
+        // record rejections so users can find out why a deposit isn't picked up
+        let tip_block_number = new_tip_block.raw().number().unpack();
+        for (deposit, reason) in rejected {
+            let out_point = deposit.cell.out_point;
+            // rejected cells stay on L1, don't rewrite the same log on every refresh,
+            // an invalid log is overwritten
+            if let Ok(Some(DepositLog::Rejected {
+                reason: prev_reason,
+                ..
+            })) = db.get_deposit_log(&out_point)
+            {
+                if prev_reason == reason {
+                    continue;
+                }
+            }
+            let deposit_log = DepositLog::Rejected {
+                tip_block_number,
+                reason,
+            };
+            db.set_deposit_log(&out_point, &deposit_log)?;
+        }
         log::debug!(
             "[mem-pool] refreshed deposits: {}",
             self.pending_deposits.len()
@@ -1139,6 +1202,9 @@ impl MemPool {
                 state.revert(snap).unwrap();
                 err
             })?;
+        gw_metrics::mem_pool()
+            .cycles_used
+            .set(self.cycles_pool.cycles_used() as i64);
 
         // check account id of sudt proxy contract creator is from whitelist
         {
@@ -1166,13 +1232,24 @@ impl MemPool {
         let tx_receipt =
             TxReceipt::build_receipt(tx.witness_hash().into(), run_result, merkle_state);
 
-        if let Some(ref sync_server) = self.sync_server {
-            sync_server.lock().unwrap().publish_transaction(tx);
+        if self.sync_server.is_some() {
+            self.unpublished_txs.push(tx);
         }
 
         Ok(tx_receipt)
     }
 
+    /// Publish executed txs, their receipts must be committed so that
+    /// subscribers can read them.
+    fn publish_txs(&mut self) {
+        if let Some(ref sync_server) = self.sync_server {
+            let mut sync_server = sync_server.lock().unwrap();
+            for tx in self.unpublished_txs.drain(..) {
+                sync_server.publish_transaction(tx);
+            }
+        }
+    }
+
     async fn restore_pending_withdrawals(&mut self) -> Result<()> {
         let db = self.store.begin_transaction();
         let withdrawals_iter = db.get_mem_pool_withdrawal_iter();
@@ -1235,6 +1312,7 @@ impl MemPool {
         deposits: Vec<DepositInfo>,
     ) -> Result<Option<u64>> {
         block_in_place(move || {
+            self.unpublished_txs.clear();
             let next_block_number = block_info.number().unpack();
             let current_tip_block_number = self.current_tip.1;
             if next_block_number <= current_tip_block_number {
@@ -1289,6 +1367,7 @@ impl MemPool {
             };
             self.mem_pool_state.store_shared(Arc::new(shared));
             db.commit()?;
+            self.publish_txs();
 
             let mem_block = &self.mem_block;
             log::info!(
@@ -0,0 +1,181 @@
+//! Prometheus metrics.
+//!
+//! Metrics are registered in a global registry on first use, and exported in
+//! the text format by the `/metrics` endpoint of the metrics server, see
+//! `rpc_server.metrics_listen`.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use prometheus::{
+    core::Collector, exponential_buckets, Encoder, Histogram, HistogramOpts, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+static MEM_POOL: Lazy<MemPoolMetrics> = Lazy::new(MemPoolMetrics::new);
+static BLOCK_PRODUCER: Lazy<BlockProducerMetrics> = Lazy::new(BlockProducerMetrics::new);
+static RPC: Lazy<RPCMetrics> = Lazy::new(RPCMetrics::new);
+static DB: Lazy<DBMetrics> = Lazy::new(DBMetrics::new);
+
+pub fn mem_pool() -> &'static MemPoolMetrics {
+    &MEM_POOL
+}
+
+pub fn block_producer() -> &'static BlockProducerMetrics {
+    &BLOCK_PRODUCER
+}
+
+pub fn rpc() -> &'static RPCMetrics {
+    &RPC
+}
+
+pub fn db() -> &'static DBMetrics {
+    &DB
+}
+
+/// Encode all registered metrics in the prometheus text format.
+pub fn gather() -> Result<Vec<u8>> {
+    // Register metrics that haven't been used yet, so that they are always
+    // exported.
+    Lazy::force(&MEM_POOL);
+    Lazy::force(&BLOCK_PRODUCER);
+    Lazy::force(&RPC);
+    Lazy::force(&DB);
+
+    let mut buf = Vec::new();
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buf)?;
+    Ok(buf)
+}
+
+fn register<C: Collector + Clone + 'static>(collector: C) -> C {
+    REGISTRY
+        .register(Box::new(collector.clone()))
+        .expect("register metric");
+    collector
+}
+
+fn int_gauge(name: &str, help: &str) -> IntGauge {
+    register(IntGauge::new(name, help).expect("int gauge"))
+}
+
+fn int_counter(name: &str, help: &str) -> IntCounter {
+    register(IntCounter::new(name, help).expect("int counter"))
+}
+
+/// Latency buckets from 1ms to ~65s.
+fn latency_buckets() -> Vec<f64> {
+    exponential_buckets(0.001, 2.0, 17).expect("latency buckets")
+}
+
+pub struct MemPoolMetrics {
+    /// Requests waiting in the `FeeQueue`.
+    pub fee_queue_len: IntGauge,
+    /// Requests dropped because the `FeeQueue` is full.
+    pub fee_queue_dropped: IntCounter,
+    /// Cycles used by the current mem block.
+    pub cycles_used: IntGauge,
+    /// Cycles limit of the current mem block.
+    pub cycles_limit: IntGauge,
+    /// Cycles used by each mem block, observed when the mem block is reset.
+    pub mem_block_cycles: Histogram,
+}
+
+impl MemPoolMetrics {
+    fn new() -> Self {
+        let mem_block_cycles =
+            HistogramOpts::new("gw_mem_block_cycles", "Cycles used by each mem block")
+                .buckets(exponential_buckets(1_000_000.0, 2.0, 14).expect("cycles buckets"));
+        Self {
+            fee_queue_len: int_gauge("gw_fee_queue_len", "Requests in the fee queue"),
+            fee_queue_dropped: int_counter(
+                "gw_fee_queue_dropped_total",
+                "Requests dropped because the fee queue is full",
+            ),
+            cycles_used: int_gauge(
+                "gw_mem_pool_cycles_used",
+                "Cycles used by the current mem block",
+            ),
+            cycles_limit: int_gauge(
+                "gw_mem_pool_cycles_limit",
+                "Cycles limit of the current mem block",
+            ),
+            mem_block_cycles: register(Histogram::with_opts(mem_block_cycles).expect("histogram")),
+        }
+    }
+}
+
+pub struct BlockProducerMetrics {
+    /// Produced but not yet submitted blocks.
+    pub local_count: IntGauge,
+    /// Submitted but not yet confirmed blocks.
+    pub submitted_count: IntGauge,
+    /// Latency of producing a local block.
+    pub produce_latency: Histogram,
+    /// Latency of submitting a block, including retries.
+    pub submit_latency: Histogram,
+    /// Latency of confirming a submitted block, including retries.
+    pub confirm_latency: Histogram,
+}
+
+impl BlockProducerMetrics {
+    fn new() -> Self {
+        let latency = |name: &str, help: &str| {
+            let opts = HistogramOpts::new(name, help).buckets(latency_buckets());
+            register(Histogram::with_opts(opts).expect("histogram"))
+        };
+        Self {
+            local_count: int_gauge("gw_psc_local_count", "Local blocks not submitted yet"),
+            submitted_count: int_gauge(
+                "gw_psc_submitted_count",
+                "Submitted blocks not confirmed yet",
+            ),
+            produce_latency: latency(
+                "gw_block_produce_seconds",
+                "Latency of producing a local block",
+            ),
+            submit_latency: latency("gw_block_submit_seconds", "Latency of submitting a block"),
+            confirm_latency: latency(
+                "gw_block_confirm_seconds",
+                "Latency of confirming a submitted block",
+            ),
+        }
+    }
+}
+
+pub struct RPCMetrics {
+    /// RPC request latency by method.
+    pub request_latency: HistogramVec,
+    /// RPC error responses by method.
+    pub errors: IntCounterVec,
+}
+
+impl RPCMetrics {
+    fn new() -> Self {
+        let request_latency = HistogramOpts::new("gw_rpc_request_seconds", "RPC request latency")
+            .buckets(latency_buckets());
+        Self {
+            request_latency: register(
+                HistogramVec::new(request_latency, &["method"]).expect("histogram vec"),
+            ),
+            errors: register(
+                IntCounterVec::new(Opts::new("gw_rpc_errors_total", "RPC errors"), &["method"])
+                    .expect("int counter vec"),
+            ),
+        }
+    }
+}
+
+pub struct DBMetrics {
+    /// RocksDB memory stats, see `gw_db::memory_stats`.
+    pub memory_stats: IntGaugeVec,
+}
+
+impl DBMetrics {
+    fn new() -> Self {
+        let opts = Opts::new("gw_rocksdb_memory_bytes", "RocksDB memory usage");
+        Self {
+            memory_stats: register(IntGaugeVec::new(opts, &["cf", "type"]).expect("int gauge vec")),
+        }
+    }
+}
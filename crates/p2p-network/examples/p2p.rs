@@ -42,6 +42,7 @@ async fn main() -> anyhow::Result<()> {
             dial: Vec::new(),
             secret_key_path: Some("examples/server-key".into()),
             allowed_peer_ids: Some(vec!["Qme22rAhVjej4UCYxzW52L8PtYVv3XHeY2JqRKuwJn5ZFQ".into()]),
+            ..Default::default()
         }
     } else {
         P2PNetworkConfig {
@@ -51,9 +52,11 @@ async fn main() -> anyhow::Result<()> {
             ],
             secret_key_path: Some("examples/client-key".into()),
             allowed_peer_ids: None,
+            ..Default::default()
         }
     };
-    let mut network = P2PNetwork::init(&config, [protocol()]).await?;
+    let mut network =
+        P2PNetwork::init(&config, [protocol()], PeerBook::new(&config.peer_score)).await?;
     network.run().await;
 
     Ok(())
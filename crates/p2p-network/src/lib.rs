@@ -1,10 +1,10 @@
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use gw_config::P2PNetworkConfig;
 use gw_utils::exponential_backoff::ExponentialBackoff;
 use socket2::SockRef;
@@ -19,18 +19,28 @@ use tentacle::{
     },
     traits::{ProtocolSpawn, ServiceHandle},
     utils::extract_peer_id,
-    ProtocolId, SubstreamReadPart,
+    ProtocolId, SessionId, SubstreamReadPart,
 };
 
+mod peer_book;
+
+pub use peer_book::{PeerBook, PeerStatus, SessionInfo, MAX_PEER_SCORE};
+
 const RECONNECT_BASE_DURATION: Duration = Duration::from_secs(2);
 
-/// Wrapper for tentacle Service. Automatically reconnect dial addresses.
+/// Wrapper for tentacle Service. Automatically reconnect dial addresses and
+/// refuse peers banned in the peer book.
 pub struct P2PNetwork {
     service: Service<SHandle>,
+    admin: P2PAdmin,
 }
 
 impl P2PNetwork {
-    pub async fn init<PS>(config: &P2PNetworkConfig, protocols: PS) -> Result<Self>
+    pub async fn init<PS>(
+        config: &P2PNetworkConfig,
+        protocols: PS,
+        peer_book: PeerBook,
+    ) -> Result<Self>
     where
         PS: IntoIterator,
         PS::Item: Into<ProtocolMeta>,
@@ -81,10 +91,17 @@ impl P2PNetwork {
         } else {
             None
         };
+        let allowed_peer_ids = Arc::new(Mutex::new(allowed_peer_ids));
         let mut service = builder.build(SHandle {
             dial_backoff,
-            allowed_peer_ids,
+            allowed_peer_ids: allowed_peer_ids.clone(),
+            peer_book: peer_book.clone(),
         });
+        let admin = P2PAdmin {
+            control: service.control().clone(),
+            allowed_peer_ids,
+            peer_book,
+        };
         let control = service.control().clone();
         // Send dial in another task to avoid deadlock.
         if !dial_vec.is_empty() {
@@ -105,22 +122,130 @@ impl P2PNetwork {
                 .await
                 .context("listen")?;
         }
-        Ok(Self { service })
+        Ok(Self { service, admin })
     }
 
     pub fn control(&self) -> &ServiceAsyncControl {
         self.service.control()
     }
 
+    pub fn admin(&self) -> &P2PAdmin {
+        &self.admin
+    }
+
     pub async fn run(&mut self) {
         self.service.run().await;
     }
 }
 
+/// Runtime management of the p2p network, e.g. for admin RPCs.
+#[derive(Clone)]
+pub struct P2PAdmin {
+    control: ServiceAsyncControl,
+    allowed_peer_ids: Arc<Mutex<Option<HashSet<PeerId>>>>,
+    peer_book: PeerBook,
+}
+
+impl P2PAdmin {
+    pub fn peer_book(&self) -> &PeerBook {
+        &self.peer_book
+    }
+
+    /// Dial a multiaddr. Unlike addresses in the config, the address is not
+    /// re-dialed when the session is closed.
+    pub async fn dial(&self, address: &str) -> Result<()> {
+        let address: MultiAddr = address.parse().context("parse dial address")?;
+        log::info!("dial {}", address);
+        self.control
+            .dial(address, TargetProtocol::All)
+            .await
+            .map_err(|err| anyhow!("dial: {:?}", err))
+    }
+
+    pub async fn disconnect(&self, session_id: usize) -> Result<()> {
+        let session_id = SessionId::new(session_id);
+        if !self.peer_book.sessions().iter().any(|s| s.id == session_id) {
+            return Err(anyhow!("session {} not found", session_id));
+        }
+        self.control
+            .disconnect(session_id)
+            .await
+            .map_err(|err| anyhow!("disconnect: {:?}", err))
+    }
+
+    /// Returns `None` if any peer is allowed.
+    pub fn allowed_peer_ids(&self) -> Option<Vec<String>> {
+        let allowed = self.allowed_peer_ids.lock().unwrap();
+        allowed
+            .as_ref()
+            .map(|allowed| allowed.iter().map(|p| p.to_base58()).collect())
+    }
+
+    /// Add a peer id to the allow list. The allow list must be enabled by
+    /// `allowed_peer_ids` in the config.
+    pub fn add_allowed_peer_id(&self, peer_id: &str) -> Result<()> {
+        let peer_id = parse_peer_id(peer_id)?;
+        let mut allowed = self.allowed_peer_ids.lock().unwrap();
+        let allowed = allowed
+            .as_mut()
+            .context("allow list is disabled, set allowed_peer_ids to enable it")?;
+        allowed.insert(peer_id);
+        Ok(())
+    }
+
+    /// Remove a peer id from the allow list and disconnect its sessions.
+    pub async fn remove_allowed_peer_id(&self, peer_id: &str) -> Result<()> {
+        let peer_id = parse_peer_id(peer_id)?;
+        {
+            let mut allowed = self.allowed_peer_ids.lock().unwrap();
+            let allowed = allowed
+                .as_mut()
+                .context("allow list is disabled, set allowed_peer_ids to enable it")?;
+            allowed.remove(&peer_id);
+        }
+        self.disconnect_peer(&peer_id).await;
+        Ok(())
+    }
+
+    /// Ban a peer and disconnect its sessions. Default duration is
+    /// `ban_duration_secs` in the config.
+    pub async fn ban(&self, peer_id: &str, duration: Option<Duration>) -> Result<()> {
+        let peer_id = parse_peer_id(peer_id)?;
+        let duration = duration.unwrap_or_else(|| self.peer_book.ban_duration());
+        self.peer_book.ban(&peer_id, duration, "banned by admin");
+        log::info!(
+            "ban peer {} for {}s",
+            peer_id.to_base58(),
+            duration.as_secs()
+        );
+        self.disconnect_peer(&peer_id).await;
+        Ok(())
+    }
+
+    pub fn unban(&self, peer_id: &str) -> Result<()> {
+        let peer_id = parse_peer_id(peer_id)?;
+        self.peer_book.unban(&peer_id);
+        Ok(())
+    }
+
+    async fn disconnect_peer(&self, peer_id: &PeerId) {
+        for session_id in self.peer_book.peer_sessions(peer_id) {
+            let _ = self.control.disconnect(session_id).await;
+        }
+    }
+}
+
+fn parse_peer_id(peer_id: &str) -> Result<PeerId> {
+    peer_id
+        .parse()
+        .map_err(|err| anyhow!("parse peer id {}: {:?}", peer_id, err))
+}
+
 // Implement ServiceHandle to handle tentacle events.
 struct SHandle {
-    allowed_peer_ids: Option<HashSet<PeerId>>,
+    allowed_peer_ids: Arc<Mutex<Option<HashSet<PeerId>>>>,
     dial_backoff: HashMap<MultiAddr, ExponentialBackoff>,
+    peer_book: PeerBook,
 }
 
 impl SHandle {
@@ -178,23 +303,37 @@ impl ServiceHandle for SHandle {
         log::info!("service event: {:?}", event);
         match event {
             ServiceEvent::SessionClose { session_context } => {
+                self.peer_book.close_session(session_context.id);
                 self.re_dial(context, session_context.address.clone());
             }
             ServiceEvent::SessionOpen { session_context } => {
                 // Check allow list.
                 let mut allow = true;
-                if let Some(ref allowed) = self.allowed_peer_ids {
-                    if let Some(peer_id) = extract_peer_id(&session_context.address) {
-                        if !allowed.contains(&peer_id) {
+                let peer_id = extract_peer_id(&session_context.address);
+                if let Some(ref allowed) = *self.allowed_peer_ids.lock().unwrap() {
+                    if let Some(ref peer_id) = peer_id {
+                        if !allowed.contains(peer_id) {
                             allow = false;
                         }
                     } else {
                         allow = false;
                     }
                 };
+                // Check bans.
+                if let Some(ref peer_id) = peer_id {
+                    if self.peer_book.is_banned(peer_id) {
+                        log::info!("refuse banned peer {}", peer_id.to_base58());
+                        allow = false;
+                    }
+                }
                 if !allow {
                     let _ = context.control().disconnect(session_context.id).await;
                 } else {
+                    self.peer_book.open_session(
+                        session_context.id,
+                        &session_context.address,
+                        session_context.ty.is_outbound(),
+                    );
                     self.reset(session_context.address.clone());
                 }
             }
@@ -0,0 +1,292 @@
+//! Connected sessions, peer scores and bans.
+//!
+//! Protocols reward peers for useful messages and penalize them for stalling
+//! or misbehaving. A peer whose score drops to the ban threshold is banned for
+//! a while: its sessions are disconnected as soon as they are opened.
+//!
+//! Protocols also record which protocols are opened on a session and the
+//! traffic of the session, for the p2p admin RPCs.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use gw_config::PeerScoreConfig;
+use tentacle::{
+    context::SessionContext, multiaddr::MultiAddr, secio::PeerId, utils::extract_peer_id, SessionId,
+};
+
+/// Scores are capped so that a long-lived peer can still be banned quickly.
+pub const MAX_PEER_SCORE: i32 = 100;
+
+#[derive(Clone, Debug, Default)]
+pub struct PeerStatus {
+    pub score: i32,
+    pub banned_until: Option<Instant>,
+    pub ban_reason: Option<String>,
+}
+
+impl PeerStatus {
+    pub fn is_banned(&self) -> bool {
+        matches!(self.banned_until, Some(until) if until > Instant::now())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SessionInfo {
+    pub id: SessionId,
+    pub address: MultiAddr,
+    pub peer_id: Option<PeerId>,
+    pub outbound: bool,
+    pub opened_at: SystemTime,
+    /// Names of protocols opened on this session.
+    pub protocols: Vec<String>,
+    pub sent_bytes: u64,
+    pub received_bytes: u64,
+}
+
+/// Shared sessions and peer scores. Cloning is cheap and clones share the
+/// same book.
+#[derive(Clone)]
+pub struct PeerBook {
+    peers: Arc<Mutex<HashMap<PeerId, PeerStatus>>>,
+    sessions: Arc<Mutex<HashMap<SessionId, SessionInfo>>>,
+    ban_threshold: i32,
+    ban_duration: Duration,
+}
+
+impl PeerBook {
+    pub fn new(config: &PeerScoreConfig) -> Self {
+        Self {
+            peers: Default::default(),
+            sessions: Default::default(),
+            ban_threshold: config.ban_threshold,
+            ban_duration: Duration::from_secs(config.ban_duration_secs),
+        }
+    }
+
+    pub fn ban_duration(&self) -> Duration {
+        self.ban_duration
+    }
+
+    pub fn score(&self, peer_id: &PeerId) -> i32 {
+        let peers = self.peers.lock().unwrap();
+        peers.get(peer_id).map(|s| s.score).unwrap_or_default()
+    }
+
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        let peers = self.peers.lock().unwrap();
+        peers.get(peer_id).map(|s| s.is_banned()).unwrap_or(false)
+    }
+
+    pub fn reward(&self, peer_id: &PeerId, delta: i32) {
+        let mut peers = self.peers.lock().unwrap();
+        let status = peers.entry(peer_id.clone()).or_default();
+        status.score = status.score.saturating_add(delta).min(MAX_PEER_SCORE);
+    }
+
+    /// Decrease the score of the peer. Returns true if the peer is banned.
+    pub fn penalize(&self, peer_id: &PeerId, delta: i32, reason: &str) -> bool {
+        let mut peers = self.peers.lock().unwrap();
+        let status = peers.entry(peer_id.clone()).or_default();
+        status.score = status.score.saturating_sub(delta);
+        log::warn!(
+            "penalize peer {}: {}, score {}",
+            peer_id.to_base58(),
+            reason,
+            status.score
+        );
+        if status.score <= self.ban_threshold {
+            Self::ban_status(status, self.ban_duration, reason);
+            log::warn!(
+                "ban peer {} for {}s",
+                peer_id.to_base58(),
+                self.ban_duration.as_secs()
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn ban(&self, peer_id: &PeerId, duration: Duration, reason: &str) {
+        let mut peers = self.peers.lock().unwrap();
+        let status = peers.entry(peer_id.clone()).or_default();
+        Self::ban_status(status, duration, reason);
+    }
+
+    pub fn unban(&self, peer_id: &PeerId) {
+        let mut peers = self.peers.lock().unwrap();
+        if let Some(status) = peers.get_mut(peer_id) {
+            status.banned_until = None;
+            status.ban_reason = None;
+        }
+    }
+
+    pub fn peers(&self) -> Vec<(PeerId, PeerStatus)> {
+        let peers = self.peers.lock().unwrap();
+        peers
+            .iter()
+            .map(|(id, status)| (id.clone(), status.clone()))
+            .collect()
+    }
+
+    pub fn sessions(&self) -> Vec<SessionInfo> {
+        let sessions = self.sessions.lock().unwrap();
+        let mut sessions: Vec<SessionInfo> = sessions.values().cloned().collect();
+        sessions.sort_unstable_by_key(|s| s.id);
+        sessions
+    }
+
+    /// Ids of the sessions with the peer.
+    pub fn peer_sessions(&self, peer_id: &PeerId) -> Vec<SessionId> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .values()
+            .filter(|s| s.peer_id.as_ref() == Some(peer_id))
+            .map(|s| s.id)
+            .collect()
+    }
+
+    pub(crate) fn open_session(&self, id: SessionId, address: &MultiAddr, outbound: bool) {
+        let mut sessions = self.sessions.lock().unwrap();
+        // The entry may already be created by `record_protocol`.
+        Self::session_entry(&mut sessions, id, address, outbound);
+    }
+
+    pub(crate) fn close_session(&self, id: SessionId) {
+        self.sessions.lock().unwrap().remove(&id);
+    }
+
+    /// Protocols may be opened before the service handles the session open
+    /// event, so the session is created here if it doesn't exist yet.
+    pub fn record_protocol(&self, context: &SessionContext, protocol: &str) {
+        self.add_protocol(
+            context.id,
+            &context.address,
+            context.ty.is_outbound(),
+            protocol,
+        );
+    }
+
+    fn add_protocol(&self, id: SessionId, address: &MultiAddr, outbound: bool, protocol: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+        let info = Self::session_entry(&mut sessions, id, address, outbound);
+        if !info.protocols.iter().any(|p| p == protocol) {
+            info.protocols.push(protocol.into());
+        }
+    }
+
+    pub fn record_sent(&self, id: SessionId, bytes: usize) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(info) = sessions.get_mut(&id) {
+            info.sent_bytes = info.sent_bytes.saturating_add(bytes as u64);
+        }
+    }
+
+    pub fn record_received(&self, id: SessionId, bytes: usize) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(info) = sessions.get_mut(&id) {
+            info.received_bytes = info.received_bytes.saturating_add(bytes as u64);
+        }
+    }
+
+    fn session_entry<'a>(
+        sessions: &'a mut HashMap<SessionId, SessionInfo>,
+        id: SessionId,
+        address: &MultiAddr,
+        outbound: bool,
+    ) -> &'a mut SessionInfo {
+        sessions.entry(id).or_insert_with(|| SessionInfo {
+            id,
+            address: address.clone(),
+            peer_id: extract_peer_id(address),
+            outbound,
+            opened_at: SystemTime::now(),
+            protocols: Vec::new(),
+            sent_bytes: 0,
+            received_bytes: 0,
+        })
+    }
+
+    fn ban_status(status: &mut PeerStatus, duration: Duration, reason: &str) {
+        // Start over after the ban expires.
+        status.score = 0;
+        status.banned_until = Some(Instant::now() + duration);
+        status.ban_reason = Some(reason.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_penalize_and_ban() {
+        let book = PeerBook::new(&PeerScoreConfig {
+            ban_threshold: -100,
+            ban_duration_secs: 3600,
+        });
+        let peer_id = PeerId::random();
+
+        book.reward(&peer_id, 1000);
+        assert_eq!(book.score(&peer_id), MAX_PEER_SCORE);
+
+        assert!(!book.penalize(&peer_id, 150, "stalled"));
+        assert_eq!(book.score(&peer_id), -50);
+        assert!(!book.is_banned(&peer_id));
+
+        assert!(book.penalize(&peer_id, 50, "invalid message"));
+        assert!(book.is_banned(&peer_id));
+        assert_eq!(book.score(&peer_id), 0);
+
+        book.unban(&peer_id);
+        assert!(!book.is_banned(&peer_id));
+    }
+
+    #[test]
+    fn test_sessions() {
+        let book = PeerBook::new(&PeerScoreConfig::default());
+        let peer_id = PeerId::random();
+        let address: MultiAddr = format!("/ip4/127.0.0.1/tcp/9000/p2p/{}", peer_id.to_base58())
+            .parse()
+            .unwrap();
+        let (s1, s2) = (SessionId::new(1), SessionId::new(2));
+
+        book.open_session(s1, &address, true);
+        book.add_protocol(s1, &address, true, "/p2p/sync");
+        book.add_protocol(s1, &address, true, "/p2p/sync");
+        book.record_sent(s1, 10);
+        book.record_received(s1, 20);
+        // The protocol is opened before the session open event.
+        book.add_protocol(s2, &address, false, "/p2p/sync");
+        book.open_session(s2, &address, false);
+
+        let sessions = book.sessions();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].id, s1);
+        assert_eq!(sessions[0].peer_id.as_ref(), Some(&peer_id));
+        assert!(sessions[0].outbound);
+        assert_eq!(sessions[0].protocols, vec!["/p2p/sync".to_string()]);
+        assert_eq!(
+            (sessions[0].sent_bytes, sessions[0].received_bytes),
+            (10, 20)
+        );
+        assert_eq!(sessions[1].id, s2);
+        assert!(!sessions[1].outbound);
+        assert_eq!(sessions[1].protocols, vec!["/p2p/sync".to_string()]);
+
+        let mut peer_sessions = book.peer_sessions(&peer_id);
+        peer_sessions.sort_unstable();
+        assert_eq!(peer_sessions, vec![s1, s2]);
+
+        book.close_session(s1);
+        book.close_session(s2);
+        assert!(book.sessions().is_empty());
+        // Traffic of closed sessions is not recorded.
+        book.record_sent(s1, 10);
+        assert!(book.sessions().is_empty());
+    }
+}
@@ -6,7 +6,7 @@ use gw_chain::chain::Chain;
 use gw_config::{Config, StoreConfig};
 use gw_db::{schema::COLUMNS, RocksDB};
 use gw_generator::{
-    account_lock_manage::{secp256k1::Secp256k1Eth, AccountLockManage},
+    account_lock_manage::{secp256k1::Secp256k1Eth, tron::Secp256k1Tron, AccountLockManage},
     backend_manage::BackendManage,
     genesis::init_genesis,
     Generator,
@@ -45,6 +45,8 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
         options: config.store.options.clone(),
         options_file: config.store.options_file.clone(),
         cache_size: config.store.cache_size,
+        enable_account_tx_index: config.store.enable_account_tx_index,
+        pruning: config.store.pruning.clone(),
     };
     let local_store = Store::new(RocksDB::open(&store_config, COLUMNS));
     let rollup_type_script = {
@@ -106,6 +108,16 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
             eth_lock_script_type_hash.hash().unpack(),
             Box::new(Secp256k1Eth::default()),
         );
+        // Tron EOA is optional
+        if let Some(tron_lock_script_type_hash) = allowed_eoa_type_hashes
+            .iter()
+            .find(|th| th.type_().to_entity() == AllowedEoaType::Tron.into())
+        {
+            account_lock_manage.register_lock_algorithm(
+                tron_lock_script_type_hash.hash().unpack(),
+                Box::new(Secp256k1Tron::default()),
+            );
+        }
         Arc::new(Generator::new(
             backend_manage,
             account_lock_manage,
@@ -129,6 +141,8 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
             options: config.store.options.clone(),
             options_file: config.store.options_file.clone(),
             cache_size: config.store.cache_size,
+            enable_account_tx_index: config.store.enable_account_tx_index,
+            pruning: config.store.pruning.clone(),
         };
         Store::new(RocksDB::open(&store_config, from_db_columns))
     };
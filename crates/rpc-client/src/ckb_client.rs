@@ -1,25 +1,36 @@
-use std::time::{Duration, Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
     error::RPCRequestError,
+    traits::RPCBackend,
     utils::{to_jsonh256, to_result, DEFAULT_HTTP_TIMEOUT},
 };
 use anyhow::{anyhow, bail, Context, Result};
-use async_jsonrpc_client::{HttpClient, Params as ClientParams, Transport};
+use async_jsonrpc_client::{HttpClient, Params as ClientParams};
 use gw_common::H256;
 use gw_jsonrpc_types::{blockchain::CellDep, ckb_jsonrpc_types};
 use gw_types::{offchain::TxStatus, packed::Transaction, prelude::*};
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::json;
 use tracing::instrument;
 
+/// Fee rates in shannons per KB.
+#[derive(Clone, Debug, Deserialize)]
+pub struct FeeRateStatistics {
+    pub mean: ckb_jsonrpc_types::Uint64,
+    pub median: ckb_jsonrpc_types::Uint64,
+}
+
 #[derive(Clone)]
 pub struct CKBClient {
-    ckb_client: HttpClient,
+    ckb_client: Arc<dyn RPCBackend>,
 }
 
 impl CKBClient {
-    pub fn new(ckb_client: HttpClient) -> Self {
+    pub fn new(ckb_client: Arc<dyn RPCBackend>) -> Self {
         Self { ckb_client }
     }
 
@@ -27,11 +38,11 @@ impl CKBClient {
         let client = HttpClient::builder()
             .timeout(DEFAULT_HTTP_TIMEOUT)
             .build(url)?;
-        Ok(Self::new(client))
+        Ok(Self::new(Arc::new(client)))
     }
 
-    fn client(&self) -> &HttpClient {
-        &self.ckb_client
+    fn client(&self) -> &dyn RPCBackend {
+        self.ckb_client.as_ref()
     }
 
     #[instrument(skip_all, fields(method = method))]
@@ -121,6 +132,20 @@ impl CKBClient {
         )
     }
 
+    /// Mean and median fee rates of transactions in the last `target` blocks.
+    /// Returns `None` if there are no transactions.
+    pub async fn get_fee_rate_statistics(
+        &self,
+        target: Option<u64>,
+    ) -> Result<Option<FeeRateStatistics>> {
+        let target = target.map(ckb_jsonrpc_types::Uint64::from);
+        self.request(
+            "get_fee_rate_statistics",
+            Some(ClientParams::Array(vec![json!(target)])),
+        )
+        .await
+    }
+
     pub async fn wait_tx_proposed(&self, tx_hash: H256) -> Result<()> {
         loop {
             match self.get_transaction_status(tx_hash).await? {
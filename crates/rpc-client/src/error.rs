@@ -111,4 +111,6 @@ pub enum CkbRpcError {
     PoolRejectedMalformedTransaction = -1108,
     /// (-1109): The transaction is expired from tx-pool after `expiry_hours`.
     TransactionExpired = -1109,
+    /// (-1111): The transaction is rejected for RBF checking.
+    PoolRejectedRBF = -1111,
 }
@@ -1,12 +1,14 @@
 #![allow(clippy::mutable_key_type)]
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::error::RPCRequestError;
 use crate::indexer_types::{Cell, Order, Pagination, ScriptType, SearchKey, SearchKeyFilter, Tx};
+use crate::traits::RPCBackend;
 use crate::utils::{to_result, DEFAULT_HTTP_TIMEOUT, DEFAULT_QUERY_LIMIT};
 use anyhow::{Context, Result};
-use async_jsonrpc_client::{HttpClient, Params as ClientParams, Transport};
+use async_jsonrpc_client::{HttpClient, Params as ClientParams};
 use ckb_types::prelude::Entity;
 use gw_jsonrpc_types::ckb_jsonrpc_types::{JsonBytes, Uint32};
 use gw_types::offchain::{CustodianStat, SUDTStat};
@@ -17,10 +19,10 @@ use serde_json::json;
 use tracing::instrument;
 
 #[derive(Clone)]
-pub struct CKBIndexerClient(HttpClient);
+pub struct CKBIndexerClient(Arc<dyn RPCBackend>);
 
 impl CKBIndexerClient {
-    pub fn new(ckb_indexer_client: HttpClient) -> Self {
+    pub fn new(ckb_indexer_client: Arc<dyn RPCBackend>) -> Self {
         Self(ckb_indexer_client)
     }
 
@@ -28,11 +30,11 @@ impl CKBIndexerClient {
         let client = HttpClient::builder()
             .timeout(DEFAULT_HTTP_TIMEOUT)
             .build(url)?;
-        Ok(Self::new(client))
+        Ok(Self::new(Arc::new(client)))
     }
 
-    fn client(&self) -> &HttpClient {
-        &self.0
+    fn client(&self) -> &dyn RPCBackend {
+        self.0.as_ref()
     }
 
     #[instrument(skip_all, fields(method = method))]
@@ -4,5 +4,6 @@ pub mod error;
 pub mod indexer_client;
 pub mod indexer_types;
 pub mod rpc_client;
+pub mod traits;
 mod utils;
 pub mod withdrawal;
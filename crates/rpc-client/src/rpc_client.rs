@@ -2,7 +2,9 @@
 
 use crate::ckb_client::CKBClient;
 use crate::indexer_client::CKBIndexerClient;
-use crate::indexer_types::{Cell, Order, Pagination, ScriptType, SearchKey, SearchKeyFilter};
+use crate::indexer_types::{
+    Cell, IOType, Order, Pagination, ScriptType, SearchKey, SearchKeyFilter, Tx,
+};
 use crate::utils::{to_h256, to_jsonh256, DEFAULT_QUERY_LIMIT, TYPE_ID_CODE_HASH};
 use anyhow::{anyhow, Result};
 use async_jsonrpc_client::Params as ClientParams;
@@ -963,6 +965,55 @@ impl RPCClient {
         Ok(collected)
     }
 
+    /// Search the transaction which consumes the cell, e.g. the unlock tx of a
+    /// withdrawal cell or the refund tx of a deposit cell.
+    ///
+    /// `lock` is the full lock script of the cell, the indexer returns both the
+    /// creating and consuming transactions of it.
+    #[instrument(skip_all, fields(tx_hash = %out_point.tx_hash(), index = Unpack::<u32>::unpack(&out_point.index())))]
+    pub async fn query_consuming_tx(
+        &self,
+        lock: Script,
+        out_point: &OutPoint,
+    ) -> Result<Option<H256>> {
+        let search_key = SearchKey::with_lock(lock);
+        let order = Order::Asc;
+        let mut cursor = None;
+
+        loop {
+            let txs: Pagination<Tx> = self
+                .indexer
+                .get_transactions(&search_key, &order, None, &cursor)
+                .await?;
+
+            for tx in txs.objects.into_iter() {
+                if !matches!(tx.io_type, IOType::Input) {
+                    continue;
+                }
+                let tx_hash: H256 = to_h256(tx.tx_hash);
+                let transaction = match self.ckb.get_transaction(tx_hash).await? {
+                    Some(transaction) => transaction,
+                    None => continue,
+                };
+                let input_index = tx.io_index.value() as usize;
+                let consumed = transaction
+                    .raw()
+                    .inputs()
+                    .get(input_index)
+                    .map(|input| &input.previous_output() == out_point)
+                    .unwrap_or(false);
+                if consumed {
+                    return Ok(Some(tx_hash));
+                }
+            }
+
+            if txs.last_cursor.is_empty() {
+                return Ok(None);
+            }
+            cursor = Some(txs.last_cursor);
+        }
+    }
+
     #[instrument(skip_all, fields(block_hash = %block_hash.pack()))]
     pub async fn get_header(
         &self,
@@ -2,11 +2,26 @@ use crate::indexer_client::CKBIndexerClient;
 use crate::indexer_types::{Cell, Order, Pagination, SearchKey};
 
 use anyhow::Result;
-use async_jsonrpc_client::Params;
+use async_jsonrpc_client::{HttpClient, Output, Params, Transport};
 use async_trait::async_trait;
 use gw_jsonrpc_types::ckb_jsonrpc_types::{JsonBytes, Uint32};
 use serde_json::json;
 
+/// Transport of [`CKBIndexerClient`] and [`crate::ckb_client::CKBClient`],
+/// an HTTP client of CKB node, or an in-memory chain in tests.
+#[async_trait]
+pub trait RPCBackend: Send + Sync {
+    async fn request(&self, method: &'static str, params: Option<Params>) -> Result<Output>;
+}
+
+#[async_trait]
+impl RPCBackend for HttpClient {
+    async fn request(&self, method: &'static str, params: Option<Params>) -> Result<Output> {
+        let output = Transport::request(self, method, params).await?;
+        Ok(output)
+    }
+}
+
 #[async_trait]
 pub trait IndexedCells {
     async fn get_cells(
@@ -1,59 +1,241 @@
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::{collections::HashMap, sync::Weak};
 
 use gw_common::H256;
+use gw_mem_pool::fee::types::{EvictReason, FeeEntry};
 use gw_types::packed::{L2Transaction, WithdrawalRequestExtra};
+use lru::LruCache;
 use tracing::instrument;
 
 use crate::registry::Request;
 
+/// Number of recently evicted requests to remember.
+const EVICTED_CACHE_SIZE: usize = 100_000;
+
 /// Hold in queue transactions and withdrawal requests.
 ///
 /// (For get_transaction and get_withdrawal RPC calls.)
-#[derive(Default)]
 pub struct InQueueRequestMap {
-    map: RwLock<HashMap<H256, Request>>,
+    inner: RwLock<Inner>,
+    // hash => reason, of recently evicted requests
+    evicted: Mutex<LruCache<H256, EvictReason>>,
+    // (hash, expire at) of used cancellations, so that they can't be replayed
+    used_cancellations: Mutex<LruCache<(H256, u64), ()>>,
+}
+
+impl Default for InQueueRequestMap {
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            evicted: Mutex::new(LruCache::new(EVICTED_CACHE_SIZE)),
+            used_cancellations: Mutex::new(LruCache::new(EVICTED_CACHE_SIZE)),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    map: HashMap<H256, InQueueRequest>,
+    // (sender id, nonce) => (hash, entry), for replace-by-fee
+    replaceable: HashMap<(u32, u32), (H256, FeeEntry)>,
+}
+
+struct InQueueRequest {
+    request: Request,
+    replace_key: Option<(u32, u32)>,
+    token: Arc<CancelToken>,
+}
+
+/// Shared by a request in the map and its handle in the fee queue, so that a
+/// request is either cancelled or pushed to mem pool, never both.
+#[derive(Default)]
+struct CancelToken(AtomicU8);
+
+impl CancelToken {
+    const QUEUED: u8 = 0;
+    const PUSHING: u8 = 1;
+    const CANCELLED: u8 = 2;
+
+    fn transit(&self, from: u8, to: u8) -> bool {
+        (self.0)
+            .compare_exchange(from, to, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+}
+
+/// A request is rejected to replace the queued one of the same sender and
+/// nonce.
+pub(crate) enum ReplaceError {
+    /// It doesn't pay enough fee.
+    Underpriced { queued_hash: H256 },
+    /// The queued one is being pushed to mem pool.
+    Pushing { queued_hash: H256 },
+}
+
+pub(crate) enum CancelError {
+    NotFound,
+    /// The request is being pushed to mem pool.
+    Pushing,
+    /// The cancellation is already used.
+    Used,
 }
 
 impl InQueueRequestMap {
     #[instrument(skip_all, fields(hash = %faster_hex::hex_string(k.as_slice()).expect("hex_string")))]
     pub(crate) fn insert(self: &Arc<Self>, k: H256, v: Request) -> Option<InQueueRequestHandle> {
-        let mut map = self.map.write().unwrap();
-        let inserted = map.insert(k, v).is_none();
-        if inserted {
-            tracing::info!(map.len = map.len(), "inserted");
-            Some(InQueueRequestHandle {
-                map: Arc::downgrade(self),
-                hash: k,
-            })
-        } else {
-            None
+        let mut inner = self.inner.write().unwrap();
+        if inner.map.contains_key(&k) {
+            return None;
         }
+        let token = Arc::new(CancelToken::default());
+        let request = InQueueRequest {
+            request: v,
+            replace_key: None,
+            token: Arc::clone(&token),
+        };
+        inner.map.insert(k, request);
+        tracing::info!(map.len = inner.map.len(), "inserted");
+        self.evicted.lock().unwrap().pop(&k);
+        Some(self.handle(k, token))
     }
 
+    /// Insert request, replace the queued request of the same sender and nonce
+    /// if `entry` pays at least `bump_percent` percent higher fee rate.
+    ///
+    /// The fee queue checks the fee rate of replacement again, this check
+    /// rejects underpriced replacements with a distinct RPC error. The
+    /// replaced request is removed from the map and cancelled at once, its
+    /// handle in the fee queue is evicted once the new request arrives.
     #[instrument(skip_all, fields(hash = %faster_hex::hex_string(k.as_slice()).expect("hex_string")))]
-    fn remove(&self, k: &H256) {
-        let mut map = self.map.write().unwrap();
-        map.remove(k);
-        tracing::info!(map.len = map.len(), "removed");
+    pub(crate) fn insert_replaceable(
+        self: &Arc<Self>,
+        k: H256,
+        v: Request,
+        entry: FeeEntry,
+        bump_percent: u64,
+    ) -> Result<Option<InQueueRequestHandle>, ReplaceError> {
+        let key = match entry.replace_key() {
+            Some(key) => key,
+            None => return Ok(self.insert(k, v)),
+        };
+        let mut inner = self.inner.write().unwrap();
+        if inner.map.contains_key(&k) {
+            return Ok(None);
+        }
+        if let Some((queued_hash, queued)) = inner.replaceable.get(&key) {
+            let queued_hash = *queued_hash;
+            if !entry.can_replace(queued, bump_percent) {
+                return Err(ReplaceError::Underpriced { queued_hash });
+            }
+            let queued_token = &inner.map.get(&queued_hash).expect("queued").token;
+            if !queued_token.transit(CancelToken::QUEUED, CancelToken::CANCELLED) {
+                return Err(ReplaceError::Pushing { queued_hash });
+            }
+            inner.map.remove(&queued_hash);
+            tracing::info!(
+                replaced = %faster_hex::hex_string(queued_hash.as_slice()).expect("hex_string"),
+                "replace"
+            );
+            self.record_evicted(queued_hash, EvictReason::Replaced);
+        }
+        inner.replaceable.insert(key, (k, entry));
+        let token = Arc::new(CancelToken::default());
+        let request = InQueueRequest {
+            request: v,
+            replace_key: Some(key),
+            token: Arc::clone(&token),
+        };
+        inner.map.insert(k, request);
+        tracing::info!(map.len = inner.map.len(), "inserted");
+        self.evicted.lock().unwrap().pop(&k);
+        Ok(Some(self.handle(k, token)))
+    }
+
+    fn handle(self: &Arc<Self>, hash: H256, token: Arc<CancelToken>) -> InQueueRequestHandle {
+        InQueueRequestHandle {
+            map: Arc::downgrade(self),
+            hash,
+            token,
+        }
+    }
+
+    /// Cancel the request unless it is being pushed to mem pool, a
+    /// cancellation of the hash and expiry time can only be used once.
+    ///
+    /// The request is removed from the map at once, its handle in the fee
+    /// queue is dropped by the request submitter.
+    #[instrument(skip_all, fields(hash = %faster_hex::hex_string(k.as_slice()).expect("hex_string")))]
+    pub(crate) fn cancel(&self, k: &H256, expire_at: u64) -> Result<(), CancelError> {
+        let mut inner = self.inner.write().unwrap();
+        let mut used_cancellations = self.used_cancellations.lock().unwrap();
+        if used_cancellations.contains(&(*k, expire_at)) {
+            return Err(CancelError::Used);
+        }
+        let request = inner.map.get(k).ok_or(CancelError::NotFound)?;
+        if !(request.token).transit(CancelToken::QUEUED, CancelToken::CANCELLED) {
+            return Err(CancelError::Pushing);
+        }
+        let token = Arc::clone(&request.token);
+        Self::remove_locked(&mut inner, k, &token);
+        used_cancellations.put((*k, expire_at), ());
+        tracing::info!(map.len = inner.map.len(), "cancelled");
+        self.record_evicted(*k, EvictReason::Cancelled);
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(hash = %faster_hex::hex_string(k.as_slice()).expect("hex_string")))]
+    fn remove(&self, k: &H256, token: &Arc<CancelToken>) {
+        let mut inner = self.inner.write().unwrap();
+        Self::remove_locked(&mut inner, k, token);
+        tracing::info!(map.len = inner.map.len(), "removed");
+    }
+
+    /// Remove the request of the token, a request of the same hash submitted
+    /// again after cancellation has another token.
+    fn remove_locked(inner: &mut Inner, k: &H256, token: &Arc<CancelToken>) {
+        match inner.map.get(k) {
+            Some(request) if Arc::ptr_eq(&request.token, token) => {}
+            _ => return,
+        }
+        let request = inner.map.remove(k).expect("in queue request");
+        if let Some(key) = request.replace_key {
+            if matches!(inner.replaceable.get(&key), Some((hash, _)) if hash == k) {
+                inner.replaceable.remove(&key);
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, k: &H256) -> Option<Request> {
+        let inner = self.inner.read().unwrap();
+        inner.map.get(k).map(|r| r.request.clone())
     }
 
     pub(crate) fn get_transaction(&self, k: &H256) -> Option<L2Transaction> {
-        match self.map.read().unwrap().get(k)? {
-            Request::Tx(tx) => Some(tx.clone()),
+        match self.get(k)? {
+            Request::Tx(tx) => Some(tx),
             _ => None,
         }
     }
 
     pub(crate) fn get_withdrawal(&self, k: &H256) -> Option<WithdrawalRequestExtra> {
-        match self.map.read().unwrap().get(k)? {
-            Request::Withdrawal(w) => Some(w.clone()),
+        match self.get(k)? {
+            Request::Withdrawal(w) => Some(w),
             _ => None,
         }
     }
 
     pub(crate) fn contains(&self, k: &H256) -> bool {
-        self.map.read().unwrap().contains_key(k)
+        self.inner.read().unwrap().map.contains_key(k)
+    }
+
+    fn record_evicted(&self, k: H256, reason: EvictReason) {
+        self.evicted.lock().unwrap().put(k, reason);
+    }
+
+    /// Reason of a recently evicted request.
+    pub(crate) fn get_evicted_reason(&self, k: &H256) -> Option<EvictReason> {
+        self.evicted.lock().unwrap().peek(k).copied()
     }
 }
 
@@ -61,12 +243,37 @@ impl InQueueRequestMap {
 pub(crate) struct InQueueRequestHandle {
     map: Weak<InQueueRequestMap>,
     hash: H256,
+    token: Arc<CancelToken>,
+}
+
+impl InQueueRequestHandle {
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.token.0.load(Ordering::SeqCst) == CancelToken::CANCELLED
+    }
+
+    /// Start pushing the request to mem pool, it can't be cancelled since.
+    /// Returns false if the request is already cancelled.
+    pub(crate) fn start_push(&self) -> bool {
+        (self.token).transit(CancelToken::QUEUED, CancelToken::PUSHING)
+    }
+
+    /// The request is put back to queue, it can be cancelled again.
+    pub(crate) fn abort_push(&self) {
+        (self.token).transit(CancelToken::PUSHING, CancelToken::QUEUED);
+    }
+
+    /// Remove the request and remember why it's evicted.
+    pub(crate) fn evict(self, reason: EvictReason) {
+        if let Some(map) = self.map.upgrade() {
+            map.record_evicted(self.hash, reason);
+        }
+    }
 }
 
 impl Drop for InQueueRequestHandle {
     fn drop(&mut self) {
         if let Some(map) = self.map.upgrade() {
-            map.remove(&self.hash);
+            map.remove(&self.hash, &self.token);
         }
     }
 }
@@ -1,3 +1,6 @@
 pub(crate) mod in_queue_request_map;
+pub(crate) mod proof;
 pub mod registry;
 pub mod server;
+pub(crate) mod state_override;
+pub mod ws_server;
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    h256_ext::H256Ext,
+    registry_address::RegistryAddress,
+    smt::SMT,
+    state::{
+        build_account_field_key, build_account_key, build_script_hash_to_registry_address_key,
+        build_sudt_key, GW_ACCOUNT_NONCE_TYPE, GW_ACCOUNT_SCRIPT_HASH_TYPE, SUDT_KEY_FLAG_BALANCE,
+    },
+    H256,
+};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::JsonBytes,
+    godwoken::{AccountProof, BlockProof, KVPair, StorageProof},
+};
+use gw_store::{
+    smt::smt_store::{SMTBlockStore, SMTStateStore},
+    snapshot::StoreSnapshot,
+    traits::chain_store::ChainStore,
+};
+use gw_types::{packed::L2Block, prelude::*};
+
+use crate::registry::to_jsonh256;
+
+/// Build SMT proof of the account fields and storage slots.
+///
+/// The account SMT only keeps the tree of the last valid tip block, so `block`
+/// must be the last valid tip block of the `snap`.
+pub(crate) fn build_account_proof(
+    snap: StoreSnapshot,
+    block: &L2Block,
+    account_id: u32,
+    storage_keys: Vec<H256>,
+) -> Result<Option<AccountProof>> {
+    let raw_block = block.raw();
+    let post_account = raw_block.post_account();
+    if account_id >= post_account.count().unpack() {
+        return Ok(None);
+    }
+    let block_number: u64 = raw_block.number().unpack();
+    let state_root: H256 = post_account.merkle_root().unpack();
+    let smt = SMT::new(state_root, SMTStateStore::new(snap));
+
+    let mut leaves: Vec<(H256, H256)> = Vec::new();
+    let mut get_leaf = |key: H256| -> Result<H256> {
+        let value = smt.get(&key)?;
+        if !leaves.iter().any(|(k, _)| k == &key) {
+            leaves.push((key, value));
+        }
+        Ok(value)
+    };
+
+    let nonce = get_leaf(build_account_field_key(account_id, GW_ACCOUNT_NONCE_TYPE))?;
+    let script_hash = get_leaf(build_account_field_key(
+        account_id,
+        GW_ACCOUNT_SCRIPT_HASH_TYPE,
+    ))?;
+
+    // prove the registry address mapping and the CKB balance of the address
+    let registry_address = {
+        let key = build_script_hash_to_registry_address_key(&script_hash);
+        let value = get_leaf(build_account_key(ETH_REGISTRY_ACCOUNT_ID, &key))?;
+        if value.is_zero() {
+            None
+        } else {
+            RegistryAddress::from_slice(value.as_slice())
+        }
+    };
+    let balance = match registry_address {
+        Some(ref address) => {
+            let key = build_sudt_key(SUDT_KEY_FLAG_BALANCE, address);
+            get_leaf(build_account_key(CKB_SUDT_ACCOUNT_ID, &key))?.to_u256()
+        }
+        None => Default::default(),
+    };
+
+    let mut storage = Vec::with_capacity(storage_keys.len());
+    let mut seen = HashSet::with_capacity(storage_keys.len());
+    for key in storage_keys.into_iter().filter(|k| seen.insert(*k)) {
+        let smt_key = build_account_key(account_id, key.as_slice());
+        let value = get_leaf(smt_key)?;
+        storage.push(StorageProof {
+            key: to_jsonh256(key),
+            smt_key: to_jsonh256(smt_key),
+            value: to_jsonh256(value),
+        });
+    }
+
+    let proof = smt
+        .merkle_proof(leaves.iter().map(|(k, _)| *k).collect())?
+        .compile(leaves.clone())?;
+
+    let account_proof = AccountProof {
+        block_number: block_number.into(),
+        block_hash: to_jsonh256(block.hash().into()),
+        state_root: to_jsonh256(state_root),
+        account_id: account_id.into(),
+        script_hash: to_jsonh256(script_hash),
+        nonce: nonce.to_u32().into(),
+        registry_address: registry_address.map(Into::into),
+        balance,
+        storage,
+        leaves: leaves
+            .into_iter()
+            .map(|(k, v)| KVPair {
+                k: to_jsonh256(k),
+                v: to_jsonh256(v),
+            })
+            .collect(),
+        proof: JsonBytes::from_vec(proof.0),
+    };
+    Ok(Some(account_proof))
+}
+
+/// Build SMT proof of a main chain block against the block SMT root of the last
+/// valid tip global state, the same tip `build_account_proof` proves against.
+pub(crate) fn build_block_proof(
+    snap: StoreSnapshot,
+    block_hash: H256,
+) -> Result<Option<BlockProof>> {
+    let block = match snap.get_block(&block_hash)? {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+    let block_number: u64 = block.raw().number().unpack();
+    let tip_block = snap.get_last_valid_tip_block()?;
+    let tip_block_hash: H256 = tip_block.hash().into();
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    // bad blocks and reverted blocks are not in the block SMT
+    if block_number > tip_block_number
+        || snap.get_block_hash_by_number(block_number)? != Some(block_hash)
+    {
+        return Ok(None);
+    }
+
+    let tip_global_state = snap
+        .get_block_post_global_state(&tip_block_hash)?
+        .ok_or_else(|| anyhow!("tip block global state not found"))?;
+    let block_root: H256 = tip_global_state.block().merkle_root().unpack();
+
+    let smt = SMT::new(block_root, SMTBlockStore::new(snap));
+    let leaves = vec![(block.smt_key().into(), block_hash)];
+    let proof = smt
+        .merkle_proof(vec![block.smt_key().into()])?
+        .compile(leaves)?;
+
+    let block_proof = BlockProof {
+        block_number: block_number.into(),
+        block_hash: to_jsonh256(block_hash),
+        block_root: to_jsonh256(block_root),
+        tip_block_number: tip_block_number.into(),
+        tip_block_hash: to_jsonh256(tip_block_hash),
+        proof: JsonBytes::from_vec(proof.0),
+    };
+    Ok(Some(block_proof))
+}
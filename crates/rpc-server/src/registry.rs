@@ -5,11 +5,14 @@ use gw_common::blake2b::new_blake2b;
 use gw_common::builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID};
 use gw_common::{state::State, H256};
 use gw_config::{
-    ChainConfig, ConsensusConfig, FeeConfig, MemPoolConfig, NodeMode, RPCMethods, RPCRateLimit,
-    RPCServerConfig, SyscallCyclesConfig,
+    ChainConfig, ConsensusConfig, DynamicConfig, FeeConfig, MemPoolConfig, NodeMode, RPCMethods,
+    RPCRateLimit, RPCServerConfig, SyscallCyclesConfig,
 };
+use gw_db::Direction;
 use gw_dynamic_config::manager::{DynamicConfigManager, DynamicConfigReloadResponse};
+use gw_generator::constants::L2TX_MAX_CYCLES;
 use gw_generator::generator::CyclesPool;
+use gw_generator::traits::StateExt;
 use gw_generator::utils::get_tx_type;
 use gw_generator::{
     error::TransactionError, sudt::build_l2_sudt_script,
@@ -17,52 +20,68 @@ use gw_generator::{
 };
 use gw_jsonrpc_types::godwoken::L2WithdrawalCommittedInfo;
 use gw_jsonrpc_types::{
-    blockchain::Script,
+    blockchain::{OutPoint as JsonOutPoint, Script},
     ckb_jsonrpc_types::{JsonBytes, Uint32},
     godwoken::{
-        BackendInfo, BackendType, EoaScript, EoaScriptType, ErrorTxReceipt, GlobalState, GwScript,
-        GwScriptType, L2BlockCommittedInfo, L2BlockStatus, L2BlockView, L2BlockWithStatus,
-        L2TransactionStatus, L2TransactionWithStatus, LastL2BlockCommittedInfo, NodeInfo,
-        NodeRollupConfig, RegistryAddress, RollupCell, RunResult, TxReceipt, WithdrawalStatus,
-        WithdrawalWithStatus,
+        AccountProof, AccountTx, AccountTxPage, AccountTxQuery, AccountTxRole, BackendInfo,
+        BackendType, BlockProof, BundleStepResult, DepositStatus, DepositWithStatus, EoaScript,
+        EoaScriptType, ErrorTxReceipt, EstimateFeeKind, EvictReason as JsonEvictReason,
+        FeeEstimate, GlobalState, GwScript, GwScriptType, IndexedLogItem, L2BlockCommittedInfo,
+        L2BlockStatus, L2BlockView, L2BlockWithStatus, L2DepositCommittedInfo, L2TransactionStatus,
+        L2TransactionWithStatus, LastL2BlockCommittedInfo, LogFilter, NodeInfo, NodeRollupConfig,
+        Order, P2PPeer, P2PSession, P2PSessionDirection, RegistryAddress, RollupCell, RunResult,
+        StateOverride, TransactionTrace, TxReceipt, WithdrawalFinality, WithdrawalLifecycle,
+        WithdrawalLifecycleStatus, WithdrawalStatus, WithdrawalWithStatus,
     },
     test_mode::TestModePayload,
 };
 use gw_mem_pool::fee::{
     queue::FeeQueue,
-    types::{FeeEntry, FeeItem, FeeItemKind, FeeItemSender},
+    types::{EvictReason, FeeEntry, FeeItem, FeeItemKind, FeeItemSender},
 };
+use gw_p2p_network::P2PAdmin;
 use gw_polyjuice_sender_recover::recover::PolyjuiceSenderRecover;
 use gw_rpc_client::rpc_client::RPCClient;
-use gw_store::state::history::history_state::RWConfig;
-use gw_store::state::{BlockStateDB, MemStateDB};
+use gw_store::account_tx_index::{AccountTxKey, AccountTxRole as StoreAccountTxRole};
+use gw_store::state::history::history_state::{RWConfig, StatePrunedError};
+use gw_store::state::{traits::JournalDB, BlockStateDB, MemStateDB};
 use gw_store::{
-    chain_view::ChainView, mem_pool_state::MemPoolState, traits::chain_store::ChainStore,
-    CfMemStat, Store,
+    chain_view::ChainView, deposit_log::DepositLog, mem_pool_state::MemPoolState,
+    traits::chain_store::ChainStore, transaction::StoreTransaction, CfMemStat, Store,
 };
 use gw_traits::CodeStore;
-use gw_types::offchain::RollupContext;
-use gw_types::packed::RawL2Transaction;
+use gw_types::core::ScriptHashType;
+use gw_types::offchain::{CellStatus, CellWithStatus, RollupContext, TxTrace};
+use gw_types::packed::{RawL2Transaction, TransactionKey};
 use gw_types::{
     bytes::Bytes,
     packed::{self, BlockInfo, Byte32, L2Transaction, RollupConfig, WithdrawalRequestExtra},
     prelude::*,
     U256,
 };
+use gw_utils::script_log::{parse_log, GwLog};
 use gw_version::Version;
 use jsonrpc_v2::{Data, Error as RpcError, MapRouter, Params, Server, Server as JsonrpcServer};
 use lru::LruCache;
 use once_cell::sync::Lazy;
 use pprof::ProfilerGuard;
 use std::{
+    collections::HashMap,
     convert::{TryFrom, TryInto},
-    sync::Arc,
-    time::{Duration, Instant},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, UNIX_EPOCH},
 };
 use tokio::sync::{mpsc, Mutex};
 use tracing::instrument;
 
-use crate::in_queue_request_map::{InQueueRequestHandle, InQueueRequestMap};
+use crate::in_queue_request_map::{
+    CancelError, InQueueRequestHandle, InQueueRequestMap, ReplaceError,
+};
+use crate::proof::{build_account_proof, build_block_proof};
+use crate::state_override::apply_state_override;
 
 static PROFILER_GUARD: Lazy<tokio::sync::Mutex<Option<ProfilerGuard>>> =
     Lazy::new(|| tokio::sync::Mutex::new(None));
@@ -82,11 +101,17 @@ const HEADER_NOT_FOUND_ERR_CODE: i64 = -32000;
 const INVALID_NONCE_ERR_CODE: i64 = -32001;
 const BUSY_ERR_CODE: i64 = -32006;
 const CUSTODIAN_NOT_ENOUGH_CODE: i64 = -32007;
+const STATE_PRUNED_ERR_CODE: i64 = -32008;
+const REPLACEMENT_UNDERPRICED_ERR_CODE: i64 = -32009;
 const INTERNAL_ERROR_ERR_CODE: i64 = -32099;
 const INVALID_REQUEST: i64 = -32600;
 const METHOD_NOT_AVAILABLE_ERR_CODE: i64 = -32601;
 const INVALID_PARAM_ERR_CODE: i64 = -32602;
 const RATE_LIMIT_ERR_CODE: i64 = -32603;
+const UNAUTHORIZED_ERR_CODE: i64 = -32604;
+
+/// Max seconds from now a request cancellation expires at.
+const MAX_CANCEL_REQUEST_TTL_SECS: u64 = 600;
 
 type SendTransactionRateLimiter = Mutex<LruCache<u32, Instant>>;
 
@@ -111,6 +136,33 @@ fn mem_pool_is_disabled_err() -> RpcError {
     }
 }
 
+fn account_tx_index_is_disabled_err() -> RpcError {
+    RpcError::Provided {
+        code: METHOD_NOT_AVAILABLE_ERR_CODE,
+        message: "account tx index is disabled",
+    }
+}
+
+/// History state of blocks before the pruning horizon is not available, it is
+/// checked by `BlockStateDB::from_store`.
+fn history_state_err(err: anyhow::Error) -> RpcError {
+    match err.downcast_ref::<StatePrunedError>() {
+        Some(pruned) => RpcError::Full {
+            code: STATE_PRUNED_ERR_CODE,
+            message: pruned.to_string(),
+            data: None,
+        },
+        None => err.into(),
+    }
+}
+
+fn unauthorized_err() -> RpcError {
+    RpcError::Provided {
+        code: UNAUTHORIZED_ERR_CODE,
+        message: "unauthorized",
+    }
+}
+
 fn invalid_param_err(msg: &'static str) -> RpcError {
     RpcError::Provided {
         code: INVALID_PARAM_ERR_CODE,
@@ -124,12 +176,12 @@ pub trait TestModeRPC {
     async fn produce_block(&self, payload: TestModePayload) -> Result<()>;
 }
 
-fn to_h256(v: JsonH256) -> H256 {
+pub(crate) fn to_h256(v: JsonH256) -> H256 {
     let h: [u8; 32] = v.into();
     h.into()
 }
 
-fn to_jsonh256(v: H256) -> JsonH256 {
+pub(crate) fn to_jsonh256(v: H256) -> JsonH256 {
     let h: [u8; 32] = v.into();
     h.into()
 }
@@ -146,7 +198,12 @@ pub struct ExecutionTransactionContext {
 pub struct SubmitTransactionContext {
     in_queue_request_map: Option<Arc<InQueueRequestMap>>,
     submit_tx: mpsc::Sender<(InQueueRequestHandle, Request)>,
+    cancel_tx: mpsc::Sender<H256>,
     mem_pool_state: Arc<MemPoolState>,
+    generator: Arc<Generator>,
+    dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
+    replace_fee_rate_bump_percent: u64,
+    max_nonce_gap: u32,
     rate_limiter: Option<SendTransactionRateLimiter>,
     rate_limit_config: Option<RPCRateLimit>,
     polyjuice_sender_recover: Arc<PolyjuiceSenderRecover>,
@@ -167,6 +224,7 @@ pub struct RegistryArgs<T> {
     pub consensus_config: ConsensusConfig,
     pub dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     pub polyjuice_sender_recover: PolyjuiceSenderRecover,
+    pub p2p_admin: Option<P2PAdmin>,
 }
 
 pub struct Registry {
@@ -179,6 +237,7 @@ pub struct Registry {
     backend_info: Vec<BackendInfo>,
     node_mode: NodeMode,
     submit_tx: mpsc::Sender<(InQueueRequestHandle, Request)>,
+    cancel_tx: mpsc::Sender<H256>,
     rpc_client: RPCClient,
     send_tx_rate_limit: Option<RPCRateLimit>,
     server_config: RPCServerConfig,
@@ -188,6 +247,8 @@ pub struct Registry {
     mem_pool_state: Arc<MemPoolState>,
     in_queue_request_map: Option<Arc<InQueueRequestMap>>,
     polyjuice_sender_recover: Arc<PolyjuiceSenderRecover>,
+    fee_queue_status: Arc<FeeQueueStatus>,
+    p2p_admin: Option<P2PAdmin>,
 }
 
 impl Registry {
@@ -210,6 +271,7 @@ impl Registry {
             consensus_config,
             dynamic_config_manager,
             polyjuice_sender_recover,
+            p2p_admin,
         } = args;
 
         let backend_info = get_backend_info(generator.clone());
@@ -224,24 +286,28 @@ impl Registry {
                 true,
             )),
         };
-        let in_queue_request_map = if matches!(node_mode, NodeMode::FullNode | NodeMode::Test) {
+        let in_queue_request_map = if node_mode.produces_blocks() {
             Some(Arc::new(InQueueRequestMap::default()))
         } else {
             None
         };
         let (submit_tx, submit_rx) = mpsc::channel(RequestSubmitter::MAX_CHANNEL_SIZE);
+        let (cancel_tx, cancel_rx) = mpsc::channel(RequestSubmitter::MAX_CHANNEL_SIZE);
         let polyjuice_sender_recover = Arc::new(polyjuice_sender_recover);
+        let fee_queue_status = Arc::new(FeeQueueStatus::default());
         if let Some(mem_pool) = mem_pool.as_ref().to_owned() {
             let submitter = RequestSubmitter {
                 mem_pool: Arc::clone(mem_pool),
                 submit_rx,
-                queue: FeeQueue::new(),
+                cancel_rx,
+                queue: FeeQueue::with_config(mem_pool_config.fee_queue.clone()),
                 dynamic_config_manager: dynamic_config_manager.clone(),
                 generator: generator.clone(),
                 mem_pool_state: mem_pool_state.clone(),
                 store: store.clone(),
                 polyjuice_sender_recover: Arc::clone(&polyjuice_sender_recover),
                 mem_pool_config: mem_pool_config.clone(),
+                fee_queue_status: Arc::clone(&fee_queue_status),
             };
             tokio::spawn(submitter.in_background());
         }
@@ -257,6 +323,7 @@ impl Registry {
             backend_info,
             node_mode,
             submit_tx,
+            cancel_tx,
             rpc_client,
             send_tx_rate_limit,
             server_config,
@@ -266,9 +333,15 @@ impl Registry {
             mem_pool_state,
             in_queue_request_map,
             polyjuice_sender_recover,
+            fee_queue_status,
+            p2p_admin,
         }
     }
 
+    pub(crate) fn store(&self) -> &Store {
+        &self.store
+    }
+
     pub fn build_rpc_server(self) -> Result<RPCServer> {
         let mut server = JsonrpcServer::new();
 
@@ -289,7 +362,15 @@ impl Registry {
             .with_data(Data::new(SubmitTransactionContext {
                 in_queue_request_map: self.in_queue_request_map.clone(),
                 submit_tx: self.submit_tx.clone(),
+                cancel_tx: self.cancel_tx.clone(),
                 mem_pool_state: self.mem_pool_state.clone(),
+                generator: self.generator.clone(),
+                dynamic_config_manager: self.dynamic_config_manager.clone(),
+                replace_fee_rate_bump_percent: self
+                    .mem_pool_config
+                    .fee_queue
+                    .replace_fee_rate_bump_percent,
+                max_nonce_gap: self.mem_pool_config.fee_queue.max_nonce_gap,
                 rate_limiter: send_transaction_rate_limiter,
                 rate_limit_config: self.send_tx_rate_limit,
                 polyjuice_sender_recover: self.polyjuice_sender_recover.clone(),
@@ -308,6 +389,8 @@ impl Registry {
             .with_data(Data::new(self.node_mode))
             .with_data(Data::new(self.in_queue_request_map))
             .with_data(Data::new(self.submit_tx))
+            .with_data(Data(self.fee_queue_status))
+            .with_data(Data::new(FeeRateCache::default()))
             .with_method("gw_ping", ping)
             .with_method("gw_get_tip_block_hash", get_tip_block_hash)
             .with_method("gw_get_block_hash", get_block_hash)
@@ -316,6 +399,8 @@ impl Registry {
             .with_method("gw_get_block_committed_info", get_block_committed_info)
             .with_method("gw_get_balance", get_balance)
             .with_method("gw_get_storage_at", get_storage_at)
+            .with_method("gw_get_proof", get_proof)
+            .with_method("gw_get_block_proof", get_block_proof)
             .with_method(
                 "gw_get_account_id_by_script_hash",
                 get_account_id_by_script_hash,
@@ -334,26 +419,40 @@ impl Registry {
             .with_method("gw_get_data", get_data)
             .with_method("gw_get_transaction", get_transaction)
             .with_method("gw_get_transaction_receipt", get_transaction_receipt)
+            .with_method("gw_get_logs", get_logs)
+            .with_method(
+                "gw_get_transactions_by_account",
+                get_transactions_by_account,
+            )
             .with_method("gw_get_withdrawal", get_withdrawal)
+            .with_method("gw_get_withdrawal_lifecycle", get_withdrawal_lifecycle)
+            .with_method("gw_get_deposit", get_deposit)
             .with_method("gw_get_pending_tx_hashes", get_pending_tx_hashes)
             .with_method("gw_execute_l2transaction", execute_l2transaction)
             .with_method("gw_execute_raw_l2transaction", execute_raw_l2transaction)
+            .with_method(
+                "gw_execute_raw_l2transaction_bundle",
+                execute_raw_l2transaction_bundle,
+            )
             .with_method(
                 "gw_compute_l2_sudt_script_hash",
                 compute_l2_sudt_script_hash,
             )
             .with_method("gw_get_fee_config", get_fee_config)
+            .with_method("gw_estimate_cycles", estimate_cycles)
+            .with_method("gw_estimate_fee", estimate_fee)
             .with_method("gw_get_mem_pool_state_root", get_mem_pool_state_root)
             .with_method("gw_get_mem_pool_state_ready", get_mem_pool_state_ready)
             .with_method("gw_get_node_info", get_node_info)
             .with_method("gw_reload_config", reload_config)
             .with_method("gw_get_last_submitted_info", get_last_submitted_info);
 
-        if self.node_mode != NodeMode::ReadOnly {
+        if self.node_mode.produces_blocks() {
             server = server
                 .with_method("gw_submit_l2transaction", submit_l2transaction)
                 .with_method("gw_submit_withdrawal_request", submit_withdrawal_request)
-                .with_method("gw_is_request_in_queue", is_request_in_queue);
+                .with_method("gw_is_request_in_queue", is_request_in_queue)
+                .with_method("gw_cancel_request", cancel_request);
         }
 
         // Tests
@@ -371,12 +470,41 @@ impl Registry {
                         .with_method("gw_start_profiler", start_profiler)
                         .with_method("gw_report_pprof", report_pprof);
                 }
+                RPCMethods::Debug => {
+                    server =
+                        server.with_method("gw_debug_trace_transaction", debug_trace_transaction);
+                }
                 RPCMethods::Test => {
                     server = server
                         // .with_method("gw_dump_mem_block", dump_mem_block)
                         .with_method("gw_get_rocksdb_mem_stats", get_rocksdb_memory_stats)
                         .with_method("gw_dump_jemalloc_profiling", dump_jemalloc_profiling)
                 }
+                RPCMethods::Admin => {
+                    // Pushing dynamic config requires the admin token.
+                    if self.dynamic_config_manager.load().admin_token().is_some() {
+                        server =
+                            server.with_method("gw_update_dynamic_config", update_dynamic_config);
+                    }
+                }
+                RPCMethods::P2P => {
+                    // P2P network is not enabled.
+                    let p2p_admin = match self.p2p_admin {
+                        Some(ref p2p_admin) => p2p_admin.clone(),
+                        None => continue,
+                    };
+                    server = server
+                        .with_data(Data::new(p2p_admin))
+                        .with_method("gw_p2p_list_sessions", p2p_list_sessions)
+                        .with_method("gw_p2p_list_peers", p2p_list_peers)
+                        .with_method("gw_p2p_dial", p2p_dial)
+                        .with_method("gw_p2p_disconnect", p2p_disconnect)
+                        .with_method("gw_p2p_ban", p2p_ban)
+                        .with_method("gw_p2p_unban", p2p_unban)
+                        .with_method("gw_p2p_get_allowed_peer_ids", p2p_get_allowed_peer_ids)
+                        .with_method("gw_p2p_add_allowed_peer_id", p2p_add_allowed_peer_id)
+                        .with_method("gw_p2p_remove_allowed_peer_id", p2p_remove_allowed_peer_id);
+                }
             }
         }
 
@@ -409,6 +537,8 @@ impl Request {
 struct RequestSubmitter {
     mem_pool: Arc<Mutex<gw_mem_pool::pool::MemPool>>,
     submit_rx: mpsc::Receiver<(InQueueRequestHandle, Request)>,
+    // hashes of cancelled requests, to remove them from the queue
+    cancel_rx: mpsc::Receiver<H256>,
     queue: FeeQueue<InQueueRequestHandle>,
     dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     generator: Arc<Generator>,
@@ -416,6 +546,22 @@ struct RequestSubmitter {
     store: Store,
     polyjuice_sender_recover: Arc<PolyjuiceSenderRecover>,
     mem_pool_config: MemPoolConfig,
+    fee_queue_status: Arc<FeeQueueStatus>,
+}
+
+/// Fee queue is owned by the request submitter, RPCs read its status from here.
+#[derive(Default)]
+struct FeeQueueStatus {
+    len: AtomicUsize,
+    min_fee_rate: AtomicU64,
+}
+
+impl FeeQueueStatus {
+    fn update(&self, queue: &FeeQueue<InQueueRequestHandle>) {
+        self.len.store(queue.len(), Ordering::Relaxed);
+        self.min_fee_rate
+            .store(queue.min_fee_rate(), Ordering::Relaxed);
+    }
 }
 
 #[instrument(skip_all, fields(req_kind = req.kind()))]
@@ -451,6 +597,39 @@ fn req_to_entry(
     }
 }
 
+/// Add request to queue with the nonce of its sender.
+fn add_request(
+    queue: &mut FeeQueue<InQueueRequestHandle>,
+    state: &impl State,
+    entry: FeeEntry,
+    handle: InQueueRequestHandle,
+) {
+    let sender_nonce = match entry.sender {
+        FeeItemSender::AccountId(id) => match state.get_nonce(id) {
+            Ok(nonce) => nonce,
+            Err(err) => {
+                log::error!("get nonce of sender {} error {}", id, err);
+                return;
+            }
+        },
+        FeeItemSender::PendingCreate(_) => 0,
+    };
+    queue.add(entry, handle, sender_nonce);
+}
+
+/// Remove evicted requests from the in queue request map, and remember why.
+fn evict_requests(queue: &mut FeeQueue<InQueueRequestHandle>) {
+    for (entry, handle, reason) in queue.take_evicted() {
+        log::info!(
+            "evict {:?} {} from queue, reason {:?}",
+            entry.item.kind(),
+            entry.item.hash().pack(),
+            reason
+        );
+        handle.evict(reason);
+    }
+}
+
 impl RequestSubmitter {
     const MAX_CHANNEL_SIZE: usize = 10000;
     const MAX_BATCH_SIZE: usize = 20;
@@ -493,6 +672,12 @@ impl RequestSubmitter {
         }
 
         loop {
+            evict_requests(&mut self.queue);
+            gw_metrics::mem_pool()
+                .fee_queue_len
+                .set(self.queue.len() as i64);
+            self.fee_queue_status.update(&self.queue);
+
             // check mem block empty slots
             loop {
                 let dynamic_config_manager = self.dynamic_config_manager.load();
@@ -543,8 +728,10 @@ impl RequestSubmitter {
                                 kind,
                                 hash,
                             );
+                        } else if handle.is_cancelled() {
+                            log::info!("req kind {} hash {} is cancelled, drop it", kind, hash);
                         } else {
-                            queue.add(entry, handle);
+                            add_request(queue, &state, entry, handle);
                         }
                     }
                     Err(err) => {
@@ -573,8 +760,10 @@ impl RequestSubmitter {
                                 kind,
                                 hash,
                             );
+                        } else if handle.is_cancelled() {
+                            log::info!("req kind {} hash {} is cancelled, drop it", kind, hash);
                         } else {
-                            queue.add(entry, handle);
+                            add_request(queue, &state, entry, handle);
                         }
                     }
                     Err(err) => {
@@ -588,6 +777,16 @@ impl RequestSubmitter {
                 }
             }
 
+            // remove cancelled requests, a request submitted again after
+            // cancellation has another handle
+            while let Ok(hash) = self.cancel_rx.try_recv() {
+                let queued = queue.get_by_hash(&hash);
+                let cancelled = queued.map_or(false, |(_, handle)| handle.is_cancelled());
+                if cancelled && queue.cancel(&hash) {
+                    log::info!("cancel {} from queue", hash.pack());
+                }
+            }
+
             // fetch items from PQ
             let items = match queue.fetch(&state, Self::MAX_BATCH_SIZE) {
                 Ok(items) => items,
@@ -601,6 +800,7 @@ impl RequestSubmitter {
                     continue;
                 }
             };
+            evict_requests(queue);
 
             if !items.is_empty() {
                 // recover accounts for polyjuice tx from id zero
@@ -635,8 +835,11 @@ impl RequestSubmitter {
                     {
                         log::info!("[tx from zero] mem block cycles limit reached, retry later");
 
+                        // Requests replaced since fetched are dropped.
                         for (entry, handle) in items {
-                            queue.add(entry, handle);
+                            if !handle.is_cancelled() {
+                                add_request(queue, &state, entry, handle);
+                            }
                         }
                         continue;
                     }
@@ -659,11 +862,19 @@ impl RequestSubmitter {
                         }
 
                         if block_cycles_limit_reached {
-                            queue.add(entry, handle);
+                            if !handle.is_cancelled() {
+                                add_request(queue, &state, entry, handle);
+                            }
                             continue;
                         }
                     }
 
+                    // A cancelled request is dropped, otherwise it can't be cancelled since.
+                    if !handle.start_push() {
+                        log::info!("{} is cancelled, drop it", entry.item.hash().pack());
+                        continue;
+                    }
+
                     let maybe_ok = match entry.item.clone() {
                         FeeItem::Tx(tx)
                             if matches!(entry.sender, FeeItemSender::PendingCreate(_)) =>
@@ -704,7 +915,8 @@ impl RequestSubmitter {
                             log::info!("mem block cycles limit reached for tx {}", hash);
 
                             block_cycles_limit_reached = true;
-                            queue.add(entry, handle);
+                            handle.abort_push();
+                            add_request(queue, &state, entry, handle);
 
                             continue;
                         }
@@ -774,6 +986,7 @@ async fn get_transaction(
         return Ok(Some(L2TransactionWithStatus {
             transaction: matches!(verbose, GetTxVerbose::TxWithStatus).then(|| tx.into()),
             status: L2TransactionStatus::Pending,
+            evicted_reason: None,
         }));
     }
     let db = store.get_snapshot();
@@ -790,18 +1003,46 @@ async fn get_transaction(
         }
     };
 
+    if tx_opt.is_none() {
+        let evicted_reason = in_queue_request_map
+            .as_deref()
+            .and_then(|m| m.get_evicted_reason(&tx_hash));
+        if let Some(reason) = evicted_reason {
+            return Ok(Some(L2TransactionWithStatus {
+                transaction: None,
+                status: L2TransactionStatus::Evicted,
+                evicted_reason: Some(to_json_evict_reason(reason)),
+            }));
+        }
+    }
+
     Ok(tx_opt.map(|tx| match verbose {
         GetTxVerbose::OnlyStatus => L2TransactionWithStatus {
             transaction: None,
             status,
+            evicted_reason: None,
         },
         GetTxVerbose::TxWithStatus => L2TransactionWithStatus {
             transaction: Some(tx.into()),
             status,
+            evicted_reason: None,
         },
     }))
 }
 
+fn to_json_evict_reason(reason: EvictReason) -> JsonEvictReason {
+    match reason {
+        EvictReason::QueueFull => JsonEvictReason::QueueFull,
+        EvictReason::FeeRateTooLow => JsonEvictReason::FeeRateTooLow,
+        EvictReason::SenderQuotaExceeded => JsonEvictReason::SenderQuotaExceeded,
+        EvictReason::NonceTooLow => JsonEvictReason::NonceTooLow,
+        EvictReason::NonceGapTooLarge => JsonEvictReason::NonceGapTooLarge,
+        EvictReason::Replaced => JsonEvictReason::Replaced,
+        EvictReason::ReplacementUnderpriced => JsonEvictReason::ReplacementUnderpriced,
+        EvictReason::Cancelled => JsonEvictReason::Cancelled,
+    }
+}
+
 async fn get_pending_tx_hashes(store: Data<Store>) -> Result<Vec<JsonH256>, RpcError> {
     let snap = store.get_snapshot();
     let tx_hashes = snap
@@ -822,12 +1063,113 @@ async fn is_request_in_queue(
         .map_or(false, |m| m.contains(&hash)))
 }
 
+/// Cancel a queued request, the cancellation must be signed by the sender.
+///
+/// A request already pushed to mem pool can't be cancelled. A cancellation
+/// expires at `expire_at`, and it can't be used twice.
+async fn cancel_request(
+    Params((hash, expire_at, signature)): Params<(JsonH256, GwUint64, JsonBytes)>,
+    ctx: Data<SubmitTransactionContext>,
+) -> Result<(), RpcError> {
+    let hash = to_h256(hash);
+    let expire_at: u64 = expire_at.value();
+    let now = std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)?
+        .as_secs();
+    if expire_at < now {
+        return Err(RpcError::Provided {
+            code: INVALID_PARAM_ERR_CODE,
+            message: "cancellation expired",
+        });
+    }
+    if expire_at > now.saturating_add(MAX_CANCEL_REQUEST_TTL_SECS) {
+        return Err(RpcError::Full {
+            code: INVALID_PARAM_ERR_CODE,
+            message: format!(
+                "cancellation expires later than {}s from now",
+                MAX_CANCEL_REQUEST_TTL_SECS
+            ),
+            data: None,
+        });
+    }
+    let in_queue_request_map = ctx
+        .in_queue_request_map
+        .as_ref()
+        .expect("in_queue_request_map");
+    let not_in_queue = || RpcError::Provided {
+        code: INVALID_REQUEST,
+        message: "request isn't in queue",
+    };
+
+    let state = ctx.mem_pool_state.load_state_db();
+    let sender_script_hash = match in_queue_request_map.get(&hash) {
+        Some(Request::Tx(tx)) => {
+            let from_id: u32 = tx.raw().from_id().unpack();
+            if from_id == 0 {
+                return Err(RpcError::Provided {
+                    code: INVALID_REQUEST,
+                    message: "transaction from id 0 can't be cancelled",
+                });
+            }
+            state.get_script_hash(from_id)?
+        }
+        Some(Request::Withdrawal(withdrawal)) => withdrawal.raw().account_script_hash().unpack(),
+        None => return Err(not_in_queue()),
+    };
+    let signature = signature.into_bytes();
+    if let Err(err) = (ctx.generator).check_cancel_request_signature(
+        &state,
+        &sender_script_hash,
+        &hash,
+        expire_at,
+        &signature,
+    ) {
+        return Err(RpcError::Full {
+            code: UNAUTHORIZED_ERR_CODE,
+            message: format!("invalid cancellation signature: {}", err),
+            data: None,
+        });
+    }
+
+    match in_queue_request_map.cancel(&hash, expire_at) {
+        Ok(()) => log::info!("[RPC] cancel request {}", hash.pack()),
+        Err(CancelError::NotFound) => return Err(not_in_queue()),
+        Err(CancelError::Used) => {
+            return Err(RpcError::Provided {
+                code: INVALID_REQUEST,
+                message: "cancellation is already used",
+            })
+        }
+        Err(CancelError::Pushing) => {
+            return Err(RpcError::Provided {
+                code: INVALID_REQUEST,
+                message: "request is being pushed to mem pool",
+            })
+        }
+    }
+    // The submitter drops the cancelled request once fetched if the channel
+    // is full.
+    if let Err(err) = ctx.cancel_tx.try_send(hash) {
+        log::warn!("[RPC] notify cancelled request {}: {}", hash.pack(), err);
+    }
+
+    Ok(())
+}
+
 async fn get_block_committed_info(
     Params((block_hash,)): Params<(JsonH256,)>,
     rpc_client: Data<RPCClient>,
     store: Data<Store>,
 ) -> Result<Option<L2BlockCommittedInfo>> {
-    if let Some(number) = store.get_block_number(&to_h256(block_hash))? {
+    query_block_committed_info(&rpc_client, &store, to_h256(block_hash)).await
+}
+
+async fn query_block_committed_info(
+    rpc_client: &RPCClient,
+    store: &Store,
+    block_hash: H256,
+) -> Result<Option<L2BlockCommittedInfo>> {
+    if let Some(number) = store.get_block_number(&block_hash)? {
         if let Some(transaction_hash) = store.get_block_submit_tx_hash(number) {
             let opt_block_hash = rpc_client
                 .ckb
@@ -947,6 +1289,250 @@ async fn get_transaction_receipt(
         .map(Into::into))
 }
 
+/// Max number of blocks queried by one `gw_get_logs` request.
+const MAX_GET_LOGS_BLOCK_RANGE: u64 = 10_000;
+/// Max number of logs returned by one `gw_get_logs` request.
+const MAX_GET_LOGS_RESULTS: usize = 10_000;
+
+async fn get_logs(
+    Params((filter,)): Params<(LogFilter,)>,
+    store: Data<Store>,
+) -> Result<Vec<IndexedLogItem>, RpcError> {
+    let db = store.get_snapshot();
+    let tip_number: u64 = db.get_last_valid_tip_block()?.raw().number().unpack();
+    let from_block = filter.from_block.map_or(tip_number, |n| n.value());
+    let to_block = filter.to_block.map_or(tip_number, |n| n.value());
+    if from_block > to_block {
+        return Err(invalid_param_err("from_block is greater than to_block"));
+    }
+    if to_block - from_block >= MAX_GET_LOGS_BLOCK_RANGE {
+        return Err(invalid_param_err("block range is too large"));
+    }
+    let to_block = to_block.min(tip_number);
+    let account_id = filter.account_id.map(|id| id.value());
+    let service_flag = filter.service_flag.map(|flag| flag.value());
+    let topics: Vec<Option<Vec<H256>>> = filter
+        .topics
+        .into_iter()
+        .map(|t| t.map(|t| t.into_iter().map(to_h256).collect()))
+        .collect();
+
+    let mut logs = Vec::new();
+    let mut block_hash = (u64::MAX, H256::zero());
+    for (key, log) in db.iter_logs(from_block) {
+        if key.block_number > to_block {
+            break;
+        }
+        let log_account_id: u32 = log.account_id().unpack();
+        let log_service_flag: u8 = log.service_flag().into();
+        if account_id.map_or(false, |id| id != log_account_id)
+            || service_flag.map_or(false, |flag| flag != log_service_flag as u32)
+            || !match_log_topics(&log, &topics)
+        {
+            continue;
+        }
+        if logs.len() >= MAX_GET_LOGS_RESULTS {
+            return Err(invalid_param_err("too many logs, please narrow the filter"));
+        }
+
+        if block_hash.0 != key.block_number {
+            let hash = db
+                .get_block_hash_by_number(key.block_number)?
+                .ok_or_else(|| anyhow!("block hash of #{} not found", key.block_number))?;
+            block_hash = (key.block_number, hash);
+        }
+        let tx_key = TransactionKey::build_transaction_key(block_hash.1.pack(), key.tx_index);
+        let tx = db.get_transaction_by_key(&tx_key)?.ok_or_else(|| {
+            anyhow!(
+                "tx #{} of block #{} not found",
+                key.tx_index,
+                key.block_number
+            )
+        })?;
+        logs.push(IndexedLogItem {
+            log: log.into(),
+            block_number: key.block_number.into(),
+            block_hash: to_jsonh256(block_hash.1),
+            tx_hash: to_jsonh256(tx.hash().into()),
+            tx_index: key.tx_index.into(),
+            log_index: key.log_index.into(),
+        });
+    }
+    Ok(logs)
+}
+
+/// Topics only apply to polyjuice user logs, other logs never match a non-empty
+/// topic filter.
+fn match_log_topics(log: &packed::LogItem, topics: &[Option<Vec<H256>>]) -> bool {
+    if topics.is_empty() {
+        return true;
+    }
+    let log_topics = match parse_log(log) {
+        Ok(GwLog::PolyjuiceUser { topics, .. }) => topics,
+        _ => return false,
+    };
+    topics.len() <= log_topics.len()
+        && topics
+            .iter()
+            .zip(log_topics.iter())
+            .all(|(expected, topic)| expected.as_ref().map_or(true, |e| e.contains(topic)))
+}
+
+const DEFAULT_ACCOUNT_TXS_LIMIT: u32 = 100;
+const MAX_ACCOUNT_TXS_LIMIT: u32 = 1000;
+
+// An account id, or a registry address. Account ids are tried first, a
+// registry address is always longer than an `Uint32`.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum AccountIDOrAddress {
+    AccountID(AccountID),
+    Address(RegistryAddressJsonBytes),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum GetAccountTxsParams {
+    Default((AccountIDOrAddress,)),
+    WithQuery((AccountIDOrAddress, AccountTxQuery)),
+}
+
+async fn get_transactions_by_account(
+    Params(param): Params<GetAccountTxsParams>,
+    store: Data<Store>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<AccountTxPage, RpcError> {
+    if !store.account_tx_index_enabled() {
+        return Err(account_tx_index_is_disabled_err());
+    }
+    let (account, query) = match param {
+        GetAccountTxsParams::Default((account,)) => (account, AccountTxQuery::default()),
+        GetAccountTxsParams::WithQuery((account, query)) => (account, query),
+    };
+    let account_id = match account {
+        AccountIDOrAddress::AccountID(account_id) => account_id.value(),
+        AccountIDOrAddress::Address(serialized_address) => {
+            let addr = gw_common::registry_address::RegistryAddress::from_slice(
+                serialized_address.as_bytes(),
+            )
+            .ok_or_else(|| invalid_param_err("Invalid registry address"))?;
+            let state = mem_pool_state.load_state_db();
+            let account_id = match state.get_script_hash_by_registry_address(&addr)? {
+                Some(script_hash) => state.get_account_id_by_script_hash(&script_hash)?,
+                None => None,
+            };
+            match account_id {
+                Some(account_id) => account_id,
+                // Unknown accounts have no transactions
+                None => {
+                    return Ok(AccountTxPage {
+                        transactions: Vec::new(),
+                        cursor: None,
+                    })
+                }
+            }
+        }
+    };
+    let limit = query.limit.map_or(DEFAULT_ACCOUNT_TXS_LIMIT, |l| l.value());
+    if limit == 0 || limit > MAX_ACCOUNT_TXS_LIMIT {
+        return Err(invalid_param_err("limit should be in range 1..=1000"));
+    }
+    // The cursor is (block number, tx index) of the last returned transaction.
+    let cursor = match query.cursor {
+        Some(cursor) => {
+            let cursor = cursor.as_bytes();
+            if cursor.len() != 12 {
+                return Err(invalid_param_err("invalid cursor"));
+            }
+            let block_number = u64::from_be_bytes(cursor[..8].try_into().unwrap());
+            let tx_index = u32::from_be_bytes(cursor[8..].try_into().unwrap());
+            Some((block_number, tx_index))
+        }
+        None => None,
+    };
+    let (direction, (block_number, tx_index)) = match query.order {
+        Order::Asc => (Direction::Forward, cursor.unwrap_or((0, 0))),
+        Order::Desc => (Direction::Reverse, cursor.unwrap_or((u64::MAX, u32::MAX))),
+    };
+    let start = AccountTxKey {
+        account_id,
+        block_number,
+        tx_index,
+    };
+
+    let db = store.get_snapshot();
+    let iter_role = |role| {
+        db.iter_account_txs(role, start, direction)
+            .filter(move |(key, _)| Some((key.block_number, key.tx_index)) != cursor)
+    };
+    let txs: Box<dyn Iterator<Item = (AccountTxKey, H256)> + '_> = match query.role {
+        AccountTxRole::From => Box::new(iter_role(StoreAccountTxRole::From)),
+        AccountTxRole::To => Box::new(iter_role(StoreAccountTxRole::To)),
+        AccountTxRole::Any => Box::new(merge_account_txs(
+            iter_role(StoreAccountTxRole::From),
+            iter_role(StoreAccountTxRole::To),
+            query.order,
+        )),
+    };
+
+    // Take one more to know whether there is a next page.
+    let mut transactions: Vec<AccountTx> = txs
+        .take(limit as usize + 1)
+        .map(|(key, tx_hash)| AccountTx {
+            tx_hash: to_jsonh256(tx_hash),
+            block_number: key.block_number.into(),
+            tx_index: key.tx_index.into(),
+        })
+        .collect();
+    let cursor = if transactions.len() > limit as usize {
+        transactions.truncate(limit as usize);
+        transactions.last().map(|tx| {
+            let mut cursor = tx.block_number.value().to_be_bytes().to_vec();
+            cursor.extend_from_slice(&tx.tx_index.value().to_be_bytes());
+            JsonBytes::from_vec(cursor)
+        })
+    } else {
+        None
+    };
+    Ok(AccountTxPage {
+        transactions,
+        cursor,
+    })
+}
+
+/// Merge sorted transactions sent from and to an account. Transactions sent
+/// from the account to itself are returned once.
+fn merge_account_txs(
+    from: impl Iterator<Item = (AccountTxKey, H256)>,
+    to: impl Iterator<Item = (AccountTxKey, H256)>,
+    order: Order,
+) -> impl Iterator<Item = (AccountTxKey, H256)> {
+    let mut from = from.peekable();
+    let mut to = to.peekable();
+    let position = |(key, _): &(AccountTxKey, H256)| (key.block_number, key.tx_index);
+    std::iter::from_fn(
+        move || match (from.peek().map(position), to.peek().map(position)) {
+            (Some(a), Some(b)) if a == b => {
+                to.next();
+                from.next()
+            }
+            (Some(a), Some(b)) => {
+                let from_first = match order {
+                    Order::Asc => a < b,
+                    Order::Desc => a > b,
+                };
+                if from_first {
+                    from.next()
+                } else {
+                    to.next()
+                }
+            }
+            (Some(_), None) => from.next(),
+            (None, _) => to.next(),
+        },
+    )
+}
+
 fn verify_sender_balance<S: State + CodeStore>(
     ctx: &RollupContext,
     state: &S,
@@ -1076,13 +1662,21 @@ async fn execute_l2transaction(
     Ok(run_result.into())
 }
 
-// raw_l2tx, block_number
+// raw_l2tx, block_number, registry_address, state_override
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 enum ExecuteRawL2TransactionParams {
     Tip((JsonBytes,)),
     Number((JsonBytes, Option<GwUint64>)),
     PolyjuiceFromIdZero((JsonBytes, Option<GwUint64>, RegistryAddressJsonBytes)),
+    StateOverride(
+        (
+            JsonBytes,
+            Option<GwUint64>,
+            Option<RegistryAddressJsonBytes>,
+            StateOverride,
+        ),
+    ),
 }
 
 #[instrument(skip_all)]
@@ -1091,10 +1685,11 @@ async fn execute_raw_l2transaction(
     mem_pool_config: Data<MemPoolConfig>,
     ctx: Data<ExecutionTransactionContext>,
 ) -> Result<RunResult, RpcError> {
-    let (raw_l2tx, block_number_opt, registry_address_opt) = match params {
-        ExecuteRawL2TransactionParams::Tip(p) => (p.0, None, None),
-        ExecuteRawL2TransactionParams::Number(p) => (p.0, p.1, None),
-        ExecuteRawL2TransactionParams::PolyjuiceFromIdZero(p) => (p.0, p.1, Some(p.2)),
+    let (raw_l2tx, block_number_opt, registry_address_opt, state_override_opt) = match params {
+        ExecuteRawL2TransactionParams::Tip(p) => (p.0, None, None, None),
+        ExecuteRawL2TransactionParams::Number(p) => (p.0, p.1, None, None),
+        ExecuteRawL2TransactionParams::PolyjuiceFromIdZero(p) => (p.0, p.1, Some(p.2), None),
+        ExecuteRawL2TransactionParams::StateOverride(p) => (p.0, p.1, p.2, Some(p.3)),
     };
     let block_number_opt = block_number_opt.map(|n| n.value());
     let registry_address_opt = registry_address_opt
@@ -1107,11 +1702,41 @@ async fn execute_raw_l2transaction(
     let raw_l2tx_bytes = raw_l2tx.into_bytes();
     let raw_l2tx = packed::RawL2Transaction::from_slice(&raw_l2tx_bytes)?;
 
-    let db_txn = ctx.store.begin_transaction();
+    let run_result = dry_run_raw_l2transaction(
+        &ctx,
+        raw_l2tx,
+        block_number_opt,
+        registry_address_opt,
+        state_override_opt,
+        mem_pool_config.execute_l2tx_max_cycles,
+    )
+    .await?;
+
+    Ok(run_result.into())
+}
+
+fn apply_state_override_opt(
+    state: &mut (impl State + CodeStore),
+    state_override_opt: Option<&StateOverride>,
+) -> Result<(), RpcError> {
+    if let Some(state_override) = state_override_opt {
+        apply_state_override(state, state_override).map_err(|err| RpcError::Full {
+            code: INVALID_PARAM_ERR_CODE,
+            message: format!("state override err: {}", err),
+            data: None,
+        })?;
+    }
+    Ok(())
+}
 
+/// Block info of the historical block, or the mem block if block number is none.
+fn get_execution_block_info(
+    ctx: &ExecutionTransactionContext,
+    db: &StoreTransaction,
+    block_number_opt: Option<u64>,
+) -> Result<BlockInfo, RpcError> {
     let block_info = match block_number_opt {
         Some(block_number) => {
-            let db = &db_txn;
             let block_hash = match db.get_block_hash_by_number(block_number)? {
                 Some(block_hash) => block_hash,
                 None => return Err(header_not_found_err()),
@@ -1135,8 +1760,21 @@ async fn execute_raw_l2transaction(
             .get_mem_pool_block_info()
             .expect("get mem pool block info"),
     };
+    Ok(block_info)
+}
+
+/// Execute raw tx without committing the state, a failed tx is an error.
+async fn dry_run_raw_l2transaction(
+    ctx: &ExecutionTransactionContext,
+    raw_l2tx: packed::RawL2Transaction,
+    block_number_opt: Option<u64>,
+    registry_address_opt: Option<gw_common::registry_address::RegistryAddress>,
+    state_override_opt: Option<StateOverride>,
+    execute_l2tx_max_cycles: u64,
+) -> Result<gw_types::offchain::RunResult, RpcError> {
+    let db_txn = ctx.store.begin_transaction();
+    let block_info = get_execution_block_info(ctx, &db_txn, block_number_opt)?;
 
-    let execute_l2tx_max_cycles = mem_pool_config.execute_l2tx_max_cycles;
     let tx_hash: H256 = raw_l2tx.hash().into();
     let block_number: u64 = block_info.number().unpack();
     let mut cycles_pool = CyclesPool::new(
@@ -1151,11 +1789,14 @@ async fn execute_raw_l2transaction(
         let check_balance_result = match block_number_opt {
             Some(block_number) => {
                 let db = &db_txn;
-                let state = BlockStateDB::from_store(db, RWConfig::history_block(block_number))?;
+                let mut state = BlockStateDB::from_store(db, RWConfig::history_block(block_number))
+                    .map_err(history_state_err)?;
+                apply_state_override_opt(&mut state, state_override_opt.as_ref())?;
                 verify_sender_balance(ctx.generator.rollup_context(), &state, &raw_l2tx)
             }
             None => {
-                let state = ctx.mem_pool_state.load_state_db();
+                let mut state = ctx.mem_pool_state.load_state_db();
+                apply_state_override_opt(&mut state, state_override_opt.as_ref())?;
                 verify_sender_balance(ctx.generator.rollup_context(), &state, &raw_l2tx)
             }
         };
@@ -1169,9 +1810,12 @@ async fn execute_raw_l2transaction(
     }
 
     // execute tx in task
+    let generator = ctx.generator.clone();
+    let polyjuice_sender_recover = ctx.polyjuice_sender_recover.clone();
+    let mem_pool_state = ctx.mem_pool_state.clone();
     let mut run_result = tokio::task::spawn_blocking(move || {
-        let eth_recover = &ctx.polyjuice_sender_recover.eth;
-        let rollup_context = ctx.generator.rollup_context();
+        let eth_recover = &polyjuice_sender_recover.eth;
+        let rollup_context = generator.rollup_context();
         let db = &db_txn;
         let chain_view = {
             let tip_block_hash = db.get_last_valid_tip_block_hash()?;
@@ -1182,6 +1826,9 @@ async fn execute_raw_l2transaction(
             Some(block_number) => {
                 let mut state =
                     BlockStateDB::from_store(db, RWConfig::history_block(block_number))?;
+                if let Some(state_override) = state_override_opt.as_ref() {
+                    apply_state_override(&mut state, state_override).context("state override")?;
+                }
                 let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
                     raw_l2tx,
                     registry_address_opt,
@@ -1192,7 +1839,7 @@ async fn execute_raw_l2transaction(
                         .map_err(|err| anyhow!("check balance err {}", err))?;
                 }
 
-                ctx.generator.unchecked_execute_transaction(
+                generator.unchecked_execute_transaction(
                     &chain_view,
                     &mut state,
                     &block_info,
@@ -1202,7 +1849,10 @@ async fn execute_raw_l2transaction(
                 )?
             }
             None => {
-                let mut state = ctx.mem_pool_state.load_state_db();
+                let mut state = mem_pool_state.load_state_db();
+                if let Some(state_override) = state_override_opt.as_ref() {
+                    apply_state_override(&mut state, state_override).context("state override")?;
+                }
                 let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
                     raw_l2tx,
                     registry_address_opt,
@@ -1213,7 +1863,7 @@ async fn execute_raw_l2transaction(
                         .map_err(|err| anyhow!("check balance err {}", err))?;
                 }
 
-                ctx.generator.unchecked_execute_transaction(
+                generator.unchecked_execute_transaction(
                     &chain_view,
                     &mut state,
                     &block_info,
@@ -1225,7 +1875,8 @@ async fn execute_raw_l2transaction(
         };
         Result::<_, anyhow::Error>::Ok(run_result)
     })
-    .await??;
+    .await?
+    .map_err(history_state_err)?;
 
     if run_result.exit_code != 0 {
         let receipt = gw_types::offchain::ErrorTxReceipt {
@@ -1243,7 +1894,349 @@ async fn execute_raw_l2transaction(
         });
     }
 
-    Ok(run_result.into())
+    Ok(run_result)
+}
+
+const MAX_BUNDLE_SIZE: usize = 64;
+
+// raw_l2txs, block_number, continue_on_failure, state_override
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum ExecuteRawL2TransactionBundleParams {
+    Tip((Vec<JsonBytes>,)),
+    Number((Vec<JsonBytes>, Option<GwUint64>)),
+    ContinueOnFailure((Vec<JsonBytes>, Option<GwUint64>, bool)),
+    StateOverride((Vec<JsonBytes>, Option<GwUint64>, bool, StateOverride)),
+}
+
+#[instrument(skip_all)]
+async fn execute_raw_l2transaction_bundle(
+    Params(params): Params<ExecuteRawL2TransactionBundleParams>,
+    ctx: Data<ExecutionTransactionContext>,
+) -> Result<Vec<BundleStepResult>, RpcError> {
+    let (raw_l2txs, block_number_opt, continue_on_failure, state_override_opt) = match params {
+        ExecuteRawL2TransactionBundleParams::Tip(p) => (p.0, None, false, None),
+        ExecuteRawL2TransactionBundleParams::Number(p) => (p.0, p.1, false, None),
+        ExecuteRawL2TransactionBundleParams::ContinueOnFailure(p) => (p.0, p.1, p.2, None),
+        ExecuteRawL2TransactionBundleParams::StateOverride(p) => (p.0, p.1, p.2, Some(p.3)),
+    };
+    if raw_l2txs.is_empty() || raw_l2txs.len() > MAX_BUNDLE_SIZE {
+        return Err(invalid_param_err("bundle size must be between 1 and 64"));
+    }
+    let raw_l2txs = raw_l2txs
+        .into_iter()
+        .map(|raw_l2tx| packed::RawL2Transaction::from_slice(raw_l2tx.as_bytes()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let block_number_opt = block_number_opt.map(|n| n.value());
+
+    let db_txn = ctx.store.begin_transaction();
+    let block_info = get_execution_block_info(&ctx, &db_txn, block_number_opt)?;
+    // Steps share the cycles pool like txs in a mem block
+    let mut cycles_pool = CyclesPool::new(
+        ctx.mem_pool_config.mem_block.max_cycles_limit,
+        ctx.mem_pool_config.mem_block.syscall_cycles.clone(),
+    );
+    let max_cycles = ctx.mem_pool_config.execute_l2tx_max_cycles;
+    let generator = ctx.generator.clone();
+    let mem_pool_state = ctx.mem_pool_state.clone();
+    let steps = tokio::task::spawn_blocking(move || {
+        let db = &db_txn;
+        let chain_view = {
+            let tip_block_hash = db.get_last_valid_tip_block_hash()?;
+            ChainView::new(&db, tip_block_hash)
+        };
+        let bundle = Bundle {
+            generator: &generator,
+            chain_view: &chain_view,
+            block_info: &block_info,
+            max_cycles,
+            continue_on_failure,
+        };
+        match block_number_opt {
+            Some(block_number) => {
+                let mut state =
+                    BlockStateDB::from_store(db, RWConfig::history_block(block_number))?;
+                bundle.run(
+                    &mut state,
+                    raw_l2txs,
+                    state_override_opt.as_ref(),
+                    &mut cycles_pool,
+                )
+            }
+            None => {
+                let mut state = mem_pool_state.load_state_db();
+                bundle.run(
+                    &mut state,
+                    raw_l2txs,
+                    state_override_opt.as_ref(),
+                    &mut cycles_pool,
+                )
+            }
+        }
+    })
+    .await?
+    .map_err(history_state_err)?;
+
+    Ok(steps)
+}
+
+struct Bundle<'a, C> {
+    generator: &'a Generator,
+    chain_view: &'a C,
+    block_info: &'a BlockInfo,
+    max_cycles: u64,
+    continue_on_failure: bool,
+}
+
+impl<'a, C: gw_traits::ChainView> Bundle<'a, C> {
+    /// Execute txs in order on the same state, the state is never committed.
+    fn run<S: State + CodeStore + JournalDB>(
+        &self,
+        state: &mut S,
+        raw_l2txs: Vec<RawL2Transaction>,
+        state_override_opt: Option<&StateOverride>,
+        cycles_pool: &mut CyclesPool,
+    ) -> Result<Vec<BundleStepResult>> {
+        if let Some(state_override) = state_override_opt {
+            apply_state_override(state, state_override).context("state override")?;
+        }
+
+        let mut steps = Vec::with_capacity(raw_l2txs.len());
+        for raw_l2tx in raw_l2txs {
+            // Logs of previous steps are kept in the uncommitted state
+            let logs_len = state.appended_logs().len();
+            // Generator doesn't revert on errors found after the run, e.g.
+            // ExceededMaxWriteData, so revert a failed step here
+            let snap = state.snapshot();
+            let result =
+                match verify_sender_balance(self.generator.rollup_context(), state, &raw_l2tx) {
+                    Ok(()) => self
+                        .generator
+                        .unchecked_execute_transaction(
+                            self.chain_view,
+                            state,
+                            self.block_info,
+                            &raw_l2tx,
+                            self.max_cycles,
+                            Some(&mut *cycles_pool),
+                        )
+                        .map_err(anyhow::Error::from),
+                    Err(err) => Err(anyhow!("check balance err: {}", err)),
+                };
+
+            let tx_hash = to_jsonh256(raw_l2tx.hash().into());
+            let step = match result {
+                Ok(mut run_result) => {
+                    run_result.logs.drain(..logs_len);
+                    BundleStepResult::from_run_result(tx_hash, run_result)
+                }
+                Err(err) => {
+                    state.revert(snap)?;
+                    BundleStepResult::from_error(tx_hash, err.to_string())
+                }
+            };
+            let failed = !step.is_success();
+            steps.push(step);
+            if failed && !self.continue_on_failure {
+                break;
+            }
+        }
+
+        Ok(steps)
+    }
+}
+
+// tx_hash
+#[instrument(skip_all)]
+async fn debug_trace_transaction(
+    Params((tx_hash,)): Params<(JsonH256,)>,
+    ctx: Data<ExecutionTransactionContext>,
+) -> Result<Option<TransactionTrace>, RpcError> {
+    let tx_hash = to_h256(tx_hash);
+    let db_txn = ctx.store.begin_transaction();
+
+    let block_number_opt: Option<u64> = db_txn
+        .get_transaction_info(&tx_hash)?
+        .map(|info| info.block_number().unpack());
+    // Unlimited cycles pool, only to meter syscall virtual cycles
+    let mut cycles_pool = CyclesPool::new(
+        u64::MAX,
+        ctx.mem_pool_config.mem_block.syscall_cycles.clone(),
+    );
+    // Mem pool tx is replayed on the base state of the mem block
+    let mem_block_replay = match (block_number_opt, ctx.mem_pool.as_ref()) {
+        (None, Some(mem_pool)) => {
+            let mem_pool = mem_pool.lock().await;
+            let mem_block = mem_pool.mem_block();
+            if mem_block.txs_set().contains(&tx_hash) {
+                Some(BlockReplay::from_mem_block(&db_txn, mem_block)?)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    let generator = ctx.generator.clone();
+    let traced = tokio::task::spawn_blocking(move || {
+        let db = &db_txn;
+        let replay = match (block_number_opt, mem_block_replay) {
+            (Some(block_number), _) => BlockReplay::from_block(db, block_number)?,
+            (None, Some(replay)) => replay,
+            (None, None) => {
+                if db.get_mem_pool_transaction(&tx_hash)?.is_some() {
+                    return Err(anyhow!(
+                        "mem pool transaction can only be traced by a full node"
+                    ));
+                }
+                return Ok(None);
+            }
+        };
+        let traced = replay.trace(&generator, db, &tx_hash, &mut cycles_pool)?;
+        Result::<_, anyhow::Error>::Ok(Some(traced))
+    })
+    .await?
+    .map_err(history_state_err)?;
+
+    Ok(traced.map(|(run_result, trace)| {
+        TransactionTrace::new(to_jsonh256(tx_hash), block_number_opt, run_result, trace)
+    }))
+}
+
+/// Withdrawals, deposits and txs of a block or the mem block, to be replayed
+/// on the parent block state.
+struct BlockReplay {
+    parent_block_hash: H256,
+    block_info: BlockInfo,
+    block_producer: gw_common::registry_address::RegistryAddress,
+    withdrawals: Vec<packed::WithdrawalRequest>,
+    deposits: Vec<packed::DepositRequest>,
+    txs: Vec<L2Transaction>,
+}
+
+impl BlockReplay {
+    fn from_block(db: &StoreTransaction, block_number: u64) -> Result<Self> {
+        let block = db
+            .get_block_hash_by_number(block_number)?
+            .map(|block_hash| db.get_block(&block_hash))
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| anyhow!("block #{} not found", block_number))?;
+        let raw_block = block.raw();
+        let block_info = BlockInfo::new_builder()
+            .block_producer(raw_block.block_producer())
+            .timestamp(raw_block.timestamp())
+            .number(raw_block.number())
+            .build();
+        let block_producer = gw_common::registry_address::RegistryAddress::from_slice(
+            &raw_block.block_producer().raw_data(),
+        )
+        .ok_or_else(|| anyhow!("invalid block producer address"))?;
+        let deposits = db
+            .get_block_deposit_info_vec(block_number)
+            .map(|deposit_info_vec| deposit_info_vec.into_iter().map(|i| i.request()).collect())
+            .unwrap_or_default();
+
+        Ok(BlockReplay {
+            parent_block_hash: raw_block.parent_block_hash().unpack(),
+            block_info,
+            block_producer,
+            withdrawals: block.withdrawals().into_iter().collect(),
+            deposits,
+            txs: block.transactions().into_iter().collect(),
+        })
+    }
+
+    fn from_mem_block(
+        db: &StoreTransaction,
+        mem_block: &gw_mem_pool::mem_block::MemBlock,
+    ) -> Result<Self> {
+        let block_info = mem_block.block_info().clone();
+        let block_number: u64 = block_info.number().unpack();
+        let parent_block_number = block_number.saturating_sub(1);
+        let parent_block_hash = db
+            .get_block_hash_by_number(parent_block_number)?
+            .ok_or_else(|| anyhow!("block #{} not found", parent_block_number))?;
+        let withdrawals = mem_block
+            .withdrawals()
+            .iter()
+            .map(|hash| {
+                let withdrawal = db
+                    .get_mem_pool_withdrawal(hash)?
+                    .ok_or_else(|| anyhow!("mem pool withdrawal {} not found", hash.pack()))?;
+                Ok(withdrawal.request())
+            })
+            .collect::<Result<_>>()?;
+        let txs = mem_block
+            .txs()
+            .iter()
+            .map(|hash| {
+                db.get_mem_pool_transaction(hash)?
+                    .ok_or_else(|| anyhow!("mem pool tx {} not found", hash.pack()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(BlockReplay {
+            parent_block_hash,
+            block_info,
+            block_producer: mem_block.block_producer().clone(),
+            withdrawals,
+            deposits: mem_block.deposits().iter().map(|i| i.request()).collect(),
+            txs,
+        })
+    }
+
+    /// Replay withdrawals, deposits and previous txs on the parent block
+    /// state, then trace the tx.
+    fn trace(
+        &self,
+        generator: &Generator,
+        db: &StoreTransaction,
+        tx_hash: &H256,
+        cycles_pool: &mut CyclesPool,
+    ) -> Result<(gw_types::offchain::RunResult, TxTrace)> {
+        let block_number: u64 = self.block_info.number().unpack();
+        let chain_view = ChainView::new(&db, self.parent_block_hash);
+        let mut state =
+            BlockStateDB::from_store(db, RWConfig::history_block(block_number.saturating_sub(1)))?;
+        let rollup_context = generator.rollup_context();
+        for withdrawal in self.withdrawals.iter() {
+            state.apply_withdrawal_request(rollup_context, &self.block_producer, withdrawal)?;
+        }
+        for deposit in self.deposits.iter() {
+            state.apply_deposit_request(rollup_context, deposit)?;
+        }
+        state.finalise()?;
+
+        for tx in self.txs.iter() {
+            let raw_tx = tx.raw();
+            if H256::from(tx.hash()) == *tx_hash {
+                let traced = generator.trace_transaction(
+                    &chain_view,
+                    &mut state,
+                    &self.block_info,
+                    &raw_tx,
+                    L2TX_MAX_CYCLES,
+                    Some(cycles_pool),
+                )?;
+                return Ok(traced);
+            }
+            generator.execute_transaction(
+                &chain_view,
+                &mut state,
+                &self.block_info,
+                &raw_tx,
+                L2TX_MAX_CYCLES,
+                None,
+            )?;
+            state.finalise()?;
+        }
+
+        Err(anyhow!(
+            "tx {} not found in block #{}",
+            tx_hash.pack(),
+            block_number
+        ))
+    }
 }
 
 #[allow(clippy::type_complexity)]
@@ -1290,7 +2283,8 @@ async fn submit_l2transaction(
         rate_limiter.put(sender_id, Instant::now());
     }
 
-    // check sender's nonce
+    // check sender's nonce, a future nonce within the gap is parked in the
+    // fee queue until the missing nonces are submitted
     {
         // fetch mem-pool state
         let state = ctx.mem_pool_state.load_state_db();
@@ -1301,12 +2295,24 @@ async fn submit_l2transaction(
         } else {
             state.get_nonce(sender_id)?
         };
-        if sender_nonce != tx_nonce {
+        // pending create senders are not parked by nonce
+        let max_nonce_gap = if 0 == sender_id { 0 } else { ctx.max_nonce_gap };
+        let err = if tx_nonce < sender_nonce || (0 == max_nonce_gap && tx_nonce != sender_nonce) {
             let err = TransactionError::Nonce {
                 account_id: sender_id,
                 expected: sender_nonce,
                 actual: tx_nonce,
             };
+            Some(err.to_string())
+        } else if tx_nonce > sender_nonce.saturating_add(max_nonce_gap) {
+            Some(format!(
+                "nonce gap too large, nonce of account {} is {}, actual {}, max gap {}",
+                sender_id, sender_nonce, tx_nonce, max_nonce_gap
+            ))
+        } else {
+            None
+        };
+        if let Some(err) = err {
             log::info!(
                 "[RPC] reject to submit tx {:?}, err: {}",
                 faster_hex::hex_string(&tx.hash()),
@@ -1314,7 +2320,7 @@ async fn submit_l2transaction(
             );
             return Err(RpcError::Full {
                 code: INVALID_NONCE_ERR_CODE,
-                message: err.to_string(),
+                message: err,
                 data: None,
             });
         }
@@ -1344,12 +2350,7 @@ async fn submit_l2transaction(
     };
     let request = Request::Tx(tx);
     // Use permit to insert before send so that remove won't happen before insert.
-    if let Some(handle) = ctx
-        .in_queue_request_map
-        .as_ref()
-        .expect("in_queue_request_map")
-        .insert(tx_hash_in_queue, request.clone())
-    {
+    if let Some(handle) = insert_in_queue_request(&ctx, tx_hash_in_queue, request.clone())? {
         // Send if the request wasn't already in the map.
         permit.send((handle, request));
     }
@@ -1365,8 +2366,7 @@ async fn submit_withdrawal_request(
     Params((withdrawal_request,)): Params<(JsonBytes,)>,
     generator: Data<Generator>,
     store: Data<Store>,
-    in_queue_request_map: Data<Option<Arc<InQueueRequestMap>>>,
-    submit_tx: Data<mpsc::Sender<(InQueueRequestHandle, Request)>>,
+    ctx: Data<SubmitTransactionContext>,
 ) -> Result<JsonH256, RpcError> {
     let withdrawal_bytes = withdrawal_request.into_bytes();
     let withdrawal = packed::WithdrawalRequestExtra::from_slice(&withdrawal_bytes)?;
@@ -1401,7 +2401,7 @@ async fn submit_withdrawal_request(
         });
     }
 
-    let permit = submit_tx.try_reserve().map_err(|err| match err {
+    let permit = ctx.submit_tx.try_reserve().map_err(|err| match err {
         mpsc::error::TrySendError::Closed(_) => RpcError::Provided {
             code: INTERNAL_ERROR_ERR_CODE,
             message: "internal error, unavailable",
@@ -1414,11 +2414,7 @@ async fn submit_withdrawal_request(
 
     let request = Request::Withdrawal(withdrawal);
     // Use permit to insert before send so that remove won't happen before insert.
-    if let Some(handle) = in_queue_request_map
-        .as_ref()
-        .expect("in_queue_request_map")
-        .insert(withdrawal_hash.into(), request.clone())
-    {
+    if let Some(handle) = insert_in_queue_request(&ctx, withdrawal_hash.into(), request.clone())? {
         // Send if the request wasn't already in the map.
         permit.send((handle, request));
     }
@@ -1426,6 +2422,68 @@ async fn submit_withdrawal_request(
     Ok(withdrawal_hash.into())
 }
 
+/// Insert request into the in queue request map.
+///
+/// A request of the same sender and nonce as a queued one replaces it only if
+/// it pays enough higher fee rate, otherwise it's rejected.
+fn insert_in_queue_request(
+    ctx: &SubmitTransactionContext,
+    hash: H256,
+    request: Request,
+) -> Result<Option<InQueueRequestHandle>, RpcError> {
+    let in_queue_request_map = ctx
+        .in_queue_request_map
+        .as_ref()
+        .expect("in_queue_request_map");
+    let entry = {
+        let state = ctx.mem_pool_state.load_state_db();
+        let dynamic_config_manager = ctx.dynamic_config_manager.load();
+        let fee_config = dynamic_config_manager.get_fee_config();
+        req_to_entry(
+            fee_config,
+            ctx.generator.clone(),
+            request.clone(),
+            &state,
+            0,
+        )
+    };
+    match entry {
+        Ok(entry) => {
+            let bump_percent = ctx.replace_fee_rate_bump_percent;
+            let hex = |hash: H256| faster_hex::hex_string(hash.as_slice()).expect("hex_string");
+            in_queue_request_map
+                .insert_replaceable(hash, request, entry, bump_percent)
+                .map_err(|err| match err {
+                    ReplaceError::Underpriced { queued_hash } => {
+                        let queued_hash = hex(queued_hash);
+                        log::info!(
+                            "[RPC] reject to replace 0x{}, replacement underpriced",
+                            queued_hash
+                        );
+                        RpcError::Full {
+                            code: REPLACEMENT_UNDERPRICED_ERR_CODE,
+                            message: format!(
+                                "replacement underpriced, requires {}% higher fee rate than 0x{}",
+                                bump_percent, queued_hash,
+                            ),
+                            data: None,
+                        }
+                    }
+                    ReplaceError::Pushing { queued_hash } => RpcError::Full {
+                        code: INVALID_REQUEST,
+                        message: format!(
+                            "0x{} of the same nonce is being pushed to mem pool",
+                            hex(queued_hash)
+                        ),
+                        data: None,
+                    },
+                })
+        }
+        // The background submitter logs and drops invalid requests.
+        Err(_) => Ok(in_queue_request_map.insert(hash, request)),
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 enum GetWithdrawalParams {
@@ -1524,6 +2582,399 @@ async fn get_withdrawal(
     Ok(None)
 }
 
+// Number of recent blocks used to estimate the average block interval
+const BLOCK_INTERVAL_SAMPLE_BLOCKS: u64 = 100;
+
+async fn get_withdrawal_lifecycle(
+    Params((withdrawal_hash,)): Params<(JsonH256,)>,
+    store: Data<Store>,
+    rpc_client: Data<RPCClient>,
+    rollup_config: Data<RollupConfig>,
+    in_queue_request_map: Data<Option<Arc<InQueueRequestMap>>>,
+) -> Result<Option<WithdrawalLifecycle>> {
+    let withdrawal_hash = to_h256(withdrawal_hash);
+
+    let in_queue = in_queue_request_map
+        .as_deref()
+        .map_or(false, |m| m.get_withdrawal(&withdrawal_hash).is_some());
+    let db = store.get_snapshot();
+    if in_queue || db.get_mem_pool_withdrawal(&withdrawal_hash)?.is_some() {
+        return Ok(Some(WithdrawalLifecycle {
+            status: WithdrawalLifecycleStatus::Pending,
+            ..Default::default()
+        }));
+    }
+
+    let withdrawal_info = match db.get_withdrawal_info(&withdrawal_hash)? {
+        Some(info) => info,
+        None => return Ok(None),
+    };
+    let block_number: u64 = withdrawal_info.block_number().unpack();
+    let block_hash: H256 =
+        packed::Byte32::from_slice(&withdrawal_info.key().as_slice()[..32])?.unpack();
+    let withdrawal_index: u32 =
+        packed::Uint32::from_slice(&withdrawal_info.key().as_slice()[32..36])?.unpack();
+    let l2_committed_info = Some(L2WithdrawalCommittedInfo {
+        block_number: block_number.into(),
+        block_hash: to_jsonh256(block_hash),
+        withdrawal_index: withdrawal_index.into(),
+    });
+
+    // same rule as the finalized status of `gw_get_block`
+    let finality_blocks: u64 = rollup_config.finality_blocks().unpack();
+    let finalized_block_number = block_number + finality_blocks;
+    let last_confirmed = db.get_last_confirmed_block_number_hash();
+    let last_confirmed_block_number: u64 = last_confirmed
+        .as_ref()
+        .map(|nh| nh.number().unpack())
+        .unwrap_or(0);
+    let finality = {
+        let tip_block = db.get_tip_block()?;
+        let tip_number: u64 = tip_block.raw().number().unpack();
+        let tip_timestamp: u64 = tip_block.raw().timestamp().unpack();
+        let block_interval = estimate_block_interval(&db, tip_number, tip_timestamp)?;
+        // Both remaining blocks and the estimated timestamp count from the last
+        // confirmed block
+        let last_confirmed_timestamp: u64 = match last_confirmed
+            .map(|nh| db.get_block(&nh.block_hash().unpack()))
+            .transpose()?
+            .flatten()
+        {
+            Some(block) => block.raw().timestamp().unpack(),
+            None => tip_timestamp,
+        };
+        let remaining_blocks = finalized_block_number.saturating_sub(last_confirmed_block_number);
+        WithdrawalFinality {
+            finalized_block_number: finalized_block_number.into(),
+            remaining_blocks: remaining_blocks.into(),
+            estimated_finalized_timestamp: last_confirmed_timestamp
+                .saturating_add(remaining_blocks.saturating_mul(block_interval))
+                .into(),
+        }
+    };
+
+    let l1_committed_info = query_block_committed_info(&rpc_client, &store, block_hash).await?;
+    let mut lifecycle = WithdrawalLifecycle {
+        status: WithdrawalLifecycleStatus::Included,
+        l2_committed_info,
+        finality: Some(finality),
+        ..Default::default()
+    };
+    let submit_tx_hash = match l1_committed_info {
+        Some(ref info) => to_h256(info.transaction_hash.clone()),
+        None => return Ok(Some(lifecycle)),
+    };
+    lifecycle.l1_committed_info = l1_committed_info;
+    lifecycle.status = WithdrawalLifecycleStatus::Committed;
+
+    let submit_tx = match db.get_block_submit_tx(block_number) {
+        Some(tx) => Some(tx),
+        None => rpc_client.ckb.get_transaction(submit_tx_hash).await?,
+    };
+    let withdrawal = db
+        .get_withdrawal_by_key(&withdrawal_info.key())?
+        .context("get withdrawal")?
+        .raw();
+    // Identical withdrawals of the block are put in the outputs in order
+    let nth = db
+        .get_block(&block_hash)?
+        .context("get withdrawal block")?
+        .withdrawals()
+        .into_iter()
+        .take(withdrawal_index as usize)
+        .filter(|w| is_same_withdrawal(&w.raw(), &withdrawal))
+        .count();
+    let withdrawal_cell = submit_tx.and_then(|tx| {
+        find_withdrawal_cell(
+            &rpc_client.rollup_context,
+            &tx,
+            &block_hash,
+            &withdrawal,
+            nth,
+        )
+    });
+    lifecycle.withdrawal_cell = withdrawal_cell
+        .as_ref()
+        .map(|(out_point, _lock)| out_point.clone().into());
+
+    if last_confirmed_block_number < finalized_block_number {
+        return Ok(Some(lifecycle));
+    }
+    lifecycle.status = WithdrawalLifecycleStatus::Finalized;
+
+    if let Some((out_point, lock)) = withdrawal_cell {
+        let consumed = matches!(
+            rpc_client.get_cell(out_point.clone()).await?,
+            Some(CellWithStatus {
+                status: CellStatus::Dead,
+                ..
+            })
+        );
+        if consumed {
+            lifecycle.status = WithdrawalLifecycleStatus::Unlocked;
+            lifecycle.unlock_tx_hash = rpc_client
+                .query_consuming_tx(lock, &out_point)
+                .await?
+                .map(to_jsonh256);
+        }
+    }
+
+    Ok(Some(lifecycle))
+}
+
+// Average interval in milliseconds of the recent blocks
+fn estimate_block_interval(
+    db: &impl ChainStore,
+    tip_number: u64,
+    tip_timestamp: u64,
+) -> Result<u64> {
+    let sample_blocks = tip_number.min(BLOCK_INTERVAL_SAMPLE_BLOCKS);
+    if sample_blocks == 0 {
+        return Ok(0);
+    }
+    let start_block = db
+        .get_block_hash_by_number(tip_number - sample_blocks)?
+        .map(|hash| db.get_block(&hash))
+        .transpose()?
+        .flatten();
+    let start_timestamp: u64 = match start_block {
+        Some(block) => block.raw().timestamp().unpack(),
+        None => return Ok(0),
+    };
+    Ok(tip_timestamp.saturating_sub(start_timestamp) / sample_blocks)
+}
+
+fn is_same_withdrawal(a: &packed::RawWithdrawalRequest, b: &packed::RawWithdrawalRequest) -> bool {
+    a.owner_lock_hash().as_slice() == b.owner_lock_hash().as_slice()
+        && a.capacity().as_slice() == b.capacity().as_slice()
+        && a.amount().as_slice() == b.amount().as_slice()
+        && a.sudt_script_hash().as_slice() == b.sudt_script_hash().as_slice()
+}
+
+// Find the nth withdrawal cell of the block matching the owner lock hash and
+// amounts of the withdrawal.
+fn find_withdrawal_cell(
+    rollup_context: &RollupContext,
+    submit_tx: &packed::Transaction,
+    block_hash: &H256,
+    withdrawal: &packed::RawWithdrawalRequest,
+    nth: usize,
+) -> Option<(packed::OutPoint, packed::Script)> {
+    let tx_hash = submit_tx.hash();
+    let withdrawal_script_type_hash = rollup_context.rollup_config.withdrawal_script_type_hash();
+    let capacity: u64 = withdrawal.capacity().unpack();
+    let amount: u128 = withdrawal.amount().unpack();
+    let outputs_data = submit_tx.raw().outputs_data();
+    submit_tx
+        .raw()
+        .outputs()
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, output)| {
+            let lock = output.lock();
+            if lock.code_hash() != withdrawal_script_type_hash
+                || lock.hash_type() != ScriptHashType::Type.into()
+            {
+                return false;
+            }
+            let args = match gw_utils::withdrawal::parse_lock_args(&lock.args().raw_data()) {
+                Ok(args) => args,
+                Err(_) => return false,
+            };
+            let output_capacity: u64 = output.capacity().unpack();
+            // Withdrawal cell data is the sudt amount
+            let output_amount = outputs_data.get(*idx).and_then(|data| {
+                let data = data.raw_data();
+                data.get(..16).map(|buf| {
+                    let mut amount = [0u8; 16];
+                    amount.copy_from_slice(buf);
+                    u128::from_le_bytes(amount)
+                })
+            });
+            args.rollup_type_hash[..] == *rollup_context.rollup_script_hash.as_slice()
+                && args.lock_args.withdrawal_block_hash().as_slice() == block_hash.as_slice()
+                && args.lock_args.owner_lock_hash() == withdrawal.owner_lock_hash()
+                && output_capacity == capacity
+                && (amount == 0 || output_amount == Some(amount))
+        })
+        .nth(nth)
+        .map(|(idx, output)| {
+            let out_point = packed::OutPoint::new_builder()
+                .tx_hash(tx_hash.pack())
+                .index((idx as u32).pack())
+                .build();
+            (out_point, output.lock())
+        })
+}
+
+// deposit cell out point, or L1 transaction hash of the deposit
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum GetDepositParams {
+    OutPoint((JsonOutPoint,)),
+    TxHash((JsonH256,)),
+}
+
+async fn get_deposit(
+    Params(params): Params<GetDepositParams>,
+    store: Data<Store>,
+    rpc_client: Data<RPCClient>,
+) -> Result<Option<DepositWithStatus>> {
+    let rollup_context = &rpc_client.rollup_context;
+    let out_point: packed::OutPoint = match params {
+        GetDepositParams::OutPoint((out_point,)) => out_point.into(),
+        GetDepositParams::TxHash((tx_hash,)) => {
+            let tx = match rpc_client.ckb.get_transaction(to_h256(tx_hash)).await? {
+                Some(tx) => tx,
+                None => return Ok(None),
+            };
+            match find_deposit_cell(rollup_context, &tx) {
+                Some(out_point) => out_point,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let db = store.get_snapshot();
+    if let Some(block_number) = db.get_deposit_block_number(&out_point) {
+        let block_hash = db
+            .get_block_hash_by_number(block_number)?
+            .context("get deposit block hash")?;
+        let (deposit_index, deposit_info) = db
+            .get_block_deposit_info_vec(block_number)
+            .context("get block deposit info vec")?
+            .into_iter()
+            .enumerate()
+            .find(|(_idx, info)| info.cell().out_point() == out_point)
+            .context("get deposit info")?;
+        return Ok(Some(DepositWithStatus {
+            out_point: out_point.into(),
+            status: DepositStatus::Included,
+            request: Some(deposit_info.request().into()),
+            l2_committed_info: Some(L2DepositCommittedInfo {
+                block_number: block_number.into(),
+                block_hash: to_jsonh256(block_hash),
+                deposit_index: (deposit_index as u32).into(),
+            }),
+            ..Default::default()
+        }));
+    }
+
+    // not included, check the deposit cell on L1
+    match rpc_client.get_cell(out_point.clone()).await? {
+        Some(CellWithStatus {
+            status: CellStatus::Dead,
+            ..
+        }) => {
+            let status = match query_deposit_consumed_status(&rpc_client, &out_point).await? {
+                Some(status) => status,
+                None => return Ok(None),
+            };
+            return Ok(Some(DepositWithStatus {
+                out_point: out_point.into(),
+                status,
+                ..Default::default()
+            }));
+        }
+        Some(CellWithStatus {
+            status: CellStatus::Live,
+            cell: Some(cell),
+        }) if is_deposit_lock(rollup_context, &cell.output.lock()) => {}
+        _ => return Ok(None),
+    }
+
+    let deposit = match db.get_deposit_log(&out_point)? {
+        Some(DepositLog::Collected { tip_block_number }) => DepositWithStatus {
+            status: DepositStatus::Collected,
+            tip_block_number: Some(tip_block_number.into()),
+            ..Default::default()
+        },
+        Some(DepositLog::Rejected {
+            tip_block_number,
+            reason,
+        }) => DepositWithStatus {
+            status: DepositStatus::Rejected,
+            tip_block_number: Some(tip_block_number.into()),
+            reason: Some(reason),
+            ..Default::default()
+        },
+        None => DepositWithStatus {
+            status: DepositStatus::Pending,
+            ..Default::default()
+        },
+    };
+    Ok(Some(DepositWithStatus {
+        out_point: out_point.into(),
+        ..deposit
+    }))
+}
+
+// Status of a consumed deposit cell which isn't included in a local block.
+//
+// Only a tx without the rollup cell refunds the deposit, a block submission
+// consuming it is pending until the block is synced.
+async fn query_deposit_consumed_status(
+    rpc_client: &RPCClient,
+    out_point: &packed::OutPoint,
+) -> Result<Option<DepositStatus>> {
+    let rollup_context = &rpc_client.rollup_context;
+    let tx_hash: H256 = out_point.tx_hash().unpack();
+    let index: u32 = out_point.index().unpack();
+    let lock = match rpc_client.ckb.get_transaction(tx_hash).await? {
+        Some(tx) => match tx.raw().outputs().get(index as usize) {
+            Some(output) if is_deposit_lock(rollup_context, &output.lock()) => output.lock(),
+            _ => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+    let consuming_tx = match rpc_client.query_consuming_tx(lock, out_point).await? {
+        Some(tx_hash) => rpc_client.ckb.get_transaction(tx_hash).await?,
+        None => None,
+    };
+    let consuming_tx = match consuming_tx {
+        Some(tx) => tx,
+        None => return Ok(None),
+    };
+    let submits_block = consuming_tx.raw().outputs().into_iter().any(|output| {
+        output.type_().to_opt().map_or(false, |type_| {
+            type_.hash() == rollup_context.rollup_script_hash.as_slice()
+        })
+    });
+    let status = if submits_block {
+        DepositStatus::Pending
+    } else {
+        DepositStatus::Refunded
+    };
+    Ok(Some(status))
+}
+
+fn is_deposit_lock(rollup_context: &RollupContext, lock: &packed::Script) -> bool {
+    lock.code_hash() == rollup_context.rollup_config.deposit_script_type_hash()
+        && lock.hash_type() == ScriptHashType::Type.into()
+        && lock
+            .args()
+            .raw_data()
+            .starts_with(rollup_context.rollup_script_hash.as_slice())
+}
+
+// Returns the first deposit cell of the L1 transaction
+fn find_deposit_cell(
+    rollup_context: &RollupContext,
+    tx: &packed::Transaction,
+) -> Option<packed::OutPoint> {
+    let tx_hash = tx.hash();
+    tx.raw()
+        .outputs()
+        .into_iter()
+        .position(|output| is_deposit_lock(rollup_context, &output.lock()))
+        .map(|idx| {
+            packed::OutPoint::new_builder()
+                .tx_hash(tx_hash.pack())
+                .index((idx as u32).pack())
+                .build()
+        })
+}
+
 // registry address, sudt_id, block_number
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
@@ -1548,7 +2999,8 @@ async fn get_balance(
     let balance = match block_number {
         Some(block_number) => {
             let db = store.begin_transaction();
-            let tree = BlockStateDB::from_store(&db, RWConfig::history_block(block_number.into()))?;
+            let tree = BlockStateDB::from_store(&db, RWConfig::history_block(block_number.into()))
+                .map_err(history_state_err)?;
             tree.get_sudt_balance(sudt_id.into(), &address)?
         }
         None => {
@@ -1580,7 +3032,8 @@ async fn get_storage_at(
     let value = match block_number {
         Some(block_number) => {
             let db = store.begin_transaction();
-            let tree = BlockStateDB::from_store(&db, RWConfig::history_block(block_number.into()))?;
+            let tree = BlockStateDB::from_store(&db, RWConfig::history_block(block_number.into()))
+                .map_err(history_state_err)?;
             let key: H256 = to_h256(key);
             tree.get_value(account_id.into(), key.as_slice())?
         }
@@ -1595,6 +3048,53 @@ async fn get_storage_at(
     Ok(json_value)
 }
 
+// Limit the number of storage keys to prove in one request
+const MAX_PROOF_STORAGE_KEYS: usize = 256;
+
+// account_id, storage_keys, block_number
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum GetProofParams {
+    Tip((AccountID, Vec<JsonH256>)),
+    Number((AccountID, Vec<JsonH256>, Option<GwUint64>)),
+}
+
+async fn get_proof(
+    Params(params): Params<GetProofParams>,
+    store: Data<Store>,
+) -> Result<Option<AccountProof>, RpcError> {
+    let (account_id, storage_keys, block_number) = match params {
+        GetProofParams::Tip(p) => (p.0, p.1, None),
+        GetProofParams::Number(p) => p,
+    };
+    if storage_keys.len() > MAX_PROOF_STORAGE_KEYS {
+        return Err(invalid_param_err("Too many storage keys"));
+    }
+
+    // Only the account SMT of the tip block is kept in the store
+    let snap = store.get_snapshot();
+    let tip_block = snap.get_last_valid_tip_block()?;
+    if let Some(block_number) = block_number {
+        let tip_number: u64 = tip_block.raw().number().unpack();
+        if block_number.value() != tip_number {
+            return Err(invalid_param_err(
+                "Only the last valid tip block is supported",
+            ));
+        }
+    }
+    let storage_keys = storage_keys.into_iter().map(to_h256).collect();
+    let proof = build_account_proof(snap, &tip_block, account_id.into(), storage_keys)?;
+    Ok(proof)
+}
+
+async fn get_block_proof(
+    Params((block_hash,)): Params<(JsonH256,)>,
+    store: Data<Store>,
+) -> Result<Option<BlockProof>, RpcError> {
+    let proof = build_block_proof(store.get_snapshot(), to_h256(block_hash))?;
+    Ok(proof)
+}
+
 async fn get_account_id_by_script_hash(
     Params((script_hash,)): Params<(JsonH256,)>,
     mem_pool_state: Data<Arc<MemPoolState>>,
@@ -1631,7 +3131,8 @@ async fn get_nonce(
     let nonce = match block_number {
         Some(block_number) => {
             let db = store.begin_transaction();
-            let tree = BlockStateDB::from_store(&db, RWConfig::history_block(block_number.into()))?;
+            let tree = BlockStateDB::from_store(&db, RWConfig::history_block(block_number.into()))
+                .map_err(history_state_err)?;
             tree.get_nonce(account_id.into())?
         }
         None => {
@@ -1920,6 +3421,7 @@ pub fn to_rpc_node_mode(node_mode: &NodeMode) -> RpcNodeMode {
         NodeMode::FullNode => RpcNodeMode::FullNode,
         NodeMode::ReadOnly => RpcNodeMode::ReadOnly,
         NodeMode::Test => RpcNodeMode::Test,
+        NodeMode::Watchtower => RpcNodeMode::Watchtower,
     }
 }
 
@@ -1973,6 +3475,212 @@ async fn get_fee_config(
     Ok(fee_config)
 }
 
+// l2tx, cycles limit
+#[instrument(skip_all)]
+async fn estimate_cycles(
+    Params((l2tx,)): Params<(JsonBytes,)>,
+    ctx: Data<ExecutionTransactionContext>,
+) -> Result<GwUint64, RpcError> {
+    let tx = packed::L2Transaction::from_slice(l2tx.as_bytes())?;
+    let run_result = dry_run_raw_l2transaction(
+        &ctx,
+        tx.raw(),
+        None,
+        None,
+        None,
+        ctx.mem_pool_config.execute_l2tx_max_cycles,
+    )
+    .await?;
+
+    Ok(run_result.cycles.total().into())
+}
+
+// tx or withdrawal, kind
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum EstimateFeeParams {
+    Default((JsonBytes,)),
+    Kind((JsonBytes, EstimateFeeKind)),
+}
+
+#[instrument(skip_all)]
+async fn estimate_fee(
+    Params(params): Params<EstimateFeeParams>,
+    ctx: Data<ExecutionTransactionContext>,
+    dynamic_config_manager: Data<Arc<ArcSwap<DynamicConfigManager>>>,
+    fee_queue_status: Data<FeeQueueStatus>,
+    fee_rate_cache: Data<FeeRateCache>,
+) -> Result<FeeEstimate, RpcError> {
+    let (bytes, kind) = match params {
+        EstimateFeeParams::Default((bytes,)) => (bytes, EstimateFeeKind::default()),
+        EstimateFeeParams::Kind((bytes, kind)) => (bytes, kind),
+    };
+    let fee_config = dynamic_config_manager.load().get_fee_config().clone();
+
+    let (req, cycles) = match kind {
+        EstimateFeeKind::Tx => {
+            let tx = packed::L2Transaction::from_slice(bytes.as_bytes())?;
+            let run_result = dry_run_raw_l2transaction(
+                &ctx,
+                tx.raw(),
+                None,
+                None,
+                None,
+                ctx.mem_pool_config.execute_l2tx_max_cycles,
+            )
+            .await?;
+            (Request::Tx(tx), Some(run_result.cycles.total()))
+        }
+        EstimateFeeKind::Withdrawal => {
+            let withdrawal = WithdrawalRequestExtra::from_slice(bytes.as_bytes())?;
+            (Request::Withdrawal(withdrawal), None)
+        }
+    };
+
+    let state = ctx.mem_pool_state.load_state_db();
+    let entry =
+        req_to_entry(&fee_config, ctx.generator.clone(), req, &state, 0).map_err(|err| {
+            RpcError::Full {
+                code: INVALID_PARAM_ERR_CODE,
+                message: err.to_string(),
+                data: None,
+            }
+        })?;
+
+    let mut fee_rates = recent_fee_rates(&ctx, &fee_rate_cache, &fee_config, &state)?;
+    fee_rates.sort_unstable();
+    let low_fee_rate = percentile(&fee_rates, 25);
+    let medium_fee_rate = percentile(&fee_rates, 50);
+    let high_fee_rate = percentile(&fee_rates, 75);
+
+    // Pick a higher percentile when the queue is busier than a mem block can take
+    let queue_len = fee_queue_status.len.load(Ordering::Relaxed);
+    let queue_min_fee_rate: u128 = fee_queue_status.min_fee_rate.load(Ordering::Relaxed).into();
+    let fee_rate = if queue_len == 0 {
+        low_fee_rate
+    } else if queue_len <= ctx.mem_pool_config.mem_block.max_txs {
+        medium_fee_rate
+    } else {
+        high_fee_rate
+    };
+    let fee_rate = fee_rate.max(queue_min_fee_rate);
+    let fee = fee_rate.saturating_mul(entry.cycles_limit.into());
+
+    Ok(FeeEstimate {
+        cycles: cycles.map(Into::into),
+        cycles_limit: entry.cycles_limit.into(),
+        low_fee_rate: low_fee_rate.into(),
+        medium_fee_rate: medium_fee_rate.into(),
+        high_fee_rate: high_fee_rate.into(),
+        queue_min_fee_rate: queue_min_fee_rate.into(),
+        queue_len: (queue_len.min(u32::MAX as usize) as u32).into(),
+        fee_rate: fee_rate.into(),
+        fee: fee.into(),
+    })
+}
+
+/// Fee rates of recent blocks, a block is only read once after it becomes
+/// one of the recent blocks.
+///
+/// The lock is never held across DB reads.
+#[derive(Default)]
+struct FeeRateCache {
+    inner: std::sync::Mutex<FeeRateCacheInner>,
+}
+
+#[derive(Default)]
+struct FeeRateCacheInner {
+    // fee config of the cached fee rates
+    fee_config: Option<FeeConfig>,
+    // block hash => fee rates of the block
+    blocks: HashMap<H256, Vec<u128>>,
+}
+
+/// Fee rates (fee / cycles limit) of txs and withdrawals in recent blocks.
+///
+/// Cached fee rates are refreshed when the tip changes, reverted blocks and
+/// blocks out of the recent ones are dropped.
+fn recent_fee_rates(
+    ctx: &ExecutionTransactionContext,
+    cache: &FeeRateCache,
+    fee_config: &FeeConfig,
+    state: &(impl State + CodeStore),
+) -> Result<Vec<u128>> {
+    let db = &ctx.store.begin_transaction();
+    let tip_number: u64 = db.get_last_valid_tip_block()?.raw().number().unpack();
+    let recent_blocks = ctx.mem_pool_config.fee_estimate_blocks.max(1);
+    let start = tip_number.saturating_sub(recent_blocks - 1);
+    let mut hashes = Vec::with_capacity(recent_blocks as usize);
+    for number in (start..=tip_number).rev() {
+        if let Some(hash) = db.get_block_hash_by_number(number)? {
+            hashes.push(hash);
+        }
+    }
+
+    let mut blocks: HashMap<H256, Vec<u128>> = {
+        let cache = cache.inner.lock().unwrap();
+        if cache.fee_config.as_ref() == Some(fee_config) {
+            (hashes.iter())
+                .filter_map(|hash| Some((*hash, cache.blocks.get(hash)?.clone())))
+                .collect()
+        } else {
+            HashMap::new()
+        }
+    };
+    let mut fee_rates = Vec::new();
+    for hash in hashes {
+        if !blocks.contains_key(&hash) {
+            match db.get_block(&hash)? {
+                Some(block) => {
+                    let block_fee_rates = block_fee_rates(ctx, fee_config, state, &block);
+                    blocks.insert(hash, block_fee_rates);
+                }
+                None => continue,
+            }
+        }
+        fee_rates.extend_from_slice(&blocks[&hash]);
+    }
+
+    let mut cache = cache.inner.lock().unwrap();
+    cache.fee_config = Some(fee_config.clone());
+    cache.blocks = blocks;
+
+    Ok(fee_rates)
+}
+
+fn block_fee_rates(
+    ctx: &ExecutionTransactionContext,
+    fee_config: &FeeConfig,
+    state: &(impl State + CodeStore),
+    block: &packed::L2Block,
+) -> Vec<u128> {
+    let mut fee_rates = Vec::new();
+    for tx in block.transactions() {
+        // Skip txs whose backend can't be resolved anymore
+        if let Ok(entry) =
+            req_to_entry(fee_config, ctx.generator.clone(), Request::Tx(tx), state, 0)
+        {
+            if entry.cycles_limit > 0 {
+                fee_rates.push(entry.fee / u128::from(entry.cycles_limit));
+            }
+        }
+    }
+    if fee_config.withdraw_cycles_limit > 0 {
+        for withdrawal in block.withdrawals() {
+            let fee: u128 = withdrawal.raw().fee().unpack();
+            fee_rates.push(fee / u128::from(fee_config.withdraw_cycles_limit));
+        }
+    }
+    fee_rates
+}
+
+fn percentile(sorted: &[u128], p: usize) -> u128 {
+    match sorted.len() {
+        0 => 0,
+        len => sorted[(len - 1) * p / 100],
+    }
+}
+
 async fn get_mem_pool_state_root(
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<JsonH256, RpcError> {
@@ -2025,6 +3733,107 @@ async fn report_pprof() -> Result<()> {
     Ok(())
 }
 
+async fn p2p_list_sessions(p2p_admin: Data<P2PAdmin>) -> Result<Vec<P2PSession>> {
+    let sessions = p2p_admin.peer_book().sessions();
+    let sessions = sessions
+        .into_iter()
+        .map(|s| {
+            let opened_at = s
+                .opened_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            P2PSession {
+                session_id: (s.id.value() as u64).into(),
+                address: s.address.to_string(),
+                peer_id: s.peer_id.map(|p| p.to_base58()),
+                direction: if s.outbound {
+                    P2PSessionDirection::Outbound
+                } else {
+                    P2PSessionDirection::Inbound
+                },
+                opened_at: opened_at.into(),
+                protocols: s.protocols,
+                sent_bytes: s.sent_bytes.into(),
+                received_bytes: s.received_bytes.into(),
+            }
+        })
+        .collect();
+    Ok(sessions)
+}
+
+async fn p2p_list_peers(p2p_admin: Data<P2PAdmin>) -> Result<Vec<P2PPeer>> {
+    let now = Instant::now();
+    let peers = p2p_admin.peer_book().peers();
+    let peers = peers
+        .into_iter()
+        .map(|(peer_id, status)| {
+            let ban_remaining_secs = status
+                .banned_until
+                .filter(|until| *until > now)
+                .map(|until| until.duration_since(now).as_secs().into());
+            P2PPeer {
+                peer_id: peer_id.to_base58(),
+                score: status.score,
+                ban_remaining_secs,
+                ban_reason: ban_remaining_secs.and(status.ban_reason),
+            }
+        })
+        .collect();
+    Ok(peers)
+}
+
+async fn p2p_dial(Params((address,)): Params<(String,)>, p2p_admin: Data<P2PAdmin>) -> Result<()> {
+    p2p_admin.dial(&address).await
+}
+
+async fn p2p_disconnect(
+    Params((session_id,)): Params<(GwUint64,)>,
+    p2p_admin: Data<P2PAdmin>,
+) -> Result<()> {
+    let session_id: u64 = session_id.into();
+    p2p_admin.disconnect(session_id as usize).await
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum P2PBanParams {
+    Default((String,)),
+    WithDuration((String, GwUint64)),
+}
+
+async fn p2p_ban(Params(params): Params<P2PBanParams>, p2p_admin: Data<P2PAdmin>) -> Result<()> {
+    let (peer_id, duration) = match params {
+        P2PBanParams::Default((peer_id,)) => (peer_id, None),
+        P2PBanParams::WithDuration((peer_id, duration_secs)) => {
+            (peer_id, Some(Duration::from_secs(duration_secs.into())))
+        }
+    };
+    p2p_admin.ban(&peer_id, duration).await
+}
+
+async fn p2p_unban(Params((peer_id,)): Params<(String,)>, p2p_admin: Data<P2PAdmin>) -> Result<()> {
+    p2p_admin.unban(&peer_id)
+}
+
+async fn p2p_get_allowed_peer_ids(p2p_admin: Data<P2PAdmin>) -> Result<Option<Vec<String>>> {
+    Ok(p2p_admin.allowed_peer_ids())
+}
+
+async fn p2p_add_allowed_peer_id(
+    Params((peer_id,)): Params<(String,)>,
+    p2p_admin: Data<P2PAdmin>,
+) -> Result<()> {
+    p2p_admin.add_allowed_peer_id(&peer_id)
+}
+
+async fn p2p_remove_allowed_peer_id(
+    Params((peer_id,)): Params<(String,)>,
+    p2p_admin: Data<P2PAdmin>,
+) -> Result<()> {
+    p2p_admin.remove_allowed_peer_id(&peer_id).await
+}
+
 async fn get_rocksdb_memory_stats(store: Data<Store>) -> Result<Vec<CfMemStat>, RpcError> {
     Ok(store.gather_mem_stats())
 }
@@ -2062,3 +3871,28 @@ async fn reload_config(
 ) -> Result<DynamicConfigReloadResponse> {
     gw_dynamic_config::reload(dynamic_config_manager.clone()).await
 }
+
+async fn update_dynamic_config(
+    Params((config, token)): Params<(DynamicConfig, String)>,
+    dynamic_config_manager: Data<Arc<ArcSwap<DynamicConfigManager>>>,
+) -> Result<DynamicConfigReloadResponse, RpcError> {
+    let authorized = match dynamic_config_manager.load().admin_token() {
+        Some(admin_token) => constant_time_eq(admin_token.as_bytes(), token.as_bytes()),
+        None => false,
+    };
+    if !authorized {
+        log::warn!("reject unauthorized dynamic config update");
+        return Err(unauthorized_err());
+    }
+    gw_dynamic_config::update(dynamic_config_manager.clone(), config).map_err(|err| {
+        RpcError::Full {
+            code: INVALID_PARAM_ERR_CODE,
+            message: err.to_string(),
+            data: None,
+        }
+    })
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
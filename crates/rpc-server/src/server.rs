@@ -1,28 +1,54 @@
 // Taken and adapted from https://github.com/smol-rs/smol/blob/ad0839e1b3700dd33abb9bf23c1efd3c83b5bb2d/examples/hyper-server.rs
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Error, Result};
+use gw_store::Store;
 use gw_utils::liveness::Liveness;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{body::HttpBody, server::conn::AddrIncoming, Body, Method, Request, Response, Server};
 use tokio::net::TcpListener;
 
-use jsonrpc_v2::{RequestKind, ResponseObjects, Router, Server as JsonrpcServer};
+use jsonrpc_v2::{
+    Error as RpcError, RequestKind, ResponseObject, ResponseObjects, Router,
+    Server as JsonrpcServer,
+};
 use tokio::sync::{broadcast, mpsc};
 
 use crate::registry::Registry;
+use crate::ws_server::{start_ws_server, WsServerArgs};
 
 pub async fn start_jsonrpc_server(
     listen_addr: SocketAddr,
+    ws_server_args: Option<WsServerArgs>,
+    metrics_listen_addr: Option<SocketAddr>,
     registry: Registry,
     liveness: Arc<Liveness>,
     _shutdown_send: mpsc::Sender<()>,
     mut sub_shutdown: broadcast::Receiver<()>,
 ) -> Result<()> {
+    let store = registry.store().clone();
     let rpc_server = registry.build_rpc_server()?;
 
+    if let Some(metrics_listen_addr) = metrics_listen_addr {
+        let listener = TcpListener::bind(metrics_listen_addr).await?;
+        tokio::spawn(async move {
+            if let Err(err) = start_metrics_server(listener, store).await {
+                log::error!("Error running metrics server: {:?}", err);
+            }
+        });
+    }
+
+    if let Some(args) = ws_server_args {
+        let rpc_server = Arc::clone(&rpc_server);
+        tokio::spawn(async move {
+            if let Err(err) = start_ws_server(args, rpc_server).await {
+                log::error!("Error running WebSocket JSONRPC server: {:?}", err);
+            }
+        });
+    }
+
     let listener = TcpListener::bind(listen_addr).await?;
 
     // Format the full address.
@@ -97,7 +123,7 @@ async fn serve<R: Router + 'static>(
         buf.extend(chunk?);
     }
 
-    match rpc.handle(RequestKind::Bytes(buf.freeze())).await {
+    match handle_request(&rpc, buf.freeze()).await {
         ResponseObjects::Empty => hyper::Response::builder()
             .status(hyper::StatusCode::NO_CONTENT)
             .body(hyper::Body::from(Vec::<u8>::new()))
@@ -117,3 +143,93 @@ async fn serve<R: Router + 'static>(
     }
     .map_err(|e| anyhow::anyhow!("JSONRPC Request error: {:?}", e))
 }
+
+/// Handle a JSONRPC request and record its metrics, for both the HTTP and
+/// WebSocket servers.
+pub(crate) async fn handle_request<R: Router + 'static>(
+    rpc: &JsonrpcServer<R>,
+    request: bytes_v10::Bytes,
+) -> ResponseObjects {
+    let method = serde_json::from_slice::<RequestMethod>(&request)
+        .map(|r| r.method)
+        .ok();
+    let t = Instant::now();
+    let response = rpc.handle(RequestKind::Bytes(request)).await;
+    observe_request(method, t.elapsed(), &response);
+    response
+}
+
+#[derive(serde::Deserialize)]
+struct RequestMethod {
+    method: String,
+}
+
+fn observe_request(method: Option<String>, elapsed: Duration, response: &ResponseObjects) {
+    let is_error = |r: &ResponseObject| matches!(r, ResponseObject::Error { .. });
+    // Don't use arbitrary method names from requests as labels.
+    let (method, has_error) = match response {
+        ResponseObjects::One(ResponseObject::Error {
+            error: RpcError::Provided { code: -32601, .. } | RpcError::Full { code: -32601, .. },
+            ..
+        }) => ("unknown".to_string(), true),
+        ResponseObjects::One(r) => (method.unwrap_or_else(|| "invalid".to_string()), is_error(r)),
+        ResponseObjects::Many(rs) => ("batch".to_string(), rs.iter().any(is_error)),
+        ResponseObjects::Empty => ("notification".to_string(), false),
+    };
+    let metrics = gw_metrics::rpc();
+    metrics
+        .request_latency
+        .with_label_values(&[&method])
+        .observe(elapsed.as_secs_f64());
+    if has_error {
+        metrics.errors.with_label_values(&[&method]).inc();
+    }
+}
+
+/// Serve prometheus metrics at `/metrics`. It should listen on a private
+/// address, apart from the public RPC server.
+pub async fn start_metrics_server(listener: TcpListener, store: Store) -> Result<()> {
+    log::info!(
+        "Metrics server listening on http://{}/metrics",
+        listener.local_addr()?
+    );
+    let incoming = AddrIncoming::from_listener(listener)?;
+    let server = Server::builder(incoming).serve(make_service_fn(move |_| {
+        let store = store.clone();
+        async move {
+            Ok::<_, Error>(service_fn(move |req: Request<Body>| {
+                let store = store.clone();
+                async move {
+                    if req.method() == Method::GET && req.uri().path() == "/metrics" {
+                        serve_metrics(&store)
+                    } else {
+                        hyper::Response::builder()
+                            .status(hyper::StatusCode::NOT_FOUND)
+                            .body(Body::empty())
+                            .map_err(anyhow::Error::new)
+                    }
+                }
+            }))
+        }
+    }));
+    server.await?;
+
+    Ok(())
+}
+
+fn serve_metrics(store: &Store) -> Result<Response<Body>> {
+    let memory_stats = &gw_metrics::db().memory_stats;
+    for stat in store.gather_mem_stats() {
+        if let Some(value) = stat.value() {
+            memory_stats
+                .with_label_values(&[stat.name(), stat.type_()])
+                .set(value as i64);
+        }
+    }
+
+    hyper::Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .body(Body::from(gw_metrics::gather()?))
+        .map_err(anyhow::Error::new)
+}
@@ -0,0 +1,110 @@
+use anyhow::{anyhow, bail, Result};
+use gw_common::{
+    blake2b::hash as blake2b_hash,
+    h256_ext::H256Ext,
+    registry_address::RegistryAddress,
+    state::{
+        build_account_field_key, build_script_hash_to_account_id_key, State,
+        GW_ACCOUNT_SCRIPT_HASH_TYPE,
+    },
+    H256,
+};
+use gw_jsonrpc_types::godwoken::StateOverride;
+use gw_traits::CodeStore;
+use gw_types::{packed::Script, prelude::*};
+
+/// Patch the overrides onto the state, the caller must not commit the state.
+pub(crate) fn apply_state_override(
+    state: &mut (impl State + CodeStore),
+    state_override: &StateOverride,
+) -> Result<()> {
+    for script_override in state_override.scripts.iter() {
+        let script: Script = script_override.script.clone().into();
+        match script_override.account_id {
+            Some(account_id) => replace_account_script(state, account_id.value(), script)?,
+            None => {
+                let script_hash: H256 = script.hash().into();
+                state.insert_script(script_hash, script);
+                state.create_account(script_hash)?;
+            }
+        }
+    }
+
+    for data in state_override.data.iter() {
+        let data = data.clone().into_bytes();
+        let data_hash: H256 = blake2b_hash(&data).into();
+        state.insert_data(data_hash, data);
+        state.store_data_hash(data_hash)?;
+    }
+
+    for kv in state_override.kv.iter() {
+        state.update_raw(to_h256(&kv.k), to_h256(&kv.v))?;
+    }
+
+    for nonce_override in state_override.nonces.iter() {
+        state.set_nonce(
+            nonce_override.account_id.value(),
+            nonce_override.nonce.value(),
+        )?;
+    }
+
+    for balance_override in state_override.balances.iter() {
+        let sudt_id = balance_override.sudt_id.value();
+        let address = RegistryAddress::from_slice(balance_override.address.as_bytes())
+            .ok_or_else(|| anyhow!("invalid registry address"))?;
+        // Mint or burn the difference, the sudt total supply changes with it
+        let balance = state.get_sudt_balance(sudt_id, &address)?;
+        if balance_override.balance > balance {
+            state.mint_sudt(sudt_id, &address, balance_override.balance - balance)?;
+        } else if balance_override.balance < balance {
+            state.burn_sudt(sudt_id, &address, balance - balance_override.balance)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn replace_account_script(
+    state: &mut (impl State + CodeStore),
+    account_id: u32,
+    script: Script,
+) -> Result<()> {
+    let old_script_hash = state.get_script_hash(account_id)?;
+    if old_script_hash.is_zero() {
+        bail!("account {} not found", account_id);
+    }
+    let script_hash: H256 = script.hash().into();
+    if script_hash == old_script_hash {
+        return Ok(());
+    }
+    if state.get_account_id_by_script_hash(&script_hash)?.is_some() {
+        bail!("script hash {} already exists", script_hash.pack());
+    }
+
+    state.update_raw(
+        build_script_hash_to_account_id_key(old_script_hash.as_slice()),
+        H256::zero(),
+    )?;
+    state.update_raw(
+        build_account_field_key(account_id, GW_ACCOUNT_SCRIPT_HASH_TYPE),
+        script_hash,
+    )?;
+    // the first 4 bytes is id, set exists flag(fifth byte) to 1
+    let script_hash_to_id_value: H256 = {
+        let mut buf: [u8; 32] = H256::from_u32(account_id).into();
+        buf[4] = 1;
+        buf.into()
+    };
+    state.update_raw(
+        build_script_hash_to_account_id_key(script_hash.as_slice()),
+        script_hash_to_id_value,
+    )?;
+    state.insert_script(script_hash, script);
+
+    Ok(())
+}
+
+fn to_h256(v: &ckb_fixed_hash::H256) -> H256 {
+    let h: [u8; 32] = v.clone().into();
+    h.into()
+}
@@ -0,0 +1,344 @@
+//! WebSocket JSON-RPC server.
+//!
+//! All methods of the HTTP server are served as-is. In addition, clients can
+//! `gw_subscribe` to:
+//!
+//! - `new_block`: new local L2 blocks.
+//! - `new_mem_block`: the next mem block is refreshed.
+//! - `pending_transactions`: hashes of transactions pushed into the mem pool.
+//! - `transaction_receipt`: the receipt of a transaction, notified once when
+//!   it is available.
+//!
+//! Notifications are fed from messages published by `BlockSyncServerState`.
+//! Transactions are published after their mem pool receipts are committed,
+//! and blocks after they are inserted, so receipts are checked on these
+//! messages.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use gw_common::H256;
+use gw_jsonrpc_types::godwoken::{L2BlockView, MemBlockView, TxReceipt};
+use gw_mem_pool::block_sync_server::BlockSyncServerState;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::{
+    packed::{BlockSync, BlockSyncUnion},
+    prelude::*,
+};
+use jsonrpc_v2::{MapRouter, ResponseObjects, Server as JsonrpcServer};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::server::handle_request;
+
+type JsonH256 = ckb_fixed_hash::H256;
+
+const INVALID_PARAM_ERR_CODE: i64 = -32602;
+const PARSE_ERR_CODE: i64 = -32700;
+
+pub struct WsServerArgs {
+    pub listen_addr: SocketAddr,
+    pub store: Store,
+    pub block_sync_server_state: Arc<Mutex<BlockSyncServerState>>,
+}
+
+pub(crate) async fn start_ws_server(
+    args: WsServerArgs,
+    rpc_server: Arc<JsonrpcServer<MapRouter>>,
+) -> Result<()> {
+    let WsServerArgs {
+        listen_addr,
+        store,
+        block_sync_server_state,
+    } = args;
+
+    let listener = TcpListener::bind(listen_addr).await?;
+    log::info!(
+        "WebSocket JSONRPC server listening on ws://{}",
+        listener.local_addr()?
+    );
+    serve_ws(listener, store, block_sync_server_state, rpc_server).await
+}
+
+/// Serve WebSocket connections accepted from `listener`.
+pub async fn serve_ws(
+    listener: TcpListener,
+    store: Store,
+    block_sync_server_state: Arc<Mutex<BlockSyncServerState>>,
+    rpc_server: Arc<JsonrpcServer<MapRouter>>,
+) -> Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let _ = stream.set_nodelay(true);
+        // Subscribe before the handshake so that no messages are missed.
+        let events = block_sync_server_state.lock().unwrap().subscribe();
+        let mut conn = Connection {
+            rpc_server: Arc::clone(&rpc_server),
+            store: store.clone(),
+            subscriptions: HashMap::new(),
+            next_subscription_id: 0,
+        };
+        tokio::spawn(async move {
+            if let Err(err) = conn.serve(stream, events).await {
+                log::debug!("[ws] connection {} closed: {:#}", peer, err);
+            }
+        });
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Topic {
+    NewBlock,
+    NewMemBlock,
+    PendingTransactions,
+    TransactionReceipt,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SubscribeParams {
+    Topic((Topic,)),
+    TopicWithTxHash((Topic, JsonH256)),
+}
+
+#[derive(Deserialize)]
+struct SubscriptionRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Clone, Copy)]
+enum Subscription {
+    NewBlock,
+    NewMemBlock,
+    PendingTransactions,
+    TransactionReceipt(H256),
+}
+
+struct Connection {
+    rpc_server: Arc<JsonrpcServer<MapRouter>>,
+    store: Store,
+    subscriptions: HashMap<String, Subscription>,
+    next_subscription_id: u64,
+}
+
+impl Connection {
+    async fn serve(&mut self, stream: TcpStream, mut events: Receiver<BlockSync>) -> Result<()> {
+        let ws_stream = tokio_tungstenite::accept_async(stream).await?;
+        let (mut sink, mut stream) = ws_stream.split();
+
+        loop {
+            let outgoing = tokio::select! {
+                msg = stream.next() => {
+                    let request = match msg {
+                        Some(Ok(Message::Text(text))) => text.into_bytes(),
+                        Some(Ok(Message::Binary(bytes))) => bytes,
+                        Some(Ok(Message::Close(_))) | None => break,
+                        // Pings are answered by tungstenite.
+                        Some(Ok(_)) => continue,
+                        Some(Err(err)) => return Err(err.into()),
+                    };
+                    self.handle_request(request).await?
+                }
+                event = events.recv() => {
+                    match event {
+                        Ok(event) => self.handle_event(event)?,
+                        Err(RecvError::Lagged(n)) => {
+                            log::warn!("[ws] subscription lagged, {} messages skipped", n);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            };
+            for msg in outgoing {
+                sink.send(Message::Text(msg)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_request(&mut self, request: Vec<u8>) -> Result<Vec<String>> {
+        let req = match serde_json::from_slice::<SubscriptionRequest>(&request) {
+            Ok(req) => req,
+            Err(_) if serde_json::from_slice::<Value>(&request).is_err() => {
+                let response = error_response(Value::Null, PARSE_ERR_CODE, "Parse error");
+                return Ok(vec![response.to_string()]);
+            }
+            // Batch requests, let the jsonrpc server handle them.
+            Err(_) => return self.forward(request).await,
+        };
+        match req.method.as_str() {
+            "gw_subscribe" => self.subscribe(req),
+            "gw_unsubscribe" => Ok(vec![self.unsubscribe(req).to_string()]),
+            _ => self.forward(request).await,
+        }
+    }
+
+    async fn forward(&self, request: Vec<u8>) -> Result<Vec<String>> {
+        match handle_request(&self.rpc_server, bytes_v10::Bytes::from(request)).await {
+            ResponseObjects::Empty => Ok(Vec::new()),
+            json => Ok(vec![serde_json::to_string(&json)?]),
+        }
+    }
+
+    fn subscribe(&mut self, req: SubscriptionRequest) -> Result<Vec<String>> {
+        let subscription = match serde_json::from_value(req.params) {
+            Ok(SubscribeParams::Topic((Topic::NewBlock,))) => Subscription::NewBlock,
+            Ok(SubscribeParams::Topic((Topic::NewMemBlock,))) => Subscription::NewMemBlock,
+            Ok(SubscribeParams::Topic((Topic::PendingTransactions,))) => {
+                Subscription::PendingTransactions
+            }
+            Ok(SubscribeParams::TopicWithTxHash((Topic::TransactionReceipt, tx_hash))) => {
+                let tx_hash: [u8; 32] = tx_hash.into();
+                Subscription::TransactionReceipt(tx_hash.into())
+            }
+            _ => {
+                let response =
+                    error_response(req.id, INVALID_PARAM_ERR_CODE, "Invalid subscription");
+                return Ok(vec![response.to_string()]);
+            }
+        };
+
+        self.next_subscription_id += 1;
+        let id = format!("{:#x}", self.next_subscription_id);
+        self.subscriptions.insert(id.clone(), subscription);
+        let mut msgs = vec![result_response(req.id, json!(id)).to_string()];
+
+        // The receipt may be already available.
+        if let Subscription::TransactionReceipt(tx_hash) = subscription {
+            if let Some(receipt) = self.get_transaction_receipt(&tx_hash)? {
+                self.subscriptions.remove(&id);
+                msgs.push(notification(&id, json!(receipt)).to_string());
+            }
+        }
+        Ok(msgs)
+    }
+
+    fn unsubscribe(&mut self, req: SubscriptionRequest) -> Value {
+        match serde_json::from_value::<(String,)>(req.params) {
+            Ok((id,)) => {
+                let removed = self.subscriptions.remove(&id).is_some();
+                result_response(req.id, json!(removed))
+            }
+            Err(_) => error_response(req.id, INVALID_PARAM_ERR_CODE, "Invalid subscription id"),
+        }
+    }
+
+    fn handle_event(&mut self, event: BlockSync) -> Result<Vec<String>> {
+        let (topic, result) = match event.to_enum() {
+            BlockSyncUnion::LocalBlock(l) => (Topic::NewBlock, json!(L2BlockView::from(l.block()))),
+            BlockSyncUnion::NextMemBlock(m) => {
+                let mem_block = MemBlockView {
+                    block_info: m.block_info().into(),
+                    withdrawals: m
+                        .withdrawals()
+                        .into_iter()
+                        .map(|w| w.request().hash().into())
+                        .collect(),
+                    deposits: m
+                        .deposits()
+                        .into_iter()
+                        .map(|d| d.request().into())
+                        .collect(),
+                };
+                (Topic::NewMemBlock, json!(mem_block))
+            }
+            BlockSyncUnion::PushTransaction(push_tx) => {
+                let tx_hash: JsonH256 = push_tx.transaction().hash().into();
+                (Topic::PendingTransactions, json!(tx_hash))
+            }
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut msgs: Vec<String> = self
+            .subscriptions
+            .iter()
+            .filter(|(_, s)| s.topic() == topic)
+            .map(|(id, _)| notification(id, result.clone()).to_string())
+            .collect();
+        if matches!(topic, Topic::NewBlock | Topic::PendingTransactions) {
+            msgs.extend(self.check_receipts()?);
+        }
+        Ok(msgs)
+    }
+
+    fn check_receipts(&mut self) -> Result<Vec<String>> {
+        let mut msgs = Vec::new();
+        let mut notified = Vec::new();
+        for (id, subscription) in self.subscriptions.iter() {
+            if let Subscription::TransactionReceipt(tx_hash) = subscription {
+                if let Some(receipt) = self.get_transaction_receipt(tx_hash)? {
+                    msgs.push(notification(id, json!(receipt)).to_string());
+                    notified.push(id.clone());
+                }
+            }
+        }
+        for id in notified {
+            self.subscriptions.remove(&id);
+        }
+        Ok(msgs)
+    }
+
+    /// Same as `gw_get_transaction_receipt`.
+    fn get_transaction_receipt(&self, tx_hash: &H256) -> Result<Option<TxReceipt>> {
+        let db = self.store.get_snapshot();
+        if let Some(receipt) = db.get_transaction_receipt(tx_hash)? {
+            return Ok(Some(receipt.into()));
+        }
+        Ok(db
+            .get_mem_pool_transaction_receipt(tx_hash)?
+            .map(Into::into))
+    }
+}
+
+impl Subscription {
+    fn topic(&self) -> Topic {
+        match self {
+            Subscription::NewBlock => Topic::NewBlock,
+            Subscription::NewMemBlock => Topic::NewMemBlock,
+            Subscription::PendingTransactions => Topic::PendingTransactions,
+            Subscription::TransactionReceipt(_) => Topic::TransactionReceipt,
+        }
+    }
+}
+
+fn result_response(id: Value, result: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "result": result,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": message,
+        },
+    })
+}
+
+fn notification(subscription: &str, result: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "gw_subscription",
+        "params": {
+            "subscription": subscription,
+            "result": result,
+        },
+    })
+}
@@ -0,0 +1,49 @@
+//! Key of `gw_db::schema::COLUMN_ACCOUNT_TX_FROM_INDEX` and
+//! `gw_db::schema::COLUMN_ACCOUNT_TX_TO_INDEX`.
+
+use gw_db::schema::{Col, COLUMN_ACCOUNT_TX_FROM_INDEX, COLUMN_ACCOUNT_TX_TO_INDEX};
+use std::convert::TryInto;
+
+/// Whether the account is the sender or the receiver of transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountTxRole {
+    From,
+    To,
+}
+
+impl AccountTxRole {
+    pub fn col(&self) -> Col {
+        match self {
+            AccountTxRole::From => COLUMN_ACCOUNT_TX_FROM_INDEX,
+            AccountTxRole::To => COLUMN_ACCOUNT_TX_TO_INDEX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AccountTxKey {
+    pub account_id: u32,
+    pub block_number: u64,
+    pub tx_index: u32,
+}
+
+impl AccountTxKey {
+    pub const LEN: usize = 16;
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut key = [0u8; Self::LEN];
+        key[..4].copy_from_slice(&self.account_id.to_be_bytes());
+        key[4..12].copy_from_slice(&self.block_number.to_be_bytes());
+        key[12..].copy_from_slice(&self.tx_index.to_be_bytes());
+        key
+    }
+
+    pub fn from_slice(key: &[u8]) -> Self {
+        assert_eq!(key.len(), Self::LEN, "invalid account tx key");
+        Self {
+            account_id: u32::from_be_bytes(key[..4].try_into().unwrap()),
+            block_number: u64::from_be_bytes(key[4..12].try_into().unwrap()),
+            tx_index: u32::from_be_bytes(key[12..].try_into().unwrap()),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Value of `gw_db::schema::COLUMN_DEPOSIT_LOG`.
+
+use gw_db::error::Error;
+use std::convert::TryInto;
+
+/// What the mem-pool did with an observed deposit cell.
+///
+/// The log is removed once the deposit is included in a block, the block
+/// number is then kept in `gw_db::schema::COLUMN_DEPOSIT_INDEX`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepositLog {
+    /// Collected into the mem block on top of `tip_block_number`.
+    Collected { tip_block_number: u64 },
+    /// Rejected by the mem-pool on top of `tip_block_number`.
+    Rejected {
+        tip_block_number: u64,
+        reason: String,
+    },
+}
+
+impl DepositLog {
+    const FLAG_COLLECTED: u8 = 0;
+    const FLAG_REJECTED: u8 = 1;
+
+    pub fn tip_block_number(&self) -> u64 {
+        match self {
+            DepositLog::Collected { tip_block_number } => *tip_block_number,
+            DepositLog::Rejected {
+                tip_block_number, ..
+            } => *tip_block_number,
+        }
+    }
+
+    /// flag (1 byte) | tip block number (8 bytes, big endian) | reason (utf8)
+    pub fn to_vec(&self) -> Vec<u8> {
+        let (flag, reason) = match self {
+            DepositLog::Collected { .. } => (Self::FLAG_COLLECTED, ""),
+            DepositLog::Rejected { reason, .. } => (Self::FLAG_REJECTED, reason.as_str()),
+        };
+        let mut value = Vec::with_capacity(9 + reason.len());
+        value.push(flag);
+        value.extend_from_slice(&self.tip_block_number().to_be_bytes());
+        value.extend_from_slice(reason.as_bytes());
+        value
+    }
+
+    pub fn from_slice(value: &[u8]) -> Result<Self, Error> {
+        if value.len() < 9 {
+            return Err(format!("invalid deposit log length {}", value.len()).into());
+        }
+        let tip_block_number = u64::from_be_bytes(value[1..9].try_into().expect("u64 bytes"));
+        match value[0] {
+            Self::FLAG_COLLECTED => Ok(DepositLog::Collected { tip_block_number }),
+            Self::FLAG_REJECTED => Ok(DepositLog::Rejected {
+                tip_block_number,
+                reason: String::from_utf8_lossy(&value[9..]).into_owned(),
+            }),
+            flag => Err(format!("invalid deposit log flag {}", flag).into()),
+        }
+    }
+}
@@ -1,4 +1,7 @@
+pub mod account_tx_index;
 pub mod chain_view;
+pub mod deposit_log;
+pub mod log_index;
 pub mod mem_pool_state;
 pub mod readonly;
 pub mod smt;
@@ -0,0 +1,31 @@
+//! Key of `gw_db::schema::COLUMN_LOG_INDEX`.
+
+use std::convert::TryInto;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LogIndexKey {
+    pub block_number: u64,
+    pub tx_index: u32,
+    pub log_index: u32,
+}
+
+impl LogIndexKey {
+    pub const LEN: usize = 16;
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut key = [0u8; Self::LEN];
+        key[..8].copy_from_slice(&self.block_number.to_be_bytes());
+        key[8..12].copy_from_slice(&self.tx_index.to_be_bytes());
+        key[12..].copy_from_slice(&self.log_index.to_be_bytes());
+        key
+    }
+
+    pub fn from_slice(key: &[u8]) -> Self {
+        assert_eq!(key.len(), Self::LEN, "invalid log index key");
+        Self {
+            block_number: u64::from_be_bytes(key[..8].try_into().unwrap()),
+            tx_index: u32::from_be_bytes(key[8..12].try_into().unwrap()),
+            log_index: u32::from_be_bytes(key[12..].try_into().unwrap()),
+        }
+    }
+}
@@ -1,13 +1,18 @@
 use gw_common::H256;
 use gw_db::{
     read_only_db::ReadOnlyDB,
-    schema::{Col, COLUMN_REVERTED_BLOCK_SMT_ROOT},
+    schema::{
+        Col, COLUMN_BLOCK_STATE_RECORD, COLUMN_BLOCK_STATE_REVERSE_RECORD,
+        COLUMN_REVERTED_BLOCK_SMT_ROOT,
+    },
+    DBIterator, Direction, IteratorMode,
 };
 use gw_types::{
     from_box_should_be_ok, packed,
     prelude::{Entity, FromSliceShouldBeOk, Unpack},
 };
 
+use crate::state::history::block_state_record::{BlockStateRecordKey, BlockStateRecordKeyReverse};
 use crate::traits::{chain_store::ChainStore, kv_store::KVStoreRead};
 
 #[derive(Clone)]
@@ -29,6 +34,47 @@ impl StoreReadonly {
             next_root: root,
         }
     }
+
+    /// Value of `state_key` after `block_number`, from the last state record at
+    /// or before the block.
+    pub fn get_history_state(&self, block_number: u64, state_key: &H256) -> Option<H256> {
+        let key = BlockStateRecordKeyReverse::new(block_number, state_key);
+        let (prev_key, _) = self
+            .inner
+            .iter(
+                COLUMN_BLOCK_STATE_REVERSE_RECORD,
+                IteratorMode::From(key.as_slice(), Direction::Reverse),
+            )
+            .expect("db operation should be ok")
+            .next()?;
+        let prev_key = BlockStateRecordKeyReverse::from_slice(&prev_key);
+        if &prev_key.state_key() != state_key {
+            return None;
+        }
+        let record_key = BlockStateRecordKey::new(prev_key.block_number(), state_key);
+        self.get(COLUMN_BLOCK_STATE_RECORD, record_key.as_slice())
+            .map(|raw| {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(&raw);
+                buf.into()
+            })
+    }
+
+    /// State keys changed by blocks after `block_number`, a key is returned
+    /// once for every block changing it.
+    pub fn iter_state_keys_changed_after(
+        &self,
+        block_number: u64,
+    ) -> impl Iterator<Item = H256> + '_ {
+        let start_key = BlockStateRecordKey::new(block_number + 1, &H256::zero());
+        self.inner
+            .iter(
+                COLUMN_BLOCK_STATE_RECORD,
+                IteratorMode::From(start_key.as_slice(), Direction::Forward),
+            )
+            .expect("db operation should be ok")
+            .map(|(key, _value)| BlockStateRecordKey::from_slice(&key).state_key())
+    }
 }
 
 impl ChainStore for StoreReadonly {}
@@ -1,11 +1,18 @@
+use gw_common::H256;
 use gw_db::{
-    schema::{Col, COLUMN_MEM_POOL_TRANSACTION},
-    DBIterator, RocksDBSnapshot,
+    schema::{Col, COLUMN_LOG_INDEX, COLUMN_MEM_POOL_TRANSACTION},
+    DBIterator, Direction, IteratorMode, RocksDBSnapshot,
 };
+use gw_types::{from_box_should_be_ok, packed, prelude::*};
+use std::convert::TryInto;
 
-use crate::traits::{
-    chain_store::ChainStore,
-    kv_store::{KVStore, KVStoreRead, KVStoreWrite},
+use crate::{
+    account_tx_index::{AccountTxKey, AccountTxRole},
+    log_index::LogIndexKey,
+    traits::{
+        chain_store::ChainStore,
+        kv_store::{KVStore, KVStoreRead, KVStoreWrite},
+    },
 };
 
 pub struct StoreSnapshot {
@@ -49,4 +56,44 @@ impl StoreSnapshot {
             .expect("db read should not fail")
             .map(|(k, _)| k)
     }
+
+    /// Iterate indexed logs in order, starting from `from_block`.
+    pub fn iter_logs(
+        &self,
+        from_block: u64,
+    ) -> impl Iterator<Item = (LogIndexKey, packed::LogItem)> + '_ {
+        let start = from_block.to_be_bytes();
+        self.inner
+            .iter(
+                COLUMN_LOG_INDEX,
+                IteratorMode::From(&start, Direction::Forward),
+            )
+            .expect("db read should not fail")
+            .map(|(k, v)| {
+                (
+                    LogIndexKey::from_slice(&k),
+                    from_box_should_be_ok!(packed::LogItemReader, v),
+                )
+            })
+    }
+
+    /// Iterate indexed transactions of `start.account_id` in `direction`,
+    /// starting from `start` (inclusive).
+    pub fn iter_account_txs(
+        &self,
+        role: AccountTxRole,
+        start: AccountTxKey,
+        direction: Direction,
+    ) -> impl Iterator<Item = (AccountTxKey, H256)> + '_ {
+        let account_id = start.account_id;
+        let start = start.to_bytes();
+        self.inner
+            .iter(role.col(), IteratorMode::From(&start, direction))
+            .expect("db read should not fail")
+            .map(|(k, v)| {
+                let tx_hash: [u8; 32] = v.as_ref().try_into().expect("tx hash");
+                (AccountTxKey::from_slice(&k), tx_hash.into())
+            })
+            .take_while(move |(key, _)| key.account_id == account_id)
+    }
 }
@@ -62,6 +62,21 @@ impl RWConfig {
             write: WriteOpt::Deny,
         }
     }
+
+    /// Block number of history state to read.
+    pub fn read_block(&self) -> Option<u64> {
+        match self.read {
+            ReadOpt::Block(number) => Some(number),
+            ReadOpt::Any => None,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("state of block #{block_number} is pruned, history state is available since block #{pruned_before}")]
+pub struct StatePrunedError {
+    pub block_number: u64,
+    pub pruned_before: u64,
 }
 
 pub trait HistoryStateStore {
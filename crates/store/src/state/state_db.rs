@@ -26,7 +26,7 @@ use gw_common::{error::Error as StateError, smt::SMT, state::State, H256};
 use crate::{
     smt::smt_store::SMTStateStore,
     snapshot::StoreSnapshot,
-    state::history::history_state::{HistoryState, HistoryStateStore},
+    state::history::history_state::{HistoryState, HistoryStateStore, StatePrunedError},
     traits::{chain_store::ChainStore, kv_store::KVStore},
 };
 
@@ -84,6 +84,7 @@ pub struct Revision {
 #[derive(Debug, Default)]
 pub struct StateTracker {
     touched_keys: Mutex<HashSet<H256>>,
+    written_keys: Mutex<HashMap<H256, H256>>,
     write_data: Mutex<HashMap<H256, Bytes>>,
     read_data: Mutex<HashMap<H256, Bytes>>,
 }
@@ -94,6 +95,11 @@ impl StateTracker {
         &self.touched_keys
     }
 
+    /// Return written keys with the values before the first write
+    pub fn written_keys(&self) -> &Mutex<HashMap<H256, H256>> {
+        &self.written_keys
+    }
+
     pub fn write_data(&self) -> &Mutex<HashMap<H256, Bytes>> {
         &self.write_data
     }
@@ -106,6 +112,15 @@ impl StateTracker {
     pub fn touch_key(&self, key: &H256) {
         self.touched_keys.lock().unwrap().insert(*key);
     }
+
+    /// Record a written key and its previous value
+    pub fn write_key(&self, key: &H256, prev_value: &H256) {
+        self.written_keys
+            .lock()
+            .unwrap()
+            .entry(*key)
+            .or_insert(*prev_value);
+    }
 }
 
 pub struct StateDB<S> {
@@ -183,6 +198,16 @@ impl MemStateDB {
 impl<Store: ChainStore + HistoryStateStore + CodeStore + KVStore> BlockStateDB<Store> {
     /// From store
     pub fn from_store(store: Store, rw_config: RWConfig) -> Result<Self> {
+        if let Some(block_number) = rw_config.read_block() {
+            let pruned_before = store.get_state_pruned_block_number()?;
+            if block_number < pruned_before {
+                return Err(StatePrunedError {
+                    block_number,
+                    pruned_before,
+                }
+                .into());
+            }
+        }
         // build from last valid block
         let block = store.get_last_valid_tip_block()?;
         let tip_state = block.raw().post_account();
@@ -422,13 +447,12 @@ impl<S: State + CodeStore> State for StateDB<S> {
     }
 
     fn update_raw(&mut self, key: H256, value: H256) -> Result<(), StateError> {
+        let prev_value = self.get_raw(&key)?;
         if let Some(tracker) = self.state_tracker.as_ref() {
-            tracker.touch_key(&key);
+            tracker.write_key(&key, &prev_value);
         }
-        self.journal.push(JournalEntry::UpdateRaw {
-            key,
-            prev_value: self.get_raw(&key)?,
-        });
+        self.journal
+            .push(JournalEntry::UpdateRaw { key, prev_value });
         self.dirty_state.insert(key, value);
         Ok(())
     }
@@ -670,4 +694,33 @@ mod tests {
         // the dirty state is cleared, but the state is write into the store
         assert!(cmp_dirty_state(&mem_1, &state));
     }
+
+    #[test]
+    fn test_state_tracker_written_keys() {
+        let store = Store::open_tmp().unwrap();
+        let mut state = new_state(store.get_snapshot());
+        state
+            .update_raw(H256::from_u32(1), H256::from_u32(1))
+            .unwrap();
+        state.set_state_tracker(Default::default());
+
+        state.get_raw(&H256::from_u32(2)).unwrap();
+        state
+            .update_raw(H256::from_u32(1), H256::from_u32(2))
+            .unwrap();
+        state
+            .update_raw(H256::from_u32(1), H256::from_u32(3))
+            .unwrap();
+
+        let tracker = state.take_state_tracker().unwrap();
+        let touched_keys = tracker.touched_keys().lock().unwrap();
+        assert_eq!(touched_keys.len(), 2);
+        // the value before the first write is recorded
+        let written_keys = tracker.written_keys().lock().unwrap();
+        assert_eq!(written_keys.len(), 1);
+        assert_eq!(
+            written_keys.get(&H256::from_u32(1)),
+            Some(&H256::from_u32(1))
+        );
+    }
 }
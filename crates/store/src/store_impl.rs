@@ -20,11 +20,26 @@ use gw_types::prelude::*;
 #[derive(Clone)]
 pub struct Store {
     db: RocksDB,
+    account_tx_index: bool,
 }
 
 impl<'a> Store {
     pub fn new(db: RocksDB) -> Self {
-        Store { db }
+        Store {
+            db,
+            account_tx_index: false,
+        }
+    }
+
+    /// Maintain the account transaction index when inserting and detaching
+    /// blocks.
+    pub fn with_account_tx_index(mut self, enable: bool) -> Self {
+        self.account_tx_index = enable;
+        self
+    }
+
+    pub fn account_tx_index_enabled(&self) -> bool {
+        self.account_tx_index
     }
 
     pub fn open_tmp() -> Result<Self> {
@@ -41,6 +56,7 @@ impl<'a> Store {
     pub fn begin_transaction(&self) -> StoreTransaction {
         StoreTransaction {
             inner: self.db.transaction(),
+            account_tx_index: self.account_tx_index,
         }
     }
 
@@ -74,8 +90,10 @@ impl<'a> Store {
         {
             let db = self.get_snapshot();
             let tip_number: u64 = db.get_last_valid_tip_block()?.raw().number().unpack();
+            let snapshot_number = db.get_snapshot_block_number()?.unwrap_or(0);
             let smt = SMTBlockStore::new(db).to_smt()?;
-            for number in tip_number.saturating_sub(100)..tip_number {
+            let from_number = tip_number.saturating_sub(100).max(snapshot_number);
+            for number in from_number..tip_number {
                 let block_hash = self.get_block_hash_by_number(number)?.expect("exist");
                 let block = self.get_block(&block_hash)?.expect("exist");
                 let key = block.smt_key();
@@ -1,5 +1,9 @@
 use crate::{
-    state::{history::history_state::RWConfig, traits::JournalDB, BlockStateDB},
+    state::{
+        history::history_state::{HistoryStateStore, RWConfig},
+        traits::JournalDB,
+        BlockStateDB,
+    },
     traits::{chain_store::ChainStore, kv_store::KVStoreWrite},
     transaction::StoreTransaction,
     Store,
@@ -239,3 +243,24 @@ fn test_state_with_version() {
         assert_eq!(v, H256::zero());
     }
 }
+
+#[test]
+fn test_prune_block_state_record() {
+    let store = Store::open_tmp().unwrap();
+    let db = &store.begin_transaction();
+    let (k1, k2) = (H256::from_u32(1), H256::from_u32(2));
+    db.record_block_state(1, k1, H256::from_u32(1)).unwrap();
+    db.record_block_state(1, k2, H256::from_u32(2)).unwrap();
+    db.record_block_state(2, k1, H256::from_u32(3)).unwrap();
+    db.record_block_state(3, k1, H256::from_u32(4)).unwrap();
+
+    db.prune_block_state_record(1).unwrap();
+    db.prune_block_state_record(2).unwrap();
+
+    // k1 of block 1 is overwritten by block 2
+    assert_eq!(db.get_history_state(1, &k1), None);
+    assert_eq!(db.get_history_state(1, &k2), Some(H256::from_u32(2)));
+    assert_eq!(db.get_history_state(2, &k1), Some(H256::from_u32(3)));
+    assert_eq!(db.get_history_state(2, &k2), Some(H256::from_u32(2)));
+    assert_eq!(db.get_history_state(3, &k1), Some(H256::from_u32(4)));
+}
@@ -3,6 +3,7 @@ use crate::{
     Store,
 };
 use gw_db::{DBRawIterator, Direction::Forward, IteratorMode};
+use gw_types::{packed, prelude::*};
 use std::collections::HashMap;
 
 #[test]
@@ -372,3 +373,125 @@ fn range_search_and_delete() {
 
     assert!(store_txn.get(1, &[6, 0]).is_some());
 }
+
+#[test]
+fn insert_and_delete_block_logs() {
+    let store = Store::open_tmp().unwrap();
+
+    let log = |account_id: u32| {
+        packed::LogItem::new_builder()
+            .account_id(account_id.pack())
+            .build()
+    };
+    let receipt =
+        |logs: Vec<packed::LogItem>| packed::TxReceipt::new_builder().logs(logs.pack()).build();
+
+    let store_txn = store.begin_transaction();
+    store_txn
+        .insert_block_logs(1, &[receipt(vec![log(1), log(2)]), receipt(vec![log(3)])])
+        .unwrap();
+    store_txn
+        .insert_block_logs(2, &[receipt(vec![]), receipt(vec![log(4)])])
+        .unwrap();
+    store_txn.commit().unwrap();
+
+    let logs: Vec<_> = store
+        .get_snapshot()
+        .iter_logs(0)
+        .map(|(key, log)| {
+            let account_id: u32 = log.account_id().unpack();
+            (key.block_number, key.tx_index, key.log_index, account_id)
+        })
+        .collect();
+    assert_eq!(
+        logs,
+        vec![(1, 0, 0, 1), (1, 0, 1, 2), (1, 1, 0, 3), (2, 1, 0, 4)]
+    );
+
+    let store_txn = store.begin_transaction();
+    store_txn.delete_block_logs(2).unwrap();
+    store_txn.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    assert_eq!(snap.iter_logs(0).count(), 3);
+    assert_eq!(snap.iter_logs(2).count(), 0);
+}
+
+#[test]
+fn iter_account_txs() {
+    use crate::account_tx_index::{AccountTxKey, AccountTxRole};
+    use gw_db::Direction::Reverse;
+
+    let store = Store::open_tmp().unwrap();
+    let store_txn = &store.begin_transaction();
+    let keys = [(1, 1, 0), (2, 1, 1), (2, 3, 0), (2, 4, 2), (3, 0, 0)];
+    for (account_id, block_number, tx_index) in keys {
+        let key = AccountTxKey {
+            account_id,
+            block_number,
+            tx_index,
+        };
+        store_txn
+            .insert_raw(AccountTxRole::From.col(), &key.to_bytes(), &[0u8; 32])
+            .unwrap();
+    }
+    store_txn.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    let positions = |start: (u64, u32), direction| {
+        let start = AccountTxKey {
+            account_id: 2,
+            block_number: start.0,
+            tx_index: start.1,
+        };
+        snap.iter_account_txs(AccountTxRole::From, start, direction)
+            .map(|(key, _)| (key.block_number, key.tx_index))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(positions((0, 0), Forward), vec![(1, 1), (3, 0), (4, 2)]);
+    assert_eq!(positions((3, 0), Forward), vec![(3, 0), (4, 2)]);
+    assert_eq!(
+        positions((u64::MAX, u32::MAX), Reverse),
+        vec![(4, 2), (3, 0), (1, 1)]
+    );
+    assert_eq!(positions((3, 1), Reverse), vec![(3, 0), (1, 1)]);
+}
+
+#[test]
+fn set_and_get_deposit_log() {
+    use crate::{deposit_log::DepositLog, traits::chain_store::ChainStore};
+
+    let store = Store::open_tmp().unwrap();
+    let store_txn = &store.begin_transaction();
+    let out_point = |index: u32| {
+        packed::OutPoint::new_builder()
+            .tx_hash([1u8; 32].pack())
+            .index(index.pack())
+            .build()
+    };
+    let collected = DepositLog::Collected {
+        tip_block_number: 3,
+    };
+    let rejected = DepositLog::Rejected {
+        tip_block_number: 4,
+        reason: "Invalid deposit capacity".to_string(),
+    };
+    store_txn
+        .set_deposit_log(&out_point(0), &collected)
+        .unwrap();
+    store_txn.set_deposit_log(&out_point(1), &rejected).unwrap();
+    store_txn.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    assert_eq!(
+        snap.get_deposit_log(&out_point(0)).unwrap(),
+        Some(collected)
+    );
+    assert_eq!(snap.get_deposit_log(&out_point(1)).unwrap(), Some(rejected));
+    assert_eq!(snap.get_deposit_log(&out_point(2)).unwrap(), None);
+    assert_eq!(snap.get_deposit_block_number(&out_point(0)), None);
+
+    // Invalid log is an error instead of a panic
+    assert!(DepositLog::from_slice(&[0u8; 8]).is_err());
+    assert!(DepositLog::from_slice(&[2u8; 9]).is_err());
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::mutable_key_type)]
 
+use crate::deposit_log::DepositLog;
 use crate::traits::kv_store::KVStoreRead;
 use anyhow::Result;
 use gw_common::H256;
@@ -8,13 +9,14 @@ use gw_db::schema::{
     COLUMN_ASSET_SCRIPT, COLUMN_BAD_BLOCK, COLUMN_BAD_BLOCK_CHALLENGE_TARGET, COLUMN_BLOCK,
     COLUMN_BLOCK_DEPOSIT_INFO_VEC, COLUMN_BLOCK_GLOBAL_STATE,
     COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY, COLUMN_BLOCK_SUBMIT_TX,
-    COLUMN_BLOCK_SUBMIT_TX_HASH, COLUMN_INDEX, COLUMN_MEM_POOL_TRANSACTION,
-    COLUMN_MEM_POOL_TRANSACTION_RECEIPT, COLUMN_MEM_POOL_WITHDRAWAL, COLUMN_META,
-    COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_TRANSACTION, COLUMN_TRANSACTION_INFO,
+    COLUMN_BLOCK_SUBMIT_TX_HASH, COLUMN_DEPOSIT_INDEX, COLUMN_DEPOSIT_LOG, COLUMN_INDEX,
+    COLUMN_MEM_POOL_TRANSACTION, COLUMN_MEM_POOL_TRANSACTION_RECEIPT, COLUMN_MEM_POOL_WITHDRAWAL,
+    COLUMN_META, COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_TRANSACTION, COLUMN_TRANSACTION_INFO,
     COLUMN_TRANSACTION_RECEIPT, COLUMN_WITHDRAWAL, COLUMN_WITHDRAWAL_INFO, META_BLOCK_SMT_ROOT_KEY,
     META_CHAIN_ID_KEY, META_LAST_CONFIRMED_BLOCK_NUMBER_HASH_KEY,
     META_LAST_SUBMITTED_BLOCK_NUMBER_HASH_KEY, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
-    META_REVERTED_BLOCK_SMT_ROOT_KEY, META_TIP_BLOCK_HASH_KEY,
+    META_REVERTED_BLOCK_SMT_ROOT_KEY, META_SNAPSHOT_BLOCK_NUMBER_KEY, META_SNAPSHOT_IMPORTING_KEY,
+    META_STATE_PRUNED_BLOCK_NUMBER_KEY, META_TIP_BLOCK_HASH_KEY,
 };
 use gw_types::{
     from_box_should_be_ok,
@@ -96,6 +98,31 @@ pub trait ChainStore: KVStoreRead {
         Ok(byte32.unpack())
     }
 
+    /// Block state history before this block number is pruned, 0 if nothing
+    /// is pruned.
+    fn get_state_pruned_block_number(&self) -> Result<u64, Error> {
+        let number = self
+            .get(COLUMN_META, META_STATE_PRUNED_BLOCK_NUMBER_KEY)
+            .map(|slice| packed::Uint64Reader::from_slice_should_be_ok(slice.as_ref()).unpack())
+            .unwrap_or(0);
+        Ok(number)
+    }
+
+    /// Block number of the snapshot this store is imported from, blocks before
+    /// it are not available.
+    fn get_snapshot_block_number(&self) -> Result<Option<u64>, Error> {
+        let number = self
+            .get(COLUMN_META, META_SNAPSHOT_BLOCK_NUMBER_KEY)
+            .map(|slice| packed::Uint64Reader::from_slice_should_be_ok(slice.as_ref()).unpack());
+        Ok(number)
+    }
+
+    /// A snapshot import is in progress, or failed and left an incomplete
+    /// store.
+    fn is_snapshot_importing(&self) -> bool {
+        self.get(COLUMN_META, META_SNAPSHOT_IMPORTING_KEY).is_some()
+    }
+
     fn get_last_confirmed_block_number_hash(&self) -> Option<NumberHash> {
         let data = self.get(COLUMN_META, META_LAST_CONFIRMED_BLOCK_NUMBER_HASH_KEY)?;
         Some(from_box_should_be_ok!(NumberHashReader, data))
@@ -139,6 +166,21 @@ pub trait ChainStore: KVStoreRead {
         Some(from_box_should_be_ok!(packed::DepositInfoVecReader, data))
     }
 
+    /// Get the number of the block which includes the deposit cell.
+    fn get_deposit_block_number(&self, out_point: &packed::OutPoint) -> Option<u64> {
+        let data = self.get(COLUMN_DEPOSIT_INDEX, out_point.as_slice())?;
+        let mut number = [0u8; 8];
+        number.copy_from_slice(&data);
+        Some(u64::from_be_bytes(number))
+    }
+
+    fn get_deposit_log(&self, out_point: &packed::OutPoint) -> Result<Option<DepositLog>, Error> {
+        match self.get(COLUMN_DEPOSIT_LOG, out_point.as_slice()) {
+            Some(data) => DepositLog::from_slice(&data).map(Some),
+            None => Ok(None),
+        }
+    }
+
     fn get_block_post_finalized_custodian_capacity(
         &self,
         block_number: u64,
@@ -43,38 +43,14 @@ impl HistoryStateStore for &StoreTransaction {
     }
 
     fn get_history_state(&self, block_number: u64, state_key: &H256) -> Option<H256> {
-        let key = BlockStateRecordKeyReverse::new(block_number, state_key);
-        let mut raw_iter: DBRawIterator = self
-            .get_iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, IteratorMode::Start)
-            .into();
-        raw_iter.seek_for_prev(key.as_slice());
-
-        if !raw_iter.valid() {
-            return None;
-        }
-        match raw_iter.key() {
-            Some(prev_key) => {
-                // not a some key
-                if &prev_key[..32] != key.state_key().as_slice() {
-                    return None;
-                }
-
-                // get old value
-                let prev_reverse_key = BlockStateRecordKeyReverse::from_slice(prev_key);
-                let prev_key = BlockStateRecordKey::new(
-                    prev_reverse_key.block_number(),
-                    &prev_reverse_key.state_key(),
-                );
-
-                self.get(COLUMN_BLOCK_STATE_RECORD, prev_key.as_slice())
-                    .map(|raw| {
-                        let mut buf = [0u8; 32];
-                        buf.copy_from_slice(&raw);
-                        buf.into()
-                    })
-            }
-            _ => None,
-        }
+        // get old value
+        let prev_key = self.find_block_state_record(block_number, state_key)?;
+        self.get(COLUMN_BLOCK_STATE_RECORD, prev_key.as_slice())
+            .map(|raw| {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(&raw);
+                buf.into()
+            })
     }
 
     fn record_block_state(
@@ -96,3 +72,51 @@ impl HistoryStateStore for &StoreTransaction {
         Ok(())
     }
 }
+
+impl StoreTransaction {
+    /// Find the last state record of `state_key` at or before `block_number`.
+    fn find_block_state_record(
+        &self,
+        block_number: u64,
+        state_key: &H256,
+    ) -> Option<BlockStateRecordKey> {
+        let key = BlockStateRecordKeyReverse::new(block_number, state_key);
+        let mut raw_iter: DBRawIterator = self
+            .get_iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, IteratorMode::Start)
+            .into();
+        raw_iter.seek_for_prev(key.as_slice());
+
+        if !raw_iter.valid() {
+            return None;
+        }
+        let prev_key = raw_iter.key()?;
+        // not a some key
+        if &prev_key[..32] != key.state_key().as_slice() {
+            return None;
+        }
+        let prev_reverse_key = BlockStateRecordKeyReverse::from_slice(prev_key);
+        Some(BlockStateRecordKey::new(
+            prev_reverse_key.block_number(),
+            &prev_reverse_key.state_key(),
+        ))
+    }
+
+    /// Prune state records overwritten by `block_number`.
+    ///
+    /// History state before `block_number` is not available afterwards.
+    pub fn prune_block_state_record(&self, block_number: u64) -> Result<(), Error> {
+        if block_number == 0 {
+            return Ok(());
+        }
+        for record_key in self.iter_block_state_record(block_number) {
+            let state_key = record_key.state_key();
+            if let Some(prev_key) = self.find_block_state_record(block_number - 1, &state_key) {
+                self.delete(COLUMN_BLOCK_STATE_RECORD, prev_key.as_slice())?;
+                let reverse_key =
+                    BlockStateRecordKeyReverse::new(prev_key.block_number(), &state_key);
+                self.delete(COLUMN_BLOCK_STATE_REVERSE_RECORD, reverse_key.as_slice())?;
+            }
+        }
+        Ok(())
+    }
+}
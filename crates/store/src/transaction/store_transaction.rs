@@ -1,5 +1,8 @@
 #![allow(clippy::mutable_key_type)]
 
+use crate::account_tx_index::AccountTxKey;
+use crate::deposit_log::DepositLog;
+use crate::log_index::LogIndexKey;
 use crate::smt::smt_store::{SMTBlockStore, SMTRevertedBlockStore, SMTStateStore};
 use crate::traits::chain_store::ChainStore;
 use crate::traits::kv_store::KVStoreRead;
@@ -7,18 +10,21 @@ use crate::traits::kv_store::{KVStore, KVStoreWrite};
 use gw_common::h256_ext::H256Ext;
 use gw_common::{merkle_utils::calculate_state_checkpoint, smt::SMT, H256};
 use gw_db::schema::{
-    Col, COLUMN_ASSET_SCRIPT, COLUMN_BAD_BLOCK, COLUMN_BAD_BLOCK_CHALLENGE_TARGET, COLUMN_BLOCK,
+    Col, COLUMN_ACCOUNT_TX_FROM_INDEX, COLUMN_ACCOUNT_TX_TO_INDEX, COLUMN_ASSET_SCRIPT,
+    COLUMN_BAD_BLOCK, COLUMN_BAD_BLOCK_CHALLENGE_TARGET, COLUMN_BLOCK,
     COLUMN_BLOCK_DEPOSIT_INFO_VEC, COLUMN_BLOCK_GLOBAL_STATE,
     COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY, COLUMN_BLOCK_SUBMIT_TX,
-    COLUMN_BLOCK_SUBMIT_TX_HASH, COLUMN_INDEX, COLUMN_MEM_POOL_TRANSACTION,
-    COLUMN_MEM_POOL_TRANSACTION_RECEIPT, COLUMN_MEM_POOL_WITHDRAWAL, COLUMN_META,
-    COLUMN_REVERTED_BLOCK_SMT_LEAF, COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_TRANSACTION,
-    COLUMN_TRANSACTION_INFO, COLUMN_TRANSACTION_RECEIPT, COLUMN_WITHDRAWAL, COLUMN_WITHDRAWAL_INFO,
-    META_BLOCK_SMT_ROOT_KEY, META_CHAIN_ID_KEY, META_LAST_CONFIRMED_BLOCK_NUMBER_HASH_KEY,
+    COLUMN_BLOCK_SUBMIT_TX_HASH, COLUMN_DEPOSIT_INDEX, COLUMN_DEPOSIT_LOG, COLUMN_INDEX,
+    COLUMN_LOG_INDEX, COLUMN_MEM_POOL_TRANSACTION, COLUMN_MEM_POOL_TRANSACTION_RECEIPT,
+    COLUMN_MEM_POOL_WITHDRAWAL, COLUMN_META, COLUMN_REVERTED_BLOCK_SMT_LEAF,
+    COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_TRANSACTION, COLUMN_TRANSACTION_INFO,
+    COLUMN_TRANSACTION_RECEIPT, COLUMN_WITHDRAWAL, COLUMN_WITHDRAWAL_INFO, META_BLOCK_SMT_ROOT_KEY,
+    META_CHAIN_ID_KEY, META_LAST_CONFIRMED_BLOCK_NUMBER_HASH_KEY,
     META_LAST_SUBMITTED_BLOCK_NUMBER_HASH_KEY, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
-    META_REVERTED_BLOCK_SMT_ROOT_KEY, META_TIP_BLOCK_HASH_KEY,
+    META_REVERTED_BLOCK_SMT_ROOT_KEY, META_SNAPSHOT_BLOCK_NUMBER_KEY, META_SNAPSHOT_IMPORTING_KEY,
+    META_STATE_PRUNED_BLOCK_NUMBER_KEY, META_TIP_BLOCK_HASH_KEY,
 };
-use gw_db::{error::Error, iter::DBIter, DBIterator, IteratorMode, RocksDBTransaction};
+use gw_db::{error::Error, iter::DBIter, DBIterator, Direction, IteratorMode, RocksDBTransaction};
 use gw_types::packed::NumberHash;
 use gw_types::{
     from_box_should_be_ok,
@@ -32,6 +38,7 @@ use std::collections::HashSet;
 
 pub struct StoreTransaction {
     pub(crate) inner: RocksDBTransaction,
+    pub(crate) account_tx_index: bool,
 }
 
 impl KVStoreRead for &StoreTransaction {
@@ -135,16 +142,23 @@ impl StoreTransaction {
         debug_assert_eq!(block.transactions().len(), tx_receipts.len());
         debug_assert_eq!(block.withdrawals().len(), withdrawals.len());
         let block_hash = block.hash();
+        let block_number: u64 = block.raw().number().unpack();
         self.insert_raw(COLUMN_BLOCK, &block_hash, block.as_slice())?;
         self.insert_raw(
             COLUMN_BLOCK_GLOBAL_STATE,
             &block_hash,
             global_state.as_slice(),
         )?;
-        self.set_block_deposit_info_vec(
-            block.raw().number().unpack(),
-            &deposit_info_vec.as_reader(),
-        )?;
+        self.set_block_deposit_info_vec(block_number, &deposit_info_vec.as_reader())?;
+        for deposit_info in deposit_info_vec.into_iter() {
+            let out_point = deposit_info.cell().out_point();
+            self.insert_raw(
+                COLUMN_DEPOSIT_INDEX,
+                out_point.as_slice(),
+                &block_number.to_be_bytes(),
+            )?;
+            self.delete(COLUMN_DEPOSIT_LOG, out_point.as_slice())?;
+        }
 
         // Verify prev tx state and insert
         {
@@ -189,7 +203,13 @@ impl StoreTransaction {
                 key.as_slice(),
                 tx_receipt.as_slice(),
             )?;
+            if self.account_tx_index {
+                let (from_key, to_key) = account_tx_keys(&tx, block_number, index as u32);
+                self.insert_raw(COLUMN_ACCOUNT_TX_FROM_INDEX, &from_key, &tx.hash())?;
+                self.insert_raw(COLUMN_ACCOUNT_TX_TO_INDEX, &to_key, &tx.hash())?;
+            }
         }
+        self.insert_block_logs(block_number, &tx_receipts)?;
         for (index, withdrawal) in withdrawals.into_iter().enumerate() {
             let key = WithdrawalKey::build_withdrawal_key(block_hash.pack(), index as u32);
             self.insert_raw(COLUMN_WITHDRAWAL, key.as_slice(), withdrawal.as_slice())?;
@@ -279,6 +299,14 @@ impl StoreTransaction {
         )
     }
 
+    pub fn set_state_pruned_block_number(&self, block_number: u64) -> Result<(), Error> {
+        self.insert_raw(
+            COLUMN_META,
+            META_STATE_PRUNED_BLOCK_NUMBER_KEY,
+            block_number.pack().as_slice(),
+        )
+    }
+
     pub fn set_last_submitted_block_number_hash(
         &self,
         number_hash: &packed::NumberHashReader,
@@ -334,6 +362,22 @@ impl StoreTransaction {
         self.delete(COLUMN_BLOCK_DEPOSIT_INFO_VEC, &block_number.to_be_bytes())
     }
 
+    pub fn set_deposit_log(
+        &self,
+        out_point: &packed::OutPoint,
+        deposit_log: &DepositLog,
+    ) -> Result<(), Error> {
+        self.insert_raw(
+            COLUMN_DEPOSIT_LOG,
+            out_point.as_slice(),
+            &deposit_log.to_vec(),
+        )
+    }
+
+    pub fn delete_deposit_log(&self, out_point: &packed::OutPoint) -> Result<(), Error> {
+        self.delete(COLUMN_DEPOSIT_LOG, out_point.as_slice())
+    }
+
     pub fn set_block_post_finalized_custodian_capacity(
         &self,
         block_number: u64,
@@ -357,6 +401,41 @@ impl StoreTransaction {
         )
     }
 
+    /// Index logs of the block's transaction receipts.
+    pub(crate) fn insert_block_logs(
+        &self,
+        block_number: u64,
+        tx_receipts: &[packed::TxReceipt],
+    ) -> Result<(), Error> {
+        for (tx_index, tx_receipt) in tx_receipts.iter().enumerate() {
+            for (log_index, log) in tx_receipt.logs().into_iter().enumerate() {
+                let key = LogIndexKey {
+                    block_number,
+                    tx_index: tx_index as u32,
+                    log_index: log_index as u32,
+                };
+                self.insert_raw(COLUMN_LOG_INDEX, &key.to_bytes(), log.as_slice())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn delete_block_logs(&self, block_number: u64) -> Result<(), Error> {
+        let prefix = block_number.to_be_bytes();
+        let keys: Vec<Box<[u8]>> = self
+            .get_iter(
+                COLUMN_LOG_INDEX,
+                IteratorMode::From(&prefix, Direction::Forward),
+            )
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(&prefix))
+            .collect();
+        for key in keys {
+            self.delete(COLUMN_LOG_INDEX, &key)?;
+        }
+        Ok(())
+    }
+
     pub fn set_reverted_block_smt_root(&self, root: H256) -> Result<(), Error> {
         self.insert_raw(
             COLUMN_META,
@@ -479,6 +558,37 @@ impl StoreTransaction {
         Ok(())
     }
 
+    /// Mark the store as being imported from a snapshot, the mark is cleared
+    /// by `set_snapshot_tip`.
+    pub fn set_snapshot_importing(&self) -> Result<(), Error> {
+        self.insert_raw(COLUMN_META, META_SNAPSHOT_IMPORTING_KEY, &[1])
+    }
+
+    /// Set snapshot block as the tip of an imported store
+    ///
+    /// The block is treated as confirmed and submitted, and history state
+    /// before it is marked as pruned. The importing mark is cleared.
+    pub fn set_snapshot_tip(&self, block: &packed::L2Block) -> Result<(), Error> {
+        let block_number: u64 = block.raw().number().unpack();
+        let block_hash = block.hash();
+        let nh = packed::NumberHash::new_builder()
+            .number(block_number.pack())
+            .block_hash(block_hash.pack())
+            .build();
+
+        self.insert_raw(COLUMN_META, META_TIP_BLOCK_HASH_KEY, &block_hash)?;
+        self.set_last_valid_tip_block_hash(&block_hash.into())?;
+        self.set_last_confirmed_block_number_hash(&nh.as_reader())?;
+        self.set_last_submitted_block_number_hash(&nh.as_reader())?;
+        self.set_state_pruned_block_number(block_number)?;
+        self.insert_raw(
+            COLUMN_META,
+            META_SNAPSHOT_BLOCK_NUMBER_KEY,
+            block_number.pack().as_slice(),
+        )?;
+        self.delete(COLUMN_META, META_SNAPSHOT_IMPORTING_KEY)
+    }
+
     /// Delete block from DB
     ///
     /// Will update last confirmed / last submitted block to parent block if the
@@ -499,9 +609,15 @@ impl StoreTransaction {
             log::warn!("detach block #{} {}", number, hash);
         }
         // remove transaction info
-        for tx in block.transactions().into_iter() {
+        for (index, tx) in block.transactions().into_iter().enumerate() {
             let tx_hash = tx.hash();
             self.delete(COLUMN_TRANSACTION_INFO, &tx_hash)?;
+            // Delete even if the index is disabled now, it may have been
+            // enabled when the block was inserted, or be enabled again later.
+            let block_number = block.raw().number().unpack();
+            let (from_key, to_key) = account_tx_keys(&tx, block_number, index as u32);
+            self.delete(COLUMN_ACCOUNT_TX_FROM_INDEX, &from_key)?;
+            self.delete(COLUMN_ACCOUNT_TX_TO_INDEX, &to_key)?;
         }
         // withdrawal info
         for withdrawal in block.withdrawals() {
@@ -526,6 +642,7 @@ impl StoreTransaction {
 
         // update tip
         let block_number: u64 = block_number.unpack();
+        self.delete_block_logs(block_number)?;
         let parent_number = block_number.saturating_sub(1);
         let parent_block_hash = self
             .get_block_hash_by_number(parent_number)?
@@ -559,6 +676,12 @@ impl StoreTransaction {
         }
 
         self.delete_submit_tx(block_number)?;
+        if let Some(deposit_info_vec) = self.get_block_deposit_info_vec(block_number) {
+            for deposit_info in deposit_info_vec.into_iter() {
+                let out_point = deposit_info.cell().out_point();
+                self.delete(COLUMN_DEPOSIT_INDEX, out_point.as_slice())?;
+            }
+        }
         self.delete_block_deposit_info_vec(block_number)?;
         self.delete_block_post_finalized_custodian_capacity(block_number)?;
 
@@ -654,3 +777,22 @@ impl StoreTransaction {
             })
     }
 }
+
+fn account_tx_keys(
+    tx: &packed::L2Transaction,
+    block_number: u64,
+    tx_index: u32,
+) -> ([u8; AccountTxKey::LEN], [u8; AccountTxKey::LEN]) {
+    let raw = tx.raw();
+    let from_key = AccountTxKey {
+        account_id: raw.from_id().unpack(),
+        block_number,
+        tx_index,
+    };
+    let to_key = AccountTxKey {
+        account_id: raw.to_id().unpack(),
+        block_number,
+        tx_index,
+    };
+    (from_key.to_bytes(), to_key.to_bytes())
+}
@@ -203,6 +203,16 @@ impl TestChain {
         }
     }
 
+    pub async fn setup_with_store(rollup_type_script: Script, store: Store) -> Self {
+        let inner = setup_chain_with_store(rollup_type_script.clone(), Some(store)).await;
+
+        Self {
+            l1_committed_block_number: 1,
+            rollup_type_script,
+            inner,
+        }
+    }
+
     pub async fn update_mem_pool_config(self, mut mem_pool_config: MemPoolConfig) -> Self {
         let Self {
             l1_committed_block_number,
@@ -347,8 +357,12 @@ pub fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
 }
 
 pub async fn setup_chain(rollup_type_script: Script) -> Chain {
-    let mut account_lock_manage = AccountLockManage::default();
-    let rollup_config = RollupConfig::new_builder()
+    setup_chain_with_store(rollup_type_script, None).await
+}
+
+/// Rollup config of `setup_chain`.
+pub fn default_rollup_config() -> RollupConfig {
+    RollupConfig::new_builder()
         .allowed_eoa_type_hashes(
             vec![
                 AllowedTypeHash::new(AllowedEoaType::Eth, *ETH_ACCOUNT_LOCK_CODE_HASH),
@@ -374,7 +388,12 @@ pub async fn setup_chain(rollup_type_script: Script) -> Chain {
         .l2_sudt_validator_script_type_hash(SUDT_VALIDATOR_CODE_HASH.pack())
         .finality_blocks(DEFAULT_FINALITY_BLOCKS.pack())
         .chain_id(TEST_CHAIN_ID.pack())
-        .build();
+        .build()
+}
+
+pub async fn setup_chain_with_store(rollup_type_script: Script, opt_store: Option<Store>) -> Chain {
+    let mut account_lock_manage = AccountLockManage::default();
+    let rollup_config = default_rollup_config();
     account_lock_manage
         .register_lock_algorithm((*ALWAYS_SUCCESS_CODE_HASH).into(), Box::new(AlwaysSuccess));
     account_lock_manage.register_lock_algorithm(
@@ -385,7 +404,7 @@ pub async fn setup_chain(rollup_type_script: Script) -> Chain {
         rollup_type_script,
         rollup_config,
         account_lock_manage,
-        None,
+        opt_store,
         None,
         None,
     )
@@ -472,6 +491,19 @@ pub fn chain_generator(chain: &Chain, rollup_type_script: Script) -> Arc<Generat
     ))
 }
 
+/// Genesis config of `setup_chain_with_account_lock_manage`. Init the store
+/// with it before to set the genesis submit transaction.
+pub fn genesis_config(rollup_type_script: &Script, rollup_config: &RollupConfig) -> GenesisConfig {
+    GenesisConfig {
+        timestamp: 0,
+        meta_contract_validator_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+        eth_registry_validator_type_hash: ETH_REGISTRY_SCRIPT_TYPE_HASH.into(),
+        rollup_config: rollup_config.clone().into(),
+        rollup_type_hash: rollup_type_script.hash().into(),
+        secp_data_dep: Default::default(),
+    }
+}
+
 pub async fn setup_chain_with_account_lock_manage(
     rollup_type_script: Script,
     rollup_config: RollupConfig,
@@ -486,14 +518,7 @@ pub async fn setup_chain_with_account_lock_manage(
         ..Default::default()
     });
     let rollup_script_hash = rollup_type_script.hash();
-    let genesis_config = GenesisConfig {
-        timestamp: 0,
-        meta_contract_validator_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
-        eth_registry_validator_type_hash: ETH_REGISTRY_SCRIPT_TYPE_HASH.into(),
-        rollup_config: rollup_config.clone().into(),
-        rollup_type_hash: rollup_script_hash.into(),
-        secp_data_dep: Default::default(),
-    };
+    let genesis_config = genesis_config(&rollup_type_script, &rollup_config);
     init_genesis(&store, &genesis_config, &[0u8; 32], Bytes::default()).unwrap();
     let backend_manage = build_backend_manage(&rollup_config);
     let rollup_context = RollupContext {
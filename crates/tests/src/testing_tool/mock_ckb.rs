@@ -0,0 +1,1204 @@
+//! In-memory CKB chain and indexer, a `RPCBackend` to run `RPCClient` offline.
+//!
+//! Sent transactions are resolved against live cells, checked with the since,
+//! fee rate and RBF rules of the CKB tx pool in its default config, verified
+//! with ckb-script, and kept in the pool until `generate_block`. There is no
+//! proposal window, since is checked against the next block. Fee rates are
+//! fee per KB of the transaction size, cycles don't count. The cellbase is not
+//! supported.
+//!
+//! The genesis block has the system cells and dep groups where
+//! `CKBGenesisInfo` looks for them, with placeholder data. Their scripts can't
+//! run, use always success locks for wallets instead.
+
+#![allow(clippy::mutable_key_type)]
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::{TryFrom, TryInto};
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use async_jsonrpc_client::{Output, Params};
+use async_trait::async_trait;
+use ckb_types::bytes::Bytes;
+use ckb_types::core::{
+    BlockBuilder, BlockView, DepType, TransactionBuilder, TransactionInfo, TransactionView,
+};
+use ckb_types::packed::{Byte32, CellInput, CellOutput, OutPoint, OutPointVec, Script};
+use ckb_types::prelude::{Builder, Entity, Pack, Unpack};
+use gw_jsonrpc_types::ckb_jsonrpc_types::{self, JsonBytes};
+use gw_rpc_client::ckb_client::CKBClient;
+use gw_rpc_client::error::CkbRpcError;
+use gw_rpc_client::indexer_client::CKBIndexerClient;
+use gw_rpc_client::indexer_types::{Cell, IOType, Order, Pagination, ScriptType, SearchKey, Tx};
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_rpc_client::traits::RPCBackend;
+use gw_types::offchain::{CellInfo, InputCellInfo, RollupContext};
+use gw_utils::since::{LockValue, Since};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use super::verify_tx::{verify_tx_with_headers, TxHeaders, TxWithContext};
+
+const MAX_TX_VERIFY_CYCLES: u64 = 70_000_000;
+const MEDIAN_TIME_BLOCK_COUNT: usize = 37;
+const BLOCK_INTERVAL_MS: u64 = 8_000;
+const EPOCH_LENGTH: u64 = 1_000;
+/// Shannons per KB, defaults of CKB.
+const MIN_FEE_RATE: u64 = 1_000;
+const MIN_RBF_RATE: u64 = 1_500;
+const MAX_REPLACEMENT_CANDIDATES: usize = 100;
+/// Blocks of `get_fee_rate_statistics`.
+const DEFAULT_FEE_RATE_TARGET: u64 = 21;
+const MAX_FEE_RATE_TARGET: u64 = 101;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Clone)]
+pub struct MockCKB(Arc<Mutex<MockChain>>);
+
+impl Default for MockCKB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockCKB {
+    pub fn new() -> Self {
+        MockCKB(Arc::new(Mutex::new(MockChain::new())))
+    }
+
+    /// `RPCClient` of this chain for both CKB and indexer.
+    pub fn rpc_client(
+        &self,
+        rollup_type_script: gw_types::packed::Script,
+        rollup_context: RollupContext,
+    ) -> RPCClient {
+        let backend: Arc<dyn RPCBackend> = Arc::new(self.clone());
+        let rollup_type_script = Script::new_unchecked(rollup_type_script.as_bytes());
+        RPCClient::new(
+            rollup_type_script,
+            rollup_context,
+            CKBClient::new(Arc::clone(&backend)),
+            CKBIndexerClient::new(backend),
+        )
+    }
+
+    /// Commit a transaction without inputs in a new block, to deploy scripts or
+    /// create cells. Returns out points of the cells.
+    pub fn deploy_cells(
+        &self,
+        cells: Vec<(gw_types::packed::CellOutput, Bytes)>,
+    ) -> Vec<gw_types::packed::OutPoint> {
+        let (outputs, outputs_data): (Vec<_>, Vec<_>) = cells
+            .into_iter()
+            .map(|(output, data)| (CellOutput::new_unchecked(output.as_bytes()), data.pack()))
+            .unzip();
+        let tx = TransactionBuilder::default()
+            .outputs(outputs)
+            .outputs_data(outputs_data)
+            // Different hash for same cells
+            .witness(Bytes::from(rand::random::<[u8; 32]>().to_vec()).pack())
+            .build();
+
+        let mut chain = self.0.lock().unwrap();
+        chain.commit_block(vec![tx.clone()]);
+        (0..tx.outputs().len() as u32)
+            .map(|index| {
+                let out_point = OutPoint::new(tx.hash(), index);
+                gw_types::packed::OutPoint::new_unchecked(out_point.as_bytes())
+            })
+            .collect()
+    }
+
+    /// Commit a transaction spending `inputs` in a new block without
+    /// verification, e.g. to refund deposit cells.
+    pub fn spend_cells(&self, inputs: Vec<gw_types::packed::OutPoint>) {
+        let inputs = inputs.into_iter().map(|out_point| {
+            let out_point = OutPoint::new_unchecked(out_point.as_bytes());
+            CellInput::new_builder().previous_output(out_point).build()
+        });
+        let tx = TransactionBuilder::default()
+            .inputs(inputs)
+            .output(CellOutput::default())
+            .output_data(Bytes::new().pack())
+            .build();
+
+        let mut chain = self.0.lock().unwrap();
+        chain.commit_block(vec![tx]);
+    }
+
+    /// Commit transactions in a new block without verification, as if they
+    /// were sent to another node, e.g. a replaced transaction. Pool
+    /// transactions spending the same cells are rejected. Returns the block
+    /// number.
+    pub fn commit_transactions(&self, txs: Vec<gw_types::packed::Transaction>) -> u64 {
+        let txs = txs.into_iter().map(|tx| {
+            let tx = ckb_types::packed::Transaction::new_unchecked(tx.as_bytes());
+            tx.into_view()
+        });
+
+        let mut chain = self.0.lock().unwrap();
+        chain.commit_block(txs.collect())
+    }
+
+    /// Commit pool transactions in a new block. Returns the block number.
+    pub fn generate_block(&self) -> u64 {
+        let mut chain = self.0.lock().unwrap();
+        let txs = std::mem::take(&mut chain.pool);
+        chain.commit_block(txs)
+    }
+
+    pub fn tip_block_number(&self) -> u64 {
+        let chain = self.0.lock().unwrap();
+        chain.tip().number()
+    }
+
+    pub fn pool_size(&self) -> usize {
+        let chain = self.0.lock().unwrap();
+        chain.pool.len()
+    }
+
+    pub fn pool_tx_hashes(&self) -> Vec<[u8; 32]> {
+        let chain = self.0.lock().unwrap();
+        chain
+            .pool
+            .iter()
+            .map(|tx| to_jsonh256(tx.hash()).0)
+            .collect()
+    }
+
+    /// Committed live cells of a lock script.
+    pub fn live_cells_by_lock(&self, lock: &gw_types::packed::Script) -> Vec<CellInfo> {
+        let chain = self.0.lock().unwrap();
+        let lock = Script::new_unchecked(lock.as_bytes());
+        let cells = chain.live_cells.values().filter_map(|out_point| {
+            let (output, data) = &chain.cells[out_point];
+            (output.lock() == lock).then(|| CellInfo {
+                out_point: gw_types::packed::OutPoint::new_unchecked(out_point.as_bytes()),
+                output: gw_types::packed::CellOutput::new_unchecked(output.as_bytes()),
+                data: data.clone(),
+            })
+        });
+        cells.collect()
+    }
+}
+
+#[async_trait]
+impl RPCBackend for MockCKB {
+    async fn request(&self, method: &'static str, params: Option<Params>) -> Result<Output> {
+        let params = match params {
+            Some(Params::Array(params)) => params,
+            None => vec![],
+            Some(_) => {
+                let err = RpcError::new(INVALID_PARAMS, "expect params array");
+                return err.into_output();
+            }
+        };
+
+        let result = {
+            let mut chain = self.0.lock().unwrap();
+            chain.handle(method, &params)
+        };
+        match result {
+            Ok(result) => {
+                let output = json!({"jsonrpc": "2.0", "result": result, "id": 0});
+                Ok(serde_json::from_value(output)?)
+            }
+            Err(err) => err.into_output(),
+        }
+    }
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new<M: ToString>(code: i64, message: M) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    fn ckb<M: ToString>(code: CkbRpcError, message: M) -> Self {
+        Self::new(code as i64, message)
+    }
+
+    fn into_output(self) -> Result<Output> {
+        let error = json!({"code": self.code, "message": self.message});
+        let output = json!({"jsonrpc": "2.0", "error": error, "id": 0});
+        Ok(serde_json::from_value(output)?)
+    }
+}
+
+type RpcResult<T> = std::result::Result<T, RpcError>;
+
+/// Position of a cell, in the order of the indexer.
+type CellKey = (u64, u32, u32);
+
+struct TxInfo {
+    tx: TransactionView,
+    block_number: u64,
+    index: usize,
+    /// `None` for transactions without inputs.
+    fee_rate: Option<u64>,
+}
+
+/// Epoch as `(number, index, length)`.
+type Epoch = (u64, u64, u64);
+
+struct MockChain {
+    blocks: Vec<BlockView>,
+    block_numbers: HashMap<Byte32, u64>,
+    txs: HashMap<Byte32, TxInfo>,
+    /// All committed cells, including dead ones.
+    cells: HashMap<OutPoint, (CellOutput, Bytes)>,
+    live_cells: BTreeMap<CellKey, OutPoint>,
+    live_cell_keys: HashMap<OutPoint, CellKey>,
+    pool: Vec<TransactionView>,
+    /// Transactions replaced by RBF or conflicting with committed ones, and
+    /// the reasons.
+    rejected: HashMap<Byte32, String>,
+}
+
+impl MockChain {
+    fn new() -> Self {
+        let mut chain = MockChain {
+            blocks: vec![],
+            block_numbers: HashMap::new(),
+            txs: HashMap::new(),
+            cells: HashMap::new(),
+            live_cells: BTreeMap::new(),
+            live_cell_keys: HashMap::new(),
+            pool: vec![],
+            rejected: HashMap::new(),
+        };
+        chain.commit_block(genesis_transactions());
+        chain
+    }
+
+    fn tip(&self) -> &BlockView {
+        self.blocks.last().expect("genesis")
+    }
+
+    fn commit_block(&mut self, txs: Vec<TransactionView>) -> u64 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let block = match self.blocks.last() {
+            Some(parent) => {
+                let number = parent.number() + 1;
+                let timestamp = (now.as_millis() as u64).max(parent.timestamp() + 1);
+                BlockBuilder::default()
+                    .number(number.pack())
+                    .epoch(epoch_full_value(epoch_of(number)).pack())
+                    .parent_hash(parent.hash())
+                    .timestamp(timestamp.pack())
+                    .transactions(txs)
+                    .build()
+            }
+            None => {
+                let timestamp = now.as_millis() as u64 - BLOCK_INTERVAL_MS;
+                let builder = BlockBuilder::default()
+                    .epoch(epoch_full_value(epoch_of(0)).pack())
+                    .timestamp(timestamp.pack());
+                builder.transactions(txs).build()
+            }
+        };
+
+        let block_number = block.number();
+        for (tx_index, tx) in block.transactions().into_iter().enumerate() {
+            for out_point in tx.input_pts_iter() {
+                if let Some(key) = self.live_cell_keys.remove(&out_point) {
+                    self.live_cells.remove(&key);
+                }
+            }
+            let fee_rate = self.committed_fee_rate(&tx);
+            for (index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+                let out_point = OutPoint::new(tx.hash(), index as u32);
+                let key = (block_number, tx_index as u32, index as u32);
+                self.cells.insert(out_point.clone(), (output, data));
+                self.live_cells.insert(key, out_point.clone());
+                self.live_cell_keys.insert(out_point, key);
+            }
+            let info = TxInfo {
+                tx: tx.clone(),
+                block_number,
+                index: tx_index,
+                fee_rate,
+            };
+            self.txs.insert(tx.hash(), info);
+        }
+
+        self.block_numbers.insert(block.hash(), block_number);
+        self.blocks.push(block);
+        self.remove_committed_and_conflicts();
+        block_number
+    }
+
+    /// Remove committed transactions from the pool, and reject pool
+    /// transactions spending cells consumed by committed ones.
+    fn remove_committed_and_conflicts(&mut self) {
+        let txs = &self.txs;
+        self.pool.retain(|p| !txs.contains_key(&p.hash()));
+
+        let mut dead_inputs = HashMap::new();
+        for tx in self.pool.iter() {
+            let is_dead = |i: &OutPoint| {
+                let committed = self.cells.contains_key(i);
+                committed && !self.live_cell_keys.contains_key(i)
+            };
+            if let Some(dead) = tx.input_pts_iter().find(is_dead) {
+                dead_inputs.insert(tx.hash(), dead);
+            }
+        }
+        if dead_inputs.is_empty() {
+            return;
+        }
+
+        let conflicts = self.pool_descendants(dead_inputs.keys().cloned().collect());
+        self.pool.retain(|p| !conflicts.contains(&p.hash()));
+        for tx_hash in conflicts {
+            let reason = match dead_inputs.get(&tx_hash) {
+                Some(out_point) => format!("Resolve failed Dead({})", out_point),
+                None => "Resolve failed Unknown(parent transaction is rejected)".to_string(),
+            };
+            self.rejected.insert(tx_hash, reason);
+        }
+    }
+
+    /// Fee rate of a transaction being committed, its input cells must be in
+    /// `cells`.
+    fn committed_fee_rate(&self, tx: &TransactionView) -> Option<u64> {
+        if tx.inputs().is_empty() {
+            return None;
+        }
+        let mut inputs_capacity = 0u64;
+        for out_point in tx.input_pts_iter() {
+            let (output, _) = self.cells.get(&out_point)?;
+            let capacity: u64 = output.capacity().unpack();
+            inputs_capacity = inputs_capacity.checked_add(capacity)?;
+        }
+        let outputs_capacity = tx.outputs_capacity().ok()?.as_u64();
+        let fee = inputs_capacity.checked_sub(outputs_capacity)?;
+        Some(fee_rate(fee, tx_size(tx)))
+    }
+
+    fn handle(&mut self, method: &str, params: &[Value]) -> RpcResult<Value> {
+        match method {
+            "get_tip" => {
+                let tip = self.tip();
+                Ok(json!({
+                    "block_number": ckb_jsonrpc_types::Uint64::from(tip.number()),
+                    "block_hash": to_jsonh256(tip.hash()),
+                }))
+            }
+            "get_block" => {
+                let block = self.get_block_by_hash(param(params, 0)?);
+                to_value(block.map(|b| ckb_jsonrpc_types::BlockView::from(b.to_owned())))
+            }
+            "get_block_by_number" => {
+                let number: ckb_jsonrpc_types::BlockNumber = param(params, 0)?;
+                let block = self.blocks.get(number.value() as usize);
+                to_value(block.map(|b| ckb_jsonrpc_types::BlockView::from(b.to_owned())))
+            }
+            "get_header" => {
+                let block = self.get_block_by_hash(param(params, 0)?);
+                to_value(block.map(|b| ckb_jsonrpc_types::HeaderView::from(b.header())))
+            }
+            "get_header_by_number" => {
+                let number: ckb_jsonrpc_types::BlockNumber = param(params, 0)?;
+                let block = self.blocks.get(number.value() as usize);
+                to_value(block.map(|b| ckb_jsonrpc_types::HeaderView::from(b.header())))
+            }
+            "get_block_median_time" => {
+                let block = self.get_block_by_hash(param(params, 0)?);
+                let median_time = block.map(|b| self.median_time(b.number()));
+                to_value(median_time.map(ckb_jsonrpc_types::Uint64::from))
+            }
+            "get_transaction" => self.get_transaction(param(params, 0)?),
+            "get_live_cell" => self.get_live_cell(param(params, 0)?, param(params, 1)?),
+            "send_transaction" => {
+                let tx: ckb_jsonrpc_types::Transaction = param(params, 0)?;
+                let tx = ckb_types::packed::Transaction::from(tx).into_view();
+                let tx_hash = self.send_transaction(tx)?;
+                to_value(to_jsonh256(tx_hash))
+            }
+            "dry_run_transaction" => {
+                let tx: ckb_jsonrpc_types::Transaction = param(params, 0)?;
+                let tx = ckb_types::packed::Transaction::from(tx).into_view();
+                let cycles = self.verify_transaction(&tx, &HashSet::new())?;
+                to_value(ckb_jsonrpc_types::DryRunResult {
+                    cycles: cycles.into(),
+                })
+            }
+            "get_cells" => {
+                let search_key: SearchKey = param(params, 0)?;
+                let order: Order = param(params, 1)?;
+                let limit: ckb_jsonrpc_types::Uint32 = param(params, 2)?;
+                let cursor: Option<JsonBytes> = param(params, 3)?;
+                to_value(self.get_cells(&search_key, order, limit.value(), cursor)?)
+            }
+            "get_transactions" => {
+                let search_key: SearchKey = param(params, 0)?;
+                let order: Order = param(params, 1)?;
+                let limit: ckb_jsonrpc_types::Uint32 = param(params, 2)?;
+                let cursor: Option<JsonBytes> = param(params, 3)?;
+                to_value(self.get_transactions(&search_key, order, limit.value(), cursor)?)
+            }
+            "get_current_epoch" => {
+                let (number, _, length) = epoch_of(self.tip().number());
+                to_value(ckb_jsonrpc_types::EpochView {
+                    number: number.into(),
+                    start_number: (number * length).into(),
+                    length: length.into(),
+                    compact_target: 0x2001_0000.into(),
+                })
+            }
+            "local_node_info" => Ok(json!({
+                "version": "0.110.0 (mock)",
+                "node_id": "mock",
+                "active": true,
+                "addresses": [],
+                "protocols": [],
+                "connections": "0x0",
+            })),
+            "get_fee_rate_statistics" => {
+                let target: Option<ckb_jsonrpc_types::Uint64> = param(params, 0)?;
+                Ok(self.fee_rate_statistics(target.map(|t| t.value())))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("method {} not found", method),
+            )),
+        }
+    }
+
+    fn get_block_by_hash(&self, block_hash: ckb_types::H256) -> Option<&BlockView> {
+        let number = self.block_numbers.get(&block_hash.pack())?;
+        self.blocks.get(*number as usize)
+    }
+
+    /// Mean and median fee rates of transactions in the last `target` blocks,
+    /// `null` if there are no transactions.
+    fn fee_rate_statistics(&self, target: Option<u64>) -> Value {
+        let target = target
+            .unwrap_or(DEFAULT_FEE_RATE_TARGET)
+            .clamp(1, MAX_FEE_RATE_TARGET);
+        let blocks = self.blocks.iter().rev().take(target as usize);
+        let txs = blocks.flat_map(|b| b.transactions().into_iter());
+        let mut fee_rates: Vec<u64> = txs
+            .filter_map(|tx| self.txs.get(&tx.hash()).and_then(|info| info.fee_rate))
+            .collect();
+        if fee_rates.is_empty() {
+            return Value::Null;
+        }
+
+        fee_rates.sort_unstable();
+        let len = fee_rates.len();
+        let mean = fee_rates.iter().sum::<u64>() / len as u64;
+        let median = if len % 2 == 0 {
+            (fee_rates[len / 2 - 1] + fee_rates[len / 2]) / 2
+        } else {
+            fee_rates[len / 2]
+        };
+        json!({
+            "mean": ckb_jsonrpc_types::Uint64::from(mean),
+            "median": ckb_jsonrpc_types::Uint64::from(median),
+        })
+    }
+
+    fn median_time(&self, block_number: u64) -> u64 {
+        let end = block_number as usize + 1;
+        let start = end.saturating_sub(MEDIAN_TIME_BLOCK_COUNT);
+        let mut timestamps: Vec<u64> = self.blocks[start..end]
+            .iter()
+            .map(|b| b.timestamp())
+            .collect();
+        timestamps.sort_unstable();
+        timestamps[timestamps.len() / 2]
+    }
+
+    fn get_transaction(&self, tx_hash: ckb_types::H256) -> RpcResult<Value> {
+        let tx_hash = tx_hash.pack();
+        let with_status = |tx: &TransactionView, status: &str, block_hash: Option<Byte32>| {
+            let tx = ckb_jsonrpc_types::TransactionView::from(tx.to_owned());
+            json!({
+                "transaction": tx,
+                "tx_status": {"status": status, "block_hash": block_hash.map(to_jsonh256)},
+            })
+        };
+
+        if let Some(info) = self.txs.get(&tx_hash) {
+            let block_hash = self.blocks[info.block_number as usize].hash();
+            Ok(with_status(&info.tx, "committed", Some(block_hash)))
+        } else if let Some(tx) = self.pool.iter().find(|tx| tx.hash() == tx_hash) {
+            Ok(with_status(tx, "pending", None))
+        } else if let Some(reason) = self.rejected.get(&tx_hash) {
+            Ok(json!({
+                "transaction": null,
+                "tx_status": {"status": "rejected", "block_hash": null, "reason": reason},
+            }))
+        } else {
+            Ok(Value::Null)
+        }
+    }
+
+    fn get_live_cell(
+        &self,
+        out_point: ckb_jsonrpc_types::OutPoint,
+        with_data: bool,
+    ) -> RpcResult<Value> {
+        use ckb_jsonrpc_types::{CellData, CellInfo, CellWithStatus};
+
+        let out_point = OutPoint::from(out_point);
+        let cell = match self.live_cell_keys.get(&out_point) {
+            Some(_) => &self.cells[&out_point],
+            None => {
+                let status = match self.cells.get(&out_point) {
+                    Some(_) => "dead",
+                    None => "unknown",
+                };
+                let cell_with_status = CellWithStatus {
+                    cell: None,
+                    status: status.to_string(),
+                };
+                return to_value(cell_with_status);
+            }
+        };
+
+        let (output, data) = cell;
+        let data = with_data.then(|| CellData {
+            content: JsonBytes::from_bytes(data.clone()),
+            hash: CellOutput::calc_data_hash(data).unpack(),
+        });
+        let cell_with_status = CellWithStatus {
+            cell: Some(CellInfo {
+                output: output.to_owned().into(),
+                data,
+            }),
+            status: "live".to_string(),
+        };
+        to_value(cell_with_status)
+    }
+
+    fn send_transaction(&mut self, tx: TransactionView) -> RpcResult<Byte32> {
+        let tx_hash = tx.hash();
+        if self.txs.contains_key(&tx_hash) || self.pool.iter().any(|p| p.hash() == tx_hash) {
+            return Err(RpcError::ckb(
+                CkbRpcError::PoolRejectedDuplicatedTransaction,
+                format!("Duplicated({})", tx_hash),
+            ));
+        }
+
+        // Pool transactions spending the same inputs are replaced if the new
+        // one pays enough more fee.
+        let inputs: HashSet<OutPoint> = tx.input_pts_iter().collect();
+        let conflicts: HashSet<Byte32> = {
+            let pool = self.pool.iter();
+            let spend_inputs = pool.filter(|p| p.input_pts_iter().any(|i| inputs.contains(&i)));
+            spend_inputs.map(|p| p.hash()).collect()
+        };
+        let replaced = self.pool_descendants(conflicts);
+
+        self.verify_transaction(&tx, &replaced)?;
+        self.verify_since(&tx)?;
+        let fee = self.fee(&tx, &replaced)?;
+        let size = tx_size(&tx);
+        let min_fee = MIN_FEE_RATE * size / 1000;
+        if fee < min_fee {
+            let msg = format!(
+                "The min fee rate is {} shannons/KW, so the transaction fee should be {} shannons at least, but only got {}",
+                MIN_FEE_RATE, min_fee, fee
+            );
+            return Err(RpcError::ckb(
+                CkbRpcError::PoolRejectedTransactionByMinFeeRate,
+                msg,
+            ));
+        }
+        if !replaced.is_empty() {
+            self.check_rbf(&tx, fee, &replaced)?;
+            self.pool.retain(|p| !replaced.contains(&p.hash()));
+            let reason = format!("RBFRejected(replaced by tx {})", tx_hash);
+            let rejected = replaced.into_iter().map(|h| (h, reason.clone()));
+            self.rejected.extend(rejected);
+        }
+
+        self.pool.push(tx);
+        Ok(tx_hash)
+    }
+
+    /// RBF rules of CKB. The fee must be at least the fee of `replaced`
+    /// transactions plus `MIN_RBF_RATE` of the transaction size.
+    fn check_rbf(
+        &self,
+        tx: &TransactionView,
+        fee: u64,
+        replaced: &HashSet<Byte32>,
+    ) -> RpcResult<()> {
+        let rbf_rejected = |msg: String| RpcError::ckb(CkbRpcError::PoolRejectedRBF, msg);
+        if replaced.len() > MAX_REPLACEMENT_CANDIDATES {
+            let msg = format!(
+                "RBF rejected: Tx conflict too many txs, conflict txs count: {}, expect <= {}",
+                replaced.len(),
+                MAX_REPLACEMENT_CANDIDATES
+            );
+            return Err(rbf_rejected(msg));
+        }
+        if tx.input_pts_iter().any(|i| replaced.contains(&i.tx_hash())) {
+            let msg = "RBF rejected: new Tx contains inputs in descendants of to be replaced Tx";
+            return Err(rbf_rejected(msg.to_string()));
+        }
+
+        let replaced_fee: u64 = {
+            let txs = self.pool.iter().filter(|p| replaced.contains(&p.hash()));
+            let fees = txs.map(|p| self.fee(p, &HashSet::new()));
+            fees.collect::<RpcResult<Vec<_>>>()?.into_iter().sum()
+        };
+        let min_replace_fee = replaced_fee + MIN_RBF_RATE * tx_size(tx) / 1000;
+        if fee < min_replace_fee {
+            let msg = format!(
+                "RBF rejected: Tx's current fee is {}, expect it to >= {} to replace old txs",
+                fee, min_replace_fee
+            );
+            return Err(rbf_rejected(msg));
+        }
+        Ok(())
+    }
+
+    /// Check since of inputs against the next block.
+    fn verify_since(&self, tx: &TransactionView) -> RpcResult<()> {
+        let tip = self.tip();
+        let block_number = tip.number() + 1;
+        let epoch = epoch_of(block_number);
+        let median_time = self.median_time(tip.number());
+
+        for (index, input) in tx.inputs().into_iter().enumerate() {
+            let since = Since::new(input.since().unpack());
+            if since.as_u64() == 0 {
+                continue;
+            }
+            let verify_failed = |err: &str| {
+                let msg = format!(
+                    "Verification failed Transaction({}(Inputs[{}]))",
+                    err, index
+                );
+                RpcError::ckb(CkbRpcError::TransactionFailedToVerify, msg)
+            };
+            let lock_value = since
+                .extract_lock_value()
+                .filter(|_| since.flags_is_valid())
+                .ok_or_else(|| verify_failed("InvalidSince"))?;
+            if let LockValue::EpochNumberWithFraction(e) = lock_value {
+                if e.index() >= e.length() {
+                    return Err(verify_failed("InvalidSince"));
+                }
+            }
+
+            let is_mature = if since.is_absolute() {
+                match lock_value {
+                    LockValue::BlockNumber(n) => block_number >= n,
+                    LockValue::EpochNumberWithFraction(e) => {
+                        epoch_reached(epoch, (0, 0, 1), (e.number(), e.index(), e.length()))
+                    }
+                    LockValue::Timestamp(t) => median_time >= t,
+                }
+            } else {
+                // Relative to the block committing the cell, cells in the pool
+                // are immature.
+                let out_point = input.previous_output();
+                let cell_block = match self.txs.get(&out_point.tx_hash()) {
+                    Some(info) => &self.blocks[info.block_number as usize],
+                    None => return Err(verify_failed("Immature")),
+                };
+                match lock_value {
+                    LockValue::BlockNumber(n) => block_number >= cell_block.number() + n,
+                    LockValue::EpochNumberWithFraction(e) => epoch_reached(
+                        epoch,
+                        epoch_of(cell_block.number()),
+                        (e.number(), e.index(), e.length()),
+                    ),
+                    LockValue::Timestamp(t) => {
+                        let parent_number = cell_block.number().saturating_sub(1);
+                        median_time >= self.median_time(parent_number) + t
+                    }
+                }
+            };
+            if !is_mature {
+                return Err(verify_failed("Immature"));
+            }
+        }
+        Ok(())
+    }
+
+    /// `txs` and pool transactions spending their outputs, recursively.
+    fn pool_descendants(&self, mut txs: HashSet<Byte32>) -> HashSet<Byte32> {
+        loop {
+            let descendants: Vec<Byte32> = {
+                let pool = self.pool.iter().filter(|p| !txs.contains(&p.hash()));
+                let spend = pool.filter(|p| {
+                    let mut inputs = p.input_pts_iter();
+                    inputs.any(|i| txs.contains(&i.tx_hash()))
+                });
+                spend.map(|p| p.hash()).collect()
+            };
+            if descendants.is_empty() {
+                return txs;
+            }
+            txs.extend(descendants);
+        }
+    }
+
+    /// Get a cell which isn't spent, from committed cells or outputs of pool
+    /// transactions. Pool transactions in `excluded` are ignored.
+    fn get_unspent_cell(
+        &self,
+        out_point: &OutPoint,
+        excluded: &HashSet<Byte32>,
+    ) -> Option<(CellOutput, Bytes)> {
+        let pool = self.pool.iter().filter(|p| !excluded.contains(&p.hash()));
+        let is_spent = pool
+            .clone()
+            .any(|p| p.input_pts_iter().any(|i| &i == out_point));
+        if is_spent {
+            return None;
+        }
+
+        if self.live_cell_keys.contains_key(out_point) {
+            return self.cells.get(out_point).cloned();
+        }
+        let mut pool = pool;
+        let tx = pool.find(|p| p.hash() == out_point.tx_hash())?;
+        let index: u32 = out_point.index().unpack();
+        tx.output_with_data(index as usize)
+    }
+
+    /// Cell deps can also be spent by other pool transactions.
+    fn get_dep_cell(
+        &self,
+        out_point: &OutPoint,
+        excluded: &HashSet<Byte32>,
+    ) -> Option<(CellOutput, Bytes)> {
+        if self.live_cell_keys.contains_key(out_point) {
+            return self.cells.get(out_point).cloned();
+        }
+        let mut pool = self.pool.iter().filter(|p| !excluded.contains(&p.hash()));
+        let tx = pool.find(|p| p.hash() == out_point.tx_hash())?;
+        let index: u32 = out_point.index().unpack();
+        tx.output_with_data(index as usize)
+    }
+
+    fn resolve_transaction(
+        &self,
+        tx: &TransactionView,
+        excluded: &HashSet<Byte32>,
+    ) -> RpcResult<(TxWithContext, TxHeaders)> {
+        let unknown = |out_point: &OutPoint| {
+            RpcError::ckb(
+                CkbRpcError::TransactionFailedToResolve,
+                format!("Resolve failed Unknown({})", out_point),
+            )
+        };
+
+        let mut inputs = Vec::with_capacity(tx.inputs().len());
+        for out_point in tx.input_pts_iter() {
+            let cell = self.get_unspent_cell(&out_point, excluded);
+            let cell = cell.ok_or_else(|| unknown(&out_point))?;
+            inputs.push(to_input_cell_info(out_point, cell));
+        }
+
+        let mut cell_deps = Vec::with_capacity(tx.cell_deps().len());
+        for cell_dep in tx.cell_deps_iter() {
+            let out_point = cell_dep.out_point();
+            let cell = self.get_dep_cell(&out_point, excluded);
+            let (output, data) = cell.ok_or_else(|| unknown(&out_point))?;
+            let dep_type = DepType::try_from(cell_dep.dep_type())
+                .map_err(|_| RpcError::new(INVALID_PARAMS, "invalid dep type"))?;
+            if let DepType::DepGroup = dep_type {
+                let out_points = OutPointVec::from_slice(&data).map_err(|_| {
+                    let msg = format!("Resolve failed InvalidDepGroup({})", out_point);
+                    RpcError::ckb(CkbRpcError::TransactionFailedToResolve, msg)
+                })?;
+                for out_point in out_points.into_iter() {
+                    let cell = self.get_dep_cell(&out_point, excluded);
+                    let cell = cell.ok_or_else(|| unknown(&out_point))?;
+                    cell_deps.push(to_input_cell_info(out_point, cell));
+                }
+            }
+            cell_deps.push(to_input_cell_info(out_point, (output, data)));
+        }
+
+        let mut header_deps = Vec::with_capacity(tx.header_deps().len());
+        for block_hash in tx.header_deps_iter() {
+            let block = self
+                .block_numbers
+                .get(&block_hash)
+                .map(|n| &self.blocks[*n as usize]);
+            let block = block.ok_or_else(|| {
+                let msg = format!("Resolve failed InvalidHeader({})", block_hash);
+                RpcError::ckb(CkbRpcError::TransactionFailedToResolve, msg)
+            })?;
+            header_deps.push(block.header());
+        }
+        let cell_tx_infos = {
+            let cells = inputs.iter().chain(cell_deps.iter());
+            let out_points = cells.map(|c| OutPoint::new_unchecked(c.cell.out_point.as_bytes()));
+            let tx_infos = out_points.filter_map(|out_point| {
+                let info = self.txs.get(&out_point.tx_hash())?;
+                let block = &self.blocks[info.block_number as usize];
+                let tx_info =
+                    TransactionInfo::new(block.number(), block.epoch(), block.hash(), info.index);
+                Some((out_point, tx_info))
+            });
+            tx_infos.collect()
+        };
+
+        let tx_with_context = TxWithContext {
+            cell_deps,
+            inputs,
+            tx: gw_types::packed::Transaction::new_unchecked(tx.data().as_bytes()),
+        };
+        let headers = TxHeaders {
+            header_deps,
+            cell_tx_infos,
+        };
+        Ok((tx_with_context, headers))
+    }
+
+    fn verify_transaction(
+        &self,
+        tx: &TransactionView,
+        excluded: &HashSet<Byte32>,
+    ) -> RpcResult<u64> {
+        let (tx_with_context, headers) = self.resolve_transaction(tx, excluded)?;
+        self.fee(tx, excluded)?;
+        verify_tx_with_headers(tx_with_context, headers, MAX_TX_VERIFY_CYCLES).map_err(|err| {
+            let msg = format!("Verification failed Script({})", err);
+            RpcError::ckb(CkbRpcError::TransactionFailedToVerify, msg)
+        })
+    }
+
+    fn fee(&self, tx: &TransactionView, excluded: &HashSet<Byte32>) -> RpcResult<u64> {
+        let mut inputs_capacity = 0u64;
+        for out_point in tx.input_pts_iter() {
+            // Replaced transactions are checked before they are removed
+            let cell = self.get_dep_cell(&out_point, excluded).or_else(|| {
+                let pool = self.pool.iter().find(|p| p.hash() == out_point.tx_hash());
+                let index: u32 = out_point.index().unpack();
+                pool.and_then(|p| p.output_with_data(index as usize))
+            });
+            let (output, _) = cell.ok_or_else(|| {
+                let msg = format!("Resolve failed Unknown({})", out_point);
+                RpcError::ckb(CkbRpcError::TransactionFailedToResolve, msg)
+            })?;
+            let capacity: u64 = output.capacity().unpack();
+            inputs_capacity = inputs_capacity.saturating_add(capacity);
+        }
+
+        let outputs_capacity = tx.outputs_capacity().map_err(|err| {
+            let msg = format!("Verification failed Transaction({})", err);
+            RpcError::ckb(CkbRpcError::TransactionFailedToVerify, msg)
+        })?;
+        let fee = inputs_capacity.checked_sub(outputs_capacity.as_u64());
+        fee.ok_or_else(|| {
+            let msg = "Verification failed Transaction(OutputsSumOverflow)";
+            RpcError::ckb(CkbRpcError::TransactionFailedToVerify, msg)
+        })
+    }
+
+    fn get_cells(
+        &self,
+        search_key: &SearchKey,
+        order: Order,
+        limit: u32,
+        cursor: Option<JsonBytes>,
+    ) -> RpcResult<Pagination<Cell>> {
+        let cursor = cursor.map(|c| decode_cell_key(&c)).transpose()?;
+        let range = match (&order, cursor) {
+            (Order::Asc, Some(c)) => (Bound::Excluded(c), Bound::Unbounded),
+            (Order::Desc, Some(c)) => (Bound::Unbounded, Bound::Excluded(c)),
+            (_, None) => (Bound::Unbounded, Bound::Unbounded),
+        };
+        let live_cells = self.live_cells.range(range);
+        let live_cells: Box<dyn Iterator<Item = _>> = match order {
+            Order::Asc => Box::new(live_cells),
+            Order::Desc => Box::new(live_cells.rev()),
+        };
+
+        let mut objects = Vec::new();
+        let mut last_key = None;
+        for (key, out_point) in live_cells {
+            let (output, data) = &self.cells[out_point];
+            if !match_cell(search_key, output, data, key.0) {
+                continue;
+            }
+            objects.push(Cell {
+                output: output.to_owned().into(),
+                output_data: JsonBytes::from_bytes(data.clone()),
+                out_point: out_point.to_owned().into(),
+                block_number: key.0.into(),
+                tx_index: key.1.into(),
+            });
+            last_key = Some(*key);
+            if objects.len() >= limit as usize {
+                break;
+            }
+        }
+
+        Ok(Pagination {
+            objects,
+            last_cursor: last_key.map(encode_cell_key).unwrap_or_default(),
+        })
+    }
+
+    fn get_transactions(
+        &self,
+        search_key: &SearchKey,
+        order: Order,
+        limit: u32,
+        cursor: Option<JsonBytes>,
+    ) -> RpcResult<Pagination<Tx>> {
+        // (block number, tx index, io type, io index)
+        let mut txs: Vec<((u64, u32, u8, u32), Tx)> = Vec::new();
+        for block in self.blocks.iter() {
+            for (tx_index, tx) in block.transactions().into_iter().enumerate() {
+                let tx_index = tx_index as u32;
+                let to_tx = |io_type, io_index: usize| Tx {
+                    tx_hash: tx.hash().unpack(),
+                    block_number: block.number().into(),
+                    tx_index: tx_index.into(),
+                    io_index: (io_index as u32).into(),
+                    io_type,
+                };
+
+                for (io_index, out_point) in tx.input_pts_iter().enumerate() {
+                    let (output, data) = match self.cells.get(&out_point) {
+                        Some(cell) => cell,
+                        None => continue,
+                    };
+                    if match_cell(search_key, output, data, block.number()) {
+                        let key = (block.number(), tx_index, 0, io_index as u32);
+                        txs.push((key, to_tx(IOType::Input, io_index)));
+                    }
+                }
+                for (io_index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+                    if match_cell(search_key, &output, &data, block.number()) {
+                        let key = (block.number(), tx_index, 1, io_index as u32);
+                        txs.push((key, to_tx(IOType::Output, io_index)));
+                    }
+                }
+            }
+        }
+        if let Order::Desc = order {
+            txs.reverse();
+        }
+
+        let cursor = cursor.map(|c| decode_tx_key(&c)).transpose()?;
+        let after_cursor = |key: &(u64, u32, u8, u32)| match (&order, cursor) {
+            (Order::Asc, Some(c)) => *key > c,
+            (Order::Desc, Some(c)) => *key < c,
+            (_, None) => true,
+        };
+        let page: Vec<_> = txs
+            .into_iter()
+            .filter(|(key, _)| after_cursor(key))
+            .take(limit as usize)
+            .collect();
+
+        let last_cursor = page.last().map(|(key, _)| encode_tx_key(*key));
+        Ok(Pagination {
+            objects: page.into_iter().map(|(_, tx)| tx).collect(),
+            last_cursor: last_cursor.unwrap_or_default(),
+        })
+    }
+}
+
+/// System cells at the positions of `CKBGenesisInfo`: sighash, dao and
+/// multisig at outputs 1, 2 and 4 of the first transaction, and the sighash and
+/// multisig dep groups in the second one.
+fn genesis_transactions() -> Vec<TransactionView> {
+    let system_cell = |name: &'static [u8]| {
+        let type_ = Script::new_builder()
+            .args(Bytes::from_static(name).pack())
+            .build();
+        let output = CellOutput::new_builder().type_(Some(type_).pack()).build();
+        (output, Bytes::from_static(name).pack())
+    };
+    let cells = vec![
+        system_cell(b"placeholder"),
+        system_cell(b"secp256k1_blake160_sighash_all"),
+        system_cell(b"dao"),
+        system_cell(b"secp256k1_data"),
+        system_cell(b"secp256k1_blake160_multisig_all"),
+    ];
+    let (outputs, outputs_data): (Vec<_>, Vec<_>) = cells.into_iter().unzip();
+    let system_cells_tx = TransactionBuilder::default()
+        .outputs(outputs)
+        .outputs_data(outputs_data)
+        .build();
+
+    let dep_group = |index: u32| {
+        let out_points = OutPointVec::new_builder()
+            .push(OutPoint::new(system_cells_tx.hash(), 3))
+            .push(OutPoint::new(system_cells_tx.hash(), index))
+            .build();
+        (CellOutput::default(), out_points.as_bytes().pack())
+    };
+    let (outputs, outputs_data): (Vec<_>, Vec<_>) =
+        vec![dep_group(1), dep_group(4)].into_iter().unzip();
+    let dep_groups_tx = TransactionBuilder::default()
+        .outputs(outputs)
+        .outputs_data(outputs_data)
+        .build();
+
+    vec![system_cells_tx, dep_groups_tx]
+}
+
+fn param<T: DeserializeOwned>(params: &[Value], index: usize) -> RpcResult<T> {
+    let value = params.get(index).cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|err| {
+        let msg = format!("invalid param {}: {}", index, err);
+        RpcError::new(INVALID_PARAMS, msg)
+    })
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> RpcResult<Value> {
+    serde_json::to_value(value).map_err(|err| RpcError::ckb(CkbRpcError::CKBInternalError, err))
+}
+
+fn to_jsonh256(hash: Byte32) -> ckb_types::H256 {
+    hash.unpack()
+}
+
+fn to_input_cell_info(out_point: OutPoint, cell: (CellOutput, Bytes)) -> InputCellInfo {
+    let input = CellInput::new_builder()
+        .previous_output(out_point.clone())
+        .build();
+    let (output, data) = cell;
+
+    InputCellInfo {
+        input: gw_types::packed::CellInput::new_unchecked(input.as_bytes()),
+        cell: CellInfo {
+            out_point: gw_types::packed::OutPoint::new_unchecked(out_point.as_bytes()),
+            output: gw_types::packed::CellOutput::new_unchecked(output.as_bytes()),
+            data,
+        },
+    }
+}
+
+/// Prefix search of the indexer, script args match by prefix.
+fn match_script(prefix: &ckb_jsonrpc_types::Script, script: Option<Script>) -> bool {
+    let prefix = Script::from(prefix.to_owned());
+    match script {
+        Some(script) => {
+            script.code_hash() == prefix.code_hash()
+                && script.hash_type() == prefix.hash_type()
+                && script
+                    .args()
+                    .raw_data()
+                    .starts_with(&prefix.args().raw_data())
+        }
+        None => false,
+    }
+}
+
+fn match_cell(search_key: &SearchKey, output: &CellOutput, data: &Bytes, block: u64) -> bool {
+    let (script, filter_script) = match search_key.script_type {
+        ScriptType::Lock => (Some(output.lock()), output.type_().to_opt()),
+        ScriptType::Type => (output.type_().to_opt(), Some(output.lock())),
+    };
+    if !match_script(&search_key.script, script) {
+        return false;
+    }
+
+    let filter = match search_key.filter {
+        Some(ref filter) => filter,
+        None => return true,
+    };
+    let in_range = |range: &Option<[ckb_jsonrpc_types::Uint64; 2]>, value: u64| match range {
+        Some([start, end]) => start.value() <= value && value < end.value(),
+        None => true,
+    };
+    let capacity: u64 = output.capacity().unpack();
+    let script_matched = match filter.script {
+        Some(ref prefix) => match_script(prefix, filter_script),
+        None => true,
+    };
+
+    script_matched
+        && in_range(&filter.output_data_len_range, data.len() as u64)
+        && in_range(&filter.output_capacity_range, capacity)
+        && in_range(&filter.block_range, block)
+}
+
+fn encode_cell_key(key: CellKey) -> JsonBytes {
+    let mut buf = Vec::with_capacity(16);
+    buf.extend_from_slice(&key.0.to_be_bytes());
+    buf.extend_from_slice(&key.1.to_be_bytes());
+    buf.extend_from_slice(&key.2.to_be_bytes());
+    JsonBytes::from_vec(buf)
+}
+
+fn decode_cell_key(cursor: &JsonBytes) -> RpcResult<CellKey> {
+    let buf = cursor.as_bytes();
+    if buf.len() != 16 {
+        return Err(RpcError::new(INVALID_PARAMS, "invalid cursor"));
+    }
+    let number = u64::from_be_bytes(buf[..8].try_into().unwrap());
+    let tx_index = u32::from_be_bytes(buf[8..12].try_into().unwrap());
+    let index = u32::from_be_bytes(buf[12..].try_into().unwrap());
+    Ok((number, tx_index, index))
+}
+
+fn encode_tx_key(key: (u64, u32, u8, u32)) -> JsonBytes {
+    let mut buf = Vec::with_capacity(17);
+    buf.extend_from_slice(&key.0.to_be_bytes());
+    buf.extend_from_slice(&key.1.to_be_bytes());
+    buf.push(key.2);
+    buf.extend_from_slice(&key.3.to_be_bytes());
+    JsonBytes::from_vec(buf)
+}
+
+fn decode_tx_key(cursor: &JsonBytes) -> RpcResult<(u64, u32, u8, u32)> {
+    let buf = cursor.as_bytes();
+    if buf.len() != 17 {
+        return Err(RpcError::new(INVALID_PARAMS, "invalid cursor"));
+    }
+    let number = u64::from_be_bytes(buf[..8].try_into().unwrap());
+    let tx_index = u32::from_be_bytes(buf[8..12].try_into().unwrap());
+    let io_index = u32::from_be_bytes(buf[13..].try_into().unwrap());
+    Ok((number, tx_index, buf[12], io_index))
+}
+
+fn tx_size(tx: &TransactionView) -> u64 {
+    tx.data().serialized_size_in_block() as u64
+}
+
+/// Shannons per KB.
+fn fee_rate(fee: u64, size: u64) -> u64 {
+    fee.saturating_mul(1000) / size
+}
+
+fn epoch_of(block_number: u64) -> Epoch {
+    let number = block_number / EPOCH_LENGTH;
+    (number, block_number % EPOCH_LENGTH, EPOCH_LENGTH)
+}
+
+fn epoch_full_value(epoch: Epoch) -> u64 {
+    let (number, index, length) = epoch;
+    ckb_types::core::EpochNumberWithFraction::new(number, index, length).full_value()
+}
+
+/// Whether `base + delta <= current`, compared as rational numbers.
+fn epoch_reached(current: Epoch, base: Epoch, delta: Epoch) -> bool {
+    let (cn, ci, cl) = (current.0 as u128, current.1 as u128, current.2 as u128);
+    let (bn, bi, bl) = (base.0 as u128, base.1 as u128, base.2 as u128);
+    let (dn, di, dl) = (delta.0 as u128, delta.1 as u128, delta.2 as u128);
+    // Multiplied by cl * bl * dl.
+    let target = (bn + dn) * cl * bl * dl + bi * cl * dl + di * cl * bl;
+    let current = cn * cl * bl * dl + ci * bl * dl;
+    target <= current
+}
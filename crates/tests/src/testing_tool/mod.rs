@@ -3,6 +3,8 @@ pub mod chain;
 pub mod common;
 pub mod eth_wallet;
 pub mod mem_pool_provider;
+pub mod mock_ckb;
 pub mod polyjuice;
+pub mod psc;
 pub mod rpc_server;
 pub mod verify_tx;
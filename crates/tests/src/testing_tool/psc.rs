@@ -0,0 +1,409 @@
+//! `ProduceSubmitConfirm` on `MockCKB`. Contracts of the rollup are always
+//! success scripts, so submit transactions are checked by the tx pool rules of
+//! `MockCKB` only.
+
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use gw_block_producer::block_producer::{BlockProducer, BlockProducerCreateArgs};
+use gw_block_producer::chain_updater::ChainUpdater;
+use gw_block_producer::fee_rate::{FeeRateEstimator, SubmitHistory};
+use gw_block_producer::psc::{PSCContext, ProduceSubmitConfirm};
+use gw_chain::chain::Chain;
+use gw_challenge::offchain::{OffChainMockContext, OffChainMockContextBuildArgs};
+use gw_config::{BlockProducerConfig, ContractTypeScriptConfig, MemBlockConfig, WalletConfig};
+use gw_generator::account_lock_manage::{
+    always_success::AlwaysSuccess, secp256k1::Secp256k1Eth, AccountLockManage,
+};
+use gw_generator::genesis::{build_genesis, init_genesis};
+use gw_mem_pool::default_provider::DefaultMemPoolProvider;
+use gw_rpc_client::{contract::ContractsCellDepManager, rpc_client::RPCClient};
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::bytes::Bytes;
+use gw_types::core::{AllowedEoaType, DepType, ScriptHashType};
+use gw_types::offchain::{CellInfo, RollupContext};
+use gw_types::packed::{
+    AllowedTypeHash, CellDep, CellOutput, DepositLockArgs, Script, Transaction,
+};
+use gw_types::prelude::*;
+use gw_utils::abort_on_drop::{spawn_abort_on_drop, AbortOnDropHandle};
+use gw_utils::genesis_info::CKBGenesisInfo;
+use gw_utils::liveness::Liveness;
+use gw_utils::local_cells::LocalCellsManager;
+use gw_utils::wallet::Wallet;
+use tempfile::NamedTempFile;
+use tokio::sync::Mutex;
+
+use super::chain::{
+    chain_generator, default_rollup_config, genesis_config, setup_chain_with_account_lock_manage,
+    ALWAYS_SUCCESS_CODE_HASH, ALWAYS_SUCCESS_PROGRAM, ETH_ACCOUNT_LOCK_CODE_HASH,
+};
+use super::mock_ckb::MockCKB;
+
+const CKB: u64 = 100_000_000;
+const WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Relative 1000 blocks, deposits with a shorter timeout aren't collected.
+const DEPOSIT_CANCEL_TIMEOUT: u64 = 0x8000_0000_0000_0000 | 1000;
+
+pub struct TestPSC {
+    pub mock_ckb: MockCKB,
+    pub rpc_client: RPCClient,
+    pub store: Store,
+    pub chain: Arc<Mutex<Chain>>,
+    pub rollup_type_script: Script,
+    pub rollup_context: RollupContext,
+    /// Lock code hash of L2 accounts which can be challenged, the lock is
+    /// always success on both L1 and L2.
+    pub eoa_lock_code_hash: [u8; 32],
+    pub ckb_genesis_info: CKBGenesisInfo,
+    pub contracts_dep_manager: ContractsCellDepManager,
+    pub wallet_config: WalletConfig,
+    pub config: BlockProducerConfig,
+    pub context: Arc<PSCContext>,
+    _privkey_file: NamedTempFile,
+}
+
+impl TestPSC {
+    /// Deploy the contracts, the rollup cell and fee cells of the block
+    /// producer wallet, then init the chain with the rollup cell as the
+    /// genesis submit transaction. `wallet_config` and `rollup_config_cell_dep`
+    /// of `config` are replaced.
+    pub async fn setup(mut config: BlockProducerConfig) -> Self {
+        let mock_ckb = MockCKB::new();
+
+        let state_validator = contract_type_script("state-validator");
+        let deposit_lock = contract_type_script("deposit-lock");
+        let stake_lock = contract_type_script("stake-lock");
+        let custodian_lock = contract_type_script("custodian-lock");
+        let withdrawal_lock = contract_type_script("withdrawal-lock");
+        let challenge_lock = contract_type_script("challenge-lock");
+        let l1_sudt = contract_type_script("l1-sudt");
+        let omni_lock = contract_type_script("omni-lock");
+        let eoa_lock = contract_type_script("eoa-lock");
+        let contracts = vec![
+            state_validator.clone(),
+            deposit_lock.clone(),
+            stake_lock.clone(),
+            custodian_lock.clone(),
+            withdrawal_lock.clone(),
+            challenge_lock.clone(),
+            l1_sudt.clone(),
+            omni_lock.clone(),
+            eoa_lock.clone(),
+        ];
+        let contract_cells = contracts.into_iter().map(|type_| {
+            let output = CellOutput::new_builder()
+                .capacity((1000 * CKB).pack())
+                .lock(always_success_lock(Bytes::new()))
+                .type_(Some(type_).pack())
+                .build();
+            (output, ALWAYS_SUCCESS_PROGRAM.clone())
+        });
+        mock_ckb.deploy_cells(contract_cells.collect());
+
+        let rollup_config = default_rollup_config();
+        let allowed_eoa_type_hashes = {
+            let eoa_lock = AllowedTypeHash::new(AllowedEoaType::Eth, eoa_lock.hash());
+            let builder = rollup_config.allowed_eoa_type_hashes().as_builder();
+            builder.push(eoa_lock).build()
+        };
+        let rollup_config = rollup_config
+            .as_builder()
+            .allowed_eoa_type_hashes(allowed_eoa_type_hashes)
+            .l1_sudt_script_type_hash(l1_sudt.hash().pack())
+            .custodian_script_type_hash(custodian_lock.hash().pack())
+            .deposit_script_type_hash(deposit_lock.hash().pack())
+            .withdrawal_script_type_hash(withdrawal_lock.hash().pack())
+            .challenge_script_type_hash(challenge_lock.hash().pack())
+            .stake_script_type_hash(stake_lock.hash().pack())
+            .build();
+        let rollup_type_script = Script::new_builder()
+            .code_hash(state_validator.hash().pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(Bytes::from(rand::random::<[u8; 32]>().to_vec()).pack())
+            .build();
+
+        // The transaction creating the rollup cell is the genesis submit
+        // transaction.
+        let genesis_config = genesis_config(&rollup_type_script, &rollup_config);
+        let global_state = build_genesis(&genesis_config, Bytes::default())
+            .unwrap()
+            .global_state;
+        let rollup_cell = {
+            let data = global_state.as_bytes();
+            let output = CellOutput::new_builder()
+                .lock(always_success_lock(Bytes::new()))
+                .type_(Some(rollup_type_script.clone()).pack())
+                .build();
+            let capacity = output.occupied_capacity(data.len()).unwrap();
+            (output.as_builder().capacity(capacity.pack()).build(), data)
+        };
+        let rollup_config_cell = {
+            let output = CellOutput::new_builder()
+                .capacity((1000 * CKB).pack())
+                .lock(always_success_lock(Bytes::new()))
+                .build();
+            (output, rollup_config.as_bytes())
+        };
+        let out_points = mock_ckb.deploy_cells(vec![rollup_cell, rollup_config_cell]);
+        let genesis_tx_hash: [u8; 32] = out_points[0].tx_hash().unpack();
+
+        let store = Store::open_tmp().unwrap();
+        init_genesis(&store, &genesis_config, &genesis_tx_hash, Bytes::default()).unwrap();
+        let mut account_lock_manage = AccountLockManage::default();
+        account_lock_manage
+            .register_lock_algorithm((*ALWAYS_SUCCESS_CODE_HASH).into(), Box::new(AlwaysSuccess));
+        account_lock_manage
+            .register_lock_algorithm(eoa_lock.hash().into(), Box::new(AlwaysSuccess));
+        account_lock_manage.register_lock_algorithm(
+            (*ETH_ACCOUNT_LOCK_CODE_HASH).into(),
+            Box::new(Secp256k1Eth::default()),
+        );
+        let chain = setup_chain_with_account_lock_manage(
+            rollup_type_script.clone(),
+            rollup_config.clone(),
+            account_lock_manage,
+            Some(store.clone()),
+            None,
+            None,
+        )
+        .await;
+        let generator = chain_generator(&chain, rollup_type_script.clone());
+        let mem_pool = chain.mem_pool().clone().expect("mem pool");
+        let chain = Arc::new(Mutex::new(chain));
+
+        let rollup_context = RollupContext {
+            rollup_script_hash: rollup_type_script.hash().into(),
+            rollup_config: rollup_config.clone(),
+        };
+        let rpc_client = mock_ckb.rpc_client(rollup_type_script.clone(), rollup_context.clone());
+        let ckb_genesis_info = {
+            let block = rpc_client.get_block_by_number(0).await.unwrap().unwrap();
+            CKBGenesisInfo::from_block(&block).unwrap()
+        };
+        let contract_scripts = ContractTypeScriptConfig {
+            state_validator: state_validator.into(),
+            deposit_lock: deposit_lock.into(),
+            stake_lock: stake_lock.into(),
+            custodian_lock: custodian_lock.into(),
+            withdrawal_lock: withdrawal_lock.into(),
+            challenge_lock: challenge_lock.into(),
+            l1_sudt: l1_sudt.into(),
+            omni_lock: omni_lock.into(),
+            allowed_eoa_scripts: {
+                let eoa_lock_hash = ckb_fixed_hash::H256(eoa_lock.hash());
+                vec![(eoa_lock_hash, eoa_lock.clone().into())]
+                    .into_iter()
+                    .collect()
+            },
+            allowed_contract_scripts: Default::default(),
+        };
+        let contracts_dep_manager =
+            ContractsCellDepManager::build(rpc_client.clone(), contract_scripts)
+                .await
+                .unwrap();
+
+        let mut privkey_file = NamedTempFile::new().unwrap();
+        let privkey = format!("0x{}", hex::encode([0x11u8; 32]));
+        privkey_file.write_all(privkey.as_bytes()).unwrap();
+        let wallet_lock = always_success_lock(Bytes::from(rand::random::<[u8; 20]>().to_vec()));
+        let fee_cells = (0..10).map(|_| {
+            let output = CellOutput::new_builder()
+                .capacity((100_000 * CKB).pack())
+                .lock(wallet_lock.clone())
+                .build();
+            (output, Bytes::new())
+        });
+        mock_ckb.deploy_cells(fee_cells.collect());
+        let wallet_config = WalletConfig {
+            privkey_path: privkey_file.path().to_path_buf(),
+            lock: wallet_lock.into(),
+            signer: None,
+        };
+
+        config.wallet_config = Some(wallet_config.clone());
+        config.rollup_config_cell_dep = CellDep::new_builder()
+            .out_point(out_points[1].clone())
+            .dep_type(DepType::Code.into())
+            .build()
+            .into();
+        let block_producer = BlockProducer::create(BlockProducerCreateArgs {
+            rollup_config_hash: rollup_config.hash().into(),
+            store: store.clone(),
+            generator,
+            chain: chain.clone(),
+            rpc_client: rpc_client.clone(),
+            ckb_genesis_info: ckb_genesis_info.clone(),
+            config: config.clone(),
+            tests_control: None,
+            contracts_dep_manager: contracts_dep_manager.clone(),
+        })
+        .unwrap();
+        let chain_updater = ChainUpdater::new(
+            chain.clone(),
+            rpc_client.clone(),
+            rollup_context.clone(),
+            rollup_type_script.clone(),
+        );
+
+        let context = Arc::new(PSCContext {
+            store: store.clone(),
+            rpc_client: rpc_client.clone(),
+            chain: chain.clone(),
+            mem_pool,
+            block_producer,
+            local_cells_manager: Mutex::new(LocalCellsManager::default()),
+            chain_updater,
+            rollup_type_script: rollup_type_script.clone(),
+            psc_config: config.psc_config.clone(),
+            fee_rate_estimator: FeeRateEstimator::new(
+                config.submit_fee_rate.clone(),
+                config.fee_rate,
+            ),
+            submit_history: std::sync::Mutex::new(SubmitHistory::default()),
+            block_sync_server_state: None,
+            liveness: Arc::new(Liveness::new(Duration::from_secs(60))),
+        });
+
+        TestPSC {
+            mock_ckb,
+            rpc_client,
+            store,
+            chain,
+            rollup_type_script,
+            rollup_context,
+            eoa_lock_code_hash: eoa_lock.hash(),
+            ckb_genesis_info,
+            contracts_dep_manager,
+            wallet_config,
+            config,
+            context,
+            _privkey_file: privkey_file,
+        }
+    }
+
+    /// Init and run `ProduceSubmitConfirm` in the background. It stops when the
+    /// handle is dropped.
+    pub fn spawn(&self) -> AbortOnDropHandle<anyhow::Result<()>> {
+        let context = Arc::clone(&self.context);
+        spawn_abort_on_drop(async move {
+            let psc = ProduceSubmitConfirm::init(context).await?;
+            psc.run().await
+        })
+    }
+
+    /// Mock context to build challenge transactions, rollup cell deps are
+    /// resolved from `MockCKB`.
+    pub async fn offchain_mock_context(&self) -> OffChainMockContext {
+        let ckb_genesis_info = gw_challenge::offchain::CKBGenesisInfo {
+            sighash_dep: self.ckb_genesis_info.sighash_dep(),
+        };
+        let build_args = OffChainMockContextBuildArgs {
+            rpc_client: &self.rpc_client,
+            rollup_context: self.rollup_context.clone(),
+            wallet: Wallet::from_config(&self.wallet_config).unwrap(),
+            config: self.config.clone(),
+            ckb_genesis_info,
+            builtin_load_data: Default::default(),
+            contracts_dep_manager: self.contracts_dep_manager.clone(),
+        };
+        OffChainMockContext::build(build_args).await.unwrap()
+    }
+
+    /// Collect deposits from `MockCKB` like a full node does. Next block
+    /// timestamps are then estimated from the L1 median time, so L1 blocks have
+    /// to be generated before submit transactions are sent.
+    pub async fn collect_deposits_from_l1(&self) {
+        let provider = DefaultMemPoolProvider::new(
+            self.rpc_client.clone(),
+            self.store.clone(),
+            MemBlockConfig::default(),
+        );
+        let mut mem_pool = self.context.mem_pool.lock().await;
+        mem_pool.set_provider(Box::new(provider));
+    }
+
+    /// L2 lock of the EOA account of `address`.
+    pub fn eoa_script(&self, address: [u8; 20]) -> Script {
+        let mut args = self.rollup_context.rollup_script_hash.as_slice().to_vec();
+        args.extend_from_slice(&address);
+        Script::new_builder()
+            .code_hash(self.eoa_lock_code_hash.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(Bytes::from(args).pack())
+            .build()
+    }
+
+    /// Deploy a CKB deposit cell to `layer2_lock`. It isn't collected until
+    /// it is below the L1 tip block.
+    pub fn deploy_deposit(&self, layer2_lock: Script, capacity: u64) -> CellInfo {
+        let deposit_args = DepositLockArgs::new_builder()
+            .layer2_lock(layer2_lock)
+            .cancel_timeout(DEPOSIT_CANCEL_TIMEOUT.pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .build();
+        let mut args = self.rollup_context.rollup_script_hash.as_slice().to_vec();
+        args.extend_from_slice(deposit_args.as_slice());
+        let lock = Script::new_builder()
+            .code_hash(self.rollup_context.rollup_config.deposit_script_type_hash())
+            .hash_type(ScriptHashType::Type.into())
+            .args(Bytes::from(args).pack())
+            .build();
+        let output = CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(lock)
+            .build();
+        let out_points = self
+            .mock_ckb
+            .deploy_cells(vec![(output.clone(), Bytes::new())]);
+        CellInfo {
+            out_point: out_points[0].clone(),
+            output,
+            data: Bytes::new(),
+        }
+    }
+
+    pub fn submit_tx(&self, block_number: u64) -> Option<Transaction> {
+        self.store.get_block_submit_tx(block_number)
+    }
+
+    pub fn last_confirmed_block_number(&self) -> u64 {
+        let last_confirmed = self.store.get_last_confirmed_block_number_hash();
+        last_confirmed.expect("last confirmed").number().unpack()
+    }
+
+    /// Whether the submit transaction of `block_number` is in the tx pool.
+    pub fn is_submit_tx_pending(&self, block_number: u64) -> bool {
+        match self.submit_tx(block_number) {
+            Some(tx) => self.mock_ckb.pool_tx_hashes().contains(&tx.hash()),
+            None => false,
+        }
+    }
+}
+
+/// Poll `f` every 100ms, panic if it isn't true in 60 seconds.
+pub async fn wait_until<F: FnMut() -> bool>(what: &str, mut f: F) {
+    let started = Instant::now();
+    while !f() {
+        assert!(
+            started.elapsed() < WAIT_TIMEOUT,
+            "wait for {} timeout",
+            what
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+fn always_success_lock(args: Bytes) -> Script {
+    Script::new_builder()
+        .code_hash((*ALWAYS_SUCCESS_CODE_HASH).pack())
+        .hash_type(ScriptHashType::Data.into())
+        .args(args.pack())
+        .build()
+}
+
+/// Type script of a contract cell, the code of contracts is always success.
+fn contract_type_script(name: &'static str) -> Script {
+    always_success_lock(Bytes::from_static(name.as_bytes()))
+}
@@ -4,12 +4,17 @@ use anyhow::{bail, Result};
 use ckb_types::prelude::Entity;
 use gw_block_producer::test_mode_control::TestModeControl;
 use gw_chain::chain::Chain;
-use gw_common::H256;
+use gw_common::{registry_address::RegistryAddress, H256};
 use gw_config::{NodeMode::FullNode, RPCClientConfig, RPCMethods};
 
 use gw_jsonrpc_types::{
-    ckb_jsonrpc_types::{Byte32, JsonBytes, Uint64},
-    godwoken::RunResult,
+    blockchain::OutPoint as JsonOutPoint,
+    ckb_jsonrpc_types::{Byte32, JsonBytes, Uint32, Uint64},
+    godwoken::{
+        AccountProof, AccountTxPage, AccountTxQuery, BlockProof, BundleStepResult,
+        DepositWithStatus, FeeEstimate, L2TransactionWithStatus, RunResult, StateOverride,
+        TransactionTrace, WithdrawalLifecycle,
+    },
 };
 use gw_polyjuice_sender_recover::recover::PolyjuiceSenderRecover;
 use gw_rpc_client::{
@@ -18,8 +23,9 @@ use gw_rpc_client::{
 use gw_rpc_server::registry::{Registry, RegistryArgs};
 use gw_types::{
     bytes::Bytes,
-    packed::{L2Transaction, RawL2Transaction, Script, WithdrawalRequestExtra},
+    packed::{L2Transaction, OutPoint, RawL2Transaction, Script, WithdrawalRequestExtra},
     prelude::Pack,
+    U256,
 };
 
 use gw_utils::wallet::Wallet;
@@ -82,6 +88,7 @@ impl RPCServer {
             consensus_config: Default::default(),
             dynamic_config_manager: Default::default(),
             polyjuice_sender_recover,
+            p2p_admin: None,
         }
     }
 
@@ -168,6 +175,64 @@ impl RPCServer {
         Ok(run_result)
     }
 
+    pub async fn execute_raw_l2transaction_with_state_override(
+        &self,
+        raw_tx: &RawL2Transaction,
+        state_override: &StateOverride,
+    ) -> Result<RunResult> {
+        let raw_tx_bytes = JsonBytes::from_bytes(raw_tx.as_bytes());
+        let params = serde_json::to_value(&(
+            raw_tx_bytes,
+            Option::<Uint64>::None,
+            Option::<JsonBytes>::None,
+            state_override,
+        ))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_execute_raw_l2transaction")
+            .with_params(params)
+            .finish();
+
+        let run_result = self.handle_single_request(req).await?;
+        Ok(run_result)
+    }
+
+    pub async fn execute_raw_l2transaction_bundle(
+        &self,
+        raw_txs: &[RawL2Transaction],
+        continue_on_failure: bool,
+    ) -> Result<Vec<BundleStepResult>> {
+        let raw_txs: Vec<JsonBytes> = raw_txs
+            .iter()
+            .map(|raw_tx| JsonBytes::from_bytes(raw_tx.as_bytes()))
+            .collect();
+        let params = serde_json::to_value(&(raw_txs, Option::<Uint64>::None, continue_on_failure))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_execute_raw_l2transaction_bundle")
+            .with_params(params)
+            .finish();
+
+        self.handle_single_request(req).await
+    }
+
+    pub async fn estimate_fee(&self, tx: &L2Transaction) -> Result<FeeEstimate> {
+        let params = {
+            let bytes = JsonBytes::from_bytes(tx.as_bytes());
+            serde_json::to_value(&(bytes,))?
+        };
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_estimate_fee")
+            .with_params(params)
+            .finish();
+
+        self.handle_single_request(req).await
+    }
+
     pub async fn is_request_in_queue(&self, hash: H256) -> Result<bool> {
         let fixed_hash = ckb_fixed_hash::H256(hash.into());
         let params = serde_json::to_value(&(fixed_hash,))?;
@@ -182,6 +247,48 @@ impl RPCServer {
         Ok(result)
     }
 
+    pub async fn cancel_request(&self, hash: H256, expire_at: u64, signature: &[u8]) -> Result<()> {
+        let fixed_hash = ckb_fixed_hash::H256(hash.into());
+        let expire_at: Uint64 = expire_at.into();
+        let signature = JsonBytes::from_vec(signature.to_vec());
+        let params = serde_json::to_value(&(fixed_hash, expire_at, signature))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_cancel_request")
+            .with_params(params)
+            .finish();
+
+        self.handle_single_request(req).await
+    }
+
+    pub async fn get_transaction(&self, hash: H256) -> Result<Option<L2TransactionWithStatus>> {
+        let fixed_hash = ckb_fixed_hash::H256(hash.into());
+        let params = serde_json::to_value(&(fixed_hash,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_transaction")
+            .with_params(params)
+            .finish();
+
+        let tx = self.handle_single_request(req).await?;
+        Ok(tx)
+    }
+
+    pub async fn debug_trace_transaction(&self, hash: H256) -> Result<Option<TransactionTrace>> {
+        let fixed_hash = ckb_fixed_hash::H256(hash.into());
+        let params = serde_json::to_value(&(fixed_hash,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_debug_trace_transaction")
+            .with_params(params)
+            .finish();
+
+        self.handle_single_request(req).await
+    }
+
     pub async fn submit_withdrawal_request(&self, req: &WithdrawalRequestExtra) -> Result<H256> {
         let params = {
             let bytes = JsonBytes::from_bytes(req.as_bytes());
@@ -198,6 +305,131 @@ impl RPCServer {
         Ok(hash.0.into())
     }
 
+    pub async fn get_balance(
+        &self,
+        address: &RegistryAddress,
+        sudt_id: u32,
+        opt_block_number: Option<u64>,
+    ) -> Result<U256> {
+        let address = JsonBytes::from_vec(address.to_bytes());
+        let sudt_id: Uint32 = sudt_id.into();
+        let block_number: Option<Uint64> = opt_block_number.map(Into::into);
+        let params = serde_json::to_value(&(address, sudt_id, block_number))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_balance")
+            .with_params(params)
+            .finish();
+
+        let balance = self.handle_single_request(req).await?;
+        Ok(balance)
+    }
+
+    pub async fn get_transactions_by_account(
+        &self,
+        account: serde_json::Value,
+        query: AccountTxQuery,
+    ) -> Result<AccountTxPage> {
+        let params = serde_json::to_value(&(account, query))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_transactions_by_account")
+            .with_params(params)
+            .finish();
+
+        let page = self.handle_single_request(req).await?;
+        Ok(page)
+    }
+
+    pub async fn get_withdrawal_lifecycle(
+        &self,
+        withdrawal_hash: H256,
+    ) -> Result<Option<WithdrawalLifecycle>> {
+        let fixed_hash = ckb_fixed_hash::H256(withdrawal_hash.into());
+        let params = serde_json::to_value(&(fixed_hash,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_withdrawal_lifecycle")
+            .with_params(params)
+            .finish();
+
+        let lifecycle = self.handle_single_request(req).await?;
+        Ok(lifecycle)
+    }
+
+    pub async fn get_deposit(&self, out_point: OutPoint) -> Result<Option<DepositWithStatus>> {
+        let json_out_point: JsonOutPoint = out_point.into();
+        let params = serde_json::to_value(&(json_out_point,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_deposit")
+            .with_params(params)
+            .finish();
+
+        let deposit = self.handle_single_request(req).await?;
+        Ok(deposit)
+    }
+
+    pub async fn get_proof(
+        &self,
+        account_id: u32,
+        storage_keys: Vec<H256>,
+        block_number: Option<u64>,
+    ) -> Result<Option<AccountProof>> {
+        let account_id: Uint32 = account_id.into();
+        let storage_keys: Vec<ckb_fixed_hash::H256> = storage_keys
+            .into_iter()
+            .map(|k| ckb_fixed_hash::H256(k.into()))
+            .collect();
+        let params = match block_number {
+            Some(block_number) => {
+                let block_number: Uint64 = block_number.into();
+                serde_json::to_value(&(account_id, storage_keys, block_number))?
+            }
+            None => serde_json::to_value(&(account_id, storage_keys))?,
+        };
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_proof")
+            .with_params(params)
+            .finish();
+
+        let proof = self.handle_single_request(req).await?;
+        Ok(proof)
+    }
+
+    pub async fn get_block_proof(&self, block_hash: H256) -> Result<Option<BlockProof>> {
+        let fixed_hash = ckb_fixed_hash::H256(block_hash.into());
+        let params = serde_json::to_value(&(fixed_hash,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_block_proof")
+            .with_params(params)
+            .finish();
+
+        let proof = self.handle_single_request(req).await?;
+        Ok(proof)
+    }
+
+    pub async fn call<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<R> {
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method(method)
+            .with_params(params)
+            .finish();
+        self.handle_single_request(req).await
+    }
+
     async fn handle_single_request<R: DeserializeOwned>(&self, req: RequestObject) -> Result<R> {
         let ret = match self.inner.handle(req).await {
             ResponseObjects::One(ResponseObject::Result { result, .. }) => {
@@ -5,7 +5,7 @@ use ckb_script::TransactionScriptsVerifier;
 use ckb_traits::{CellDataProvider, HeaderProvider};
 use ckb_types::core::{
     cell::{CellMeta, CellMetaBuilder, ResolvedTransaction},
-    DepType, HeaderView,
+    DepType, HeaderView, TransactionInfo,
 };
 use ckb_types::{
     bytes::Bytes,
@@ -21,10 +21,28 @@ pub struct TxWithContext {
     pub tx: gw_types::packed::Transaction,
 }
 
+/// Headers that scripts can load with `load_header`.
+#[derive(Default)]
+pub struct TxHeaders {
+    pub header_deps: Vec<HeaderView>,
+    /// Committing transactions of input and dep cells. Headers of cells are
+    /// loadable if their blocks are in `header_deps`.
+    pub cell_tx_infos: HashMap<OutPoint, TransactionInfo>,
+}
+
 pub fn verify_tx(tx_with_context: TxWithContext, max_cycles: u64) -> Result<u64> {
+    verify_tx_with_headers(tx_with_context, TxHeaders::default(), max_cycles)
+}
+
+pub fn verify_tx_with_headers(
+    tx_with_context: TxWithContext,
+    headers: TxHeaders,
+    max_cycles: u64,
+) -> Result<u64> {
     let mut data_loader = TxDataLoader::new();
     data_loader.extend_cell_deps(tx_with_context.cell_deps);
     data_loader.extend_inputs(tx_with_context.inputs);
+    data_loader.extend_headers(headers);
 
     let resolved_tx = data_loader.resolve_tx(&tx_with_context.tx)?;
     let cycles = TransactionScriptsVerifier::new(&resolved_tx, &data_loader)
@@ -38,6 +56,7 @@ struct TxDataLoader {
     headers: HashMap<Byte32, HeaderView>,
     cell_deps: HashMap<OutPoint, CellInfo>,
     inputs: HashMap<OutPoint, CellInfo>,
+    cell_tx_infos: HashMap<OutPoint, TransactionInfo>,
 }
 
 impl TxDataLoader {
@@ -46,9 +65,16 @@ impl TxDataLoader {
             headers: Default::default(),
             cell_deps: Default::default(),
             inputs: Default::default(),
+            cell_tx_infos: Default::default(),
         }
     }
 
+    pub fn extend_headers(&mut self, headers: TxHeaders) {
+        let headers_by_hash = headers.header_deps.into_iter().map(|h| (h.hash(), h));
+        self.headers.extend(headers_by_hash);
+        self.cell_tx_infos.extend(headers.cell_tx_infos);
+    }
+
     pub fn extend_inputs(&mut self, inputs: Vec<gw_types::offchain::InputCellInfo>) {
         self.inputs.extend(inputs.into_iter().map(into_info))
     }
@@ -114,9 +140,13 @@ impl TxDataLoader {
 
     fn get_cell_meta(&self, out_point: &OutPoint) -> Option<CellMeta> {
         self.get_cell_info(out_point).map(|ci| {
-            CellMetaBuilder::from_cell_output(ci.output.to_owned(), ci.data.to_owned())
-                .out_point(out_point.clone())
-                .build()
+            let builder =
+                CellMetaBuilder::from_cell_output(ci.output.to_owned(), ci.data.to_owned())
+                    .out_point(out_point.clone());
+            match self.cell_tx_infos.get(out_point) {
+                Some(info) => builder.transaction_info(info.to_owned()).build(),
+                None => builder.build(),
+            }
         })
     }
 }
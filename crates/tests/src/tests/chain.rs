@@ -1,24 +1,29 @@
 use crate::testing_tool::{
     bad_block::generate_bad_block_using_first_withdrawal,
     chain::{
-        build_sync_tx, construct_block, into_deposit_info_cell, restart_chain, setup_chain,
-        ALWAYS_SUCCESS_CODE_HASH, DEFAULT_FINALITY_BLOCKS,
+        build_sync_tx, chain_generator, construct_block, into_deposit_info_cell, restart_chain,
+        setup_chain, ALWAYS_SUCCESS_CODE_HASH, DEFAULT_FINALITY_BLOCKS,
     },
+    psc::TestPSC,
 };
 
+use godwoken_bin::subcommand::simulate_challenge::{
+    ChallengeSimulator, STEP_CANCEL_CHALLENGE, STEP_ENTER_CHALLENGE, STEP_REVERT,
+};
 use gw_block_producer::produce_block::ProduceBlockResult;
 use gw_chain::chain::{
     Chain, ChallengeCell, L1Action, L1ActionContext, RevertL1ActionContext, RevertedL1Action,
     SyncEvent, SyncParam,
 };
 use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, ckb_decimal::CKBCapacity, state::State, H256};
+use gw_config::BlockProducerConfig;
 use gw_store::{
     state::{history::history_state::RWConfig, BlockStateDB},
     traits::chain_store::ChainStore,
 };
 use gw_types::{
     bytes::Bytes,
-    core::{ScriptHashType, Status},
+    core::{ChallengeTargetType, ScriptHashType, Status},
     packed::{
         CellInput, CellOutput, DepositInfoVec, DepositRequest, GlobalState, RawWithdrawalRequest,
         Script, WithdrawalRequest, WithdrawalRequestExtra,
@@ -1001,6 +1006,167 @@ async fn test_rewind_to_last_valid_tip_just_after_bad_block_reverted() {
     assert_eq!(tip_block_number, 8);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_simulate_challenge() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let psc = TestPSC::setup(BlockProducerConfig::default()).await;
+    let rollup_script_hash = psc.rollup_type_script.hash();
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(psc.rollup_type_script.clone()).pack())
+        .build();
+    let mut chain = psc.chain.lock().await;
+
+    let alice_script = Script::new_builder()
+        .code_hash(psc.eoa_lock_code_hash.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build();
+    let deposit = DepositRequest::new_builder()
+        .capacity((4000u64 * CKB).pack())
+        .script(alice_script.clone())
+        .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.generator().rollup_context(), deposit).pack())
+        .build();
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        construct_block(&chain, &mut mem_pool, deposit_info_vec.clone())
+            .await
+            .unwrap()
+    };
+    let deposit_block = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: block_result.block.clone(),
+            deposit_info_vec,
+            deposit_asset_scripts: Default::default(),
+            withdrawals: Default::default(),
+        },
+        transaction: build_sync_tx(rollup_cell.clone(), block_result),
+    };
+    let param = SyncParam {
+        updates: vec![deposit_block],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        produce_empty_block(&mut chain, rollup_cell.clone()).await;
+    }
+
+    let withdrawal = {
+        let owner_lock = Script::default();
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity((1000 * CKB).pack())
+            .account_script_hash(alice_script.hash().pack())
+            .sudt_script_hash(H256::zero().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .chain_id(crate::testing_tool::chain::TEST_CHAIN_ID.pack())
+            .build();
+        let withdrawal = WithdrawalRequest::new_builder().raw(raw).build();
+        WithdrawalRequestExtra::new_builder()
+            .request(withdrawal)
+            .owner_lock(owner_lock)
+            .build()
+    };
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        mem_pool.push_withdrawal_request(withdrawal).await.unwrap();
+        construct_block(&chain, &mut mem_pool, Default::default())
+            .await
+            .unwrap()
+    };
+    assert_eq!(block_result.block.withdrawals().len(), 1);
+    let withdrawal_block = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: block_result.block.clone(),
+            deposit_info_vec: Default::default(),
+            deposit_asset_scripts: Default::default(),
+            withdrawals: block_result.withdrawal_extras.clone(),
+        },
+        transaction: build_sync_tx(rollup_cell, block_result),
+    };
+    let param = SyncParam {
+        updates: vec![withdrawal_block],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+
+    let block_number: u64 = chain
+        .store()
+        .get_tip_block()
+        .unwrap()
+        .raw()
+        .number()
+        .unpack();
+    let mut simulator = ChallengeSimulator {
+        generator: chain_generator(&chain, psc.rollup_type_script.clone()),
+        store: psc.store.clone(),
+        mock_ctx: psc.offchain_mock_context().await,
+        max_cycles: u64::MAX,
+    };
+    drop(chain);
+
+    // Challenge the withdrawal of a valid block, all steps pass.
+    let simulation = simulator
+        .simulate(block_number, ChallengeTargetType::Withdrawal, 0)
+        .unwrap();
+    let report = &simulation.report;
+    assert_eq!(report.tip_block_number, block_number);
+    let steps: Vec<_> = report.steps.iter().map(|s| s.step).collect();
+    let expected_steps = vec![
+        STEP_ENTER_CHALLENGE,
+        STEP_CANCEL_CHALLENGE,
+        STEP_CANCEL_CHALLENGE,
+        STEP_REVERT,
+    ];
+    assert_eq!(steps, expected_steps);
+    for step in report.steps.iter() {
+        assert!(step.passed, "{} error {:?}", step.name(), step.error);
+        assert!(step.cycles.unwrap() > 0);
+        assert!(step.tx_size.unwrap() > 0);
+    }
+    assert_eq!(report.failed_steps().count(), 0);
+    assert!(simulation.txs.iter().all(Option::is_some));
+
+    // Transactions are still built and verified, but exceed max cycles.
+    simulator.max_cycles = 0;
+    let simulation = simulator
+        .simulate(block_number, ChallengeTargetType::Withdrawal, 0)
+        .unwrap();
+    let report = &simulation.report;
+    for step in report.steps.iter() {
+        assert!(!step.passed);
+        assert!(step.cycles.unwrap() > 0);
+        let err = step.error.as_ref().unwrap();
+        assert!(err.contains("exceeded max cycles"), "{}", err);
+    }
+    let failed: Vec<_> = report.failed_steps().collect();
+    assert_eq!(
+        failed,
+        vec![STEP_ENTER_CHALLENGE, STEP_CANCEL_CHALLENGE, STEP_REVERT]
+    );
+
+    // Out of bound target index.
+    let err = simulator
+        .simulate(block_number, ChallengeTargetType::Withdrawal, 1)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("out of bound"), "{}", err);
+}
+
 async fn produce_empty_block(chain: &mut Chain, rollup_cell: CellOutput) {
     let block_result = {
         let mem_pool = chain.mem_pool().as_ref().unwrap();
@@ -0,0 +1,69 @@
+use std::{sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use gw_config::{Config, ReloadConfig, ReloadConfigSource};
+use gw_dynamic_config::manager::DynamicConfigManager;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_watch_file_source() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dynamic_config.toml");
+    let write_config = |meta_cycles_limit: u64| {
+        let content = format!(
+            r#"
+            [dynamic_config.fee_config]
+            meta_cycles_limit = {}
+            sudt_cycles_limit = 20000
+            eth_addr_reg_cycles_limit = 20000
+            withdraw_cycles_limit = 20000
+
+            [dynamic_config.rpc_config]
+            allowed_sudt_proxy_creator_account_id = []
+            sudt_proxy_code_hashes = []
+            "#,
+            meta_cycles_limit
+        );
+        std::fs::write(&path, content).unwrap();
+    };
+    write_config(25000);
+
+    let manager = DynamicConfigManager::create(Config {
+        reload_config: ReloadConfig {
+            source: Some(ReloadConfigSource::File {
+                path: path.clone(),
+                watch_interval_secs: Some(1),
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .unwrap();
+    let manager = Arc::new(ArcSwap::from_pointee(manager));
+    let resp = gw_dynamic_config::reload(manager.clone()).await.unwrap();
+    assert!(resp
+        .changes()
+        .contains(&"fee_config.meta_cycles_limit".to_string()));
+    let handle = tokio::spawn(gw_dynamic_config::watch(manager.clone()));
+
+    // Make sure the modification time changes.
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    write_config(30000);
+    let meta_cycles_limit = || manager.load().get_fee_config().meta_cycles_limit;
+    for _ in 0..50 {
+        if meta_cycles_limit() == 30000 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(meta_cycles_limit(), 30000);
+
+    // An invalid config is not applied.
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    write_config(0);
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(meta_cycles_limit(), 30000);
+
+    handle.abort();
+}
@@ -0,0 +1,163 @@
+use crate::testing_tool::chain::{into_deposit_info_cell, TestChain, DEFAULT_FINALITY_BLOCKS};
+use crate::testing_tool::eth_wallet::EthWallet;
+
+use ckb_types::prelude::{Builder, Entity};
+use godwoken_bin::subcommand::{
+    export_snapshot::export_snapshot,
+    import_snapshot::{import_snapshot, read_manifest},
+};
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    ckb_decimal::CKBCapacity,
+    state::State,
+    H256,
+};
+use gw_config::StoreConfig;
+use gw_db::{read_only_db::ReadOnlyDB, schema::COLUMNS, RocksDB};
+use gw_store::{
+    state::{history::history_state::RWConfig, BlockStateDB},
+    traits::chain_store::ChainStore,
+    Store,
+};
+use gw_types::{
+    packed::{DepositInfoVec, DepositRequest, Script},
+    prelude::{Pack, Unpack},
+};
+use gw_utils::export_block::check_block_post_state;
+
+const CKB: u64 = 100000000;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_export_import_snapshot() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let store_dir = tempfile::tempdir().expect("create temp dir");
+    let store = {
+        let config = StoreConfig {
+            path: store_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        Store::new(RocksDB::open(&config, COLUMNS))
+    };
+    let mut chain = TestChain::setup_with_store(Script::default(), store).await;
+
+    // Deposit to the finalized wallet, then to the unfinalized wallet after
+    // finality blocks.
+    let finalized_wallet = EthWallet::random(chain.rollup_type_hash());
+    let deposit_info_vec = deposit(&chain, &finalized_wallet, 1000 * CKB);
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+    let finalized_deposit_block: u64 = chain.last_valid_block().raw().number().unpack();
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        chain
+            .produce_block(Default::default(), vec![])
+            .await
+            .unwrap();
+    }
+    let unfinalized_wallet = EthWallet::random(chain.rollup_type_hash());
+    let deposit_info_vec = deposit(&chain, &unfinalized_wallet, 2000 * CKB);
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+    let deposit_info_vec = deposit(&chain, &finalized_wallet, 3000 * CKB);
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+
+    let tip_block_hash = chain.store().get_last_valid_tip_block_hash().unwrap();
+    let last_finalized_block_number: u64 = chain
+        .store()
+        .get_block_post_global_state(&tip_block_hash)
+        .unwrap()
+        .unwrap()
+        .last_finalized_block_number()
+        .unpack();
+    let tip_block_number: u64 = chain.last_valid_block().raw().number().unpack();
+    assert!(last_finalized_block_number >= finalized_deposit_block);
+    assert!(last_finalized_block_number < tip_block_number - 1);
+
+    // Export
+    let export_dir = tempfile::tempdir().expect("create temp dir");
+    let db = {
+        let cf_names = (0..COLUMNS).map(|c| c.to_string());
+        ReadOnlyDB::open_cf(store_dir.path(), cf_names)
+            .unwrap()
+            .unwrap()
+    };
+    let rollup_type_hash = ckb_fixed_hash::H256(chain.rollup_type_hash().into());
+    let manifest = export_snapshot(
+        db,
+        rollup_type_hash,
+        DEFAULT_FINALITY_BLOCKS,
+        export_dir.path(),
+    )
+    .unwrap();
+    assert_eq!(manifest.block_number, last_finalized_block_number);
+
+    // Import
+    let import_store = Store::open_tmp().unwrap();
+    let manifest = read_manifest(export_dir.path()).unwrap();
+    import_snapshot(&import_store, export_dir.path(), &manifest).unwrap();
+    assert!(!import_store.is_snapshot_importing());
+
+    // A failed import is not mistaken for an empty database
+    let failed_store = Store::open_tmp().unwrap();
+    let mut bad_manifest = read_manifest(export_dir.path()).unwrap();
+    bad_manifest.records += 1;
+    import_snapshot(&failed_store, export_dir.path(), &bad_manifest).unwrap_err();
+    assert!(failed_store.is_snapshot_importing());
+    import_snapshot(&failed_store, export_dir.path(), &manifest).unwrap_err();
+
+    let finalized_block_hash = chain
+        .store()
+        .get_block_hash_by_number(last_finalized_block_number)
+        .unwrap()
+        .unwrap();
+    let post_global_state = chain
+        .store()
+        .get_block_post_global_state(&finalized_block_hash)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        import_store.get_last_valid_tip_block_hash().unwrap(),
+        finalized_block_hash
+    );
+    let import_db = import_store.begin_transaction();
+    check_block_post_state(&import_db, last_finalized_block_number, &post_global_state).unwrap();
+    for number in 0..=last_finalized_block_number {
+        assert_eq!(
+            import_db.get_block_hash_by_number(number).unwrap(),
+            chain.store().get_block_hash_by_number(number).unwrap()
+        );
+    }
+    let unfinalized_block_number = last_finalized_block_number + 1;
+    assert!(import_db
+        .get_block_hash_by_number(unfinalized_block_number)
+        .unwrap()
+        .is_none());
+
+    // Imported state is the finalized state, not the tip state
+    let imported_state = BlockStateDB::from_store(&import_db, RWConfig::readonly()).unwrap();
+    let imported_root = imported_state.calculate_root().unwrap();
+    let finalized_root: H256 = post_global_state.account().merkle_root().unpack();
+    assert_eq!(imported_root, finalized_root);
+    let finalized_balance = imported_state
+        .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, finalized_wallet.reg_address())
+        .unwrap();
+    assert_eq!(
+        finalized_balance,
+        CKBCapacity::from_layer1(1000 * CKB).to_layer2()
+    );
+    let unfinalized_account = imported_state
+        .get_account_id_by_script_hash(&unfinalized_wallet.account_script_hash())
+        .unwrap();
+    assert!(unfinalized_account.is_none());
+}
+
+fn deposit(chain: &TestChain, wallet: &EthWallet, capacity: u64) -> DepositInfoVec {
+    let deposit = DepositRequest::new_builder()
+        .capacity(capacity.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .amount(0.pack())
+        .script(wallet.account_script().to_owned())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.inner.generator().rollup_context(), deposit).pack())
+        .build()
+}
@@ -0,0 +1,353 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::testing_tool::chain::{ALWAYS_SUCCESS_CODE_HASH, ALWAYS_SUCCESS_PROGRAM};
+use crate::testing_tool::mock_ckb::MockCKB;
+
+use gw_common::H256;
+use gw_rpc_client::error::{get_jsonrpc_error_code, CkbRpcError};
+use gw_types::bytes::Bytes;
+use gw_types::core::{DepType, ScriptHashType};
+use gw_types::offchain::{RollupContext, TxStatus};
+use gw_types::packed::{
+    CellDep, CellInput, CellOutput, OutPoint, RawTransaction, Script, Transaction,
+};
+use gw_types::prelude::{Builder, Entity, Pack, PackVec, Unpack};
+
+const CKB: u64 = 100000000;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mock_ckb_send_transaction() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mock_ckb = MockCKB::new();
+    let always_success_out_point = {
+        let output = CellOutput::new_builder()
+            .capacity((1000 * CKB).pack())
+            .build();
+        let cells = vec![(output, ALWAYS_SUCCESS_PROGRAM.clone())];
+        mock_ckb.deploy_cells(cells).remove(0)
+    };
+    let always_success_dep = CellDep::new_builder()
+        .out_point(always_success_out_point)
+        .dep_type(DepType::Code.into())
+        .build();
+
+    let lock = random_always_success_script();
+    let rollup_type_script = random_always_success_script();
+    let cells = vec![
+        (cell_output(&lock, 1000 * CKB), Bytes::new()),
+        (cell_output(&lock, 1000 * CKB), Bytes::new()),
+        (
+            cell_output(&lock, 1000 * CKB)
+                .as_builder()
+                .type_(Some(rollup_type_script.clone()).pack())
+                .build(),
+            Bytes::from_static(b"rollup"),
+        ),
+    ];
+    let out_points = mock_ckb.deploy_cells(cells);
+
+    let rollup_context = RollupContext {
+        rollup_script_hash: Default::default(),
+        rollup_config: Default::default(),
+    };
+    let rpc_client = mock_ckb.rpc_client(rollup_type_script, rollup_context);
+
+    // Indexer
+    let rollup_cell = rpc_client.query_rollup_cell().await.unwrap().unwrap();
+    assert_eq!(rollup_cell.out_point, out_points[2]);
+    assert_eq!(rollup_cell.data, Bytes::from_static(b"rollup"));
+    let tip = rpc_client.get_tip().await.unwrap();
+    let tip_number: u64 = tip.number().unpack();
+    assert_eq!(tip_number, mock_ckb.tip_block_number());
+
+    // Send and commit
+    let tx = build_tx(&always_success_dep, &out_points[0], &lock, 999 * CKB);
+    let tx_hash = rpc_client.send_transaction(&tx).await.unwrap();
+    assert_eq!(tx_hash, H256::from(tx.hash()));
+    let status = rpc_client
+        .ckb
+        .get_transaction_status(tx_hash)
+        .await
+        .unwrap();
+    assert!(matches!(status, Some(TxStatus::Pending)));
+
+    mock_ckb.generate_block();
+    let status = rpc_client
+        .ckb
+        .get_transaction_status(tx_hash)
+        .await
+        .unwrap();
+    assert!(matches!(status, Some(TxStatus::Committed)));
+    let live_cells = mock_ckb.live_cells_by_lock(&lock);
+    let spent = live_cells.iter().any(|c| c.out_point == out_points[0]);
+    assert!(!spent);
+    let new_cell = OutPoint::new_builder()
+        .tx_hash(tx.hash().pack())
+        .index(0u32.pack())
+        .build();
+    assert!(live_cells.iter().any(|c| c.out_point == new_cell));
+
+    // Double spend
+    let tx = build_tx(&always_success_dep, &out_points[0], &lock, 998 * CKB);
+    let err = rpc_client.send_transaction(&tx).await.unwrap_err();
+    let expected_code = CkbRpcError::TransactionFailedToResolve as i64;
+    assert_eq!(get_jsonrpc_error_code(&err), Some(expected_code));
+
+    // Replace by fee
+    let tx = build_tx(&always_success_dep, &out_points[1], &lock, 999 * CKB);
+    rpc_client.send_transaction(&tx).await.unwrap();
+    let lower_fee_tx = build_tx(&always_success_dep, &out_points[1], &lock, 999 * CKB + 1);
+    let err = rpc_client
+        .send_transaction(&lower_fee_tx)
+        .await
+        .unwrap_err();
+    let expected_code = CkbRpcError::PoolRejectedRBF as i64;
+    assert_eq!(get_jsonrpc_error_code(&err), Some(expected_code));
+
+    let higher_fee_tx = build_tx(&always_success_dep, &out_points[1], &lock, 998 * CKB);
+    rpc_client.send_transaction(&higher_fee_tx).await.unwrap();
+    let status = rpc_client.ckb.get_transaction_status(tx.hash().into());
+    assert!(matches!(status.await.unwrap(), Some(TxStatus::Rejected)));
+    assert_eq!(mock_ckb.pool_size(), 1);
+
+    // Script verification
+    let unknown_lock = Script::new_builder()
+        .code_hash([1u8; 32].pack())
+        .hash_type(ScriptHashType::Data.into())
+        .build();
+    let out_point = {
+        let cells = vec![(cell_output(&unknown_lock, 1000 * CKB), Bytes::new())];
+        mock_ckb.deploy_cells(cells).remove(0)
+    };
+    let tx = build_tx(&always_success_dep, &out_point, &lock, 999 * CKB);
+    let err = rpc_client.dry_run_transaction(&tx).await.unwrap_err();
+    let expected_code = CkbRpcError::TransactionFailedToVerify as i64;
+    assert_eq!(get_jsonrpc_error_code(&err), Some(expected_code));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mock_ckb_since() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mock_ckb = MockCKB::new();
+    let always_success_dep = deploy_always_success(&mock_ckb);
+    let lock = random_always_success_script();
+    let cells = (0..5).map(|_| (cell_output(&lock, 1000 * CKB), Bytes::new()));
+    let out_points = mock_ckb.deploy_cells(cells.collect());
+    let rpc_client = mock_ckb.rpc_client(Script::default(), default_rollup_context());
+    let tip = mock_ckb.tip_block_number();
+    let verify_failed = Some(CkbRpcError::TransactionFailedToVerify as i64);
+
+    // Absolute block number, checked against the next block
+    let tx = build_tx(&always_success_dep, &out_points[0], &lock, 999 * CKB);
+    let err = rpc_client
+        .send_transaction(&with_since(&tx, tip + 2))
+        .await
+        .unwrap_err();
+    assert_eq!(get_jsonrpc_error_code(&err), verify_failed);
+    assert!(err.to_string().contains("Immature"), "{}", err);
+    rpc_client
+        .send_transaction(&with_since(&tx, tip + 1))
+        .await
+        .unwrap();
+
+    // Relative block number
+    const RELATIVE: u64 = 1 << 63;
+    let tx = build_tx(&always_success_dep, &out_points[1], &lock, 999 * CKB);
+    let relative_tx = with_since(&tx, RELATIVE | 2);
+    let err = rpc_client.send_transaction(&relative_tx).await.unwrap_err();
+    assert_eq!(get_jsonrpc_error_code(&err), verify_failed);
+    mock_ckb.generate_block();
+    rpc_client.send_transaction(&relative_tx).await.unwrap();
+
+    // Absolute timestamp in seconds, checked against the median time
+    const TIMESTAMP: u64 = 0x4000_0000_0000_0000;
+    let tx = build_tx(&always_success_dep, &out_points[2], &lock, 999 * CKB);
+    let future = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+    let err = rpc_client
+        .send_transaction(&with_since(&tx, TIMESTAMP | future))
+        .await
+        .unwrap_err();
+    assert_eq!(get_jsonrpc_error_code(&err), verify_failed);
+    rpc_client
+        .send_transaction(&with_since(&tx, TIMESTAMP | 1))
+        .await
+        .unwrap();
+
+    // Absolute epoch, the mock chain is still in epoch 0
+    const EPOCH: u64 = 0x2000_0000_0000_0000;
+    let epoch_1 = 1 | (1 << 40);
+    let tx = build_tx(&always_success_dep, &out_points[3], &lock, 999 * CKB);
+    let err = rpc_client
+        .send_transaction(&with_since(&tx, EPOCH | epoch_1))
+        .await
+        .unwrap_err();
+    assert_eq!(get_jsonrpc_error_code(&err), verify_failed);
+
+    // Invalid flags
+    let err = rpc_client
+        .send_transaction(&with_since(&tx, 1 << 56))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("InvalidSince"), "{}", err);
+
+    assert_eq!(mock_ckb.pool_size(), 3);
+    mock_ckb.generate_block();
+    assert_eq!(mock_ckb.pool_size(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mock_ckb_header_deps() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mock_ckb = MockCKB::new();
+    let always_success_dep = deploy_always_success(&mock_ckb);
+    let lock = random_always_success_script();
+    let out_point = {
+        let cells = vec![(cell_output(&lock, 1000 * CKB), Bytes::new())];
+        mock_ckb.deploy_cells(cells).remove(0)
+    };
+    let rpc_client = mock_ckb.rpc_client(Script::default(), default_rollup_context());
+    let tx = build_tx(&always_success_dep, &out_point, &lock, 999 * CKB);
+
+    let with_header_dep = |block_hash: [u8; 32]| {
+        let raw = tx.raw().as_builder();
+        let raw = raw.header_deps(vec![block_hash.pack()].pack()).build();
+        tx.clone().as_builder().raw(raw).build()
+    };
+    let unknown_header = with_header_dep(rand::random());
+    let err = rpc_client
+        .send_transaction(&unknown_header)
+        .await
+        .unwrap_err();
+    let expected_code = CkbRpcError::TransactionFailedToResolve as i64;
+    assert_eq!(get_jsonrpc_error_code(&err), Some(expected_code));
+    assert!(err.to_string().contains("InvalidHeader"), "{}", err);
+
+    let tip_hash: [u8; 32] = rpc_client.get_tip().await.unwrap().block_hash().unpack();
+    rpc_client
+        .send_transaction(&with_header_dep(tip_hash))
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_mock_ckb_fee_rate() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mock_ckb = MockCKB::new();
+    let always_success_dep = deploy_always_success(&mock_ckb);
+    let lock = random_always_success_script();
+    let cells = (0..3).map(|_| (cell_output(&lock, 1000 * CKB), Bytes::new()));
+    let out_points = mock_ckb.deploy_cells(cells.collect());
+    let rpc_client = mock_ckb.rpc_client(Script::default(), default_rollup_context());
+
+    // No transactions
+    let stats = rpc_client.ckb.get_fee_rate_statistics(None).await.unwrap();
+    assert!(stats.is_none());
+
+    // Min fee rate
+    let tx = build_tx(&always_success_dep, &out_points[0], &lock, 1000 * CKB);
+    let err = rpc_client.send_transaction(&tx).await.unwrap_err();
+    let expected_code = CkbRpcError::PoolRejectedTransactionByMinFeeRate as i64;
+    assert_eq!(get_jsonrpc_error_code(&err), Some(expected_code));
+
+    // The replacement must pay min_rbf_rate for its size on top of the fee of
+    // the replaced transaction.
+    let tx = build_tx(
+        &always_success_dep,
+        &out_points[0],
+        &lock,
+        1000 * CKB - 1000,
+    );
+    rpc_client.send_transaction(&tx).await.unwrap();
+    let size = tx.as_slice().len() as u64 + 4;
+    let rbf_fee = 1500 * size / 1000;
+    let capacity = 1000 * CKB - 1000 - rbf_fee;
+    let not_enough = build_tx(&always_success_dep, &out_points[0], &lock, capacity + 1);
+    let err = rpc_client.send_transaction(&not_enough).await.unwrap_err();
+    let expected_code = CkbRpcError::PoolRejectedRBF as i64;
+    assert_eq!(get_jsonrpc_error_code(&err), Some(expected_code));
+    let replacement = build_tx(&always_success_dep, &out_points[0], &lock, capacity);
+    rpc_client.send_transaction(&replacement).await.unwrap();
+
+    mock_ckb.generate_block();
+    let stats = rpc_client.ckb.get_fee_rate_statistics(None).await.unwrap();
+    let stats = stats.unwrap();
+    let fee_rate = (1000 + rbf_fee) * 1000 / size;
+    assert_eq!(stats.median.value(), fee_rate);
+    assert_eq!(stats.mean.value(), fee_rate);
+
+    // Pool transactions conflicting with committed ones are rejected
+    let tx = build_tx(&always_success_dep, &out_points[1], &lock, 999 * CKB);
+    rpc_client.send_transaction(&tx).await.unwrap();
+    mock_ckb.spend_cells(vec![out_points[1].clone()]);
+    assert_eq!(mock_ckb.pool_size(), 0);
+    let status = rpc_client.ckb.get_transaction_status(tx.hash().into());
+    assert!(matches!(status.await.unwrap(), Some(TxStatus::Rejected)));
+}
+
+fn deploy_always_success(mock_ckb: &MockCKB) -> CellDep {
+    let out_point = {
+        let output = CellOutput::new_builder()
+            .capacity((1000 * CKB).pack())
+            .build();
+        let cells = vec![(output, ALWAYS_SUCCESS_PROGRAM.clone())];
+        mock_ckb.deploy_cells(cells).remove(0)
+    };
+    CellDep::new_builder()
+        .out_point(out_point)
+        .dep_type(DepType::Code.into())
+        .build()
+}
+
+fn default_rollup_context() -> RollupContext {
+    RollupContext {
+        rollup_script_hash: Default::default(),
+        rollup_config: Default::default(),
+    }
+}
+
+fn with_since(tx: &Transaction, since: u64) -> Transaction {
+    let inputs = tx.raw().inputs().into_iter();
+    let inputs = inputs.map(|i| i.as_builder().since(since.pack()).build());
+    let raw = tx
+        .raw()
+        .as_builder()
+        .inputs(inputs.collect::<Vec<_>>().pack());
+    tx.clone().as_builder().raw(raw.build()).build()
+}
+
+fn random_always_success_script() -> Script {
+    let random_bytes: [u8; 20] = rand::random();
+    Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.clone().pack())
+        .hash_type(ScriptHashType::Data.into())
+        .args(random_bytes.to_vec().pack())
+        .build()
+}
+
+fn cell_output(lock: &Script, capacity: u64) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(lock.to_owned())
+        .build()
+}
+
+fn build_tx(cell_dep: &CellDep, input: &OutPoint, lock: &Script, capacity: u64) -> Transaction {
+    let input = CellInput::new_builder()
+        .previous_output(input.to_owned())
+        .build();
+    let raw = RawTransaction::new_builder()
+        .cell_deps(vec![cell_dep.to_owned()].pack())
+        .inputs(vec![input].pack())
+        .outputs(vec![cell_output(lock, capacity)].pack())
+        .outputs_data(vec![Bytes::new()].pack())
+        .build();
+    Transaction::new_builder().raw(raw).build()
+}
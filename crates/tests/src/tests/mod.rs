@@ -1,9 +1,13 @@
 mod chain;
 mod deposit_withdrawal;
+mod dynamic_config;
 mod export_import_block;
+mod export_import_snapshot;
 mod mem_block_repackage;
 mod meta_contract_args;
+mod mock_ckb;
 mod polyjuice_sender_recover;
+mod psc;
 mod restore_mem_block;
 mod restore_mem_pool_pending_withdrawal;
 mod rpc_server;
@@ -0,0 +1,388 @@
+use std::sync::Arc;
+
+use crate::testing_tool::bad_block::generate_bad_block_using_first_withdrawal;
+use crate::testing_tool::chain::{construct_block_with_timestamp, TEST_CHAIN_ID};
+use crate::testing_tool::psc::{wait_until, TestPSC};
+
+use gw_block_producer::block_producer::ComposeSubmitTxArgs;
+use gw_block_producer::challenger::{Challenger, ChallengerNewArgs};
+use gw_block_producer::cleaner::Cleaner;
+use gw_block_producer::sync_l1::{revert, sync_l1};
+use gw_block_producer::types::ChainEvent;
+use gw_chain::chain::SyncEvent;
+use gw_common::{state::State, H256};
+use gw_config::{BlockProducerConfig, DebugConfig, PscConfig, SubmitFeeRateConfig};
+use gw_store::state::{history::history_state::RWConfig, BlockStateDB};
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::core::Status;
+use gw_types::offchain::TxStatus;
+use gw_types::packed::{
+    GlobalState, RawWithdrawalRequest, Script, Transaction, WithdrawalRequest,
+    WithdrawalRequestExtra,
+};
+use gw_types::prelude::*;
+use gw_utils::local_cells::LocalCellsManager;
+use gw_utils::since::Since;
+use gw_utils::wallet::Wallet;
+
+const CKB: u64 = 100_000_000;
+
+/// Fee rates of the submit transaction of block 1 are 1000, 1500, 2250 and
+/// 3375. Replacing the 1000 one requires `1000 + min_rbf_rate(1500)`, so the
+/// first two replacements are rejected and the last one is the max.
+const FINAL_FEE_RATE: u64 = 3375;
+
+fn bump_fee_config() -> BlockProducerConfig {
+    BlockProducerConfig {
+        fee_rate: 1000,
+        // Only produce block 1 until it is confirmed.
+        psc_config: PscConfig {
+            local_limit: 1,
+            submitted_limit: 1,
+            block_interval_secs: 3600,
+        },
+        submit_fee_rate: SubmitFeeRateConfig {
+            min_fee_rate: 1000,
+            max_fee_rate: FINAL_FEE_RATE,
+            bump_after_secs: 1,
+            bump_percent: 50,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Wait until the first submit transaction of block 1 is replaced. Returns
+/// the first and the replacing transactions.
+async fn submit_and_bump_fee(psc: &TestPSC) -> (Transaction, Transaction) {
+    wait_until("submit tx of block 1", || psc.is_submit_tx_pending(1)).await;
+    let first_tx = psc.submit_tx(1).unwrap();
+    let bumped = || {
+        let fee_rate = psc.context.submit_history.lock().unwrap().fee_rate(1);
+        fee_rate == Some(FINAL_FEE_RATE) && psc.is_submit_tx_pending(1)
+    };
+    wait_until("replacement of submit tx", bumped).await;
+    let replacing_tx = psc.submit_tx(1).unwrap();
+    assert_ne!(first_tx.hash(), replacing_tx.hash());
+    assert_eq!(psc.mock_ckb.pool_tx_hashes(), vec![replacing_tx.hash()]);
+
+    let status = psc
+        .rpc_client
+        .ckb
+        .get_transaction_status(first_tx.hash().into());
+    assert!(matches!(status.await.unwrap(), Some(TxStatus::Rejected)));
+    assert_eq!(psc.last_confirmed_block_number(), 0);
+    (first_tx, replacing_tx)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_psc_bump_fee_on_pending() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let psc = TestPSC::setup(bump_fee_config()).await;
+    let _psc_task = psc.spawn();
+    let (_, replacing_tx) = submit_and_bump_fee(&psc).await;
+
+    psc.mock_ckb.generate_block();
+    wait_until("block 1 confirmed", || {
+        psc.last_confirmed_block_number() == 1
+    })
+    .await;
+    assert_eq!(psc.submit_tx(1).unwrap().hash(), replacing_tx.hash());
+    let stats = psc.rpc_client.ckb.get_fee_rate_statistics(Some(1)).await;
+    let stats = stats.unwrap().expect("fee rate statistics");
+    assert!(stats.median.value() >= FINAL_FEE_RATE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_psc_replaced_submit_tx_committed() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let psc = TestPSC::setup(bump_fee_config()).await;
+    let _psc_task = psc.spawn();
+    let (first_tx, replacing_tx) = submit_and_bump_fee(&psc).await;
+
+    // Committed by a node which didn't see the replacing transaction.
+    psc.mock_ckb.commit_transactions(vec![first_tx.clone()]);
+    wait_until("block 1 confirmed", || {
+        psc.last_confirmed_block_number() == 1
+    })
+    .await;
+    assert_eq!(psc.submit_tx(1).unwrap().hash(), first_tx.hash());
+
+    let status = psc
+        .rpc_client
+        .ckb
+        .get_transaction_status(replacing_tx.hash().into())
+        .await;
+    assert!(matches!(status.unwrap(), Some(TxStatus::Rejected)));
+}
+
+fn lifecycle_config() -> BlockProducerConfig {
+    BlockProducerConfig {
+        fee_rate: 1000,
+        psc_config: PscConfig {
+            local_limit: 1,
+            submitted_limit: 1,
+            block_interval_secs: 1,
+        },
+        ..Default::default()
+    }
+}
+
+/// Generate L1 blocks until the L2 block is confirmed.
+async fn wait_confirmed(psc: &TestPSC, block_number: u64) {
+    let what = format!("block {} confirmed", block_number);
+    wait_until(&what, || {
+        psc.mock_ckb.generate_block();
+        psc.last_confirmed_block_number() >= block_number
+    })
+    .await;
+}
+
+async fn rollup_global_state(psc: &TestPSC) -> GlobalState {
+    let rollup_cell = psc.rpc_client.query_rollup_cell().await.unwrap();
+    GlobalState::from_slice(&rollup_cell.expect("rollup cell").data).unwrap()
+}
+
+/// Send the transaction of the challenger for the L1 tip, commit it and
+/// update the chain with it.
+async fn challenger_commit(psc: &TestPSC, challenger: &mut Challenger) {
+    let tip_number = psc.mock_ckb.tip_block_number();
+    let block = psc.rpc_client.get_block_by_number(tip_number).await;
+    let event = ChainEvent::NewBlock {
+        block: block.unwrap().expect("tip block"),
+    };
+    challenger.handle_event(event).await.unwrap();
+
+    let tx_hashes = psc.mock_ckb.pool_tx_hashes();
+    assert_eq!(tx_hashes.len(), 1);
+    psc.mock_ckb.generate_block();
+    let tx_hash = ckb_fixed_hash::H256(tx_hashes[0]);
+    psc.context
+        .chain_updater
+        .update_single(&tx_hash)
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_psc_produce_submit_confirm_deposit() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let psc = TestPSC::setup(lifecycle_config()).await;
+    psc.collect_deposits_from_l1().await;
+    let alice_script = psc.eoa_script([42u8; 20]);
+    let deposit = psc.deploy_deposit(alice_script.clone(), 4000 * CKB);
+    psc.mock_ckb.generate_block();
+
+    let _psc_task = psc.spawn();
+    wait_confirmed(&psc, 1).await;
+
+    let deposits = psc.store.get_block_deposit_info_vec(1).unwrap();
+    assert_eq!(deposits.len(), 1);
+    let deposit_info = deposits.get(0).unwrap();
+    let out_point = deposit_info.cell().out_point();
+    assert_eq!(out_point.as_slice(), deposit.out_point.as_slice());
+    assert!(psc
+        .mock_ckb
+        .live_cells_by_lock(&deposit.output.lock())
+        .is_empty());
+
+    let submit_tx = psc.submit_tx(1).unwrap();
+    let status = psc
+        .rpc_client
+        .ckb
+        .get_transaction_status(submit_tx.hash().into())
+        .await;
+    assert!(matches!(status.unwrap(), Some(TxStatus::Committed)));
+
+    let block_hash = psc.store.get_block_hash_by_number(1).unwrap().unwrap();
+    let global_state = psc.store.get_block_post_global_state(&block_hash).unwrap();
+    let committed_global_state = {
+        // Later blocks may be confirmed too.
+        let tx = psc.rpc_client.ckb.get_transaction(submit_tx.hash().into());
+        let tx = tx.await.unwrap().expect("submit tx");
+        tx.raw().outputs_data().get(0).unwrap().raw_data()
+    };
+    assert_eq!(committed_global_state, global_state.unwrap().as_bytes());
+
+    let db = psc.store.begin_transaction();
+    let state = BlockStateDB::from_store(&db, RWConfig::readonly()).unwrap();
+    let alice_id = state
+        .get_account_id_by_script_hash(&alice_script.hash().into())
+        .unwrap();
+    assert!(alice_id.is_some());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_challenge_and_revert_bad_block() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let psc = TestPSC::setup(lifecycle_config()).await;
+    psc.collect_deposits_from_l1().await;
+    let alice_script = psc.eoa_script([42u8; 20]);
+    psc.deploy_deposit(alice_script.clone(), 4000 * CKB);
+    psc.mock_ckb.generate_block();
+
+    // Confirm the deposit and finalize its custodian.
+    let psc_task = psc.spawn();
+    let finality_blocks: u64 = {
+        let config = &psc.rollup_context.rollup_config;
+        config.finality_blocks().unpack()
+    };
+    wait_confirmed(&psc, finality_blocks + 1).await;
+    drop(psc_task);
+
+    // The bad block is submitted by another block producer, revert local
+    // blocks that aren't confirmed.
+    sync_l1(&*psc.context).await.unwrap();
+    let last_confirmed = psc.last_confirmed_block_number();
+    {
+        let store_tx = psc.store.begin_transaction();
+        revert(&*psc.context, &store_tx, last_confirmed)
+            .await
+            .unwrap();
+        store_tx.commit().unwrap();
+    }
+
+    let owner_lock = Script::default();
+    let withdrawal = {
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity((1000 * CKB).pack())
+            .account_script_hash(alice_script.hash().pack())
+            .sudt_script_hash(H256::zero().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .chain_id(TEST_CHAIN_ID.pack())
+            .build();
+        let withdrawal = WithdrawalRequest::new_builder().raw(raw).build();
+        WithdrawalRequestExtra::new_builder()
+            .request(withdrawal)
+            .owner_lock(owner_lock.clone())
+            .build()
+    };
+    // L2 block timestamp < since <= L1 median time.
+    let timestamp = {
+        let tip_hash = psc.rpc_client.get_tip().await.unwrap().block_hash();
+        let median_time = psc.rpc_client.get_block_median_time(tip_hash.unpack());
+        median_time.await.unwrap().unwrap().as_millis() as u64
+    };
+    let (bad_block, bad_global_state) = {
+        let chain = psc.chain.lock().await;
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        let tip_hash = psc.store.get_last_valid_tip_block_hash().unwrap();
+        let local_cells_manager = LocalCellsManager::default();
+        mem_pool
+            .notify_new_tip(tip_hash, &local_cells_manager)
+            .await
+            .unwrap();
+        mem_pool.push_withdrawal_request(withdrawal).await.unwrap();
+        let block_result = construct_block_with_timestamp(
+            &chain,
+            &mut mem_pool,
+            Default::default(),
+            timestamp,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(block_result.block.withdrawals().len(), 1);
+        generate_bad_block_using_first_withdrawal(
+            &chain,
+            block_result.block,
+            block_result.global_state,
+        )
+    };
+    let withdrawal_extras = bad_block.withdrawals().into_iter().map(|w| {
+        WithdrawalRequestExtra::new_builder()
+            .request(w)
+            .owner_lock(owner_lock.clone())
+            .build()
+    });
+    let local_cells_manager = LocalCellsManager::default();
+    let bad_tx = psc
+        .context
+        .block_producer
+        .compose_submit_tx(ComposeSubmitTxArgs {
+            deposit_cells: Default::default(),
+            block: bad_block.clone(),
+            global_state: bad_global_state,
+            since: Since::new_timestamp_seconds(timestamp / 1000 + 1),
+            withdrawal_extras: withdrawal_extras.collect(),
+            local_cells_manager: &local_cells_manager,
+            fee_rate: psc.config.fee_rate,
+        })
+        .await
+        .unwrap();
+    psc.mock_ckb.commit_transactions(vec![bad_tx]);
+
+    sync_l1(&*psc.context).await.unwrap();
+    {
+        let chain = psc.chain.lock().await;
+        let event = chain.last_sync_event();
+        assert!(matches!(event, SyncEvent::BadBlock { .. }), "{:?}", event);
+    }
+
+    let wallet = Wallet::from_config(&psc.wallet_config).unwrap();
+    let cleaner = Cleaner::new(
+        psc.rpc_client.clone(),
+        psc.ckb_genesis_info.clone(),
+        Wallet::from_config(&psc.wallet_config).unwrap(),
+        psc.config.fee_rate,
+    );
+    let mut challenger = Challenger::new(ChallengerNewArgs {
+        rollup_context: psc.rollup_context.clone(),
+        rpc_client: psc.rpc_client.clone(),
+        wallet,
+        config: psc.config.clone(),
+        debug_config: DebugConfig::default(),
+        builtin_load_data: Default::default(),
+        ckb_genesis_info: psc.ckb_genesis_info.clone(),
+        chain: psc.chain.clone(),
+        tests_control: None,
+        cleaner: Arc::new(cleaner),
+        offchain_mock_context: psc.offchain_mock_context().await,
+        contracts_dep_manager: psc.contracts_dep_manager.clone(),
+    });
+
+    // Challenge the bad block.
+    challenger_commit(&psc, &mut challenger).await;
+    {
+        let chain = psc.chain.lock().await;
+        let event = chain.last_sync_event();
+        assert!(
+            matches!(event, SyncEvent::WaitChallenge { .. }),
+            "{:?}",
+            event
+        );
+    }
+    let status: u8 = rollup_global_state(&psc).await.status().into();
+    assert_eq!(status, u8::from(Status::Halting));
+
+    // Challenge maturity blocks of the rollup config is 0, revert right away.
+    challenger_commit(&psc, &mut challenger).await;
+    {
+        let chain = psc.chain.lock().await;
+        assert!(chain.last_sync_event().is_success());
+    }
+    let global_state = rollup_global_state(&psc).await;
+    let status: u8 = global_state.status().into();
+    assert_eq!(status, u8::from(Status::Running));
+
+    let tip_block = psc.store.get_last_valid_tip_block().unwrap();
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    assert_eq!(tip_block_number, last_confirmed);
+    let tip_block_hash: [u8; 32] = global_state.tip_block_hash().unpack();
+    assert_eq!(tip_block_hash, tip_block.hash());
+    let db = psc.store.begin_transaction();
+    let reverted_block_root: H256 = global_state.reverted_block_root().unpack();
+    assert_eq!(
+        reverted_block_root,
+        db.get_reverted_block_smt_root().unwrap()
+    );
+    let reverted_block_hashes = db.get_reverted_block_hashes().unwrap();
+    let bad_block_hash: H256 = bad_block.hash().into();
+    assert!(reverted_block_hashes.contains(&bad_block_hash));
+}
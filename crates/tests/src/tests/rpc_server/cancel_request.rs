@@ -0,0 +1,165 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, state::State, H256};
+use gw_generator::account_lock_manage::cancel_request_message;
+use gw_jsonrpc_types::godwoken::{EvictReason, L2TransactionStatus};
+use gw_store::state::traits::JournalDB;
+use gw_types::{
+    packed::{RawL2Transaction, Script},
+    prelude::{Builder, Entity, Pack},
+};
+use sha3::{Digest, Keccak256};
+
+use crate::testing_tool::{
+    chain::TestChain,
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount},
+    rpc_server::{wait_tx_committed, RPCServer},
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_cancel_request() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let wallet = EthWallet::random(chain.rollup_type_hash());
+    let account_id = wallet
+        .create_account(&mut state, 1000000u128.into())
+        .unwrap();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(account_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .args(deploy_args.pack())
+        .build();
+    let tx0 = {
+        let raw_tx = raw_tx.clone().as_builder().nonce(0u32.pack()).build();
+        wallet.sign_polyjuice_tx(&state, raw_tx).unwrap()
+    };
+    let tx1 = {
+        let raw_tx = raw_tx.as_builder().nonce(1u32.pack()).build();
+        wallet.sign_polyjuice_tx(&state, raw_tx).unwrap()
+    };
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+
+    // Nonce 1 tx stays in the queue of request submitter until nonce 0 tx is pushed
+    let tx1_hash = rpc_server
+        .submit_l2transaction(&tx1)
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(rpc_server.is_request_in_queue(tx1_hash).await.unwrap());
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let sign_cancel = |wallet: &EthWallet, hash: &H256, expire_at: u64| {
+        let message = cancel_request_message(&chain.rollup_type_hash(), hash, expire_at);
+        let mut hasher = Keccak256::new();
+        hasher.update("\x19Ethereum Signed Message:\n32");
+        hasher.update(message.as_slice());
+        wallet.sign_message(hasher.finalize().into()).unwrap()
+    };
+
+    // Only the sender can cancel
+    let other_wallet = EthWallet::random(chain.rollup_type_hash());
+    let expire_at = now + 60;
+    let err = rpc_server
+        .cancel_request(
+            tx1_hash,
+            expire_at,
+            &sign_cancel(&other_wallet, &tx1_hash, expire_at),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid cancellation signature"));
+    assert!(rpc_server.is_request_in_queue(tx1_hash).await.unwrap());
+
+    // The signature is bound to the expiry time
+    let signature = sign_cancel(&wallet, &tx1_hash, expire_at);
+    let err = rpc_server
+        .cancel_request(tx1_hash, expire_at + 1, &signature)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid cancellation signature"));
+    let expired_at = now - 1;
+    let err = rpc_server
+        .cancel_request(
+            tx1_hash,
+            expired_at,
+            &sign_cancel(&wallet, &tx1_hash, expired_at),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("cancellation expired"));
+    assert!(rpc_server.is_request_in_queue(tx1_hash).await.unwrap());
+
+    rpc_server
+        .cancel_request(tx1_hash, expire_at, &signature)
+        .await
+        .unwrap();
+    assert!(!rpc_server.is_request_in_queue(tx1_hash).await.unwrap());
+    let tx1_status = rpc_server.get_transaction(tx1_hash).await.unwrap().unwrap();
+    assert_eq!(tx1_status.status, L2TransactionStatus::Evicted);
+    assert_eq!(tx1_status.evicted_reason, Some(EvictReason::Cancelled));
+
+    // The cancellation can't be replayed, even if the tx is submitted again
+    rpc_server.submit_l2transaction(&tx1).await.unwrap();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let err = rpc_server
+        .cancel_request(tx1_hash, expire_at, &signature)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("cancellation is already used"));
+    assert!(rpc_server.is_request_in_queue(tx1_hash).await.unwrap());
+
+    let expire_at = now + 120;
+    rpc_server
+        .cancel_request(
+            tx1_hash,
+            expire_at,
+            &sign_cancel(&wallet, &tx1_hash, expire_at),
+        )
+        .await
+        .unwrap();
+    assert!(!rpc_server.is_request_in_queue(tx1_hash).await.unwrap());
+
+    // The cancelled tx isn't pushed after nonce 0 tx
+    let tx0_hash = rpc_server
+        .submit_l2transaction(&tx0)
+        .await
+        .unwrap()
+        .unwrap();
+    wait_tx_committed(&chain, &tx0_hash, Duration::from_secs(30))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    {
+        let mem_pool = chain.mem_pool().await;
+        assert!(!mem_pool.mem_block().txs_set().contains(&tx1_hash));
+    }
+    let state = mem_pool_state.load_state_db();
+    assert_eq!(state.get_nonce(account_id).unwrap(), 1);
+
+    // A cancelled tx can be submitted again
+    let tx1_hash = rpc_server
+        .submit_l2transaction(&tx1)
+        .await
+        .unwrap()
+        .unwrap();
+    wait_tx_committed(&chain, &tx1_hash, Duration::from_secs(30))
+        .await
+        .unwrap();
+}
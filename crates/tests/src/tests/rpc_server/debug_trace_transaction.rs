@@ -0,0 +1,197 @@
+use ckb_types::prelude::{Builder, Entity};
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
+    h256_ext::H256Ext,
+    state::{build_account_field_key, State, GW_ACCOUNT_NONCE_TYPE},
+    H256,
+};
+use gw_config::RPCMethods;
+use gw_generator::account_lock_manage::secp256k1::Secp256k1Eth;
+use gw_jsonrpc_types::godwoken::{LogItem, TransactionTrace};
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{
+    packed::{
+        CreateAccount, DepositInfoVec, DepositRequest, Fee, L2Transaction, MetaContractArgs,
+        RawL2Transaction, Script,
+    },
+    prelude::{Pack, Unpack},
+};
+
+use crate::testing_tool::{
+    chain::{into_deposit_info_cell, TestChain},
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount},
+    rpc_server::RPCServer,
+};
+
+const META_CONTRACT_ACCOUNT_ID: u32 = RESERVED_ACCOUNT_ID;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_trace_second_tx_of_block() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script.clone()).await;
+    let rpc_server = {
+        let mut args = RPCServer::default_registry_args(&chain.inner, rollup_type_script, None);
+        args.server_config.enable_methods.insert(RPCMethods::Debug);
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+
+    // Deposit test account
+    const DEPOSIT_CAPACITY: u64 = 1000000 * 10u64.pow(8);
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let deposit = DepositRequest::new_builder()
+        .capacity(DEPOSIT_CAPACITY.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .amount(0.pack())
+        .script(test_wallet.account_script().to_owned())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.inner.generator().rollup_context(), deposit).pack())
+        .build();
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+
+    // Create polyjuice account by meta contract
+    let mem_pool_state = chain.mem_pool_state().await;
+    let state = mem_pool_state.load_state_db();
+    let test_account_id = state
+        .get_account_id_by_script_hash(&test_wallet.account_script_hash())
+        .unwrap()
+        .unwrap();
+    let meta_contract_script_hash = state.get_script_hash(META_CONTRACT_ACCOUNT_ID).unwrap();
+    let fee = Fee::new_builder()
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .amount(0u128.pack())
+        .build();
+    let create_polyjuice = CreateAccount::new_builder()
+        .fee(fee)
+        .script(PolyjuiceAccount::build_script(chain.rollup_type_hash()))
+        .build();
+    let args = MetaContractArgs::new_builder()
+        .set(create_polyjuice)
+        .build();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(META_CONTRACT_ACCOUNT_ID.pack())
+        .nonce(0u32.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    let signing_message = Secp256k1Eth::eip712_signing_message(
+        chain.chain_id(),
+        &raw_tx,
+        test_wallet.reg_address().to_owned(),
+        meta_contract_script_hash,
+    )
+    .unwrap();
+    let sign = test_wallet.sign_message(signing_message.into()).unwrap();
+    let tx = L2Transaction::new_builder()
+        .raw(raw_tx)
+        .signature(sign.pack())
+        .build();
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(tx).unwrap();
+    }
+    chain
+        .produce_block(Default::default(), vec![])
+        .await
+        .unwrap();
+
+    // Deploy erc20 twice in a block
+    let state = mem_pool_state.load_state_db();
+    let polyjuice_script_hash = PolyjuiceAccount::build_script(chain.rollup_type_hash()).hash();
+    let polyjuice_account_id = state
+        .get_account_id_by_script_hash(&polyjuice_script_hash.into())
+        .unwrap()
+        .unwrap();
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let txs: Vec<_> = (1..3u32)
+        .map(|nonce| {
+            let raw_tx = RawL2Transaction::new_builder()
+                .chain_id(chain.chain_id().pack())
+                .from_id(test_account_id.pack())
+                .to_id(polyjuice_account_id.pack())
+                .nonce(nonce.pack())
+                .args(deploy_args.pack())
+                .build();
+            test_wallet.sign_polyjuice_tx(&state, raw_tx).unwrap()
+        })
+        .collect();
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        for tx in txs.iter() {
+            mem_pool.push_transaction(tx.to_owned()).unwrap();
+        }
+    }
+    let tx_hash: H256 = txs[1].hash().into();
+
+    let check_trace = |trace: &TransactionTrace, expected_logs: Vec<LogItem>| {
+        assert_eq!(trace.exit_code.value(), 0);
+        // Logs of the first tx aren't included
+        assert!(!trace.logs.is_empty());
+        assert_eq!(trace.logs, expected_logs);
+
+        let nonce_key = build_account_field_key(test_account_id, GW_ACCOUNT_NONCE_TYPE);
+        let nonce_write = trace
+            .state_writes
+            .iter()
+            .find(|w| w.key == ckb_fixed_hash::H256(nonce_key.into()))
+            .expect("nonce write");
+        assert_eq!(
+            nonce_write.old_value,
+            ckb_fixed_hash::H256(H256::from_u32(2).into())
+        );
+        assert_eq!(
+            nonce_write.new_value,
+            ckb_fixed_hash::H256(H256::from_u32(3).into())
+        );
+    };
+
+    // Trace the mem pool tx
+    let trace = rpc_server
+        .debug_trace_transaction(tx_hash)
+        .await
+        .unwrap()
+        .expect("mem pool tx trace");
+    assert!(trace.block_number.is_none());
+    let receipt = chain
+        .store()
+        .get_snapshot()
+        .get_mem_pool_transaction_receipt(&tx_hash)
+        .unwrap()
+        .expect("mem pool tx receipt");
+    check_trace(&trace, receipt.logs().into_iter().map(Into::into).collect());
+
+    // Trace the committed tx
+    chain
+        .produce_block(Default::default(), vec![])
+        .await
+        .unwrap();
+    let block = chain.last_valid_block();
+    let block_txs: Vec<H256> = block
+        .transactions()
+        .into_iter()
+        .map(|tx| tx.hash().into())
+        .collect();
+    assert_eq!(block_txs.iter().position(|h| *h == tx_hash), Some(1));
+
+    let trace = rpc_server
+        .debug_trace_transaction(tx_hash)
+        .await
+        .unwrap()
+        .expect("committed tx trace");
+    assert_eq!(
+        trace.block_number.map(|n| n.value()),
+        Some(block.raw().number().unpack())
+    );
+    let receipt = chain
+        .store()
+        .get_snapshot()
+        .get_transaction_receipt(&tx_hash)
+        .unwrap()
+        .expect("tx receipt");
+    check_trace(&trace, receipt.logs().into_iter().map(Into::into).collect());
+}
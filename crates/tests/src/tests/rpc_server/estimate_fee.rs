@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use gw_common::builtins::CKB_SUDT_ACCOUNT_ID;
+use gw_store::state::traits::JournalDB;
+use gw_types::{
+    packed::{RawL2Transaction, Script},
+    prelude::{Builder, Entity, Pack},
+};
+
+use crate::testing_tool::{
+    chain::TestChain,
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount},
+    rpc_server::{wait_tx_committed, RPCServer},
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_estimate_fee_on_new_tip() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let wallet = EthWallet::random(chain.rollup_type_hash());
+    let account_id = wallet
+        .create_account(&mut state, 10000000u128.into())
+        .unwrap();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    let gas_price = 3;
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18)
+        .gas_price(gas_price)
+        .finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(account_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .args(deploy_args.pack())
+        .build();
+    let tx0 = {
+        let raw_tx = raw_tx.clone().as_builder().nonce(0u32.pack()).build();
+        wallet.sign_polyjuice_tx(&state, raw_tx).unwrap()
+    };
+    let tx1 = {
+        let raw_tx = raw_tx.as_builder().nonce(1u32.pack()).build();
+        wallet.sign_polyjuice_tx(&state, raw_tx).unwrap()
+    };
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+
+    // No txs in recent blocks
+    let estimate = rpc_server.estimate_fee(&tx0).await.unwrap();
+    assert_eq!(estimate.low_fee_rate.value(), 0);
+    assert_eq!(estimate.medium_fee_rate.value(), 0);
+    assert_eq!(estimate.high_fee_rate.value(), 0);
+
+    let tx0_hash = rpc_server
+        .submit_l2transaction(&tx0)
+        .await
+        .unwrap()
+        .unwrap();
+    wait_tx_committed(&chain, &tx0_hash, Duration::from_secs(30))
+        .await
+        .unwrap();
+    chain
+        .produce_block(Default::default(), vec![])
+        .await
+        .unwrap();
+
+    // Fee rates of the new tip block are picked up
+    let estimate = rpc_server.estimate_fee(&tx1).await.unwrap();
+    assert_eq!(estimate.low_fee_rate.value(), gas_price);
+    assert_eq!(estimate.medium_fee_rate.value(), gas_price);
+    assert_eq!(estimate.high_fee_rate.value(), gas_price);
+
+    // And an empty new tip block doesn't drop them
+    chain
+        .produce_block(Default::default(), vec![])
+        .await
+        .unwrap();
+    let estimate = rpc_server.estimate_fee(&tx1).await.unwrap();
+    assert_eq!(estimate.medium_fee_rate.value(), gas_price);
+}
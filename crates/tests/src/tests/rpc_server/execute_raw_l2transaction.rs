@@ -1,11 +1,16 @@
 use ckb_types::prelude::{Builder, Entity};
 use gw_common::{
     builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
+    h256_ext::H256Ext,
     registry_address::RegistryAddress,
-    state::State,
+    state::{build_account_field_key, State, GW_ACCOUNT_NONCE_TYPE},
     H256,
 };
 use gw_generator::account_lock_manage::secp256k1::Secp256k1Eth;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::JsonBytes,
+    godwoken::{BalanceOverride, KVPair, NonceOverride, StateOverride},
+};
 use gw_store::state::{history::history_state::RWConfig, traits::JournalDB, BlockStateDB};
 use gw_types::{
     bytes::Bytes,
@@ -79,6 +84,119 @@ async fn test_polyjuice_erc20_tx() {
     assert_eq!(system_log.status_code, 0);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_state_override() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let test_account_id = test_wallet
+        .create_account(&mut state, 0u128.into())
+        .unwrap();
+
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+
+    let total_supply = {
+        let state = mem_pool_state.load_state_db();
+        state.get_sudt_total_supply(CKB_SUDT_ACCOUNT_ID).unwrap()
+    };
+
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+
+    // Sender has no balance to pay for the tx
+    let err = rpc_server
+        .execute_raw_l2transaction(&raw_tx, None, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("check balance err"), "{}", err);
+
+    // Override balance
+    let balance_override = BalanceOverride {
+        sudt_id: CKB_SUDT_ACCOUNT_ID.into(),
+        address: JsonBytes::from_vec(test_wallet.reg_address().to_bytes()),
+        balance: 1000000u128.into(),
+    };
+    let state_override = StateOverride {
+        balances: vec![balance_override.clone()],
+        ..Default::default()
+    };
+    let run_result = rpc_server
+        .execute_raw_l2transaction_with_state_override(&raw_tx, &state_override)
+        .await
+        .unwrap();
+    let logs = run_result.logs.into_iter().map(Into::into);
+    let system_log = PolyjuiceSystemLog::parse_logs(logs).unwrap();
+    assert_eq!(system_log.status_code, 0);
+    let created_address = system_log.created_address;
+
+    // Override nonce, the created address depends on the sender nonce
+    let raw_tx = raw_tx.as_builder().nonce(5u32.pack()).build();
+    let state_override = StateOverride {
+        nonces: vec![NonceOverride {
+            account_id: test_account_id.into(),
+            nonce: 5u32.into(),
+        }],
+        balances: vec![balance_override.clone()],
+        ..Default::default()
+    };
+    let run_result = rpc_server
+        .execute_raw_l2transaction_with_state_override(&raw_tx, &state_override)
+        .await
+        .unwrap();
+    let logs = run_result.logs.into_iter().map(Into::into);
+    let system_log = PolyjuiceSystemLog::parse_logs(logs).unwrap();
+    assert_eq!(system_log.status_code, 0);
+    assert_ne!(system_log.created_address, created_address);
+    let nonce_created_address = system_log.created_address;
+
+    // Override the nonce storage key directly
+    let nonce_key = build_account_field_key(test_account_id, GW_ACCOUNT_NONCE_TYPE);
+    let state_override = StateOverride {
+        kv: vec![KVPair {
+            k: ckb_fixed_hash::H256(nonce_key.into()),
+            v: ckb_fixed_hash::H256(H256::from_u32(5).into()),
+        }],
+        balances: vec![balance_override],
+        ..Default::default()
+    };
+    let run_result = rpc_server
+        .execute_raw_l2transaction_with_state_override(&raw_tx, &state_override)
+        .await
+        .unwrap();
+    let logs = run_result.logs.into_iter().map(Into::into);
+    let system_log = PolyjuiceSystemLog::parse_logs(logs).unwrap();
+    assert_eq!(system_log.status_code, 0);
+    assert_eq!(system_log.created_address, nonce_created_address);
+
+    // Real state is untouched
+    let state = mem_pool_state.load_state_db();
+    let balance = state
+        .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, test_wallet.reg_address())
+        .unwrap();
+    assert_eq!(balance, U256::zero());
+    assert_eq!(state.get_nonce(test_account_id).unwrap(), 0);
+    assert_eq!(
+        state.get_sudt_total_supply(CKB_SUDT_ACCOUNT_ID).unwrap(),
+        total_supply
+    );
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn test_polyjuice_tx_from_id_zero() {
     let _ = env_logger::builder().is_test(true).try_init();
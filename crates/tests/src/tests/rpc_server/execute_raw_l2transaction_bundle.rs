@@ -0,0 +1,92 @@
+use ckb_types::prelude::{Builder, Entity};
+use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, state::State};
+use gw_store::state::traits::JournalDB;
+use gw_types::{
+    packed::{RawL2Transaction, Script},
+    prelude::Pack,
+};
+
+use crate::testing_tool::{
+    chain::TestChain,
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount, PolyjuiceSystemLog},
+    rpc_server::RPCServer,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_bundle_with_failed_step() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let test_account_id = test_wallet
+        .create_account(&mut state, 1000000u128.into())
+        .unwrap();
+
+    // Can't pay for the tx
+    let poor_wallet = EthWallet::random(chain.rollup_type_hash());
+    let poor_account_id = poor_wallet
+        .create_account(&mut state, 0u128.into())
+        .unwrap();
+
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let deploy_tx = |from_id: u32, nonce: u32| {
+        RawL2Transaction::new_builder()
+            .chain_id(chain.chain_id().pack())
+            .from_id(from_id.pack())
+            .to_id(polyjuice_account.id.pack())
+            .nonce(nonce.pack())
+            .args(deploy_args.pack())
+            .build()
+    };
+    let raw_txs = vec![
+        deploy_tx(test_account_id, 0),
+        deploy_tx(poor_account_id, 0),
+        deploy_tx(test_account_id, 1),
+    ];
+
+    // Stop at the failed step
+    let steps = rpc_server
+        .execute_raw_l2transaction_bundle(&raw_txs, false)
+        .await
+        .unwrap();
+    assert_eq!(steps.len(), 2);
+    assert!(steps[0].is_success());
+    let err = steps[1].error.as_ref().expect("step error");
+    assert!(err.contains("check balance err"), "{}", err);
+
+    // Continue on failure, the last step sees the state of the first one
+    let steps = rpc_server
+        .execute_raw_l2transaction_bundle(&raw_txs, true)
+        .await
+        .unwrap();
+    assert_eq!(steps.len(), 3);
+    assert!(steps[0].is_success());
+    assert!(!steps[1].is_success());
+    assert!(steps[1].logs.is_empty());
+    assert!(steps[2].is_success());
+
+    let first_log =
+        PolyjuiceSystemLog::parse_logs(steps[0].logs.clone().into_iter().map(Into::into)).unwrap();
+    let last_log =
+        PolyjuiceSystemLog::parse_logs(steps[2].logs.clone().into_iter().map(Into::into)).unwrap();
+    assert_eq!(first_log.status_code, 0);
+    assert_eq!(last_log.status_code, 0);
+    // Created addresses depend on the sender nonce increased by the first step
+    assert_ne!(first_log.created_address, last_log.created_address);
+
+    // Nothing is committed
+    let state = mem_pool_state.load_state_db();
+    assert_eq!(state.get_nonce(test_account_id).unwrap(), 0);
+}
@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, H256};
+use gw_jsonrpc_types::godwoken::DepositStatus;
+use gw_types::{
+    core::ScriptHashType,
+    offchain::DepositInfo,
+    packed::{self, DepositInfoVec, DepositRequest, OutPoint, Script},
+    prelude::{Builder, Entity, Pack, Unpack},
+};
+use gw_utils::local_cells::LocalCellsManager;
+
+use crate::testing_tool::{
+    chain::{into_deposit_info_cell, TestChain},
+    eth_wallet::EthWallet,
+    mem_pool_provider::DummyMemPoolProvider,
+    mock_ckb::MockCKB,
+    rpc_server::RPCServer,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_get_deposit() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    const DEPOSIT_CAPACITY: u64 = 1000000 * 10u64.pow(8);
+    let deposits: Vec<DepositInfo> = (0..2)
+        .map(|i| {
+            let wallet = EthWallet::random(chain.rollup_type_hash());
+            let deposit = DepositRequest::new_builder()
+                .capacity((DEPOSIT_CAPACITY + i).pack())
+                .sudt_script_hash(H256::zero().pack())
+                .amount(0.pack())
+                .script(wallet.account_script().to_owned())
+                .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+                .build();
+            into_deposit_info_cell(chain.inner.generator().rollup_context(), deposit)
+        })
+        .collect();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .extend(deposits.iter().map(|d| d.pack()))
+        .build();
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+
+    let tip_block = chain.last_valid_block();
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    let deposit = rpc_server
+        .get_deposit(deposits[1].cell.out_point.clone())
+        .await
+        .unwrap()
+        .expect("deposit");
+    assert_eq!(deposit.status, DepositStatus::Included);
+    let out_point: packed::OutPoint = deposit.out_point.into();
+    assert_eq!(out_point, deposits[1].cell.out_point);
+    let request = deposit.request.expect("deposit request");
+    assert_eq!(request.capacity.value(), DEPOSIT_CAPACITY + 1);
+    let committed_info = deposit.l2_committed_info.expect("committed info");
+    assert_eq!(committed_info.block_number.value(), tip_block_number);
+    let block_hash: H256 = tip_block.hash().into();
+    assert_eq!(H256::from(committed_info.block_hash.0), block_hash);
+    assert_eq!(committed_info.deposit_index.value(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_get_deposit_status() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script.clone()).await;
+    let mock_ckb = MockCKB::new();
+    let rpc_server = {
+        let mut args =
+            RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+        let rollup_context = args.generator.rollup_context().to_owned();
+        args.rpc_client = mock_ckb.rpc_client(rollup_type_script, rollup_context);
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+
+    // Deposit cells live on L1
+    let mut deposits = build_deposits(&chain, 4);
+    // Invalid sUDT type script
+    let invalid_type = Script::new_builder()
+        .hash_type(ScriptHashType::Data.into())
+        .build();
+    deposits[1].cell.output = deposits[1]
+        .cell
+        .output
+        .clone()
+        .as_builder()
+        .type_(Some(invalid_type).pack())
+        .build();
+    let out_points = mock_ckb.deploy_cells(
+        deposits
+            .iter()
+            .map(|d| (d.cell.output.clone(), d.cell.data.clone()))
+            .collect(),
+    );
+    for (deposit, out_point) in deposits.iter_mut().zip(out_points) {
+        deposit.cell.out_point = out_point;
+    }
+    let [collected, rejected, pending, refunded] = [0, 1, 2, 3].map(|i| deposits[i].clone());
+
+    set_deposit_cells_and_reset(&chain, vec![collected.clone(), rejected.clone()]).await;
+    let tip_block_number: u64 = chain.last_valid_block().raw().number().unpack();
+
+    let deposit = rpc_server
+        .get_deposit(collected.cell.out_point.clone())
+        .await
+        .unwrap()
+        .expect("collected deposit");
+    assert_eq!(deposit.status, DepositStatus::Collected);
+    assert_eq!(
+        deposit.tip_block_number.map(|n| n.value()),
+        Some(tip_block_number)
+    );
+
+    let deposit = rpc_server
+        .get_deposit(rejected.cell.out_point.clone())
+        .await
+        .unwrap()
+        .expect("rejected deposit");
+    assert_eq!(deposit.status, DepositStatus::Rejected);
+    assert_eq!(
+        deposit.tip_block_number.map(|n| n.value()),
+        Some(tip_block_number)
+    );
+    assert!(deposit
+        .reason
+        .expect("reason")
+        .contains("Invalid deposit sUDT"));
+
+    let deposit = rpc_server
+        .get_deposit(pending.cell.out_point.clone())
+        .await
+        .unwrap()
+        .expect("pending deposit");
+    assert_eq!(deposit.status, DepositStatus::Pending);
+    assert!(deposit.tip_block_number.is_none());
+
+    // Consumed by a tx without the rollup cell
+    mock_ckb.spend_cells(vec![refunded.cell.out_point.clone()]);
+    let deposit = rpc_server
+        .get_deposit(refunded.cell.out_point.clone())
+        .await
+        .unwrap()
+        .expect("refunded deposit");
+    assert_eq!(deposit.status, DepositStatus::Refunded);
+
+    // Unknown cell
+    let unknown = OutPoint::new_builder().tx_hash([42u8; 32].pack()).build();
+    assert!(rpc_server.get_deposit(unknown).await.unwrap().is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_get_deposit_dropped_from_mem_block() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script.clone()).await;
+    let mock_ckb = MockCKB::new();
+    let rpc_server = {
+        let mut args =
+            RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+        let rollup_context = args.generator.rollup_context().to_owned();
+        args.rpc_client = mock_ckb.rpc_client(rollup_type_script, rollup_context);
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+
+    let mut deposit = build_deposits(&chain, 1).remove(0);
+    deposit.cell.out_point = mock_ckb
+        .deploy_cells(vec![(
+            deposit.cell.output.clone(),
+            deposit.cell.data.clone(),
+        )])
+        .remove(0);
+    let out_point = deposit.cell.out_point.clone();
+
+    set_deposit_cells_and_reset(&chain, vec![deposit]).await;
+    let status = rpc_server.get_deposit(out_point.clone()).await.unwrap();
+    assert_eq!(status.unwrap().status, DepositStatus::Collected);
+
+    // The deposit isn't collected by the next mem block
+    set_deposit_cells_and_reset(&chain, vec![]).await;
+    assert!(chain.mem_pool().await.mem_block().deposits().is_empty());
+    let deposit = rpc_server.get_deposit(out_point).await.unwrap().unwrap();
+    assert_eq!(deposit.status, DepositStatus::Pending);
+    assert!(deposit.tip_block_number.is_none());
+}
+
+fn build_deposits(chain: &TestChain, count: u64) -> Vec<DepositInfo> {
+    const DEPOSIT_CAPACITY: u64 = 1000000 * 10u64.pow(8);
+    (0..count)
+        .map(|i| {
+            let wallet = EthWallet::random(chain.rollup_type_hash());
+            let deposit = DepositRequest::new_builder()
+                .capacity((DEPOSIT_CAPACITY + i).pack())
+                .sudt_script_hash(H256::zero().pack())
+                .amount(0.pack())
+                .script(wallet.account_script().to_owned())
+                .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+                .build();
+            into_deposit_info_cell(chain.inner.generator().rollup_context(), deposit)
+        })
+        .collect()
+}
+
+async fn set_deposit_cells_and_reset(chain: &TestChain, deposit_cells: Vec<DepositInfo>) {
+    let mut mem_pool = chain.mem_pool().await;
+    let provider = DummyMemPoolProvider {
+        deposit_cells,
+        fake_blocktime: Duration::from_millis(0),
+    };
+    mem_pool.set_provider(Box::new(provider));
+    mem_pool
+        .reset_mem_block(&LocalCellsManager::default())
+        .await
+        .unwrap();
+}
@@ -0,0 +1,143 @@
+use gw_common::{
+    builtins::ETH_REGISTRY_ACCOUNT_ID,
+    ckb_decimal::CKBCapacity,
+    h256_ext::H256Ext,
+    registry_address::RegistryAddress,
+    smt::Blake2bHasher,
+    sparse_merkle_tree::CompiledMerkleProof,
+    state::{build_account_key, State},
+    H256,
+};
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{
+    packed::{DepositInfoVec, DepositRequest, Script},
+    prelude::{Builder, Pack, Unpack},
+};
+
+use crate::testing_tool::{
+    chain::{into_deposit_info_cell, TestChain},
+    eth_wallet::EthWallet,
+    rpc_server::RPCServer,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_get_proof() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    // Deposit test account
+    const DEPOSIT_CAPACITY: u64 = 1000000 * 10u64.pow(8);
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let deposit = DepositRequest::new_builder()
+        .capacity(DEPOSIT_CAPACITY.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .amount(0.pack())
+        .script(test_wallet.account_script().to_owned())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.inner.generator().rollup_context(), deposit).pack())
+        .build();
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+
+    let account_id = {
+        let mem_pool_state = chain.mem_pool_state().await;
+        let state = mem_pool_state.load_state_db();
+        state
+            .get_account_id_by_script_hash(&test_wallet.account_script_hash())
+            .unwrap()
+            .unwrap()
+    };
+
+    let tip_block = chain.last_valid_block();
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    let state_root: H256 = tip_block.raw().post_account().merkle_root().unpack();
+
+    let storage_key = H256::from_u32(42);
+    let proof = rpc_server
+        .get_proof(account_id, vec![storage_key], None)
+        .await
+        .unwrap()
+        .expect("account proof");
+    assert_eq!(proof.block_number.value(), tip_block_number);
+    assert_eq!(H256::from(proof.state_root.0), state_root);
+    assert_eq!(
+        H256::from(proof.script_hash.0),
+        test_wallet.account_script_hash()
+    );
+    assert_eq!(proof.nonce.value(), 0);
+    let registry_address: Option<RegistryAddress> = proof.registry_address.map(Into::into);
+    assert_eq!(registry_address.as_ref(), Some(test_wallet.reg_address()));
+    let ckb_balance = CKBCapacity::from_layer1(DEPOSIT_CAPACITY);
+    assert_eq!(proof.balance, ckb_balance.to_layer2());
+    assert_eq!(proof.storage.len(), 1);
+    assert_eq!(
+        H256::from(proof.storage[0].smt_key.0),
+        build_account_key(account_id, storage_key.as_slice())
+    );
+
+    // Verify the proof against the state root
+    let leaves: Vec<(H256, H256)> = proof
+        .leaves
+        .iter()
+        .map(|kv| (kv.k.0.into(), kv.v.0.into()))
+        .collect();
+    let root = CompiledMerkleProof(proof.proof.into_bytes().to_vec())
+        .compute_root::<Blake2bHasher>(leaves)
+        .unwrap();
+    assert_eq!(root, state_root);
+
+    // Proof at the tip block number is the same proof
+    let tip_proof = rpc_server
+        .get_proof(account_id, vec![storage_key], Some(tip_block_number))
+        .await
+        .unwrap()
+        .expect("account proof");
+    assert_eq!(tip_proof.proof, proof.proof);
+
+    // History blocks aren't supported
+    let err = rpc_server
+        .get_proof(account_id, vec![storage_key], Some(tip_block_number - 1))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("\"code\":-32602"), "{}", err);
+    assert!(err.contains("Only the last valid tip block is supported"));
+
+    // Block proof is against the same tip
+    let block_proof = rpc_server
+        .get_block_proof(tip_block.hash().into())
+        .await
+        .unwrap()
+        .expect("block proof");
+    assert_eq!(block_proof.tip_block_hash, proof.block_hash);
+
+    // Non-exist account
+    let proof = rpc_server.get_proof(u32::MAX, vec![], None).await.unwrap();
+    assert!(proof.is_none());
+
+    // Block proof against the tip global state
+    let block_hash: H256 = tip_block.hash().into();
+    let block_proof = rpc_server
+        .get_block_proof(block_hash)
+        .await
+        .unwrap()
+        .expect("block proof");
+    let global_state = chain
+        .store()
+        .get_block_post_global_state(&block_hash)
+        .unwrap()
+        .unwrap();
+    let block_root: H256 = global_state.block().merkle_root().unpack();
+    assert_eq!(H256::from(block_proof.block_root.0), block_root);
+    let root = CompiledMerkleProof(block_proof.proof.into_bytes().to_vec())
+        .compute_root::<Blake2bHasher>(vec![(tip_block.smt_key().into(), block_hash)])
+        .unwrap();
+    assert_eq!(root, block_root);
+
+    let block_proof = rpc_server.get_block_proof(H256::one()).await.unwrap();
+    assert!(block_proof.is_none());
+}
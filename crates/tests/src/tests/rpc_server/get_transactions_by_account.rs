@@ -0,0 +1,156 @@
+use ckb_types::prelude::{Builder, Entity};
+use gw_common::{
+    builtins::{ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
+    state::State,
+    H256,
+};
+use gw_generator::account_lock_manage::secp256k1::Secp256k1Eth;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::{JsonBytes, Uint32},
+    godwoken::{AccountTxQuery, AccountTxRole},
+};
+use gw_store::Store;
+use gw_types::{
+    packed::{
+        CreateAccount, DepositInfoVec, DepositRequest, Fee, L2Transaction, MetaContractArgs,
+        RawL2Transaction, Script,
+    },
+    prelude::Pack,
+};
+
+use crate::testing_tool::{
+    chain::{into_deposit_info_cell, TestChain},
+    eth_wallet::EthWallet,
+    polyjuice::PolyjuiceAccount,
+    rpc_server::RPCServer,
+};
+
+const META_CONTRACT_ACCOUNT_ID: u32 = RESERVED_ACCOUNT_ID;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_get_transactions_by_account() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let store = Store::open_tmp().unwrap().with_account_tx_index(true);
+    let mut chain = TestChain::setup_with_store(rollup_type_script, store).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    // Deposit test account
+    const DEPOSIT_CAPACITY: u64 = 1000000 * 10u64.pow(8);
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let deposit = DepositRequest::new_builder()
+        .capacity(DEPOSIT_CAPACITY.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .amount(0.pack())
+        .script(test_wallet.account_script().to_owned())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.inner.generator().rollup_context(), deposit).pack())
+        .build();
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+
+    // Create polyjuice account by meta contract
+    let mem_pool_state = chain.mem_pool_state().await;
+    let state = mem_pool_state.load_state_db();
+    let test_account_id = state
+        .get_account_id_by_script_hash(&test_wallet.account_script_hash())
+        .unwrap()
+        .unwrap();
+    let meta_contract_script_hash = state.get_script_hash(META_CONTRACT_ACCOUNT_ID).unwrap();
+    let fee = Fee::new_builder()
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .amount(0u128.pack())
+        .build();
+    let create_polyjuice = CreateAccount::new_builder()
+        .fee(fee)
+        .script(PolyjuiceAccount::build_script(chain.rollup_type_hash()))
+        .build();
+    let args = MetaContractArgs::new_builder()
+        .set(create_polyjuice)
+        .build();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(META_CONTRACT_ACCOUNT_ID.pack())
+        .nonce(0u32.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    let signing_message = Secp256k1Eth::eip712_signing_message(
+        chain.chain_id(),
+        &raw_tx,
+        test_wallet.reg_address().to_owned(),
+        meta_contract_script_hash,
+    )
+    .unwrap();
+    let sign = test_wallet.sign_message(signing_message.into()).unwrap();
+    let tx = L2Transaction::new_builder()
+        .raw(raw_tx)
+        .signature(sign.pack())
+        .build();
+    let tx_hash = ckb_fixed_hash::H256(tx.hash());
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(tx).unwrap();
+    }
+    chain
+        .produce_block(Default::default(), vec![])
+        .await
+        .unwrap();
+
+    let from_query = AccountTxQuery {
+        role: AccountTxRole::From,
+        ..Default::default()
+    };
+
+    // By account id
+    let account_id = serde_json::to_value(Uint32::from(test_account_id)).unwrap();
+    let page = rpc_server
+        .get_transactions_by_account(account_id, from_query.clone())
+        .await
+        .unwrap();
+    assert_eq!(page.transactions.len(), 1);
+    assert_eq!(page.transactions[0].tx_hash, tx_hash);
+    assert_eq!(page.cursor, None);
+
+    // By registry address
+    let reg_address = JsonBytes::from_vec(test_wallet.reg_address().to_bytes());
+    let reg_address = serde_json::to_value(reg_address).unwrap();
+    let page = rpc_server
+        .get_transactions_by_account(reg_address, from_query.clone())
+        .await
+        .unwrap();
+    assert_eq!(page.transactions.len(), 1);
+    assert_eq!(page.transactions[0].tx_hash, tx_hash);
+
+    let meta_contract_id = serde_json::to_value(Uint32::from(META_CONTRACT_ACCOUNT_ID)).unwrap();
+    let to_query = AccountTxQuery {
+        role: AccountTxRole::To,
+        ..Default::default()
+    };
+    let page = rpc_server
+        .get_transactions_by_account(meta_contract_id, to_query)
+        .await
+        .unwrap();
+    assert_eq!(page.transactions.len(), 1);
+    assert_eq!(page.transactions[0].tx_hash, tx_hash);
+
+    // Unknown registry address
+    let unknown_wallet = EthWallet::random(chain.rollup_type_hash());
+    let unknown_address = JsonBytes::from_vec(unknown_wallet.reg_address().to_bytes());
+    let unknown_address = serde_json::to_value(unknown_address).unwrap();
+    let page = rpc_server
+        .get_transactions_by_account(unknown_address, from_query.clone())
+        .await
+        .unwrap();
+    assert!(page.transactions.is_empty());
+
+    // Invalid registry address
+    let invalid_address = serde_json::to_value(JsonBytes::from_vec(vec![1; 8])).unwrap();
+    let err = rpc_server
+        .get_transactions_by_account(invalid_address, from_query)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Invalid registry address"));
+}
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, h256_ext::H256Ext, H256};
+use gw_generator::account_lock_manage::eip712::{self, traits::EIP712Encode};
+use gw_jsonrpc_types::godwoken::{WithdrawalFinality, WithdrawalLifecycleStatus};
+use gw_types::{
+    packed::{
+        DepositInfoVec, DepositRequest, L2Block, NumberHash, RawWithdrawalRequest, Script,
+        WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::{Builder, Entity, Pack, Unpack},
+};
+
+use crate::testing_tool::{
+    chain::{into_deposit_info_cell, produce_empty_block, TestChain, DEFAULT_FINALITY_BLOCKS},
+    eth_wallet::EthWallet,
+    mock_ckb::MockCKB,
+    rpc_server::RPCServer,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_get_withdrawal_lifecycle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script.clone()).await;
+    // Submit txs of the test chain aren't on the mock CKB, blocks stay uncommitted
+    let mock_ckb = MockCKB::new();
+    let rpc_server = {
+        let mut args =
+            RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+        let rollup_context = args.generator.rollup_context().to_owned();
+        args.rpc_client = mock_ckb.rpc_client(rollup_type_script, rollup_context);
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    deposit(&mut chain, &test_wallet).await;
+
+    let withdrawal = build_withdrawal(&chain, &test_wallet);
+    let withdrawal_hash = rpc_server
+        .submit_withdrawal_request(&withdrawal)
+        .await
+        .unwrap();
+
+    let lifecycle = rpc_server
+        .get_withdrawal_lifecycle(withdrawal_hash)
+        .await
+        .unwrap()
+        .expect("pending withdrawal");
+    assert_eq!(lifecycle.status, WithdrawalLifecycleStatus::Pending);
+    assert!(lifecycle.l2_committed_info.is_none());
+    assert!(lifecycle.finality.is_none());
+
+    let unknown = rpc_server
+        .get_withdrawal_lifecycle(H256::one())
+        .await
+        .unwrap();
+    assert!(unknown.is_none());
+
+    while rpc_server
+        .is_request_in_queue(withdrawal_hash)
+        .await
+        .unwrap()
+    {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    chain
+        .produce_block(Default::default(), vec![withdrawal])
+        .await
+        .unwrap();
+    let withdrawal_block = chain.last_valid_block();
+    let withdrawal_block_number: u64 = withdrawal_block.raw().number().unpack();
+
+    let lifecycle = rpc_server
+        .get_withdrawal_lifecycle(withdrawal_hash)
+        .await
+        .unwrap()
+        .expect("included withdrawal");
+    assert_eq!(lifecycle.status, WithdrawalLifecycleStatus::Included);
+    assert!(lifecycle.l1_committed_info.is_none());
+    assert!(lifecycle.withdrawal_cell.is_none());
+    let l2_committed_info = lifecycle.l2_committed_info.expect("l2 committed info");
+    assert_eq!(
+        l2_committed_info.block_number.value(),
+        withdrawal_block_number
+    );
+    assert_eq!(
+        H256::from(l2_committed_info.block_hash.0),
+        withdrawal_block.hash().into()
+    );
+    assert_eq!(l2_committed_info.withdrawal_index.value(), 0);
+    let finality = lifecycle.finality.expect("finality");
+    let finalized_block_number = withdrawal_block_number + DEFAULT_FINALITY_BLOCKS;
+    assert_eq!(
+        finality.finalized_block_number.value(),
+        finalized_block_number
+    );
+    assert_eq!(finality.remaining_blocks.value(), DEFAULT_FINALITY_BLOCKS);
+
+    // Remaining blocks and the estimated timestamp count from the same block
+    for _ in 0..2 {
+        chain
+            .produce_block(Default::default(), vec![])
+            .await
+            .unwrap();
+    }
+    let confirmed_block = chain.last_valid_block();
+    let lagged_finality = {
+        set_last_confirmed_block(&chain, &withdrawal_block);
+        let lifecycle = rpc_server.get_withdrawal_lifecycle(withdrawal_hash).await;
+        let finality = lifecycle.unwrap().unwrap().finality.unwrap();
+        set_last_confirmed_block(&chain, &confirmed_block);
+        finality
+    };
+    let lifecycle = rpc_server
+        .get_withdrawal_lifecycle(withdrawal_hash)
+        .await
+        .unwrap()
+        .unwrap();
+    let finality = lifecycle.finality.expect("finality");
+    assert_eq!(
+        finality.remaining_blocks.value(),
+        DEFAULT_FINALITY_BLOCKS - 2
+    );
+    assert_eq!(
+        lagged_finality.remaining_blocks.value(),
+        DEFAULT_FINALITY_BLOCKS
+    );
+
+    let estimated_interval = |finality: &WithdrawalFinality, block: &L2Block| {
+        let timestamp: u64 = block.raw().timestamp().unpack();
+        let remaining_blocks = finality.remaining_blocks.value();
+        let duration = finality.estimated_finalized_timestamp.value() - timestamp;
+        assert_eq!(duration % remaining_blocks, 0);
+        duration / remaining_blocks
+    };
+    assert_eq!(
+        estimated_interval(&finality, &confirmed_block),
+        estimated_interval(&lagged_finality, &withdrawal_block)
+    );
+}
+
+const WITHDRAWAL_CAPACITY: u64 = 1000u64 * 10u64.pow(8);
+
+async fn deposit(chain: &mut TestChain, wallet: &EthWallet) {
+    const DEPOSIT_CAPACITY: u64 = 12345768 * 10u64.pow(8);
+    let deposit = DepositRequest::new_builder()
+        .capacity(DEPOSIT_CAPACITY.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .amount(0.pack())
+        .script(wallet.account_script().to_owned())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.inner.generator().rollup_context(), deposit).pack())
+        .build();
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+
+    for _ in 0..DEFAULT_FINALITY_BLOCKS + 1 {
+        produce_empty_block(&mut chain.inner).await.unwrap();
+    }
+}
+
+fn build_withdrawal(chain: &TestChain, wallet: &EthWallet) -> WithdrawalRequestExtra {
+    let raw = RawWithdrawalRequest::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .capacity(WITHDRAWAL_CAPACITY.pack())
+        .amount(0.pack())
+        .account_script_hash(wallet.account_script_hash().pack())
+        .owner_lock_hash(wallet.account_script_hash().pack())
+        .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let typed_withdrawal = eip712::types::Withdrawal::from_raw(
+        raw.clone(),
+        wallet.account_script().to_owned(),
+        wallet.registry_address.clone(),
+    )
+    .unwrap();
+    let domain_seperator = eip712::types::EIP712Domain {
+        name: "Godwoken".to_string(),
+        version: "1".to_string(),
+        chain_id: chain.chain_id(),
+        verifying_contract: None,
+        salt: None,
+    };
+    let message = typed_withdrawal.eip712_message(domain_seperator.hash_struct());
+    let sig = wallet.sign_message(message).unwrap();
+    let req = WithdrawalRequest::new_builder()
+        .raw(raw)
+        .signature(sig.pack())
+        .build();
+    WithdrawalRequestExtra::new_builder()
+        .request(req)
+        .owner_lock(wallet.account_script().to_owned())
+        .build()
+}
+
+fn set_last_confirmed_block(chain: &TestChain, block: &L2Block) {
+    let nh = NumberHash::new_builder()
+        .number(block.raw().number())
+        .block_hash(block.hash().pack())
+        .build();
+    let db = chain.store().begin_transaction();
+    db.set_last_confirmed_block_number_hash(&nh.as_reader())
+        .unwrap();
+    db.commit().unwrap();
+}
@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+
+use futures::{SinkExt, StreamExt};
+use gw_mem_pool::block_sync_server::BlockSyncServerState;
+use gw_rpc_server::registry::Registry;
+use gw_rpc_server::server::start_metrics_server;
+use gw_rpc_server::ws_server::serve_ws;
+use gw_types::packed::Script;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_metrics_server() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(start_metrics_server(listener, chain.store().clone()));
+
+    gw_metrics::mem_pool().fee_queue_len.set(3);
+    let response = http_get(addr, "/metrics").await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("\ngw_fee_queue_len 3\n"), "{}", response);
+
+    // Only metrics are served
+    let response = http_get(addr, "/").await;
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_rpc_metrics() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script.clone()).await;
+    let registry_args = RPCServer::default_registry_args(&chain.inner, rollup_type_script, None);
+    let rpc_server = Registry::create(registry_args)
+        .await
+        .build_rpc_server()
+        .unwrap();
+    let sync_server_state = Arc::new(Mutex::new(BlockSyncServerState::new(&Default::default())));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ws_addr = listener.local_addr().unwrap();
+    tokio::spawn(serve_ws(
+        listener,
+        chain.store().clone(),
+        sync_server_state,
+        rpc_server,
+    ));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let metrics_addr = listener.local_addr().unwrap();
+    tokio::spawn(start_metrics_server(listener, chain.store().clone()));
+
+    let metrics = gw_metrics::rpc();
+    let count = |method: &str| {
+        let latency = metrics.request_latency.with_label_values(&[method]);
+        let errors = metrics.errors.with_label_values(&[method]);
+        (latency.get_sample_count(), errors.get())
+    };
+    let block_hash_before = count("gw_get_block_hash");
+    let script_hash_before = count("gw_get_script_hash");
+
+    // Calls forwarded by the WebSocket server are recorded too
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", ws_addr))
+        .await
+        .unwrap();
+    let block_hash = ws_request(&mut ws, "gw_get_block_hash", json!(["0x0"])).await;
+    assert!(block_hash.get("result").is_some(), "{}", block_hash);
+    let script_hash = ws_request(&mut ws, "gw_get_script_hash", json!(["0x"])).await;
+    assert!(script_hash.get("error").is_some(), "{}", script_hash);
+
+    let (block_hash_count, block_hash_errors) = count("gw_get_block_hash");
+    assert_eq!(block_hash_count, block_hash_before.0 + 1);
+    assert_eq!(block_hash_errors, block_hash_before.1);
+    let (script_hash_count, script_hash_errors) = count("gw_get_script_hash");
+    assert_eq!(script_hash_count, script_hash_before.0 + 1);
+    assert_eq!(script_hash_errors, script_hash_before.1 + 1);
+
+    let response = http_get(metrics_addr, "/metrics").await;
+    assert!(
+        response.contains("gw_rpc_request_seconds_count{method=\"gw_get_block_hash\"}"),
+        "{}",
+        response
+    );
+    assert!(
+        response.contains("gw_rpc_request_seconds_bucket{method=\"gw_get_block_hash\",le="),
+        "{}",
+        response
+    );
+    assert!(
+        response.contains("gw_rpc_errors_total{method=\"gw_get_script_hash\"}"),
+        "{}",
+        response
+    );
+}
+
+async fn ws_request(ws: &mut WsStream, method: &str, params: Value) -> Value {
+    let req = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+    ws.send(Message::Text(req.to_string())).await.unwrap();
+    let msg = ws.next().await.expect("ws message").unwrap();
+    serde_json::from_str(msg.to_text().unwrap()).unwrap()
+}
+
+async fn http_get(addr: std::net::SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let req = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, addr
+    );
+    stream.write_all(req.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
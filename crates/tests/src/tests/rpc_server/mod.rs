@@ -1,6 +1,21 @@
 pub(crate) const BLOCK_MAX_CYCLES_LIMIT: u64 = 300_0000;
 
+pub mod cancel_request;
+pub mod debug_trace_transaction;
+pub mod estimate_fee;
 pub mod execute_l2transaction;
 pub mod execute_raw_l2transaction;
+pub mod execute_raw_l2transaction_bundle;
+pub mod get_deposit;
+pub mod get_proof;
+pub mod get_transactions_by_account;
+pub mod get_withdrawal_lifecycle;
+pub mod metrics;
+pub mod p2p_admin;
+pub mod replace_request;
+pub mod state_pruned;
 pub mod submit_l2transaction;
 pub mod submit_withdrawal_request;
+pub mod update_dynamic_config;
+pub mod watchtower;
+pub mod ws_subscribe;
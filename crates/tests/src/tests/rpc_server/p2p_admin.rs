@@ -0,0 +1,164 @@
+use std::{collections::HashSet, iter::FromIterator};
+
+use gw_config::{P2PNetworkConfig, RPCMethods};
+use gw_jsonrpc_types::godwoken::{P2PPeer, P2PSession};
+use gw_p2p_network::{P2PNetwork, PeerBook};
+use gw_types::packed::Script;
+use serde_json::json;
+use tentacle::secio::PeerId;
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+async fn setup(
+    chain: &TestChain,
+    allowed_peer_ids: Option<Vec<String>>,
+) -> (P2PNetwork, RPCServer) {
+    let config = P2PNetworkConfig {
+        allowed_peer_ids,
+        ..Default::default()
+    };
+    let peer_book = PeerBook::new(&config.peer_score);
+    let network = P2PNetwork::init(
+        &config,
+        Vec::<tentacle::service::ProtocolMeta>::new(),
+        peer_book,
+    )
+    .await
+    .unwrap();
+
+    let mut args =
+        RPCServer::default_registry_args(&chain.inner, chain.rollup_type_script.clone(), None);
+    args.server_config.enable_methods = HashSet::from_iter(vec![RPCMethods::P2P]);
+    args.p2p_admin = Some(network.admin().clone());
+    let rpc_server = RPCServer::build_from_registry_args(args).await.unwrap();
+    (network, rpc_server)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_p2p_allowed_peer_ids() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let (_network, rpc_server) = setup(&chain, Some(vec![])).await;
+    let peer_id = PeerId::random().to_base58();
+
+    let allowed: Option<Vec<String>> = rpc_server
+        .call("gw_p2p_get_allowed_peer_ids", json!([]))
+        .await
+        .unwrap();
+    assert_eq!(allowed, Some(vec![]));
+
+    let () = rpc_server
+        .call("gw_p2p_add_allowed_peer_id", json!([peer_id]))
+        .await
+        .unwrap();
+    let allowed: Option<Vec<String>> = rpc_server
+        .call("gw_p2p_get_allowed_peer_ids", json!([]))
+        .await
+        .unwrap();
+    assert_eq!(allowed, Some(vec![peer_id.clone()]));
+
+    let () = rpc_server
+        .call("gw_p2p_remove_allowed_peer_id", json!([peer_id]))
+        .await
+        .unwrap();
+    let allowed: Option<Vec<String>> = rpc_server
+        .call("gw_p2p_get_allowed_peer_ids", json!([]))
+        .await
+        .unwrap();
+    assert_eq!(allowed, Some(vec![]));
+
+    let err = rpc_server
+        .call::<()>("gw_p2p_add_allowed_peer_id", json!(["invalid"]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("parse peer id"), "{}", err);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_p2p_allow_list_disabled() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let (_network, rpc_server) = setup(&chain, None).await;
+    let peer_id = PeerId::random().to_base58();
+
+    let allowed: Option<Vec<String>> = rpc_server
+        .call("gw_p2p_get_allowed_peer_ids", json!([]))
+        .await
+        .unwrap();
+    assert_eq!(allowed, None);
+
+    let err = rpc_server
+        .call::<()>("gw_p2p_add_allowed_peer_id", json!([peer_id]))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("allow list is disabled"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_p2p_ban_and_sessions() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let (_network, rpc_server) = setup(&chain, None).await;
+    let peer_id = PeerId::random().to_base58();
+
+    let sessions: Vec<P2PSession> = rpc_server
+        .call("gw_p2p_list_sessions", json!([]))
+        .await
+        .unwrap();
+    assert!(sessions.is_empty());
+    let err = rpc_server
+        .call::<()>("gw_p2p_disconnect", json!(["0x1"]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("session 1 not found"), "{}", err);
+
+    let () = rpc_server
+        .call("gw_p2p_ban", json!([peer_id, "0x3c"]))
+        .await
+        .unwrap();
+    let peers: Vec<P2PPeer> = rpc_server
+        .call("gw_p2p_list_peers", json!([]))
+        .await
+        .unwrap();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].peer_id, peer_id);
+    let remaining: u64 = peers[0].ban_remaining_secs.unwrap().into();
+    assert!(remaining <= 60);
+    assert_eq!(peers[0].ban_reason.as_deref(), Some("banned by admin"));
+
+    let () = rpc_server
+        .call("gw_p2p_unban", json!([peer_id]))
+        .await
+        .unwrap();
+    let peers: Vec<P2PPeer> = rpc_server
+        .call("gw_p2p_list_peers", json!([]))
+        .await
+        .unwrap();
+    assert_eq!(peers[0].ban_remaining_secs, None);
+    assert_eq!(peers[0].ban_reason, None);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_p2p_disabled() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    // P2P methods are enabled but the p2p network is not.
+    let mut args =
+        RPCServer::default_registry_args(&chain.inner, chain.rollup_type_script.clone(), None);
+    args.server_config.enable_methods = HashSet::from_iter(vec![RPCMethods::P2P]);
+    let rpc_server = RPCServer::build_from_registry_args(args).await.unwrap();
+
+    let err = rpc_server
+        .call::<Vec<P2PSession>>("gw_p2p_list_sessions", json!([]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32601"), "{}", err);
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use gw_common::builtins::CKB_SUDT_ACCOUNT_ID;
+use gw_jsonrpc_types::godwoken::{EvictReason, L2TransactionStatus};
+use gw_store::state::traits::JournalDB;
+use gw_types::{
+    packed::{RawL2Transaction, Script},
+    prelude::{Builder, Pack},
+};
+
+use crate::testing_tool::{
+    chain::TestChain,
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount},
+    rpc_server::RPCServer,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_replace_request() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let wallet = EthWallet::random(chain.rollup_type_hash());
+    let account_id = wallet
+        .create_account(&mut state, 1000000000u128.into())
+        .unwrap();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    // Nonce 1 txs stay in the queue of request submitter, nonce 0 is never submitted
+    let tx_with_gas_price = |gas_price: u128| {
+        let args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18)
+            .gas_price(gas_price)
+            .finish();
+        let raw_tx = RawL2Transaction::new_builder()
+            .chain_id(chain.chain_id().pack())
+            .from_id(account_id.pack())
+            .to_id(polyjuice_account.id.pack())
+            .nonce(1u32.pack())
+            .args(args.pack())
+            .build();
+        wallet.sign_polyjuice_tx(&state, raw_tx).unwrap()
+    };
+    let tx = tx_with_gas_price(100);
+    let underpriced_tx = tx_with_gas_price(105);
+    let replacement_tx = tx_with_gas_price(110);
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+
+    let tx_hash = rpc_server.submit_l2transaction(&tx).await.unwrap().unwrap();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(rpc_server.is_request_in_queue(tx_hash).await.unwrap());
+
+    // Default fee rate bump is 10%
+    let err = rpc_server
+        .submit_l2transaction(&underpriced_tx)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains(r#""code":-32009"#));
+    assert!(rpc_server.is_request_in_queue(tx_hash).await.unwrap());
+
+    let new_hash = rpc_server
+        .submit_l2transaction(&replacement_tx)
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(!rpc_server.is_request_in_queue(tx_hash).await.unwrap());
+    assert!(rpc_server.is_request_in_queue(new_hash).await.unwrap());
+    let tx_status = rpc_server.get_transaction(tx_hash).await.unwrap().unwrap();
+    assert_eq!(tx_status.status, L2TransactionStatus::Evicted);
+    assert_eq!(tx_status.evicted_reason, Some(EvictReason::Replaced));
+
+    // The replaced tx can't replace its replacement
+    let err = rpc_server.submit_l2transaction(&tx).await.unwrap_err();
+    assert!(err.to_string().contains("replacement underpriced"));
+    assert!(rpc_server.is_request_in_queue(new_hash).await.unwrap());
+}
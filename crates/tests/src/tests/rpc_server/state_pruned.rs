@@ -0,0 +1,55 @@
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    registry_address::RegistryAddress,
+};
+use gw_types::packed::Script;
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_query_pruned_state() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+    for _ in 0..3 {
+        chain
+            .produce_block(Default::default(), vec![])
+            .await
+            .unwrap();
+    }
+
+    let address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, vec![1u8; 20]);
+    rpc_server
+        .get_balance(&address, CKB_SUDT_ACCOUNT_ID, Some(1))
+        .await
+        .unwrap();
+
+    // Prune like the state pruner does
+    {
+        let db = chain.store().begin_transaction();
+        for block_number in 1..=2 {
+            db.prune_block_state_record(block_number).unwrap();
+        }
+        db.set_state_pruned_block_number(2).unwrap();
+        db.commit().unwrap();
+    }
+
+    let err = rpc_server
+        .get_balance(&address, CKB_SUDT_ACCOUNT_ID, Some(1))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("\"code\":-32008"), "{}", err);
+    assert!(err.to_string().contains("is pruned"), "{}", err);
+
+    // History since the pruned block number and the tip are still available
+    rpc_server
+        .get_balance(&address, CKB_SUDT_ACCOUNT_ID, Some(2))
+        .await
+        .unwrap();
+    rpc_server
+        .get_balance(&address, CKB_SUDT_ACCOUNT_ID, None)
+        .await
+        .unwrap();
+}
@@ -8,6 +8,7 @@ use gw_common::{
     state::State,
     H256,
 };
+use gw_config::FeeQueueConfig;
 use gw_store::state::traits::JournalDB;
 use gw_types::{
     bytes::Bytes,
@@ -170,6 +171,67 @@ async fn test_in_queue_query_with_signature_hash() {
     assert_eq!(system_log.status_code, 0);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_submit_future_nonce() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let mut state = mem_pool_state.load_state_db();
+
+    let wallet = EthWallet::random(chain.rollup_type_hash());
+    let account_id = wallet
+        .create_account(&mut state, 1000000u128.into())
+        .unwrap();
+    state.set_nonce(account_id, 2).unwrap();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let tx_with_nonce = |nonce: u32| {
+        let raw_tx = RawL2Transaction::new_builder()
+            .chain_id(chain.chain_id().pack())
+            .from_id(account_id.pack())
+            .to_id(polyjuice_account.id.pack())
+            .nonce(nonce.pack())
+            .args(deploy_args.pack())
+            .build();
+        wallet.sign_polyjuice_tx(&state, raw_tx).unwrap()
+    };
+    let max_nonce = 2 + FeeQueueConfig::default().max_nonce_gap;
+    let stale_tx = tx_with_nonce(1);
+    let future_tx = tx_with_nonce(3);
+    let max_nonce_tx = tx_with_nonce(max_nonce);
+    let gapped_tx = tx_with_nonce(max_nonce + 1);
+    state.finalise().unwrap();
+    mem_pool_state.store_state_db(state);
+
+    let err = rpc_server
+        .submit_l2transaction(&stale_tx)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains(r#""code":-32001"#));
+    assert!(err.to_string().contains("invalid nonce"));
+
+    // Future nonces within the gap wait in the queue for the missing nonces
+    for tx in [future_tx, max_nonce_tx] {
+        let tx_hash = rpc_server.submit_l2transaction(&tx).await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(rpc_server.is_request_in_queue(tx_hash).await.unwrap());
+    }
+
+    let err = rpc_server
+        .submit_l2transaction(&gapped_tx)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains(r#""code":-32001"#));
+    assert!(err.to_string().contains("nonce gap too large"));
+    let gapped_hash: H256 = gapped_tx.hash().into();
+    assert!(!rpc_server.is_request_in_queue(gapped_hash).await.unwrap());
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn test_polyjuice_tx_from_id_zero() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use gw_config::{Config, DynamicConfig, RPCMethods, ReloadConfig};
+use gw_dynamic_config::manager::{DynamicConfigManager, DynamicConfigReloadResponse};
+use gw_types::packed::Script;
+use serde_json::json;
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+async fn build_rpc_server(
+    chain: &TestChain,
+    admin_token: Option<&str>,
+    enable_admin: bool,
+) -> (RPCServer, DynamicConfig) {
+    let mut args =
+        RPCServer::default_registry_args(&chain.inner, chain.rollup_type_script.clone(), None);
+    if enable_admin {
+        args.server_config.enable_methods.insert(RPCMethods::Admin);
+    }
+    let manager = DynamicConfigManager::create(Config {
+        reload_config: ReloadConfig {
+            admin_token: admin_token.map(Into::into),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .unwrap();
+    let config = manager.current_config();
+    args.dynamic_config_manager.store(Arc::new(manager));
+    let rpc_server = RPCServer::build_from_registry_args(args).await.unwrap();
+    (rpc_server, config)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_update_dynamic_config() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let (rpc_server, mut config) = build_rpc_server(&chain, Some("secret"), true).await;
+    config.fee_config.meta_cycles_limit += 1;
+
+    let err = rpc_server
+        .call::<DynamicConfigReloadResponse>("gw_update_dynamic_config", json!([config, "wrong"]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32604"), "{}", err);
+
+    let resp: DynamicConfigReloadResponse = rpc_server
+        .call("gw_update_dynamic_config", json!([config, "secret"]))
+        .await
+        .unwrap();
+    assert_eq!(resp.changes(), ["fee_config.meta_cycles_limit"]);
+
+    // Invalid config is rejected.
+    config.fee_config.meta_cycles_limit = 0;
+    let err = rpc_server
+        .call::<DynamicConfigReloadResponse>("gw_update_dynamic_config", json!([config, "secret"]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("meta_cycles_limit"), "{}", err);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_update_dynamic_config_disabled() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    // Admin methods are not enabled.
+    let (rpc_server, config) = build_rpc_server(&chain, Some("secret"), false).await;
+    let err = rpc_server
+        .call::<DynamicConfigReloadResponse>("gw_update_dynamic_config", json!([config, "secret"]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32601"), "{}", err);
+
+    // The admin token is not set.
+    let (rpc_server, config) = build_rpc_server(&chain, None, true).await;
+    let err = rpc_server
+        .call::<DynamicConfigReloadResponse>("gw_update_dynamic_config", json!([config, "secret"]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32601"), "{}", err);
+}
@@ -0,0 +1,38 @@
+use gw_config::NodeMode;
+use gw_types::packed::Script;
+use serde_json::json;
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+const SUBMIT_METHODS: [&str; 4] = [
+    "gw_submit_l2transaction",
+    "gw_submit_withdrawal_request",
+    "gw_is_request_in_queue",
+    "gw_cancel_request",
+];
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_watchtower_has_no_submit_methods() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let mut args =
+        RPCServer::default_registry_args(&chain.inner, chain.rollup_type_script.clone(), None);
+    args.node_mode = NodeMode::Watchtower;
+    let rpc_server = RPCServer::build_from_registry_args(args).await.unwrap();
+
+    for method in SUBMIT_METHODS.iter() {
+        let err = rpc_server
+            .call::<serde_json::Value>(method, json!([]))
+            .await
+            .unwrap_err();
+        // Method not found.
+        assert!(err.to_string().contains("-32601"), "{}: {}", method, err);
+    }
+
+    let node_info: serde_json::Value = rpc_server
+        .call("gw_get_node_info", json!([]))
+        .await
+        .unwrap();
+    assert_eq!(node_info["mode"], "watchtower");
+}
@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use gw_mem_pool::block_sync_server::BlockSyncServerState;
+use gw_rpc_server::registry::Registry;
+use gw_rpc_server::ws_server::serve_ws;
+use gw_types::{
+    bytes::Bytes,
+    packed::{L2Transaction, LocalBlock, RawL2Transaction, Script, TxReceipt},
+    prelude::{Builder, Entity, Pack},
+};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_ws_subscribe() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script.clone()).await;
+    let registry_args = RPCServer::default_registry_args(&chain.inner, rollup_type_script, None);
+    let rpc_server = Registry::create(registry_args)
+        .await
+        .build_rpc_server()
+        .unwrap();
+    let sync_server_state = Arc::new(Mutex::new(BlockSyncServerState::new(&Default::default())));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve_ws(
+        listener,
+        chain.store().clone(),
+        Arc::clone(&sync_server_state),
+        rpc_server,
+    ));
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+        .await
+        .unwrap();
+
+    // Other methods are forwarded to the JSONRPC server
+    let tip_block_hash = request(&mut ws, 1, "gw_get_tip_block_hash", json!([])).await;
+    let expected_hash = ckb_fixed_hash::H256(chain.last_valid_block().hash());
+    assert_eq!(tip_block_hash["result"], json!(expected_hash));
+
+    let block_sub = request(&mut ws, 2, "gw_subscribe", json!(["new_block"])).await;
+    let block_sub = block_sub["result"].as_str().unwrap().to_owned();
+
+    // The receipt is notified once it is committed and the tx is published
+    let tx = L2Transaction::new_builder()
+        .raw(
+            RawL2Transaction::new_builder()
+                .args(Bytes::from_static(b"ws").pack())
+                .build(),
+        )
+        .build();
+    let tx_hash = ckb_fixed_hash::H256(tx.hash());
+    let receipt_sub = json!(["transaction_receipt", tx_hash]);
+    let receipt_sub = request(&mut ws, 3, "gw_subscribe", receipt_sub).await;
+    let receipt_sub = receipt_sub["result"].as_str().unwrap().to_owned();
+    {
+        let db = chain.store().begin_transaction();
+        let receipt = TxReceipt::new_builder()
+            .tx_witness_hash(tx.witness_hash().pack())
+            .build();
+        db.insert_mem_pool_transaction_receipt(&tx.hash().into(), receipt)
+            .unwrap();
+        db.commit().unwrap();
+    }
+    sync_server_state
+        .lock()
+        .unwrap()
+        .publish_transaction(tx.clone());
+    let notification = recv(&mut ws).await;
+    assert_eq!(notification["method"], "gw_subscription");
+    assert_eq!(notification["params"]["subscription"], receipt_sub.as_str());
+    let witness_hash = ckb_fixed_hash::H256(tx.witness_hash());
+    assert_eq!(
+        notification["params"]["result"]["tx_witness_hash"],
+        json!(witness_hash)
+    );
+
+    // The receipt subscription is removed after notified
+    let unsubscribed = request(&mut ws, 4, "gw_unsubscribe", json!([receipt_sub])).await;
+    assert_eq!(unsubscribed["result"], json!(false));
+
+    // Already available receipts are notified right after subscribing
+    let receipt_sub = json!(["transaction_receipt", tx_hash]);
+    let response = request(&mut ws, 5, "gw_subscribe", receipt_sub).await;
+    let receipt_sub = response["result"].as_str().unwrap().to_owned();
+    let notification = recv(&mut ws).await;
+    assert_eq!(notification["params"]["subscription"], receipt_sub.as_str());
+
+    let block = chain.last_valid_block();
+    let local_block = LocalBlock::new_builder().block(block.clone()).build();
+    sync_server_state
+        .lock()
+        .unwrap()
+        .publish_local_block(local_block);
+    let notification = recv(&mut ws).await;
+    assert_eq!(notification["params"]["subscription"], block_sub.as_str());
+    let block_hash = ckb_fixed_hash::H256(block.hash());
+    assert_eq!(notification["params"]["result"]["hash"], json!(block_hash));
+
+    let unsubscribed = request(&mut ws, 6, "gw_unsubscribe", json!([block_sub])).await;
+    assert_eq!(unsubscribed["result"], json!(true));
+    let invalid = request(&mut ws, 7, "gw_subscribe", json!(["unknown"])).await;
+    assert_eq!(invalid["error"]["code"], json!(-32602));
+}
+
+async fn request(ws: &mut WsStream, id: u64, method: &str, params: Value) -> Value {
+    let req = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+    ws.send(Message::Text(req.to_string())).await.unwrap();
+    let response = recv(ws).await;
+    assert_eq!(response["id"], json!(id));
+    response
+}
+
+async fn recv(ws: &mut WsStream) -> Value {
+    let msg = tokio::time::timeout(Duration::from_secs(10), ws.next())
+        .await
+        .expect("ws message")
+        .unwrap()
+        .unwrap();
+    serde_json::from_str(msg.to_text().unwrap()).unwrap()
+}
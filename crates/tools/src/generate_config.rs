@@ -139,6 +139,7 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
     let wallet_config: WalletConfig = WalletConfig {
         privkey_path: privkey_path.into(),
         lock,
+        signer: None,
     };
 
     let backends: Vec<BackendConfig> = vec![
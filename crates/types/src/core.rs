@@ -201,6 +201,7 @@ impl From<GlobalStateV0> for GlobalState {
 pub enum AllowedEoaType {
     Unknown,
     Eth,
+    Tron,
 }
 
 impl From<AllowedEoaType> for u8 {
@@ -217,6 +218,7 @@ impl TryFrom<u8> for AllowedEoaType {
         match value {
             0 => Ok(AllowedEoaType::Unknown),
             1 => Ok(AllowedEoaType::Eth),
+            2 => Ok(AllowedEoaType::Tron),
             n => Err(n),
         }
     }
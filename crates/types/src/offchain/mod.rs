@@ -8,6 +8,7 @@ mod rollup_context;
 mod rpc;
 mod run_result;
 mod store;
+mod trace;
 
 pub use error_receipt::*;
 pub use exported_block::*;
@@ -19,3 +20,4 @@ pub use rollup_context::*;
 pub use rpc::*;
 pub use run_result::*;
 pub use store::*;
+pub use trace::*;
@@ -0,0 +1,32 @@
+use sparse_merkle_tree::H256;
+
+/// A syscall invoked by the backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyscallTrace {
+    pub code: u64,
+    pub name: &'static str,
+    pub virtual_cycles: u64,
+}
+
+/// A SMT key read but not written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateReadTrace {
+    pub key: H256,
+    pub value: H256,
+}
+
+/// A SMT key written, `old_value` is the value before the tx.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateWriteTrace {
+    pub key: H256,
+    pub old_value: H256,
+    pub new_value: H256,
+}
+
+/// Trace of a tx execution, syscalls are in invocation order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxTrace {
+    pub syscalls: Vec<SyscallTrace>,
+    pub reads: Vec<StateReadTrace>,
+    pub writes: Vec<StateWriteTrace>,
+}
@@ -9,6 +9,7 @@ pub mod local_cells;
 pub mod polyjuice_parser;
 mod query_rollup_cell;
 pub mod script_log;
+pub mod signer;
 pub mod since;
 pub mod transaction_skeleton;
 pub mod wallet;
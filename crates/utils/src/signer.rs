@@ -0,0 +1,114 @@
+//! Signers of layer 1 transactions.
+//!
+//! The wallet key is either held by this process, read from a plain hex file
+//! or an encrypted keystore, or held by a remote signer reached over a Unix
+//! socket or HTTP. See [`daemon`] for the remote signer protocol.
+
+pub mod daemon;
+pub mod keystore;
+mod remote;
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use ckb_crypto::secp::Privkey;
+use faster_hex::hex_decode;
+use gw_config::{SignerConfig, WalletConfig};
+
+pub use remote::RemoteSigner;
+
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// Sign a 32 bytes message, returns the 65 bytes recoverable secp256k1
+    /// signature.
+    async fn sign(&self, message: [u8; 32]) -> Result<[u8; 65]>;
+
+    /// The private key, if it is held by this process.
+    fn local_key(&self) -> Option<&Privkey> {
+        None
+    }
+}
+
+pub struct LocalSigner {
+    privkey: Privkey,
+}
+
+impl LocalSigner {
+    pub fn new(privkey: Privkey) -> Self {
+        LocalSigner { privkey }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let secret = read_privkey_file(path)?;
+        Ok(Self::new(Privkey::from_slice(&secret)))
+    }
+
+    pub fn from_keystore(path: &Path, password_path: &Path) -> Result<Self> {
+        let password = read_password_file(password_path)?;
+        let secret = keystore::decrypt_file(path, password.as_bytes())
+            .with_context(|| format!("decrypt keystore {}", path.display()))?;
+        Ok(Self::new(Privkey::from_slice(&secret)))
+    }
+
+    pub fn sign_message(&self, message: [u8; 32]) -> Result<[u8; 65]> {
+        let signature = self
+            .privkey
+            .sign_recoverable(&message.into())
+            .map_err(|err| anyhow!("signing error: {}", err))?;
+        let mut inner = [0u8; 65];
+        inner.copy_from_slice(&signature.serialize());
+        Ok(inner)
+    }
+}
+
+#[async_trait]
+impl Signer for LocalSigner {
+    async fn sign(&self, message: [u8; 32]) -> Result<[u8; 65]> {
+        self.sign_message(message)
+    }
+
+    fn local_key(&self) -> Option<&Privkey> {
+        Some(&self.privkey)
+    }
+}
+
+/// Read a hex encoded private key file.
+pub fn read_privkey_file(path: &Path) -> Result<[u8; 32]> {
+    let content = std::fs::read_to_string(path).with_context(|| "read wallet privkey")?;
+    let content = content.trim_start_matches("0x").trim();
+    if content.as_bytes().len() != 64 {
+        bail!("invalid privkey length");
+    }
+    let mut decoded = [0u8; 32];
+    hex_decode(content.as_bytes(), &mut decoded)?;
+    Ok(decoded)
+}
+
+pub fn read_password_file(path: &Path) -> Result<String> {
+    let password = std::fs::read_to_string(path)
+        .with_context(|| format!("read keystore password from {}", path.display()))?;
+    // Ignore the trailing newline of the password file
+    Ok(password.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+pub fn build_signer(config: &WalletConfig) -> Result<Arc<dyn Signer>> {
+    let signer: Arc<dyn Signer> = match config.signer {
+        None => Arc::new(LocalSigner::from_file(&config.privkey_path)?),
+        Some(SignerConfig::Keystore {
+            ref path,
+            ref password_path,
+        }) => Arc::new(LocalSigner::from_keystore(path, password_path)?),
+        Some(SignerConfig::Remote {
+            ref url,
+            ref auth_token,
+        }) => Arc::new(RemoteSigner::new(url, auth_token.as_deref())?),
+    };
+    Ok(signer)
+}
+
+/// Compare secrets, e.g. MACs and tokens, without leaking the position of the
+/// first difference through timing.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
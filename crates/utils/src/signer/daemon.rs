@@ -0,0 +1,295 @@
+//! Reference remote signer daemon.
+//!
+//! Listens on `unix:///path/to/socket` or `http://host:port`. A request is
+//! `{"message": "0x<32 bytes>"}`, the response is
+//! `{"signature": "0x<65 bytes recoverable signature>"}` or
+//! `{"error": "..."}`.
+//!
+//! Over a Unix socket requests and responses are lines of JSON, a connection
+//! can send any number of requests. Over HTTP the request is the body of a
+//! POST to any path, with an `Authorization: Bearer <token>` header if the
+//! daemon is started with an auth token.
+//!
+//! The daemon signs anything it is asked to. The Unix socket is only
+//! accessible by its owner, and HTTP only listens on loopback addresses unless
+//! explicitly allowed. Keep the socket or the port reachable only by the node.
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
+use hyper::{
+    header::AUTHORIZATION,
+    server::conn::AddrIncoming,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+};
+
+use super::{constant_time_eq, Signer};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignRequest {
+    pub message: JsonBytes,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SignResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<JsonBytes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub enum Daemon {
+    Unix {
+        listener: UnixListener,
+        path: PathBuf,
+    },
+    Http(AddrIncoming),
+}
+
+impl Daemon {
+    /// Must be called in a tokio runtime. HTTP must listen on a loopback
+    /// address unless `allow_non_loopback` is set.
+    pub fn bind(listen: &str, allow_non_loopback: bool) -> Result<Self> {
+        if let Some(path) = listen.strip_prefix("unix://") {
+            remove_stale_socket(Path::new(path))?;
+            let listener =
+                UnixListener::bind(path).with_context(|| format!("bind unix socket {}", path))?;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+                .with_context(|| format!("set permissions of unix socket {}", path))?;
+            Ok(Daemon::Unix {
+                listener,
+                path: path.into(),
+            })
+        } else if let Some(addr) = listen.strip_prefix("http://") {
+            let addr: SocketAddr = addr.parse().context("parse listen address")?;
+            if !addr.ip().is_loopback() && !allow_non_loopback {
+                bail!("refuse to listen on non-loopback address {}", addr);
+            }
+            let incoming = AddrIncoming::bind(&addr).context("bind http")?;
+            Ok(Daemon::Http(incoming))
+        } else {
+            bail!("invalid listen address {}", listen);
+        }
+    }
+
+    /// Requests over HTTP must carry `auth_token` as a bearer token if it is
+    /// set.
+    pub async fn serve(self, signer: Arc<dyn Signer>, auth_token: Option<String>) -> Result<()> {
+        match self {
+            Daemon::Unix { listener, path } => {
+                log::info!("signer daemon listens on unix://{}", path.display());
+                loop {
+                    let (stream, _) = listener.accept().await?;
+                    let signer = Arc::clone(&signer);
+                    tokio::spawn(async move {
+                        if let Err(err) = serve_unix_stream(signer, stream).await {
+                            log::warn!("signer daemon connection: {:#}", err);
+                        }
+                    });
+                }
+            }
+            Daemon::Http(incoming) => {
+                log::info!("signer daemon listens on http://{}", incoming.local_addr());
+                let auth_token = Arc::new(auth_token);
+                let make_service = make_service_fn(move |_| {
+                    let signer = Arc::clone(&signer);
+                    let auth_token = Arc::clone(&auth_token);
+                    async move {
+                        Ok::<_, Infallible>(service_fn(move |req| {
+                            serve_http_request(Arc::clone(&signer), Arc::clone(&auth_token), req)
+                        }))
+                    }
+                });
+                Server::builder(incoming).serve(make_service).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Remove the socket file left by a previous daemon. Refuse to remove other
+/// files or a socket that is still in use.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err).with_context(|| format!("stat {}", path.display())),
+    };
+    if !metadata.file_type().is_socket() {
+        bail!("{} exists and is not a unix socket", path.display());
+    }
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+        bail!("unix socket {} is in use", path.display());
+    }
+    log::info!("remove stale unix socket {}", path.display());
+    std::fs::remove_file(path).with_context(|| format!("remove {}", path.display()))?;
+    Ok(())
+}
+
+async fn serve_unix_stream(signer: Arc<dyn Signer>, stream: UnixStream) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let response = sign(signer.as_ref(), line.as_bytes()).await;
+        let mut line = serde_json::to_vec(&response)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+    }
+    Ok(())
+}
+
+async fn serve_http_request(
+    signer: Arc<dyn Signer>,
+    auth_token: Arc<Option<String>>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    if let Some(ref auth_token) = *auth_token {
+        let expected = format!("Bearer {}", auth_token);
+        let authorized = req.headers().get(AUTHORIZATION).map_or(false, |v| {
+            constant_time_eq(v.as_bytes(), expected.as_bytes())
+        });
+        if !authorized {
+            log::warn!("reject unauthorized sign request");
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::UNAUTHORIZED;
+            return Ok(response);
+        }
+    }
+    if req.method() != Method::POST {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+        return Ok(response);
+    }
+    let response = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => sign(signer.as_ref(), &body).await,
+        Err(err) => SignResponse {
+            error: Some(err.to_string()),
+            ..Default::default()
+        },
+    };
+    let body = serde_json::to_vec(&response).expect("serialize sign response");
+    let response = Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .expect("build response");
+    Ok(response)
+}
+
+async fn sign(signer: &dyn Signer, request: &[u8]) -> SignResponse {
+    let result = async {
+        let request: SignRequest = serde_json::from_slice(request).context("parse request")?;
+        if request.message.len() != 32 {
+            bail!("invalid message length {}", request.message.len());
+        }
+        let mut message = [0u8; 32];
+        message.copy_from_slice(request.message.as_bytes());
+        signer.sign(message).await
+    }
+    .await;
+    match result {
+        Ok(signature) => SignResponse {
+            signature: Some(JsonBytes::from_vec(signature.to_vec())),
+            error: None,
+        },
+        Err(err) => SignResponse {
+            signature: None,
+            error: Some(format!("{:#}", err)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_crypto::secp::Privkey;
+
+    use super::*;
+    use crate::signer::{LocalSigner, RemoteSigner};
+
+    fn local_signer() -> LocalSigner {
+        LocalSigner::new(Privkey::from_slice(&[7u8; 32]))
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_over_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let listen = format!("unix://{}", path.display());
+        let local = local_signer();
+        let expected = local.sign_message([1u8; 32]).unwrap();
+
+        let daemon = Daemon::bind(&listen, false).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        tokio::spawn(daemon.serve(Arc::new(local), None));
+
+        let remote = RemoteSigner::new(&listen, None).unwrap();
+        assert_eq!(remote.sign([1u8; 32]).await.unwrap(), expected);
+        assert!(remote.local_key().is_none());
+
+        // The socket is in use.
+        let err = Daemon::bind(&listen, false).err().unwrap();
+        assert!(err.to_string().contains("in use"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_bind_unix_socket_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let listen = format!("unix://{}", path.display());
+
+        // The socket file is left after the listener is dropped.
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        Daemon::bind(&listen, false).unwrap();
+
+        // Other files are not removed.
+        let path = dir.path().join("file");
+        std::fs::write(&path, "").unwrap();
+        let err = Daemon::bind(&format!("unix://{}", path.display()), false)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("not a unix socket"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_over_http() {
+        let local = local_signer();
+        let expected = local.sign_message([1u8; 32]).unwrap();
+
+        let daemon = Daemon::bind("http://127.0.0.1:0", false).unwrap();
+        let url = match daemon {
+            Daemon::Http(ref incoming) => format!("http://{}", incoming.local_addr()),
+            _ => unreachable!(),
+        };
+        tokio::spawn(daemon.serve(Arc::new(local), Some("secret".into())));
+
+        let remote = RemoteSigner::new(&url, Some("secret")).unwrap();
+        assert_eq!(remote.sign([1u8; 32]).await.unwrap(), expected);
+
+        for auth_token in [None, Some("wrong")] {
+            let remote = RemoteSigner::new(&url, auth_token).unwrap();
+            let err = remote.sign([1u8; 32]).await.unwrap_err();
+            assert!(format!("{:#}", err).contains("401"), "{:#}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bind_http_non_loopback() {
+        let err = Daemon::bind("http://0.0.0.0:0", false).err().unwrap();
+        assert!(err.to_string().contains("non-loopback"), "{}", err);
+        Daemon::bind("http://0.0.0.0:0", true).unwrap();
+    }
+}
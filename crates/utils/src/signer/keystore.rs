@@ -0,0 +1,274 @@
+//! Web3 secret storage (version 3) keystore, the format used by ckb-cli and
+//! most Ethereum wallets.
+//!
+//! Keys are derived with scrypt or PBKDF2-HMAC-SHA256 and encrypted with
+//! AES-128-CTR.
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use faster_hex::{hex_decode, hex_encode};
+use hmac::Hmac;
+use rand::{rngs::OsRng, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+use super::constant_time_eq;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+const CIPHER: &str = "aes-128-ctr";
+const DKLEN: u32 = 32;
+/// scrypt N = 2^18, same as ckb-cli.
+pub const DEFAULT_SCRYPT_LOG_N: u8 = 18;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Keystore {
+    #[serde(alias = "Crypto")]
+    pub crypto: Crypto,
+    #[serde(default)]
+    pub id: String,
+    pub version: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Crypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    pub mac: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KdfParams {
+    Scrypt {
+        dklen: u32,
+        n: u32,
+        r: u32,
+        p: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        c: u32,
+        dklen: u32,
+        prf: String,
+        salt: String,
+    },
+}
+
+impl Keystore {
+    /// Encrypt a secp256k1 secret key with a scrypt derived key.
+    pub fn encrypt(secret: &[u8; 32], password: &[u8], scrypt_log_n: u8) -> Result<Self> {
+        secp256k1::SecretKey::from_slice(secret).context("invalid secret key")?;
+
+        let salt: [u8; 32] = OsRng.gen();
+        let iv: [u8; 16] = OsRng.gen();
+        let (r, p) = (8, 1);
+        let kdfparams = KdfParams::Scrypt {
+            dklen: DKLEN,
+            n: 1 << scrypt_log_n,
+            r,
+            p,
+            salt: to_hex(&salt)?,
+        };
+        let derived_key = derive_key(&kdfparams, password)?;
+
+        let mut ciphertext = secret.to_vec();
+        Aes128Ctr::new_from_slices(&derived_key[..16], &iv)
+            .map_err(|_| anyhow!("invalid aes key or iv length"))?
+            .apply_keystream(&mut ciphertext);
+        let mac = mac(&derived_key, &ciphertext);
+
+        let crypto = Crypto {
+            cipher: CIPHER.into(),
+            cipherparams: CipherParams { iv: to_hex(&iv)? },
+            ciphertext: to_hex(&ciphertext)?,
+            kdf: "scrypt".into(),
+            kdfparams,
+            mac: to_hex(&mac)?,
+        };
+        Ok(Keystore {
+            crypto,
+            id: random_uuid(),
+            version: 3,
+        })
+    }
+
+    /// Returns the secret key.
+    pub fn decrypt(&self, password: &[u8]) -> Result<[u8; 32]> {
+        if self.version != 3 {
+            bail!("unsupported keystore version {}", self.version);
+        }
+        let crypto = &self.crypto;
+        if crypto.cipher != CIPHER {
+            bail!("unsupported cipher {}", crypto.cipher);
+        }
+        match (crypto.kdf.as_str(), &crypto.kdfparams) {
+            ("scrypt", KdfParams::Scrypt { .. }) | ("pbkdf2", KdfParams::Pbkdf2 { .. }) => {}
+            (kdf, _) => bail!("unsupported kdf {} or invalid kdfparams", kdf),
+        }
+
+        let derived_key = derive_key(&crypto.kdfparams, password)?;
+        let mut ciphertext = from_hex(&crypto.ciphertext)?;
+        if !constant_time_eq(&mac(&derived_key, &ciphertext), &from_hex(&crypto.mac)?) {
+            bail!("mac mismatch, wrong password?");
+        }
+
+        let iv = from_hex(&crypto.cipherparams.iv)?;
+        Aes128Ctr::new_from_slices(&derived_key[..16], &iv)
+            .map_err(|_| anyhow!("invalid iv length"))?
+            .apply_keystream(&mut ciphertext);
+        if ciphertext.len() != 32 {
+            bail!("invalid secret key length");
+        }
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&ciphertext);
+        secp256k1::SecretKey::from_slice(&secret).context("invalid secret key")?;
+        Ok(secret)
+    }
+}
+
+pub fn decrypt_file(path: &Path, password: &[u8]) -> Result<[u8; 32]> {
+    let content = std::fs::read_to_string(path).context("read keystore")?;
+    let keystore: Keystore = serde_json::from_str(&content).context("parse keystore")?;
+    keystore.decrypt(password)
+}
+
+fn derive_key(kdfparams: &KdfParams, password: &[u8]) -> Result<[u8; 32]> {
+    let mut derived_key = [0u8; 32];
+    match kdfparams {
+        KdfParams::Scrypt {
+            dklen,
+            n,
+            r,
+            p,
+            salt,
+        } => {
+            if *dklen != DKLEN {
+                bail!("unsupported dklen {}", dklen);
+            }
+            if !n.is_power_of_two() {
+                bail!("invalid scrypt n {}", n);
+            }
+            let log_n = n.trailing_zeros() as u8;
+            let params = scrypt::Params::new(log_n, *r, *p)
+                .map_err(|err| anyhow!("invalid scrypt params: {}", err))?;
+            scrypt::scrypt(password, &from_hex(salt)?, &params, &mut derived_key)
+                .map_err(|err| anyhow!("scrypt: {}", err))?;
+        }
+        KdfParams::Pbkdf2 {
+            c,
+            dklen,
+            prf,
+            salt,
+        } => {
+            if *dklen != DKLEN {
+                bail!("unsupported dklen {}", dklen);
+            }
+            if prf != "hmac-sha256" {
+                bail!("unsupported prf {}", prf);
+            }
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(password, &from_hex(salt)?, *c, &mut derived_key);
+        }
+    }
+    Ok(derived_key)
+}
+
+fn mac(derived_key: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(&derived_key[16..]);
+    hasher.update(ciphertext);
+    hasher.finalize().into()
+}
+
+fn random_uuid() -> String {
+    let mut bytes: [u8; 16] = OsRng.gen();
+    // version 4, variant 1
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = to_hex(&bytes).expect("hex encode");
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn to_hex(bytes: &[u8]) -> Result<String> {
+    let mut dst = vec![0u8; bytes.len() * 2];
+    hex_encode(bytes, &mut dst)?;
+    Ok(String::from_utf8(dst)?)
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>> {
+    let s = s.trim_start_matches("0x");
+    if s.len() % 2 != 0 {
+        bail!("invalid hex length");
+    }
+    let mut dst = vec![0u8; s.len() / 2];
+    hex_decode(s.as_bytes(), &mut dst)?;
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let secret = [7u8; 32];
+        let keystore = Keystore::encrypt(&secret, b"password", 10).unwrap();
+        let json = serde_json::to_string(&keystore).unwrap();
+
+        let keystore: Keystore = serde_json::from_str(&json).unwrap();
+        assert_eq!(keystore.decrypt(b"password").unwrap(), secret);
+        let err = keystore.decrypt(b"wrong password").unwrap_err();
+        assert!(err.to_string().contains("mac mismatch"));
+    }
+
+    #[test]
+    fn test_decrypt_pbkdf2() {
+        let secret = [9u8; 32];
+        let salt = [1u8; 32];
+        let iv = [2u8; 16];
+        let kdfparams = KdfParams::Pbkdf2 {
+            c: 1024,
+            dklen: DKLEN,
+            prf: "hmac-sha256".into(),
+            salt: to_hex(&salt).unwrap(),
+        };
+        let derived_key = derive_key(&kdfparams, b"password").unwrap();
+        let mut ciphertext = secret.to_vec();
+        Aes128Ctr::new_from_slices(&derived_key[..16], &iv)
+            .unwrap()
+            .apply_keystream(&mut ciphertext);
+        let keystore = Keystore {
+            crypto: Crypto {
+                cipher: CIPHER.into(),
+                cipherparams: CipherParams {
+                    iv: to_hex(&iv).unwrap(),
+                },
+                ciphertext: to_hex(&ciphertext).unwrap(),
+                kdf: "pbkdf2".into(),
+                kdfparams,
+                mac: to_hex(&mac(&derived_key, &ciphertext)).unwrap(),
+            },
+            id: random_uuid(),
+            version: 3,
+        };
+        assert_eq!(keystore.decrypt(b"password").unwrap(), secret);
+    }
+}
@@ -0,0 +1,112 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+};
+
+use super::{
+    daemon::{SignRequest, SignResponse},
+    Signer,
+};
+
+const SIGN_TIMEOUT: Duration = Duration::from_secs(30);
+
+enum Endpoint {
+    Unix(PathBuf),
+    Http {
+        client: reqwest::Client,
+        url: String,
+        auth_token: Option<String>,
+    },
+}
+
+/// Signer that sends messages to a remote signer, see [`super::daemon`].
+pub struct RemoteSigner {
+    endpoint: Endpoint,
+}
+
+impl RemoteSigner {
+    /// `url` is `unix:///path/to/socket` or `http(s)://host:port/path`.
+    /// `auth_token` is sent as a bearer token to an HTTP signer.
+    pub fn new(url: &str, auth_token: Option<&str>) -> Result<Self> {
+        let endpoint = if let Some(path) = url.strip_prefix("unix://") {
+            if auth_token.is_some() {
+                log::warn!(
+                    "auth token is not used by unix socket remote signer {}",
+                    url
+                );
+            }
+            Endpoint::Unix(path.into())
+        } else if url.starts_with("http://") || url.starts_with("https://") {
+            if url.starts_with("http://") {
+                log::warn!("remote signer {} is not https", url);
+            }
+            let client = reqwest::Client::builder().timeout(SIGN_TIMEOUT).build()?;
+            Endpoint::Http {
+                client,
+                url: url.into(),
+                auth_token: auth_token.map(Into::into),
+            }
+        } else {
+            bail!("invalid remote signer url {}", url);
+        };
+        Ok(RemoteSigner { endpoint })
+    }
+
+    async fn request(&self, request: &SignRequest) -> Result<SignResponse> {
+        match self.endpoint {
+            Endpoint::Unix(ref path) => {
+                let stream = UnixStream::connect(path)
+                    .await
+                    .with_context(|| format!("connect remote signer {}", path.display()))?;
+                let (reader, mut writer) = stream.into_split();
+                let mut line = serde_json::to_vec(request)?;
+                line.push(b'\n');
+                writer.write_all(&line).await?;
+
+                let mut response = String::new();
+                BufReader::new(reader).read_line(&mut response).await?;
+                Ok(serde_json::from_str(&response)?)
+            }
+            Endpoint::Http {
+                ref client,
+                ref url,
+                ref auth_token,
+            } => {
+                let mut req = client.post(url).json(request);
+                if let Some(auth_token) = auth_token {
+                    req = req.bearer_auth(auth_token);
+                }
+                let response = req.send().await?.error_for_status()?.json().await?;
+                Ok(response)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn sign(&self, message: [u8; 32]) -> Result<[u8; 65]> {
+        let request = SignRequest {
+            message: JsonBytes::from_vec(message.to_vec()),
+        };
+        let response = tokio::time::timeout(SIGN_TIMEOUT, self.request(&request))
+            .await
+            .context("remote signer timeout")?
+            .context("remote signer")?;
+        if let Some(err) = response.error {
+            bail!("remote signer: {}", err);
+        }
+        let signature = match response.signature {
+            Some(signature) if signature.len() == 65 => signature,
+            _ => bail!("remote signer: invalid signature"),
+        };
+        let mut inner = [0u8; 65];
+        inner.copy_from_slice(signature.as_bytes());
+        Ok(inner)
+    }
+}
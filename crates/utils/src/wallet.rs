@@ -1,6 +1,7 @@
-use anyhow::{anyhow, Context, Result};
-use ckb_crypto::secp::Privkey;
-use faster_hex::hex_decode;
+use std::{ops::Range, sync::Arc};
+
+use anyhow::{anyhow, bail, Result};
+use ckb_crypto::secp::{self, Privkey};
 use gw_common::{blake2b::new_blake2b, H256};
 use gw_config::WalletConfig;
 use gw_types::{
@@ -9,55 +10,88 @@ use gw_types::{
     packed::{Script, Transaction},
     prelude::{Builder, Entity, Pack, Unpack},
 };
+use rand::{rngs::OsRng, Rng};
 use sha3::{Digest, Keccak256};
 
-use crate::transaction_skeleton::{Signature, TransactionSkeleton};
+use crate::{
+    signer::{build_signer, LocalSigner, Signer},
+    transaction_skeleton::{Signature, SignatureEntry, TransactionSkeleton},
+};
 
+#[derive(Clone)]
 pub struct Wallet {
-    privkey: Privkey,
+    signer: Arc<dyn Signer>,
     lock: Script,
 }
 
 impl Wallet {
     pub fn new(privkey: Privkey, lock: Script) -> Self {
-        Wallet { privkey, lock }
+        Self::with_signer(Arc::new(LocalSigner::new(privkey)), lock)
+    }
+
+    pub fn with_signer(signer: Arc<dyn Signer>, lock: Script) -> Self {
+        Wallet { signer, lock }
     }
 
     pub fn from_config(config: &WalletConfig) -> Result<Self> {
         let lock = config.lock.clone().into();
-        let privkey = {
-            let content = std::fs::read_to_string(&config.privkey_path)
-                .with_context(|| "read wallet privkey")?;
-            let content = content.trim_start_matches("0x").trim();
-            assert_eq!(content.as_bytes().len(), 64, "invalid privkey length");
-            let mut decoded = [0u8; 32];
-            hex_decode(content.as_bytes(), &mut decoded)?;
-            Privkey::from_slice(&decoded)
+        let signer = build_signer(config)?;
+        Ok(Self::with_signer(signer, lock))
+    }
+
+    /// A wallet with a random key, the lock is `lock` with the key's pubkey
+    /// hash. `lock` must be a secp256k1 sighash lock (20 bytes args) or an
+    /// omni lock with secp256k1 auth (the flag byte then the pubkey hash).
+    ///
+    /// Used to sign mock transactions, which are never sent, when the key of
+    /// the wallet is held by a remote signer.
+    pub fn random_like(lock: &Script) -> Result<Self> {
+        let privkey = loop {
+            let secret: [u8; 32] = OsRng.gen();
+            if secp256k1::SecretKey::from_slice(&secret).is_ok() {
+                break Privkey::from_slice(&secret);
+            }
         };
-        let wallet = Self::new(privkey, lock);
-        Ok(wallet)
+        let pubkey_hash = blake160(&privkey.pubkey()?.serialize());
+        let mut args = lock.args().raw_data().to_vec();
+        match pubkey_hash_range(&args) {
+            Some(range) => args[range].copy_from_slice(&pubkey_hash),
+            None => bail!("unsupported lock args length {}", args.len()),
+        }
+        let lock = lock.clone().as_builder().args(args.pack()).build();
+        Ok(Self::new(privkey, lock))
     }
 
     pub fn lock_script(&self) -> &Script {
         &self.lock
     }
 
+    pub fn signer(&self) -> &Arc<dyn Signer> {
+        &self.signer
+    }
+
+    fn local_key(&self) -> Result<&Privkey> {
+        self.signer
+            .local_key()
+            .ok_or_else(|| anyhow!("wallet key is not held locally"))
+    }
+
     pub fn eth_lock_script(
         &self,
         rollup_script_hash: &H256,
         eth_account_lock_code_hash: &H256,
     ) -> Result<Script> {
         privkey_to_eth_account_script(
-            &self.privkey,
+            self.local_key()?,
             rollup_script_hash,
             eth_account_lock_code_hash,
         )
     }
 
-    // sign message
+    /// Sign message with the local key.
     pub fn sign_message(&self, msg: [u8; 32]) -> Result<[u8; 65]> {
         let signature = self
-            .privkey
+            .local_key()?
             .sign_recoverable(&msg.into())
             .map_err(|err| anyhow!("signing error: {}", err))?;
         let mut inner = [0u8; 65];
@@ -65,61 +99,136 @@ impl Wallet {
         Ok(inner)
     }
 
-    pub fn sign_tx_skeleton(&self, tx_skeleton: TransactionSkeleton) -> Result<Transaction> {
-        let signature_entries = tx_skeleton.signature_entries();
-        let dummy_signatures: Vec<_> = {
-            let entries = signature_entries.iter();
-            entries.map(Signature::zero_bytes_from_entry).collect()
-        };
+    pub async fn sign_tx_skeleton(&self, tx_skeleton: TransactionSkeleton) -> Result<Transaction> {
+        let (signature_entries, messages) = signing_messages(&tx_skeleton)?;
+        let mut signatures = Vec::with_capacity(signature_entries.len());
+        for (entry, message) in signature_entries.iter().zip(messages) {
+            let signature = self.signer.sign(message).await?;
+            if self.signer.local_key().is_none() {
+                self.check_remote_signature(message, &signature)?;
+            }
+            let signature = Signature::new(entry.kind, signature);
+            signatures.push(signature.as_bytes());
+        }
+        seal(tx_skeleton, &signature_entries, signatures)
+    }
 
-        // seal a dummy tx for calculation
-        let tx = tx_skeleton
-            .seal(&signature_entries, dummy_signatures)?
-            .transaction;
-        let tx_hash = {
-            let mut hasher = new_blake2b();
-            hasher.update(tx.raw().as_slice());
-            let mut hash = [0u8; 32];
-            hasher.finalize(&mut hash);
-            hash
+    // A remote signer holding another key would make the lock reject the tx,
+    // so check the signer against the pubkey hash of the lock.
+    fn check_remote_signature(&self, message: [u8; 32], signature: &[u8; 65]) -> Result<()> {
+        let args = self.lock.args().raw_data();
+        let range = match pubkey_hash_range(&args) {
+            Some(range) => range,
+            None => return Ok(()),
         };
+        let pubkey = secp::Signature::from_slice(signature)
+            .and_then(|signature| signature.recover(&message.into()))
+            .map_err(|err| anyhow!("recover remote signature: {}", err))?;
+        if blake160(&pubkey.serialize()) != args[range] {
+            bail!("remote signer key doesn't match the wallet lock");
+        }
+        Ok(())
+    }
+
+    /// Sign with the local key, for callers which can't wait for a remote
+    /// signer.
+    pub fn sign_tx_skeleton_locally(
+        &self,
+        tx_skeleton: TransactionSkeleton,
+    ) -> Result<Transaction> {
+        let (signature_entries, messages) = signing_messages(&tx_skeleton)?;
         let mut signatures = Vec::with_capacity(signature_entries.len());
-        for entry in &signature_entries {
-            let mut hasher = new_blake2b();
-            // hash tx_hash
-            hasher.update(&tx_hash);
-            // hash the first witness: len | witness
-            let first_witness: Bytes = tx
-                .witnesses()
-                .get(entry.indexes[0])
-                .expect("get first witness")
-                .unpack();
-            hasher.update(&(first_witness.len() as u64).to_le_bytes());
-            hasher.update(&first_witness);
-            // hash the other witnesses in the group
-            for &index in &entry.indexes[1..] {
-                let witness: Bytes = tx.witnesses().get(index).expect("get witness").unpack();
-                hasher.update(&(witness.len() as u64).to_le_bytes());
-                hasher.update(&witness);
-            }
-            // hash witnesses which do not in any input group
-            for index in tx.raw().inputs().len()..tx.witnesses().len() {
-                let witness: Bytes = tx.witnesses().get(index).expect("get witness").unpack();
-                hasher.update(&(witness.len() as u64).to_le_bytes());
-                hasher.update(&witness);
-            }
-            let mut message = [0u8; 32];
-            hasher.finalize(&mut message);
-            // sign tx
+        for (entry, message) in signature_entries.iter().zip(messages) {
             let signature = Signature::new(entry.kind, self.sign_message(message)?);
             signatures.push(signature.as_bytes());
         }
-        // seal
-        let sealed_tx = tx_skeleton.seal(&signature_entries, signatures)?;
-        // check fee rate
-        sealed_tx.check_fee_rate()?;
-        Ok(sealed_tx.transaction)
+        seal(tx_skeleton, &signature_entries, signatures)
+    }
+}
+
+/// Range of the pubkey hash in args of a secp256k1 sighash lock (20 bytes
+/// args) or an omni lock with secp256k1 auth (the flag byte then the pubkey
+/// hash).
+fn pubkey_hash_range(args: &[u8]) -> Option<Range<usize>> {
+    match args.len() {
+        20 => Some(0..20),
+        len if len >= 21 && args[0] == 0 => Some(1..21),
+        _ => None,
+    }
+}
+
+fn blake160(data: &[u8]) -> [u8; 20] {
+    let mut hasher = new_blake2b();
+    hasher.update(data);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    let mut blake160 = [0u8; 20];
+    blake160.copy_from_slice(&hash[..20]);
+    blake160
+}
+
+/// Signature entries and the message to sign of each entry.
+fn signing_messages(
+    tx_skeleton: &TransactionSkeleton,
+) -> Result<(Vec<SignatureEntry>, Vec<[u8; 32]>)> {
+    let signature_entries = tx_skeleton.signature_entries();
+    let dummy_signatures: Vec<_> = {
+        let entries = signature_entries.iter();
+        entries.map(Signature::zero_bytes_from_entry).collect()
+    };
+
+    // seal a dummy tx for calculation
+    let tx = tx_skeleton
+        .seal(&signature_entries, dummy_signatures)?
+        .transaction;
+    let tx_hash = {
+        let mut hasher = new_blake2b();
+        hasher.update(tx.raw().as_slice());
+        let mut hash = [0u8; 32];
+        hasher.finalize(&mut hash);
+        hash
+    };
+    let mut messages = Vec::with_capacity(signature_entries.len());
+    for entry in &signature_entries {
+        let mut hasher = new_blake2b();
+        // hash tx_hash
+        hasher.update(&tx_hash);
+        // hash the first witness: len | witness
+        let first_witness: Bytes = tx
+            .witnesses()
+            .get(entry.indexes[0])
+            .expect("get first witness")
+            .unpack();
+        hasher.update(&(first_witness.len() as u64).to_le_bytes());
+        hasher.update(&first_witness);
+        // hash the other witnesses in the group
+        for &index in &entry.indexes[1..] {
+            let witness: Bytes = tx.witnesses().get(index).expect("get witness").unpack();
+            hasher.update(&(witness.len() as u64).to_le_bytes());
+            hasher.update(&witness);
+        }
+        // hash witnesses which do not in any input group
+        for index in tx.raw().inputs().len()..tx.witnesses().len() {
+            let witness: Bytes = tx.witnesses().get(index).expect("get witness").unpack();
+            hasher.update(&(witness.len() as u64).to_le_bytes());
+            hasher.update(&witness);
+        }
+        let mut message = [0u8; 32];
+        hasher.finalize(&mut message);
+        messages.push(message);
     }
+    Ok((signature_entries, messages))
+}
+
+fn seal(
+    tx_skeleton: TransactionSkeleton,
+    signature_entries: &[SignatureEntry],
+    signatures: Vec<Bytes>,
+) -> Result<Transaction> {
+    let sealed_tx = tx_skeleton.seal(signature_entries, signatures)?;
+    // check fee rate
+    sealed_tx.check_fee_rate()?;
+    Ok(sealed_tx.transaction)
 }
 
 pub fn privkey_to_eth_account_script(
@@ -146,3 +255,26 @@ pub fn privkey_to_eth_account_script(
 
     Ok(script)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_remote_signature() {
+        let privkey = Privkey::from_slice(&[7u8; 32]);
+        let pubkey_hash = blake160(&privkey.pubkey().unwrap().serialize());
+        let lock = Script::new_builder()
+            .args(Bytes::copy_from_slice(&pubkey_hash).pack())
+            .build();
+        let remote = Wallet::with_signer(Arc::new(LocalSigner::new(privkey.clone())), lock);
+        let message = [1u8; 32];
+        let signature = LocalSigner::new(privkey).sign_message(message).unwrap();
+        remote.check_remote_signature(message, &signature).unwrap();
+
+        let other = LocalSigner::new(Privkey::from_slice(&[8u8; 32]));
+        let signature = other.sign_message(message).unwrap();
+        let err = remote.check_remote_signature(message, &signature);
+        assert!(err.is_err());
+    }
+}